@@ -3,7 +3,7 @@
 
 # Example 1: Find all ARM64 binaries
 echo "=== Finding all ARM64 binaries ==="
-symwalker --json /usr/bin | jq '.[] | select(.architecture == "ARM64") | .file_path'
+symwalker --json --arch arm64 /usr/bin | jq '.[].file_path'
 
 # Example 2: Find binaries without PIE
 echo ""
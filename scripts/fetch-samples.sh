@@ -0,0 +1,36 @@
+#!/usr/bin/env bash
+# Populate tests/samples/ with real-world binaries for `cargo test --test samples`.
+#
+# Copies a few well-known binaries from this host, then downloads any URLs
+# given as arguments. Samples are host-specific and are not committed.
+#
+#   scripts/fetch-samples.sh [URL...]
+
+set -euo pipefail
+
+dest="$(cd "$(dirname "$0")/.." && pwd)/tests/samples"
+mkdir -p "$dest"
+
+candidates=(
+    /bin/ls
+    /bin/sh
+    /usr/bin/env
+    /usr/lib/dyld
+    /usr/lib/x86_64-linux-gnu/libc.so.6
+    /usr/lib/aarch64-linux-gnu/libc.so.6
+    /lib64/libc.so.6
+)
+
+for path in "${candidates[@]}"; do
+    if [ -f "$path" ]; then
+        name="$(echo "${path#/}" | tr '/' '_')"
+        cp -L "$path" "$dest/$name"
+        echo "copied $path"
+    fi
+done
+
+for url in "$@"; do
+    name="$(basename "${url%%\?*}")"
+    curl -fsSL -o "$dest/$name" "$url"
+    echo "downloaded $url"
+done
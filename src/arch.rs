@@ -0,0 +1,104 @@
+//! Canonical architecture names shared by every analyzer
+//!
+//! `architecture` is always one of [`CANONICAL`], whatever the format calls
+//! it; the format's own name is kept in `architecture_raw`.
+
+use goblin::elf::header::*;
+use goblin::mach::constants::cputype::*;
+
+/// Not in goblin's table yet
+const EM_LOONGARCH: u16 = 258;
+
+pub const UNKNOWN: &str = "unknown";
+
+/// Every value `architecture` can take
+pub const CANONICAL: &[&str] = &[
+    "x86_64", "i386",
+    "aarch64", "arm64_32", "arm",
+    "riscv64", "riscv32",
+    "ppc64le", "ppc64", "ppc",
+    "mips64el", "mips64", "mipsel", "mips",
+    "s390x", "s390",
+    "sparc64", "sparc",
+    "loongarch64",
+    "parisc",
+    "wasm32",
+    UNKNOWN,
+];
+
+/// Alternative spellings accepted by `--arch`, compared case-insensitively
+const ALIASES: &[(&str, &str)] = &[
+    ("amd64", "x86_64"), ("x86-64", "x86_64"), ("x64", "x86_64"),
+    ("x86", "i386"), ("i486", "i386"), ("i586", "i386"), ("i686", "i386"), ("ia32", "i386"),
+    ("arm64", "aarch64"), ("armv8", "aarch64"), ("arm64e", "aarch64"),
+    ("armv7", "arm"), ("armhf", "arm"), ("armel", "arm"),
+    ("riscv", "riscv64"), ("risc-v", "riscv64"),
+    ("powerpc", "ppc"), ("powerpc64", "ppc64"), ("powerpc64le", "ppc64le"), ("ppc64el", "ppc64le"),
+    ("pa-risc", "parisc"), ("hppa", "parisc"),
+    ("loong64", "loongarch64"),
+    ("wasm", "wasm32"),
+];
+
+/// Canonical name for an ELF `e_machine`; class and byte order pick the variant
+pub fn from_elf(e_machine: u16, is_64: bool, is_little_endian: bool) -> &'static str {
+    match e_machine {
+        EM_X86_64 => "x86_64",
+        EM_386 => "i386",
+        EM_AARCH64 => "aarch64",
+        EM_ARM => "arm",
+        EM_RISCV if is_64 => "riscv64",
+        EM_RISCV => "riscv32",
+        EM_PPC64 if is_little_endian => "ppc64le",
+        EM_PPC64 => "ppc64",
+        EM_PPC => "ppc",
+        EM_MIPS => match (is_64, is_little_endian) {
+            (true, true) => "mips64el",
+            (true, false) => "mips64",
+            (false, true) => "mipsel",
+            (false, false) => "mips",
+        },
+        EM_S390 if is_64 => "s390x",
+        EM_S390 => "s390",
+        EM_SPARCV9 => "sparc64",
+        EM_SPARC => "sparc",
+        EM_LOONGARCH => "loongarch64",
+        EM_PARISC => "parisc",
+        _ => UNKNOWN,
+    }
+}
+
+/// Canonical name for a Mach-O `cputype`
+pub fn from_macho(cputype: u32) -> &'static str {
+    match cputype {
+        CPU_TYPE_X86_64 => "x86_64",
+        CPU_TYPE_X86 => "i386",
+        CPU_TYPE_ARM64 => "aarch64",
+        CPU_TYPE_ARM64_32 => "arm64_32",
+        CPU_TYPE_ARM => "arm",
+        CPU_TYPE_POWERPC64 => "ppc64",
+        CPU_TYPE_POWERPC => "ppc",
+        CPU_TYPE_SPARC => "sparc",
+        CPU_TYPE_HPPA => "parisc",
+        _ => UNKNOWN,
+    }
+}
+
+/// Canonical name for a canonical name or a common alias ("amd64", "ARM64")
+pub fn normalize(name: &str) -> Option<&'static str> {
+    let lower = name.to_ascii_lowercase();
+    CANONICAL.iter()
+        .find(|canonical| **canonical == lower)
+        .copied()
+        .or_else(|| {
+            ALIASES.iter()
+                .find(|(alias, _)| *alias == lower)
+                .map(|(_, canonical)| *canonical)
+        })
+}
+
+/// `--arch` value parser: accepts aliases, yields the canonical name
+pub fn parse_arch(value: &str) -> Result<String, String> {
+    normalize(value)
+        .map(str::to_string)
+        .ok_or_else(|| format!("unknown architecture '{}' (expected one of: {})", value, CANONICAL.join(", ")))
+}
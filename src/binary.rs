@@ -1,91 +1,617 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::fs;
-use anyhow::Result;
+use std::io::Read;
+use std::sync::Mutex;
+use anyhow::{Context, Result};
 use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc};
 
+use crate::arch;
+use crate::carve::CarvedImage;
 use crate::cli::Args;
-use crate::elf::ElfAnalyzer;
-use crate::macho::MachoAnalyzer;
+use crate::container::{self, ContainerFormat, EmbeddedFile};
+use crate::debuginfod::{self, Artifact, DebuginfodClient, DownloadCache};
+use crate::deep;
+use crate::deps::{self, ResolvedDependency};
+use crate::dwarf::{self, DwarfOrigin, FunctionSize};
+use crate::elf::{self, ElfAnalyzer, NoteInfo, PackageInfo};
+use crate::environment::Environment;
+use crate::fixtures::{FixtureConfig, FixtureSignal};
+use crate::hardening::HardeningReport;
+use crate::imports::ImportedSymbol;
+use crate::macho::{self, MachoAnalyzer, SliceInfo};
+use crate::plugin::Plugin;
+use crate::relative::ScanRoot;
+use crate::repro;
+use crate::rpath::{self, AuditRoot, RpathFinding};
+use crate::scan_errors::{MapFailed, NotABinary};
+use crate::signatures::{SignatureMatch, SignatureSet};
+use crate::suspicious;
+use crate::symbol_finder::{DebugCandidate, DsymUuidCache, Strategy};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Version of the `BinaryInfo` JSON layout, bumped on incompatible changes
+/// 2: `architecture` holds canonical names (see `arch::CANONICAL`)
+/// 3: `has_debug_info` is null when it can't be told
+pub const SCHEMA_VERSION: u32 = 3;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BinaryInfo {
+    pub schema_version: u32,
     pub file_path: PathBuf,
     pub file_size: u64,
     pub file_modified: DateTime<Utc>,
+    pub file_modified_epoch: i64,  // file_modified in seconds since the Unix epoch
     pub binary_type: String,  // "ELF" or "Mach-O"
-    pub architecture: String,
+    pub architecture: String,      // canonical, same across formats
+    pub architecture_raw: String,  // the format's own name ("AArch64", "ARM64")
     pub is_64bit: bool,
+    pub is_big_endian: bool,  // ELF EI_DATA, or a byte-swapped Mach-O magic
     pub is_stripped: bool,
-    pub has_debug_info: bool,
+    pub has_debug_info: Option<bool>,  // None without section names to look for
+    pub partial_analysis: bool,  // full parsing failed; only header fields are set
+    pub parse_error: Option<String>,
     
     // ELF specific
+    pub machine_code: Option<u16>,  // raw e_machine, also for unknown architectures
+    pub os_abi: Option<String>,  // EI_OSABI ("SYSV", "GNU/Linux", "FreeBSD")
+    pub abi_version: Option<u8>,  // EI_ABIVERSION, normally 0
+    pub elf_version: Option<u32>,  // e_version, 1 (EV_CURRENT) from every toolchain
+    pub abi_os: Option<String>,  // .note.ABI-tag OS ("Linux")
+    pub min_kernel_version: Option<String>,  // .note.ABI-tag version ("3.2.0"), the oldest kernel it runs on
     pub build_id: Option<String>,
+    pub build_id_type: Option<String>,  // sha1, md5, uuid, xxhash, sha256 or hex, from the id's length
+    pub go_build_id: Option<String>,
+    pub has_go_pclntab: bool,  // Go runtime symbol table: stack traces resolve even when stripped
+    pub go_function_count: Option<u64>,  // functions in the pclntab
+    pub has_minidebuginfo: bool,  // .gnu_debugdata (Fedora/RHEL): stripped, but functions still symbolize
+    pub minidebuginfo_symbols: Option<u64>,  // symbols in it, when it decompresses
+    pub package_info: Option<PackageInfo>,  // from .note.package
     pub gnu_debuglink: Option<String>,
+    pub soname: Option<String>,  // DT_SONAME, or a Mach-O dylib's install name
+    pub version_definitions: Vec<String>,  // ELF .gnu.version_d versions ("LIBFOO_1.0"), without the base entry
+    pub hash_style: Option<String>,  // sysv (.hash), gnu (.gnu.hash) or both; sysv alone means an old or --hash-style=sysv link
+    pub rpath: Vec<String>,
+    pub runpath: Vec<String>,
     pub debug_sections: Vec<String>,
+    pub is_debug_only: bool,  // separate debug file: DWARF, with the allocated sections emptied to NOBITS
+    pub section_names_unavailable: bool,  // ELF e_shstrndx is missing or bogus
+    pub has_dynamic_section: bool,  // PT_DYNAMIC: linked against shared libraries, or one itself
+    pub largest_load_bytes: Option<u64>,  // file bytes of the largest PT_LOAD segment
     
     // Mach-O specific
     pub uuid: Option<String>,
     pub dsym_bundle: Option<PathBuf>,
+    pub dyld_environment: Vec<String>,  // LC_DYLD_ENVIRONMENT entries ("DYLD_INSERT_LIBRARIES=...")
+    pub unknown_header_flags: u32,  // mach_header flag bits outside macho::KNOWN_HEADER_FLAGS; 0 for ELF
+    pub has_objc: bool,  // __objc_* sections (Objective-C runtime metadata)
+    pub has_swift: bool,  // __swift5_* sections (Swift runtime metadata)
+    pub fat_slices: Option<Vec<SliceInfo>>,  // universal binaries only
+    pub fat_padding_bytes: Option<u64>,  // alignment gaps before and between the slices
     
     // Common debug info
     pub debug_file_path: Option<PathBuf>,
+    pub debug_file_source: Option<Strategy>,  // symbol strategy that found debug_file_path
+    pub alternate_debug_files: Vec<DebugCandidate>,  // other local debug files found, in preference order
+    pub resolution_method: String,  // embedded, a symbol strategy, remote or none (see `BinaryInfo::resolution`)
+    pub external_paths: Vec<String>,  // path fields --relative-paths left absolute (outside the scan root)
     pub debuginfod_available: Option<bool>,
     pub debuginfod_url: Option<String>,
+    pub executable_available: Option<bool>,  // checked for debug-only files
+    pub executable_url: Option<String>,
+    pub skipped_reason: Option<String>,  // why remote checks didn't run ("circuit-open")
+    pub is_skeleton: bool,  // split DWARF: the full debug info is in .dwo files
+    pub dwo_names: Vec<String>,
     
     // Binary details
     pub entry_point: Option<String>,
+    pub entry_section: Option<String>,  // ELF section containing the entry point
     pub interpreter: Option<String>,
+    pub needed: Vec<String>,  // DT_NEEDED entries, or a Mach-O binary's LC_LOAD_DYLIB install names
+    pub libc: Option<String>,  // glibc, musl, bionic, libc (BSD), static, libSystem
+    pub isa_level: Option<String>,  // with --check-isa: x86-64, x86-64-v2, -v3 or -v4 from the GNU property note
     pub is_pie: bool,
     pub is_executable: bool,
     pub is_library: bool,
     
-    // Security features
+    // File mode bits (always false on non-Unix hosts)
+    pub setuid: bool,
+    pub setgid: bool,
+    
+    // Security features (flattened from `hardening` for compatibility)
     pub has_nx: bool,
     pub has_canary: bool,
     pub has_relro: bool,
     pub has_fortify: bool,
+    pub dangerous_imports: Vec<String>,  // gets, strcpy, system, exec*, ... (see hardening::DANGEROUS_FUNCTIONS)
+    pub imports: Option<Vec<ImportedSymbol>>,  // with --imports: imported functions by library, then name
+    pub hardening: HardeningReport,
+    pub rpath_findings: Vec<RpathFinding>,
+    
+    // Symbol names per mangling scheme (itanium, rust-v0, rust-legacy, swift, c)
+    pub mangling_stats: BTreeMap<String, u64>,
+    pub panic_strategy: Option<String>,  // Rust only: abort or unwind, from the panic runtime's symbols; null when unclear
+    
+    // ELF .symtab symbols by binding (null for Mach-O and without a .symtab)
+    pub global_symbol_count: Option<u64>,  // defined global and weak symbols: the exported surface
+    pub local_symbol_count: Option<u64>,  // leaving out section and file symbols
+    
+    // ELF relocations and GOT, for startup cost (null for Mach-O)
+    pub relocation_count: Option<u64>,
+    pub irelative_count: Option<u64>,  // IFUNC resolvers run at load time
+    pub plt_entries: Option<u64>,
+    pub got_bytes: Option<u64>,
+    
+    // ELF notes (-vv): every entry of the note sections and segments
+    pub notes: Option<Vec<NoteInfo>>,
+    pub stapsdt_probes: Option<u64>,  // SystemTap SDT probes (stapsdt notes)
+    
+    // ELF thread-local storage (PT_TLS)
+    pub has_tls: bool,
+    pub tls_size: u64,  // p_memsz: the per-thread block, .tdata plus .tbss
+    
+    // DWARF analysis (--dwarf)
+    pub function_count: Option<u64>,
+    pub largest_functions: Option<Vec<FunctionSize>>,
+    pub comp_dirs: Vec<String>,  // DW_AT_comp_dir of the compile units
+    pub producers: Vec<String>,  // DW_AT_producer ("GNU C17 12.2.0 -O2 ...")
+    pub source_files: Vec<String>,  // DW_AT_name of the compile units
+    pub dwarf_origin: Option<DwarfOrigin>,  // file the fields above were read from
+    
+    // ELF dependency resolution (--check-deps)
+    pub resolved_dependencies: Vec<ResolvedDependency>,
+    pub missing_dependencies: Vec<String>,
+    
+    // AppImage / .NET single-file bundle (payload listed with --scan-archives)
+    pub container_format: Option<ContainerFormat>,
+    pub payload_files: Option<u64>,
+    pub embedded_libraries: Vec<EmbeddedFile>,
+    
+    // ELF image found inside a larger file (--carve)
+    pub carved_from: Option<CarvedImage>,
+    
+    // Signature scan (--scan-signatures)
+    pub signature_matches: Vec<SignatureMatch>,
+    
+    // Test fixture heuristic (see `fixtures`)
+    pub likely_test_fixture: bool,
+    pub fixture_signals: Vec<FixtureSignal>,  // every signal that fired, with what triggered it
+    
+    // Non-fatal problems noticed while scanning
+    pub warnings: Vec<ScanWarning>,
 }
 
+/// Non-fatal problem attached to a scan result
+/// `code` is stable for scripting, `message` is for people
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanWarning {
+    pub code: String,
+    pub message: String,
+}
+
+impl ScanWarning {
+    pub fn new(code: &str, message: impl Into<String>) -> Self {
+        Self { code: code.to_string(), message: message.into() }
+    }
+}
+
+/// Suffix on `binary_type` for formats that are recognized but not analyzed
+const UNSUPPORTED_SUFFIX: &str = " (unsupported)";
+
 impl BinaryInfo {
+    /// Runs with elevated privileges when executed
+    pub fn is_privileged(&self) -> bool {
+        self.setuid || self.setgid
+    }
+    
+    /// Elevated privileges with weak hardening is a high-priority finding
+    pub fn is_privileged_unhardened(&self) -> bool {
+        self.is_privileged()
+            && self.is_executable
+            && (!self.is_pie || (self.binary_type == "ELF" && !self.has_relro))
+    }
+    
+    /// Worth a look in `--problems` mode: any warning or signature match,
+    /// plus failed hardening checks when `hardening` is set (`--security`);
+    /// a separate debug file has no code to harden
+    pub fn has_problems(&self, hardening: bool) -> bool {
+        !self.warnings.is_empty()
+            || !self.signature_matches.is_empty()
+            || (hardening && !self.is_debug_only && self.hardening.failures().next().is_some())
+    }
+    
+    /// Recognized format that symwalker doesn't analyze (--show-unsupported)
+    pub fn is_unsupported(&self) -> bool {
+        self.binary_type.ends_with(UNSUPPORTED_SUFFIX)
+    }
+    
     pub fn has_local_debug_symbols(&self) -> bool {
-        self.debug_file_path.is_some() || self.has_debug_info
+        self.debug_file_path.is_some() || self.has_debug_info == Some(true)
     }
     
     pub fn has_remote_debug_symbols(&self) -> bool {
         self.debuginfod_available.unwrap_or(false)
     }
+    
+    /// Bytes of universal-binary slices no current Apple OS runs
+    pub fn reclaimable_bytes(&self) -> u64 {
+        self.fat_slices.iter().flatten().filter(|slice| slice.obsolete).map(|slice| slice.size).sum()
+    }
+    
+    /// Where the symbols were found: `embedded`, the name of the strategy
+    /// that found the debug file, `remote`, or `none`
+    pub fn resolution(&self) -> &'static str {
+        if self.has_debug_info == Some(true) {
+            "embedded"
+        } else if let Some(source) = self.debug_file_source {
+            source.name()
+        } else if self.has_remote_debug_symbols() {
+            "remote"
+        } else {
+            "none"
+        }
+    }
+    
+    /// DEBUG (a separate debug file), EXE, LIB or BIN, as in the human
+    /// output's block headers
+    pub fn kind_label(&self) -> &'static str {
+        if self.is_debug_only {
+            "DEBUG"
+        } else if self.is_executable {
+            "EXE"
+        } else if self.is_library {
+            "LIB"
+        } else {
+            "BIN"
+        }
+    }
+    
+    /// Where symbols come from, in one word: header-only, debug,
+    /// local-debug, remote-debug, go-runtime-symbols (stripped, but the Go
+    /// pclntab names every function), stripped or symbols
+    pub fn debug_status(&self) -> &'static str {
+        if self.partial_analysis {
+            "header-only"
+        } else if self.has_debug_info == Some(true) {
+            "debug"
+        } else if self.has_local_debug_symbols() {
+            "local-debug"
+        } else if self.has_remote_debug_symbols() {
+            "remote-debug"
+        } else if self.is_stripped && self.has_go_pclntab {
+            "go-runtime-symbols"
+        } else if self.is_stripped && self.has_minidebuginfo {
+            "minidebuginfo"
+        } else if self.is_stripped {
+            "stripped"
+        } else {
+            "symbols"
+        }
+    }
+}
+
+/// State shared by every scanned binary over the lifetime of a run
+pub struct ScanContext {
+    pub signatures: Option<SignatureSet>,
+    pub dsym_cache: DsymUuidCache,
+    /// Shared by all remote lookups; `None` unless --check-remote/--download-remote/--prefetch
+    pub debuginfod: Option<DebuginfodClient>,
+    /// System library directories for --check-deps; empty otherwise
+    pub library_dirs: Vec<PathBuf>,
+    /// Ways of finding local debug files, from --symbol-strategy/--no-symbol-strategy
+    pub symbol_strategies: Vec<Strategy>,
+    /// Remote debug files fetched for --remote-dwarf
+    pub remote_debug_files: Option<DownloadCache>,
+    /// Root that output paths are relative to, with --relative-paths
+    pub scan_root: Option<ScanRoot>,
+    /// Analyzer for files no built-in parser recognizes, with --plugin
+    pub plugin: Option<Plugin>,
+    /// Environment variables the scan may use; none with --hermetic
+    pub environment: Environment,
+    /// Build-ids and UUIDs of the binaries reported so far, with --dedup-by-id
+    pub reported_ids: Option<Mutex<HashSet<String>>>,
+    /// Test fixture signals, from --fixture-config or the defaults
+    pub fixtures: FixtureConfig,
+}
+
+impl ScanContext {
+    pub fn new(args: &Args) -> Result<Self> {
+        let signatures = match args.scan_signatures {
+            Some(ref path) => Some(SignatureSet::load(path)?),
+            None => None,
+        };
+        
+        let environment = Environment::capture(args.hermetic);
+        
+        // Built once so bad --debuginfod-urls entries fail before scanning
+        let debuginfod = if args.check_remote || args.download_remote || args.download_executable || args.prefetch {
+            let max_failures = (!args.no_circuit_breaker).then_some(args.max_remote_failures);
+            let servers = if args.debuginfod_urls.is_empty() { environment.debuginfod_urls() } else { args.debuginfod_urls.clone() };
+            Some(DebuginfodClient::new(servers)?.with_circuit_breaker(max_failures))
+        } else {
+            None
+        };
+        
+        Ok(Self {
+            signatures,
+            dsym_cache: DsymUuidCache::default(),
+            debuginfod,
+            library_dirs: if args.check_deps { deps::system_dirs(args.sysroot.as_deref()) } else { Vec::new() },
+            symbol_strategies: args.symbol_strategies(),
+            remote_debug_files: if args.remote_dwarf { Some(DownloadCache::new()?) } else { None },
+            scan_root: if args.relative_paths { Some(ScanRoot::new(args.scan_root(&args.paths[0]))?) } else { None },
+            plugin: args.plugin.as_deref().map(Plugin::new).transpose()?,
+            environment,
+            reported_ids: args.dedup_by_id.then(Default::default),
+            fixtures: match args.fixture_config {
+                Some(ref path) => FixtureConfig::load(path)?,
+                None => FixtureConfig::default(),
+            },
+        })
+    }
+    
+    /// With --dedup-by-id, whether a binary with this build-id or UUID was
+    /// already reported
+    pub fn already_reported(&self, id: Option<&str>) -> bool {
+        match (&self.reported_ids, id) {
+            (Some(ids), Some(id)) => ids.lock().unwrap().contains(id),
+            _ => false,
+        }
+    }
+    
+    /// With --dedup-by-id, skip later binaries with this build-id or UUID
+    pub fn mark_reported(&self, id: Option<&str>) {
+        if let (Some(ids), Some(id)) = (&self.reported_ids, id) {
+            ids.lock().unwrap().insert(id.to_string());
+        }
+    }
+}
+
+/// Scan a binary, running the remote checks (`--check-remote`) and deep
+/// analyses (`--dwarf`) in this thread
+pub fn scan_binary(path: &Path, args: &Args, ctx: &ScanContext) -> Result<BinaryInfo> {
+    let mut info = scan_headers(path, args, ctx)?;
+    check_remote_all(std::slice::from_mut(&mut info), args, ctx);
+    deep::annotate(&mut info, args, ctx)?;
+    Ok(info)
 }
 
-pub fn scan_binary(path: &Path, args: &Args) -> Result<BinaryInfo> {
+/// Scan a binary without the remote checks and deep analyses, which
+/// `check_remote_all` and `deep::annotate_all` run in batches
+pub fn scan_headers(path: &Path, args: &Args, ctx: &ScanContext) -> Result<BinaryInfo> {
     // Read file metadata
     let metadata = fs::metadata(path)?;
-    let file_size = metadata.len();
-    let file_modified: DateTime<Utc> = metadata.modified()?.into();
     
     // Memory map the file for efficient parsing
     let file = fs::File::open(path)?;
-    let mmap = unsafe { memmap2::Mmap::map(&file)? };
+    let mmap = unsafe { memmap2::Mmap::map(&file) }.context(MapFailed)?;
+    
+    scan_data(path, &mmap, &metadata, args, ctx)
+}
+
+/// Scan `data`, the contents of `path` or an image carved out of the file
+/// `metadata` describes
+pub fn scan_data(path: &Path, data: &[u8], metadata: &fs::Metadata, args: &Args, ctx: &ScanContext) -> Result<BinaryInfo> {
+    let mut info = analyze_data(path, data, metadata, args, ctx)?;
+    info.resolution_method = info.resolution().to_string();
+    info.file_modified_epoch = info.file_modified.timestamp();
+    Ok(info)
+}
+
+fn analyze_data(path: &Path, data: &[u8], metadata: &fs::Metadata, args: &Args, ctx: &ScanContext) -> Result<BinaryInfo> {
+    let file_size = data.len() as u64;
+    let file_modified: DateTime<Utc> = metadata.modified()?.into();
     
     // Determine binary type and parse
-    let binary_type = detect_binary_type(&mmap)?;
+    let binary_type = match detect_binary_type(data) {
+        Ok(binary_type) => binary_type,
+        Err(e) => {
+            // Formats we don't know may be the --plugin's
+            if let Some(ref plugin) = ctx.plugin {
+                match plugin.analyze(path, file_size) {
+                    Ok(Some(mut info)) => {
+                        info.schema_version = SCHEMA_VERSION;
+                        info.file_path = path.to_path_buf();
+                        info.file_size = file_size;
+                        info.file_modified = file_modified;
+                        set_mode_bits(&mut info, metadata);
+                        return Ok(info);
+                    }
+                    Ok(None) => {}
+                    Err(e) => log::warn!("{}: plugin: {:#}", path.display(), e),
+                }
+            }
+            
+            // Keep recognizable binaries in the inventory even if we can't analyze them
+            let format = if args.show_unsupported { unsupported_format(data) } else { None };
+            let Some((format, is_64bit, architecture)) = format else {
+                return Err(e);
+            };
+            
+            let mut info = BinaryInfo {
+                file_path: path.to_path_buf(),
+                file_size,
+                file_modified,
+                binary_type: format!("{}{}", format, UNSUPPORTED_SUFFIX),
+                architecture: arch::normalize(architecture).unwrap_or(arch::UNKNOWN).to_string(),
+                architecture_raw: architecture.to_string(),
+                is_64bit,
+                warnings: vec![ScanWarning::new(
+                    "unsupported-format",
+                    format!("{} binaries are recognized but not analyzed", format),
+                )],
+                ..Default::default()
+            };
+            info.schema_version = SCHEMA_VERSION;
+            set_mode_bits(&mut info, metadata);
+            return Ok(info);
+        }
+    };
+    
+    let analyzed = match binary_type.as_str() {
+        "ELF" => ElfAnalyzer::new(path, data, file_size, file_modified)
+            .and_then(|analyzer| analyzer.with_max_note_bytes(args.max_note_bytes).analyze(args, ctx)),
+        "Mach-O" => MachoAnalyzer::new(path, data, file_size, file_modified)
+            .and_then(|analyzer| analyzer.analyze(args, ctx)),
+        _ => anyhow::bail!("Unsupported binary type"),
+    };
     
-    match binary_type.as_str() {
-        "ELF" => {
-            let analyzer = ElfAnalyzer::new(path, &mmap, file_size, file_modified)?;
-            analyzer.analyze(args)
+    let mut info = match analyzed {
+        Ok(info) => info,
+        // The magic matched, so keep whatever the header says unless --strict-parse
+        Err(e) if !args.strict_parse => {
+            let header = match binary_type.as_str() {
+                "ELF" => elf::header_only(data),
+                _ => macho::header_only(data),
+            };
+            let Some(mut info) = header else {
+                return Err(e);
+            };
+            
+            info.file_path = path.to_path_buf();
+            info.file_size = file_size;
+            info.file_modified = file_modified;
+            info.partial_analysis = true;
+            info.parse_error = Some(format!("{:#}", e));
+            info.warnings.push(ScanWarning::new("partial-analysis", format!("header-only analysis: {:#}", e)));
+            info.schema_version = SCHEMA_VERSION;
+            set_mode_bits(&mut info, metadata);
+            return Ok(info);
         }
-        "Mach-O" => {
-            let analyzer = MachoAnalyzer::new(path, &mmap, file_size, file_modified)?;
-            analyzer.analyze(args)
+        Err(e) => return Err(e),
+    };
+    
+    info.schema_version = SCHEMA_VERSION;
+    set_mode_bits(&mut info, metadata);
+    audit_search_paths(&mut info, args);
+    if info.is_privileged_unhardened() {
+        info.warnings.push(ScanWarning::new("privileged-unhardened", "privileged binary without PIE/RELRO"));
+    }
+    info.warnings.extend(suspicious::extension_warning(path, &binary_type));
+    if args.repro_check {
+        info.warnings.extend(repro::check(data));
+    }
+    
+    if info.has_debug_info == Some(true) {
+        if let Ok(Some(dwo_names)) = dwarf::dwo_references(data) {
+            info.is_skeleton = true;
+            info.dwo_names = dwo_names;
         }
-        _ => anyhow::bail!("Unsupported binary type"),
     }
+    
+    if let Some(ref signatures) = ctx.signatures {
+        info.signature_matches = signatures.scan(data);
+    }
+    
+    if let Some(container) = container::detect(data) {
+        inspect_container(&mut info, data, &container, args);
+    }
+    
+    Ok(info)
+}
+
+/// Ask the debuginfod servers about every analyzed ELF binary, each
+/// build-id once and `--remote-concurrency` at a time; debug-only files are
+/// also checked for their executable. A binary without a build-id is
+/// unavailable, and one the circuit breaker skipped has no answer
+pub fn check_remote_all(binaries: &mut [BinaryInfo], args: &Args, ctx: &ScanContext) {
+    let Some(ref client) = ctx.debuginfod else {
+        return;
+    };
+    if !args.check_remote {
+        return;
+    }
+    
+    let checked = |info: &BinaryInfo| info.binary_type == "ELF" && !info.partial_analysis;
+    let unique_ids = |debug_only: bool| -> Vec<String> {
+        let mut ids: Vec<String> = binaries.iter()
+            .filter(|info| checked(info) && (!debug_only || info.is_debug_only))
+            .filter_map(|info| info.build_id.clone())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    };
+    let debuginfo = client.check_artifacts(&unique_ids(false), Artifact::Debuginfo, args.remote_concurrency);
+    let executables = client.check_artifacts(&unique_ids(true), Artifact::Executable, args.remote_concurrency);
+    
+    for info in binaries.iter_mut().filter(|info| checked(info)) {
+        let answer = |answers: &HashMap<String, (bool, Option<String>)>| match info.build_id {
+            Some(ref id) => answers.get(id).map(|(available, url)| (Some(*available), url.clone())).unwrap_or((None, None)),
+            None => (Some(false), None),
+        };
+        (info.debuginfod_available, info.debuginfod_url) = answer(&debuginfo);
+        // A debug-only file is useless without its binary
+        if info.is_debug_only {
+            (info.executable_available, info.executable_url) = answer(&executables);
+        }
+        // Checks that ran always have an answer; only the circuit breaker skips them
+        info.skipped_reason = info.debuginfod_available.is_none().then(|| debuginfod::SKIPPED_CIRCUIT_OPEN.to_string());
+        info.resolution_method = info.resolution().to_string();
+    }
+}
+
+/// Record the container format; the payload is only read with --scan-archives
+fn inspect_container(info: &mut BinaryInfo, data: &[u8], container: &container::Container, args: &Args) {
+    info.container_format = Some(container.format);
+    if !args.scan_archives {
+        return;
+    }
+    
+    match container::read_payload(data, container) {
+        Ok(payload) => {
+            info.payload_files = Some(payload.file_count);
+            info.embedded_libraries = payload.native_libraries;
+        }
+        Err(e) => info.warnings.push(ScanWarning::new(
+            "unreadable-payload",
+            format!("{} payload: {}", container.format.as_str(), e),
+        )),
+    }
+}
+
+/// Library search path hygiene, resolved against the scan root
+fn audit_search_paths(info: &mut BinaryInfo, args: &Args) {
+    let entries: Vec<String> = info.rpath.iter().chain(&info.runpath).cloned().collect();
+    if entries.is_empty() {
+        return;
+    }
+    
+    let root = AuditRoot {
+        binary: &info.file_path,
+        prefix: args.scan_root(&info.file_path),
+        sysroot: args.sysroot.as_deref(),
+    };
+    info.rpath_findings = rpath::audit(&entries, &root);
+    
+    if !info.rpath_findings.is_empty() {
+        info.warnings.push(ScanWarning::new(
+            "insecure-rpath",
+            format!("{} insecure RPATH/RUNPATH entr{}", info.rpath_findings.len(),
+                if info.rpath_findings.len() == 1 { "y" } else { "ies" }),
+        ));
+    }
+}
+
+#[cfg(unix)]
+fn set_mode_bits(info: &mut BinaryInfo, metadata: &fs::Metadata) {
+    use std::os::unix::fs::MetadataExt;
+    info.setuid = metadata.mode() & 0o4000 != 0;
+    info.setgid = metadata.mode() & 0o2000 != 0;
+}
+
+#[cfg(not(unix))]
+fn set_mode_bits(_info: &mut BinaryInfo, _metadata: &fs::Metadata) {}
+
+/// Whether `path` starts with ELF or Mach-O magic
+pub fn has_binary_magic(path: &Path) -> bool {
+    let mut magic = [0u8; 4];
+    fs::File::open(path).and_then(|mut file| file.read_exact(&mut magic)).is_ok() && detect_binary_type(&magic).is_ok()
 }
 
 fn detect_binary_type(data: &[u8]) -> Result<String> {
     if data.len() < 4 {
-        anyhow::bail!("File too small");
+        return Err(NotABinary("File too small").into());
     }
     
     // Check for ELF magic
@@ -103,6 +629,70 @@ fn detect_binary_type(data: &[u8]) -> Result<String> {
         _ => {}
     }
     
-    anyhow::bail!("Unknown binary format")
+    Err(NotABinary("Unknown binary format").into())
+}
+
+/// Recognize binary formats we can't analyze: (format, is_64bit, architecture)
+fn unsupported_format(data: &[u8]) -> Option<(&'static str, bool, &'static str)> {
+    if data.len() < 4 {
+        return None;
+    }
+    
+    let magic = u16::from_be_bytes([data[0], data[1]]);
+    let secondary = u16::from_be_bytes([data[2], data[3]]);
+    
+    // AIX XCOFF
+    match magic {
+        0x01df => return Some(("XCOFF", false, "PowerPC")),
+        0x01f7 => return Some(("XCOFF", true, "PowerPC64")),
+        _ => {}
+    }
+    
+    // HP-UX SOM: PA-RISC system id followed by an executable/library a_magic
+    if matches!(magic, 0x020b | 0x0210 | 0x0214)
+        && matches!(secondary, 0x0106 | 0x0107 | 0x0108 | 0x010b | 0x010d | 0x010e)
+    {
+        return Some(("SOM", false, "PA-RISC"));
+    }
+    
+    // Windows PE: MZ stub pointing at a PE signature
+    if &data[0..2] == b"MZ" && data.len() >= 0x40 {
+        let pe_offset = u32::from_le_bytes([data[0x3c], data[0x3d], data[0x3e], data[0x3f]]) as usize;
+        if data.get(pe_offset..pe_offset + 4) == Some(b"PE\0\0") {
+            // Optional header magic: 0x20b is PE32+
+            let optional_magic = data.get(pe_offset + 24..pe_offset + 26)
+                .map(|m| u16::from_le_bytes([m[0], m[1]]));
+            return Some(("PE", optional_magic == Some(0x20b), "Unknown"));
+        }
+    }
+    
+    if &data[0..4] == b"\0asm" {
+        return Some(("WebAssembly", false, "wasm"));
+    }
+    
+    None
+}
+
+/// Universal Mach-O files can't be parsed directly; use the first slice
+pub fn thin_slice(data: &[u8]) -> Result<&[u8]> {
+    use object::read::macho::{MachOFatFile32, MachOFatFile64, FatArch};
+
+    match object::FileKind::parse(data)? {
+        object::FileKind::MachOFat32 => {
+            let fat = MachOFatFile32::parse(data)?;
+            match fat.arches().first() {
+                Some(arch) => Ok(arch.data(data)?),
+                None => anyhow::bail!("Empty fat binary"),
+            }
+        }
+        object::FileKind::MachOFat64 => {
+            let fat = MachOFatFile64::parse(data)?;
+            match fat.arches().first() {
+                Some(arch) => Ok(arch.data(data)?),
+                None => anyhow::bail!("Empty fat binary"),
+            }
+        }
+        _ => Ok(data),
+    }
 }
 
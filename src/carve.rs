@@ -0,0 +1,134 @@
+//! ELF images embedded in firmware blobs (`--carve`)
+//!
+//! Firmware images concatenate or wrap ELF files at arbitrary offsets. Every
+//! interior `\x7fELF` is a candidate; only headers that make sense and parse
+//! in full within the rest of the file are kept, and candidates inside an
+//! image already found (string tables, nested copies) are skipped.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use goblin::elf::{Elf, header::*, section_header::SHT_NOBITS};
+use serde::{Serialize, Deserialize};
+
+use crate::arch;
+use crate::binary::{self, BinaryInfo, ScanContext};
+use crate::cli::Args;
+
+/// Default for `--max-file-size`
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 1 << 30;
+
+/// Most program headers a carved image may claim; real files have a few dozen
+const MAX_PROGRAM_HEADERS: u16 = 256;
+
+/// Where a carved image sits in the file it was found in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CarvedImage {
+    pub container: PathBuf,
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl CarvedImage {
+    /// `firmware.bin@0x4000`, the image's path in reports
+    pub fn path(&self) -> PathBuf {
+        let mut path = self.container.clone().into_os_string();
+        path.push(format!("@0x{:x}", self.offset));
+        path.into()
+    }
+
+    /// The image's bytes within the container's `data`
+    pub fn slice<'a>(&self, data: &'a [u8]) -> Option<&'a [u8]> {
+        let start = usize::try_from(self.offset).ok()?;
+        let end = start.checked_add(usize::try_from(self.size).ok()?)?;
+        data.get(start..end)
+    }
+}
+
+/// Scan the ELF images embedded in `path`; files over `--max-file-size`
+/// aren't searched
+pub fn scan(path: &Path, args: &Args, ctx: &ScanContext) -> Result<Vec<BinaryInfo>> {
+    let metadata = fs::metadata(path)?;
+    if metadata.len() > args.max_file_size {
+        return Ok(Vec::new());
+    }
+    let file = fs::File::open(path)?;
+    let mmap = unsafe { memmap2::Mmap::map(&file)? };
+
+    Ok(find(&mmap).into_iter()
+        .filter_map(|(offset, size)| {
+            let carved = CarvedImage { container: path.to_path_buf(), offset, size };
+            let mut info = binary::scan_data(&carved.path(), carved.slice(&mmap)?, &metadata, args, ctx).ok()?;
+            info.carved_from = Some(carved);
+            Some(info)
+        })
+        .collect())
+}
+
+/// (offset, size) of the ELF images in `data` past its start; one at offset
+/// 0 is the file itself, which the normal scan reports
+pub fn find(data: &[u8]) -> Vec<(u64, u64)> {
+    let mut images: Vec<(usize, usize)> = Vec::new();
+    for offset in memchr::memmem::find_iter(data, b"\x7fELF") {
+        if images.last().is_some_and(|&(start, size)| offset < start + size) {
+            continue;
+        }
+        if let Some(size) = image_size(&data[offset..]) {
+            images.push((offset, size));
+        }
+    }
+    images.into_iter()
+        .filter(|&(offset, _)| offset > 0)
+        .map(|(offset, size)| (offset as u64, size as u64))
+        .collect()
+}
+
+/// Size of the ELF image at the start of `data`: the end of its furthest
+/// header table, segment or section; None when the header isn't plausible
+/// or the image doesn't parse within `data`
+fn image_size(data: &[u8]) -> Option<usize> {
+    let header = Elf::parse_header(data).ok()?;
+    let is_64 = match header.e_ident[EI_CLASS] {
+        ELFCLASS32 => false,
+        ELFCLASS64 => true,
+        _ => return None,
+    };
+    let is_little_endian = match header.e_ident[EI_DATA] {
+        ELFDATA2LSB => true,
+        ELFDATA2MSB => false,
+        _ => return None,
+    };
+    let (ehsize, phentsize, shentsize) = if is_64 { (64, 56, 64) } else { (52, 32, 40) };
+    let plausible = header.e_ident[EI_VERSION] == EV_CURRENT
+        && header.e_version == EV_CURRENT as u32
+        && matches!(header.e_type, ET_REL | ET_EXEC | ET_DYN | ET_CORE)
+        && arch::from_elf(header.e_machine, is_64, is_little_endian) != arch::UNKNOWN
+        && header.e_ehsize == ehsize
+        && (header.e_phnum == 0 || header.e_phentsize == phentsize)
+        && (header.e_shnum == 0 || header.e_shentsize == shentsize)
+        && header.e_phnum <= MAX_PROGRAM_HEADERS;
+    if !plausible {
+        return None;
+    }
+
+    let elf = Elf::parse(data).ok()?;
+    let table_end = |offset: u64, count: u16, entry_size: u16| match count {
+        0 => Some(0),
+        _ => offset.checked_add(count as u64 * entry_size as u64),
+    };
+    let segments = elf.program_headers.iter()
+        .map(|ph| ph.p_offset.checked_add(ph.p_filesz));
+    let sections = elf.section_headers.iter()
+        .filter(|sh| sh.sh_type != SHT_NOBITS)
+        .map(|sh| sh.sh_offset.checked_add(sh.sh_size));
+    let end = [
+        Some(ehsize as u64),
+        table_end(header.e_phoff, header.e_phnum, phentsize),
+        table_end(header.e_shoff, header.e_shnum, shentsize),
+    ]
+        .into_iter()
+        .chain(segments)
+        .chain(sections)
+        .try_fold(0, |end: u64, item| Some(end.max(item?)))?;
+    usize::try_from(end).ok().filter(|&end| end <= data.len())
+}
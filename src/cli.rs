@@ -1,13 +1,42 @@
-use clap::Parser;
-use std::path::PathBuf;
-use anyhow::Result;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use anyhow::{Context, Result};
 use walkdir::WalkDir;
+use globset::{Glob, GlobBuilder, GlobMatcher};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 use colored::*;
 use std::fs;
+use std::io::{BufWriter, Read, Write};
 use std::time::Instant;
 
-use crate::binary::{BinaryInfo, scan_binary};
-use crate::output::{OutputFormatter, HumanFormatter, JsonFormatter};
+use crate::arch;
+use crate::binary::{self, BinaryInfo, ScanContext, scan_binary, scan_headers};
+use crate::carve::{self, CarvedImage};
+use crate::cluster;
+use crate::conflicts::{self, ConflictingLibrary};
+use crate::debuginfod::{self, DebuginfodClient};
+use crate::deep::{self, DeepStats};
+use crate::elf::{self, ElfAnalyzer};
+use crate::fixtures;
+use crate::graph::{self, GraphNode};
+use crate::hints::{self, RunOptions};
+use crate::html::HtmlFormatter;
+use crate::lock::{self, OutputLock};
+use crate::logging::LogLevel;
+use crate::manifest::{self, Artifact, Journal, JournalEntry};
+use crate::orphans::{self, StoreOrphans};
+use crate::prefetch::{self, PrefetchReport};
+use crate::scan_errors::{self, ScanError};
+use crate::self_test;
+use crate::source_dirs;
+use crate::suspicious::{self, SuspiciousFile};
+use crate::symbol_finder::Strategy;
+use crate::symbolicate;
+use crate::output::{self, FilteredReport, OutputFormatter, HumanFormatter, JsonFormatter, OverflowPolicy, ReportSink, StoreCoverage, Summary, TimeFormat, is_temp_name, temp_path, write_atomic, write_counts};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -15,16 +44,21 @@ use crate::output::{OutputFormatter, HumanFormatter, JsonFormatter};
     version,
     about = "Advanced ELF/Mach-O binary scanner with intelligent debug symbol detection",
     long_about = "Recursively scans directories for ELF and Mach-O binaries, analyzing debug symbols,\n\
-                  build IDs, dSYM bundles, and providing intelligent heuristics for symbol discovery."
+                  build IDs, dSYM bundles, and providing intelligent heuristics for symbol discovery.",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
 )]
 pub struct Args {
-    /// Directory to scan for binaries
-    #[arg(value_name = "DIRECTORY")]
-    pub directory: PathBuf,
+    #[command(subcommand)]
+    pub command: Option<Command>,
 
-    /// Show detailed information about each binary
-    #[arg(short, long)]
-    pub verbose: bool,
+    /// Directories to scan for binaries, and binaries to scan on their own (the binary itself with --reverse-lookup)
+    #[arg(value_name = "PATH", env = "SYMWALKER_DIR", required = true)]
+    pub paths: Vec<PathBuf>,
+
+    /// Show detailed information about each binary (-vv for even more)
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
 
     /// Only show binaries with local debug symbols
     #[arg(long)]
@@ -34,10 +68,26 @@ pub struct Args {
     #[arg(long)]
     pub remote_only: bool,
 
+    /// Only show binaries for these architectures (comma-separated; aliases like amd64, arm64 accepted)
+    #[arg(long, value_name = "ARCH", value_delimiter = ',', value_parser = arch::parse_arch)]
+    pub arch: Vec<String>,
+
     /// Check if remote symbols exist via debuginfod
     #[arg(long)]
     pub check_remote: bool,
 
+    /// Debuginfod lookups in flight at once with --check-remote
+    #[arg(long, value_name = "N", default_value_t = debuginfod::DEFAULT_CONCURRENCY, requires = "check_remote")]
+    pub remote_concurrency: usize,
+
+    /// Report the share of scanned binaries whose debug info the debuginfod servers have, and list the rest
+    #[arg(long, requires = "check_remote")]
+    pub compare_to_store: bool,
+
+    /// List the debug files in a symbol store that no scanned binary's build-id or UUID refers to, with their total size
+    #[arg(long, value_name = "DIR")]
+    pub symbol_store: Option<PathBuf>,
+
     /// Output directory for copying binaries and debug symbols
     #[arg(short, long, value_name = "DIR")]
     pub output: Option<PathBuf>,
@@ -50,164 +100,1387 @@ pub struct Args {
     #[arg(long, requires = "output")]
     pub download_remote: bool,
 
+    /// Verify downloaded debug files match the requested build-id, deleting them otherwise
+    #[arg(long, requires = "download_remote")]
+    pub verify_downloads: bool,
+
+    /// Download the executable for debug-only files whose binary wasn't found in the scan (requires --output)
+    #[arg(long, requires = "output")]
+    pub download_executable: bool,
+
+    /// Download the debug files of every scanned binary with a build-id into the debuginfod client cache, for working offline
+    #[arg(long)]
+    pub prefetch: bool,
+
+    /// Prefetch each binary's executable too
+    #[arg(long, requires = "prefetch")]
+    pub prefetch_executables: bool,
+
+    /// Cache to prefetch into (default: $DEBUGINFOD_CACHE_PATH, else debuginfod_client under $XDG_CACHE_HOME or ~/.cache)
+    #[arg(long, value_name = "DIR", requires = "prefetch")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Downloads running at once with --prefetch
+    #[arg(long, value_name = "N", default_value_t = prefetch::DEFAULT_JOBS, requires = "prefetch")]
+    pub prefetch_jobs: usize,
+
     /// Overwrite existing files in output directory
     #[arg(short, long)]
     pub force: bool,
 
+    /// Take over a stale --output lock left by a run that died (its process is gone, or it is over a day old)
+    #[arg(long, requires = "output")]
+    pub force_lock: bool,
+
+    /// Continue an interrupted --output run, skipping binaries it completed whose files are intact
+    #[arg(long, requires = "output")]
+    pub resume: bool,
+
     /// Output results as JSON
     #[arg(long)]
     pub json: bool,
 
-    /// Maximum recursion depth
+    /// How the human report shows modification times (JSON always has RFC 3339 plus file_modified_epoch)
+    #[arg(long, value_enum, default_value_t = TimeFormat::Utc)]
+    pub time_format: TimeFormat,
+
+    /// Leave out the next-step hints after the summary
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Skip the --output copy phase when stdout's reader goes away early (e.g. `| head`)
+    #[arg(long, requires = "output")]
+    pub skip_output_on_broken_pipe: bool,
+
+    /// Also write the results as a JSON array to this file (atomically replaced)
+    #[arg(long, value_name = "PATH")]
+    pub json_array_file: Option<PathBuf>,
+
+    /// Also write a self-contained HTML report to this file (atomically replaced)
+    #[arg(long, value_name = "PATH")]
+    pub html_report: Option<PathBuf>,
+
+    /// Also write the library dependency graph as Graphviz DOT to this file (atomically replaced)
+    #[arg(long, value_name = "PATH")]
+    pub graph: Option<PathBuf>,
+
+    /// Cap the size of the JSON written to stdout (see --overflow)
+    #[arg(long, value_name = "N", requires = "json")]
+    pub max_output_bytes: Option<u64>,
+
+    /// What to do when the JSON output would exceed --max-output-bytes
+    #[arg(long, value_enum, default_value_t = OverflowPolicy::TruncateList, requires = "max_output_bytes")]
+    pub overflow: OverflowPolicy,
+
+    /// Report paths relative to the scan root, recorded once in the JSON and manifest; paths outside it stay absolute
+    #[arg(long)]
+    pub relative_paths: bool,
+
+    /// Check the environment (debuginfod servers, debug directories, permissions, locale) instead of scanning
+    #[arg(long)]
+    pub self_test: bool,
+
+    /// Leave out these --self-test checks (comma-separated)
+    #[arg(long, value_name = "CHECK", value_delimiter = ',', requires = "self_test", value_parser = clap::builder::PossibleValuesParser::new(self_test::CHECKS))]
+    pub skip_check: Vec<String>,
+
+    /// Seconds each --self-test check may take before it counts as failed
+    #[arg(long, value_name = "SECS", default_value_t = 5, requires = "self_test")]
+    pub self_test_timeout: u64,
+
+    /// Maximum recursion depth (0 scans only the files directly in each PATH)
     #[arg(long, value_name = "N")]
     pub max_depth: Option<usize>,
 
+    /// Walk directories in sorted order so repeated runs over an unchanged tree give identical output
+    #[arg(long)]
+    pub deterministic: bool,
+
+    /// Skip paths matching GLOB, relative to the PATH walked (or names at any depth, without a `/`); a matching directory isn't descended into (repeatable)
+    #[arg(long, value_name = "GLOB", value_parser = parse_glob)]
+    pub exclude: Vec<Glob>,
+
+    /// Only analyze files matching GLOB, matched like --exclude (repeatable; --exclude wins)
+    #[arg(long, value_name = "GLOB", value_parser = parse_glob)]
+    pub include: Vec<Glob>,
+
     /// Follow symbolic links
     #[arg(long)]
     pub follow_symlinks: bool,
 
+    /// Most bytes of each ELF note section/segment searched for build-ids
+    #[arg(long, value_name = "N", default_value_t = elf::DEFAULT_MAX_NOTE_BYTES)]
+    pub max_note_bytes: usize,
+
+    /// Drop ELF/Mach-O files that fail to parse instead of reporting their header fields
+    #[arg(long)]
+    pub strict_parse: bool,
+
     /// Show stripped binaries (binaries without debug info)
     #[arg(long)]
     pub show_stripped: bool,
 
+    /// Only list binaries with warnings, signature matches or (with --security) failed hardening checks
+    #[arg(long)]
+    pub problems: bool,
+
+    /// Report only the first binary scanned for each build-id (UUID for Mach-O); later copies are skipped and counted
+    #[arg(long)]
+    pub dedup_by_id: bool,
+
+    /// Report recognized but unanalyzed formats (XCOFF, SOM, PE, WebAssembly)
+    #[arg(long)]
+    pub show_unsupported: bool,
+
     /// Custom debuginfod server URLs (comma-separated)
     #[arg(long, value_name = "URLS", value_delimiter = ',')]
     pub debuginfod_urls: Vec<String>,
 
+    /// Ignore environment-derived configuration (DEBUGINFOD_URLS, HOME, SYMWALKER_DIR, the debuginfod cache variables), so identical flags scan identically on any machine
+    #[arg(long)]
+    pub hermetic: bool,
+
+    /// Record which environment variables that change the scan were set (values redacted)
+    #[arg(long)]
+    pub record_env: bool,
+
+    /// Include the variables' values in the --record-env list
+    #[arg(long, requires = "record_env")]
+    pub record_env_values: bool,
+
+    /// Stop remote checks after this many consecutive lookups no server answered
+    #[arg(long, value_name = "N", default_value_t = debuginfod::DEFAULT_MAX_FAILURES)]
+    pub max_remote_failures: usize,
+
+    /// Keep issuing remote checks however many fail
+    #[arg(long)]
+    pub no_circuit_breaker: bool,
+
     /// Check for dSYM bundles in standard macOS locations
     #[arg(long)]
     pub check_dsym: bool,
 
+    /// Follow symlinks when searching DerivedData for dSYM bundles (with --check-dsym)
+    #[arg(long)]
+    pub include_symlinked_dsyms: bool,
+
+    /// Only look for local debug files this way (repeatable; default: all)
+    #[arg(long, value_name = "STRATEGY")]
+    pub symbol_strategy: Vec<Strategy>,
+
+    /// Never look for local debug files this way (repeatable)
+    #[arg(long, value_name = "STRATEGY")]
+    pub no_symbol_strategy: Vec<Strategy>,
+
+    /// Take the debug file these strategies find over the others' (comma-separated, best first; default: build-id, debuglink, path-mirror, adjacent)
+    #[arg(long, value_name = "STRATEGY", value_delimiter = ',')]
+    pub prefer_symbols: Vec<Strategy>,
+
+    /// Give up looking for a binary's local debug files after this many seconds (separate from the debuginfod timeout)
+    #[arg(long, value_name = "SECONDS")]
+    pub resolve_timeout: Option<u64>,
+
+    /// Root filesystem the scanned binaries belong to, for resolving absolute
+    /// RPATH/RUNPATH entries and finding its /usr/lib/debug files (e.g. an
+    /// extracted container image)
+    #[arg(long, value_name = "DIR")]
+    pub sysroot: Option<PathBuf>,
+
+    /// Resolve ELF DT_NEEDED libraries via RPATH/RUNPATH and the system library paths
+    #[arg(long)]
+    pub check_deps: bool,
+
+    /// List the payload of AppImages and .NET single-file bundles and report embedded native libraries
+    #[arg(long)]
+    pub scan_archives: bool,
+
+    /// Executable asked to analyze files no built-in parser recognizes; it answers with BinaryInfo JSON (see the plugin protocol in the README)
+    #[arg(long, value_name = "PATH")]
+    pub plugin: Option<PathBuf>,
+
+    /// Also search every file for ELF images embedded past its start (firmware blobs), reported as FILE@0xOFFSET
+    #[arg(long)]
+    pub carve: bool,
+
+    /// Largest file --carve searches for embedded ELF images
+    #[arg(long, value_name = "BYTES", default_value_t = carve::DEFAULT_MAX_FILE_SIZE)]
+    pub max_file_size: u64,
+
     /// Analyze binary security features (NX, PIE, RELRO, etc.)
     #[arg(long)]
     pub security: bool,
+
+    /// List imported functions by providing library, for sandbox and seccomp planning (counts shown; the list with -vv)
+    #[arg(long)]
+    pub imports: bool,
+
+    /// Read the x86-64 microarchitecture level (x86-64-v2, -v3, -v4) from GNU property notes
+    #[arg(long)]
+    pub check_isa: bool,
+
+    /// Flag likely non-reproducible builds as warnings: absolute DW_AT_comp_dir, __DATE__/__TIME__ strings, build-host paths in the DWARF strings
+    #[arg(long)]
+    pub repro_check: bool,
+
+    /// Analyze DWARF debug info (function count, largest functions)
+    #[arg(long)]
+    pub dwarf: bool,
+
+    /// With --dwarf, download debug info only available remotely to a temporary file and analyze it
+    #[arg(long, requires = "dwarf", requires = "check_remote")]
+    pub remote_dwarf: bool,
+
+    /// Resolve a hex address in the binary given as PATH to function+offset (file:line)
+    #[arg(long, value_name = "ADDRESS", value_parser = symbolicate::parse_address)]
+    pub reverse_lookup: Option<u64>,
+
+    /// Search binaries for patterns from a TOML signature file
+    #[arg(long, value_name = "FILE")]
+    pub scan_signatures: Option<PathBuf>,
+
+    /// Tune the test fixture heuristic: signals, directory names, size limits (TOML)
+    #[arg(long, value_name = "FILE")]
+    pub fixture_config: Option<PathBuf>,
+
+    /// Let --fail-if conditions hold for likely test fixtures too
+    #[arg(long)]
+    pub include_fixtures: bool,
+
+    /// Print scan statistics (cache effectiveness, timings) to stderr
+    #[arg(long)]
+    pub stats: bool,
+
+    /// Log what the scan does (paths probed, servers tried, files skipped) to stderr at LEVEL; overrides RUST_LOG
+    #[arg(long, value_name = "LEVEL")]
+    pub log_level: Option<LogLevel>,
+
+    /// Print binary counts grouped by FIELD instead of the per-binary listing
+    #[arg(long, value_name = "FIELD")]
+    pub count_by: Option<CountField>,
+
+    /// Sort the human report by FIELD, under a header per value (not JSON)
+    #[arg(long, value_name = "FIELD")]
+    pub group_output_by: Option<CountField>,
+
+    /// Report binaries dropped by a filter and which filter dropped them
+    #[arg(long)]
+    pub explain_filtered: bool,
+
+    /// Most filtered binaries to list with --explain-filtered (counts stay complete)
+    #[arg(long, value_name = "N", default_value_t = 50)]
+    pub explain_limit: usize,
+
+    /// Report a random sample of N binaries from all that pass the filters
+    #[arg(long, value_name = "N")]
+    pub sample: Option<usize>,
+
+    /// Seed for --sample, so the same tree yields the same sample (random and printed if not given)
+    #[arg(long, value_name = "SEED", requires = "sample")]
+    pub seed: Option<u64>,
+
+    /// Worker threads for deep analyses such as --dwarf (default: one per CPU)
+    #[arg(long, value_name = "N")]
+    pub deep_jobs: Option<usize>,
+
+    /// Memory the deep analyses running at once may need, estimated from their debug section sizes
+    #[arg(long, value_name = "BYTES", default_value_t = deep::DEFAULT_MEMORY_BYTES)]
+    pub deep_memory_bytes: u64,
+
+    /// Group reported binaries built from the same sources, listing the shared signals
+    #[arg(long)]
+    pub cluster: bool,
+
+    /// With --dwarf, list the distinct source roots (DW_AT_comp_dir) of the reported binaries, with counts
+    #[arg(long, requires = "dwarf")]
+    pub resolve_source_dirs: bool,
+
+    /// Exit with status 3 if the condition holds for any scanned binary, reported or filtered out (repeatable)
+    #[arg(long, value_name = "CONDITION")]
+    pub fail_if: Vec<FailCondition>,
+
+    /// Exit with status 5 if any scanned binary, reported or filtered out, has analysis warnings (a --fail-if condition's status 3 wins)
+    #[arg(long)]
+    pub fail_on_warning: bool,
+
+    /// List the files that couldn't be read or parsed (an "errors" array in JSON)
+    #[arg(long)]
+    pub show_errors: bool,
+
+    /// Add libraries sharing a SONAME to --json as a "conflicts" array (the human report always lists them)
+    #[arg(long)]
+    pub show_conflicts: bool,
+
+    /// Add the non-binaries named like binaries to --json as a "suspicious_files" array (the human report always lists them)
+    #[arg(long)]
+    pub show_suspicious: bool,
+
+    /// Exit with status 7 if any file couldn't be read or parsed (--fail-if and --fail-on-warning statuses win)
+    #[arg(long)]
+    pub strict: bool,
 }
 
-pub fn run(args: Args) -> Result<()> {
-    let start = Instant::now();
+/// Modes that don't scan, run instead of the options above
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Check an --output directory against its manifest.json (exit status 4 on missing, modified or extra files)
+    VerifyOutput {
+        /// The directory an --output run wrote
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+
+        /// Copy missing or modified files again from their recorded source
+        #[arg(long)]
+        repair: bool,
+
+        /// Output the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+impl Args {
+    /// The PATH of a mode that reads one file or directory instead of scanning
+    fn single_path(&self, mode: &str) -> Result<&Path> {
+        match &self.paths[..] {
+            [path] => Ok(path),
+            _ => anyhow::bail!("{} takes a single PATH, not {}", mode, self.paths.len()),
+        }
+    }
+
+    /// The PATH `file` was found under, or the first one: the directory
+    /// `$ORIGIN` RPATH entries must stay inside. A binary given as PATH
+    /// counts its own directory
+    pub fn scan_root(&self, file: &Path) -> &Path {
+        let target = self.paths.iter().find(|target| file.starts_with(target)).unwrap_or(&self.paths[0]);
+        if !target.is_file() {
+            return target;
+        }
+        target.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."))
+    }
+
+    /// Strategies left by --symbol-strategy and --no-symbol-strategy, in
+    /// preference order: --prefer-symbols first, then the default order
+    pub fn symbol_strategies(&self) -> Vec<Strategy> {
+        let mut strategies: Vec<Strategy> = Strategy::ALL.into_iter()
+            .filter(|s| self.symbol_strategy.is_empty() || self.symbol_strategy.contains(s))
+            .filter(|s| !self.no_symbol_strategy.contains(s))
+            .collect();
+        strategies.sort_by_key(|s| self.prefer_symbols.iter().position(|preferred| preferred == s).unwrap_or(usize::MAX));
+        strategies
+    }
+}
+
+/// Conditions accepted by `--fail-if`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailCondition {
+    /// A `--scan-signatures` pattern matched
+    SignatureMatch,
+    /// An RPATH/RUNPATH entry is empty, relative, missing, world-writable
+    /// or escapes the scanned directory via `$ORIGIN`
+    InsecureRpath,
+    /// A DT_NEEDED library couldn't be resolved (with `--check-deps`)
+    MissingDependency,
+    /// Libraries with different build-ids share a SONAME, reported or
+    /// filtered out
+    SonameConflict,
+}
+
+impl FailCondition {
+    fn holds(&self, binary: &BinaryInfo) -> bool {
+        match self {
+            FailCondition::SignatureMatch => !binary.signature_matches.is_empty(),
+            FailCondition::InsecureRpath => !binary.rpath_findings.is_empty(),
+            FailCondition::MissingDependency => !binary.missing_dependencies.is_empty(),
+            FailCondition::SonameConflict => binary.warnings.iter().any(|warning| warning.code == "soname-conflict"),
+        }
+    }
+}
+
+/// Fields accepted by `--count-by` and `--group-output-by`, named like
+/// their JSON keys
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CountField {
+    /// Canonical architecture
+    Arch,
+    /// Binary format (ELF, Mach-O)
+    Type,
+    /// C library flavor (glibc, musl, bionic, static, ...)
+    Libc,
+    /// ELF EI_OSABI
+    #[value(name = "os_abi")]
+    OsAbi,
+    #[value(name = "is_stripped")]
+    IsStripped,
+    #[value(name = "has_debug_info")]
+    HasDebugInfo,
+    #[value(name = "is_pie")]
+    IsPie,
+    /// ELF program interpreter
+    Interpreter,
+    /// x86-64 level from --check-isa
+    #[value(name = "isa_level")]
+    IsaLevel,
+    /// Directory holding the binary
+    Dir,
+}
+
+impl CountField {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CountField::Arch => "arch",
+            CountField::Type => "type",
+            CountField::Libc => "libc",
+            CountField::OsAbi => "os_abi",
+            CountField::IsStripped => "is_stripped",
+            CountField::HasDebugInfo => "has_debug_info",
+            CountField::IsPie => "is_pie",
+            CountField::Interpreter => "interpreter",
+            CountField::IsaLevel => "isa_level",
+            CountField::Dir => "dir",
+        }
+    }
     
-    if !args.directory.exists() {
-        anyhow::bail!("Directory does not exist: {}", args.directory.display());
+    /// Group of `binary`; missing values group under "none"
+    pub fn key(&self, binary: &BinaryInfo) -> String {
+        let optional = |value: &Option<String>| value.clone().unwrap_or_else(|| "none".to_string());
+        match self {
+            CountField::Arch => binary.architecture.clone(),
+            CountField::Type => binary.binary_type.clone(),
+            CountField::Libc => optional(&binary.libc),
+            CountField::OsAbi => optional(&binary.os_abi),
+            CountField::IsStripped => binary.is_stripped.to_string(),
+            CountField::HasDebugInfo => binary.has_debug_info.map_or_else(|| "none".to_string(), |known| known.to_string()),
+            CountField::IsPie => binary.is_pie.to_string(),
+            CountField::Interpreter => optional(&binary.interpreter),
+            CountField::IsaLevel => optional(&binary.isa_level),
+            CountField::Dir => match binary.file_path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir.display().to_string(),
+                _ => ".".to_string(),
+            },
+        }
     }
+}
+
+/// Filters a scanned binary must pass to be reported, in evaluation order
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Filter {
+    LocalOnly,
+    RemoteOnly,
+    Arch,
+    Problems,
+    /// Stripped binaries are hidden unless `--show-stripped`; problems and
+    /// containers are always shown
+    Stripped,
+}
+
+pub const FILTERS: [Filter; 5] = [Filter::LocalOnly, Filter::RemoteOnly, Filter::Arch, Filter::Problems, Filter::Stripped];
 
-    if !args.directory.is_dir() {
-        anyhow::bail!("Path is not a directory: {}", args.directory.display());
+/// Why `--explain-filtered` says a copy of a reported build was dropped
+const DUPLICATE_ID_REASON: &str = "duplicate-id (--dedup-by-id)";
+
+impl Filter {
+    /// Why `--explain-filtered` says a binary was dropped
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Filter::LocalOnly => "local_only",
+            Filter::RemoteOnly => "remote_only",
+            Filter::Arch => "arch",
+            Filter::Problems => "problems",
+            Filter::Stripped => "stripped-without-symbols (pass --show-stripped)",
+        }
+    }
+    
+    pub fn rejects(&self, binary: &BinaryInfo, args: &Args) -> bool {
+        match self {
+            Filter::LocalOnly => args.local_only && !binary.has_local_debug_symbols(),
+            Filter::RemoteOnly => args.remote_only && !binary.has_remote_debug_symbols(),
+            Filter::Arch => !args.arch.is_empty() && !args.arch.contains(&binary.architecture),
+            Filter::Problems => args.problems && !binary.has_problems(args.security),
+            Filter::Stripped => {
+                !args.show_stripped && !args.problems && binary.is_stripped && !binary.has_local_debug_symbols()
+                    && !binary.has_go_pclntab
+                    && !binary.has_minidebuginfo
+                    && binary.container_format.is_none()
+            }
+        }
     }
+}
 
-    // Create output directory if specified
-    if let Some(ref output) = args.output {
-        fs::create_dir_all(output)?;
+/// Uniform random sample of up to `size` items from a stream of unknown
+/// length (reservoir sampling), kept in stream order
+pub struct Sample<T> {
+    size: usize,
+    seen: usize,
+    rng: StdRng,
+    items: Vec<(usize, T)>,
+}
+
+impl<T> Sample<T> {
+    /// The same seed over the same stream gives the same sample
+    pub fn new(size: usize, seed: u64) -> Self {
+        Self { size, seen: 0, rng: StdRng::seed_from_u64(seed), items: Vec::new() }
     }
 
-    // Print header for human output
-    if !args.json {
-        println!("{}", "Symbol Walker - ELF/Mach-O Binary Scanner".bright_cyan().bold());
-        println!("{}", "=".repeat(50).bright_black());
-        println!("Scanning directory: {}", args.directory.display().to_string().bright_white());
-        println!();
+    pub fn offer(&mut self, item: T) {
+        let index = self.seen;
+        self.seen += 1;
+        if self.items.len() < self.size {
+            self.items.push((index, item));
+        } else {
+            // Keep the new item with probability size / seen
+            let slot = self.rng.random_range(0..self.seen);
+            if slot < self.size {
+                self.items[slot] = (index, item);
+            }
+        }
     }
 
-    // Collect all binaries
-    let mut binaries = Vec::new();
-    let mut walker = WalkDir::new(&args.directory);
+    /// Items offered, sampled or not
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+
+    pub fn into_vec(mut self) -> Vec<T> {
+        self.items.sort_by_key(|(index, _)| *index);
+        self.items.into_iter().map(|(_, item)| item).collect()
+    }
+}
+
+/// Exit status used when a `--fail-if` condition is met
+const EXIT_FAIL_IF: u8 = 3;
+
+/// Exit status used when `verify-output` finds missing or changed files
+const EXIT_VERIFY_FAILED: u8 = 4;
+
+/// Exit status used when `--fail-on-warning` finds a binary with warnings
+const EXIT_WARNINGS: u8 = 5;
+
+/// Exit status used when a `--self-test` check fails
+const EXIT_SELF_TEST_FAILED: u8 = 6;
+
+/// Exit status used when `--strict` finds files that couldn't be scanned
+const EXIT_SCAN_ERRORS: u8 = 7;
+
+/// Exit status when stdout's reader went away, as if killed by SIGPIPE (128 + 13)
+const EXIT_BROKEN_PIPE: u8 = 141;
+
+pub fn run(args: Args) -> Result<ExitCode> {
+    let start = Instant::now();
     
-    if let Some(depth) = args.max_depth {
-        walker = walker.max_depth(depth);
+    if let Some(Command::VerifyOutput { ref dir, repair, json }) = args.command {
+        return verify_output(dir, repair, json);
+    }
+    
+    if let Some(address) = args.reverse_lookup {
+        return reverse_lookup(&args, address);
     }
     
-    if !args.follow_symlinks {
-        walker = walker.follow_links(false);
+    if args.self_test {
+        return self_test(&args);
+    }
+    
+    if let [path] = &args.paths[..] {
+        // A saved --json report is printed again; any other file is scanned
+        // (--carve, --show-unsupported and plugins find binaries in those)
+        if path.is_file() && !binary::has_binary_magic(path) {
+            if let Some(binaries) = saved_report(path)? {
+                return reformat_report(&args, binaries);
+            }
+        }
+    }
+    
+    if let Some(path) = args.paths.iter().find(|path| !path.exists()) {
+        anyhow::bail!("Path does not exist: {}", path.display());
     }
 
-    for entry in walker.into_iter().filter_map(|e| e.ok()) {
-        let path = entry.path();
-        
-        // Skip directories
-        if !path.is_file() {
-            continue;
+    let ctx = ScanContext::new(&args)?;
+    let prefetch_cache = if args.prefetch {
+        match args.cache_dir.clone().or_else(|| prefetch::default_cache_dir(&ctx.environment)) {
+            Some(cache) => Some(cache),
+            None => anyhow::bail!("--prefetch needs --cache-dir when no cache directory comes from the environment"),
         }
+    } else {
+        None
+    };
+
+    // Create output directory if specified, and keep other runs out of it
+    let _lock = match args.output {
+        Some(ref output) => {
+            fs::create_dir_all(output)?;
+            let lock = OutputLock::acquire(output, args.force_lock)?;
+            lock::release_on_interrupt();
+            Some(lock)
+        }
+        None => None,
+    };
+
+    // Pipes get a line per binary, like `ls`; -v still asks for the blocks
+    let formatter = HumanFormatter::new(args.verbose).compact(!atty::is(atty::Stream::Stdout)).time_format(args.time_format)
+        .group_by(args.group_output_by);
+    
+    // Reports stop when stdout's reader goes away; the rest of the run
+    // (report files, --output) still happens. Buffered, since a locked
+    // stdout flushes every line
+    let mut out = ReportSink::new(BufWriter::new(std::io::stdout().lock()));
+    
+    // Print header for human output
+    if !args.json && !formatter.is_compact() {
+        out.report(|out| {
+            writeln!(out, "{}", "Symbol Walker - ELF/Mach-O Binary Scanner".bright_cyan().bold())?;
+            writeln!(out, "{}", "=".repeat(50).bright_black())?;
+            let paths: Vec<String> = args.paths.iter().map(|path| path.display().to_string()).collect();
+            let label = if args.paths.iter().all(|path| path.is_dir()) { "directory" } else { "path" };
+            writeln!(out, "Scanning {}{}: {}", label, if paths.len() == 1 { "" } else { "s" }, paths.join(", ").bright_white())?;
+            if let Some(ref root) = ctx.scan_root {
+                writeln!(out, "Paths relative to: {}", root.path().display())?;
+            }
+            if args.record_env {
+                let set: Vec<String> = ctx.environment.record(args.record_env_values).into_iter()
+                    .filter(|variable| variable.set)
+                    .map(|variable| match variable.value {
+                        Some(value) => format!("{}={}", variable.name, value),
+                        None => variable.name.to_string(),
+                    })
+                    .collect();
+                writeln!(out, "Environment: {}{}",
+                    if set.is_empty() { "none set".to_string() } else { set.join(", ") },
+                    if ctx.environment.is_hermetic() { " (ignored, --hermetic)" } else { "" }
+                )?;
+            }
+            if ctx.symbol_strategies[..] != Strategy::ALL[..] {
+                let names: Vec<&str> = ctx.symbol_strategies.iter().map(Strategy::name).collect();
+                writeln!(out, "Symbol strategies: {}", if names.is_empty() { "none".to_string() } else { names.join(", ") })?;
+            }
+            writeln!(out)?;
+            // Shown while the scan runs
+            out.flush()?;
+            Ok(())
+        })?;
+    }
 
-        // Try to scan the binary
-        if let Ok(info) = scan_binary(path, &args) {
-            // Apply filters
-            if args.local_only && !info.has_local_debug_symbols() {
+    // Collect all binaries
+    let mut binaries = Vec::new();
+    let mut filtered = args.explain_filtered.then(|| FilteredReport::new(args.explain_limit));
+    // Unseeded runs pick a seed and print it, so a sample can be repeated
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut sample = args.sample.map(|size| Sample::new(size, seed));
+    // Every scanned binary keeps its store files, reported or not
+    let mut referenced = HashSet::new();
+    // Likewise every build-id is prefetched, in scan order
+    let mut prefetch_ids: Vec<String> = Vec::new();
+    // And every library with a SONAME is checked for conflicts
+    let mut sonames = Vec::new();
+    // And every binary is part of the --graph
+    let mut graph_nodes = Vec::new();
+    // And every binary is held to --fail-if and --fail-on-warning
+    let mut policy = Policy::default();
+    // Copies of an already reported build, with --dedup-by-id
+    let mut duplicates_skipped = 0;
+    // With --check-remote, binaries wait here for their remote answers
+    // (and --remote-only) before being sampled
+    let remote_pending = args.check_remote && ctx.debuginfod.is_some();
+    let mut pending = Vec::new();
+    // Files that couldn't be read or parsed, for --show-errors
+    let mut scan_errors = Vec::new();
+    // Non-binaries named like binaries (a `.so` linker script, ...)
+    let mut suspicious_files: Vec<SuspiciousFile> = Vec::new();
+    // Files reachable from more than one PATH are scanned once
+    let mut scanned = (args.paths.len() > 1).then(HashSet::new);
+    for path in walk_files(&args) {
+        let path = match path {
+            Ok(path) => path,
+            Err(error) => {
+                log::debug!("skipped {}: {}", error.path.display(), error.message);
+                scan_errors.push(error);
+                continue;
+            }
+        };
+        if let Some(ref mut scanned) = scanned {
+            if !scanned.insert(fs::canonicalize(&path).unwrap_or_else(|_| path.clone())) {
+                log::debug!("skipped {}: already scanned under another PATH", path.display());
                 continue;
             }
+        }
+        // Try to scan the binary, and the images embedded in it with --carve
+        let mut found: Vec<BinaryInfo> = match scan_headers(&path, &args, &ctx) {
+            Ok(info) => vec![info],
+            Err(e) => {
+                log::debug!("skipped {}: {:#}", path.display(), e);
+                match ScanError::from_scan(&path, &e) {
+                    Some(error) => scan_errors.push(error),
+                    None => suspicious_files.extend(suspicious::check(&path)),
+                }
+                Vec::new()
+            }
+        };
+        if args.carve {
+            found.extend(carve::scan(&path, &args, &ctx).unwrap_or_default());
+        }
+        
+        for info in found {
+            if args.symbol_store.is_some() {
+                referenced.extend(orphans::referenced_id(&info).map(str::to_string));
+            }
+            if let (Some(id), true) = (&info.build_id, args.prefetch) {
+                if !prefetch_ids.contains(id) {
+                    prefetch_ids.push(id.clone());
+                }
+            }
+            sonames.extend(ConflictingLibrary::from_binary(&info, report_path(&ctx, &info.file_path)));
+            if args.graph.is_some() {
+                graph_nodes.push(GraphNode::from_binary(&info, report_path(&ctx, &info.file_path)));
+            }
+            policy.record(&args, &info);
             
-            if args.remote_only && !info.has_remote_debug_symbols() {
+            let id = info.build_id.as_deref().or(info.uuid.as_deref());
+            if ctx.already_reported(id) {
+                log::debug!("skipped {}: {} was already reported", info.file_path.display(), id.unwrap_or_default());
+                duplicates_skipped += 1;
+                if let Some(ref mut report) = filtered {
+                    report.record(&report_path(&ctx, &info.file_path), DUPLICATE_ID_REASON);
+                }
                 continue;
             }
             
-            // Skip stripped binaries unless explicitly requested
-            if !args.show_stripped && info.is_stripped && !info.has_local_debug_symbols() {
+            let deferred = |filter: &&Filter| remote_pending && **filter == Filter::RemoteOnly;
+            if let Some(filter) = FILTERS.iter().filter(|filter| !deferred(filter)).find(|filter| filter.rejects(&info, &args)) {
+                log::debug!("filtered out {}: {}", info.file_path.display(), filter.reason());
+                if let Some(ref mut report) = filtered {
+                    report.record(&report_path(&ctx, &info.file_path), filter.reason());
+                }
                 continue;
             }
+            ctx.mark_reported(id);
             
-            binaries.push(info);
+            match sample {
+                _ if remote_pending => pending.push(info),
+                Some(ref mut sample) => sample.offer(info),
+                None => binaries.push(info),
+            }
+        }
+    }
+    if remote_pending {
+        binary::check_remote_all(&mut pending, &args, &ctx);
+        for info in pending {
+            if Filter::RemoteOnly.rejects(&info, &args) {
+                log::debug!("filtered out {}: {}", info.file_path.display(), Filter::RemoteOnly.reason());
+                if let Some(ref mut report) = filtered {
+                    report.record(&report_path(&ctx, &info.file_path), Filter::RemoteOnly.reason());
+                }
+                continue;
+            }
+            match sample {
+                Some(ref mut sample) => sample.offer(info),
+                None => binaries.push(info),
+            }
+        }
+    }
+    if let Some(sample) = sample {
+        let seen = sample.seen();
+        binaries = sample.into_vec();
+        if !args.json && !formatter.is_compact() {
+            out.report(|out| {
+                writeln!(out, "Sampled {} of {} binaries (--seed {})", binaries.len(), seen, seed)?;
+                writeln!(out)?;
+                Ok(())
+            })?;
+        }
+    }
+
+    let soname_conflicts = conflicts::soname_conflicts(sonames);
+    for info in &mut binaries {
+        if let Some(warning) = conflicts::warning(&soname_conflicts, &report_path(&ctx, &info.file_path)) {
+            info.warnings.push(warning);
         }
     }
+    fixtures::classify_all(&mut binaries, &args.paths, &ctx.fixtures);
+    fixtures::classify_all(&mut policy.failing, &args.paths, &ctx.fixtures);
 
+    // Deep analyses run once the cheap scan is done, on the binaries that
+    // will be reported
+    let scan_elapsed = start.elapsed();
+    let deep_stats = args.dwarf.then(|| deep::annotate_all(&mut binaries, &args, &ctx));
     let elapsed = start.elapsed();
 
+    // Only the reports get relative paths; copying into --output needs the real ones
+    let shown: Cow<[BinaryInfo]> = match ctx.scan_root {
+        Some(ref root) => Cow::Owned(binaries.iter().cloned()
+            .map(|mut info| {
+                root.relativize(&mut info);
+                info
+            })
+            .collect()),
+        None => Cow::Borrowed(&binaries),
+    };
+    let clusters = args.cluster.then(|| cluster::clusters(&shown));
+    let source_dirs = args.resolve_source_dirs.then(|| source_dirs::source_dirs(&shown));
+    let store_coverage = args.compare_to_store.then(|| StoreCoverage::new(&shown));
+    let store_orphans = args.symbol_store.as_deref()
+        .map(|store| StoreOrphans::find(store, &referenced))
+        .transpose()?;
+    let prefetched: Option<PrefetchReport> = match (&prefetch_cache, &ctx.debuginfod) {
+        (Some(cache), Some(client)) => Some(prefetch::run(client, cache, &prefetch_ids, args.prefetch_executables, args.prefetch_jobs)),
+        _ => None,
+    };
+
     // Output results
-    if args.json {
-        let formatter = JsonFormatter;
-        formatter.format(&binaries)?;
-    } else {
-        let formatter = HumanFormatter::new(args.verbose);
-        formatter.format(&binaries)?;
+    let formatting = Instant::now();
+    out.report(|out| {
+        if let Some(field) = args.count_by {
+            let counts = shown.iter().fold(BTreeMap::new(), |mut counts, b| {
+                *counts.entry(field.key(b)).or_insert(0) += 1;
+                counts
+            });
+            write_counts(field.name(), &counts, filtered.as_ref(), args.json, out)?;
+        } else if args.json {
+            let mut formatter = JsonFormatter::new();
+            if let Some(max_bytes) = args.max_output_bytes {
+                formatter = formatter.with_budget(max_bytes, args.overflow);
+            }
+            if let Some(ref report) = filtered {
+                formatter = formatter.with_filtered(report.clone());
+            }
+            if let Some(ref clusters) = clusters {
+                formatter = formatter.with_clusters(clusters.clone());
+            }
+            if let Some(ref dirs) = source_dirs {
+                formatter = formatter.with_source_dirs(dirs.clone());
+            }
+            if let Some(ref coverage) = store_coverage {
+                formatter = formatter.with_store_coverage(coverage.clone());
+            }
+            if let Some(ref orphans) = store_orphans {
+                formatter = formatter.with_store_orphans(orphans.clone());
+            }
+            if let Some(ref report) = prefetched {
+                formatter = formatter.with_prefetch(report.clone());
+            }
+            if args.show_conflicts {
+                formatter = formatter.with_conflicts(soname_conflicts.clone());
+            }
+            if args.dedup_by_id {
+                formatter = formatter.with_duplicates_skipped(duplicates_skipped);
+            }
+            if let Some(ref root) = ctx.scan_root {
+                formatter = formatter.with_scan_root(root.path().to_path_buf());
+            }
+            if args.record_env {
+                formatter = formatter.with_environment(ctx.environment.record(args.record_env_values));
+            }
+            if args.show_errors {
+                formatter = formatter.with_errors(scan_errors.clone());
+            }
+            if args.show_suspicious {
+                formatter = formatter.with_suspicious_files(suspicious_files.clone());
+            }
+            formatter.format(&shown, out)?;
+        } else {
+            formatter.format(&shown, out)?;
+            
+            // Print summary
+            if !formatter.is_compact() {
+                print_summary(out, &shown, Some(elapsed), store_coverage.as_ref(), args.dedup_by_id.then_some(duplicates_skipped), scan_errors.len())?;
+                if !args.quiet {
+                    print_hints(out, &Summary::new(&shown), &RunOptions::from_args(&args))?;
+                }
+            }
+        }
+        
+        if let (Some(report), false) = (&filtered, args.json) {
+            report.write_human(out)?;
+        }
+        
+        if let (Some(clusters), false, None) = (&clusters, args.json, args.count_by) {
+            cluster::write_human(clusters, out)?;
+        }
+        
+        if let (Some(dirs), false, None) = (&source_dirs, args.json, args.count_by) {
+            source_dirs::write_human(dirs, out)?;
+        }
+        
+        if let (Some(orphans), false, None) = (&store_orphans, args.json, args.count_by) {
+            orphans.write_human(out)?;
+        }
         
-        // Print summary
-        print_summary(&binaries, elapsed);
+        if let (Some(report), false, None) = (&prefetched, args.json, args.count_by) {
+            report.write_human(out)?;
+        }
+        
+        if let (false, false, None) = (soname_conflicts.is_empty(), args.json, args.count_by) {
+            conflicts::write_human(&soname_conflicts, out)?;
+        }
+        
+        if let (true, false, false, None) = (args.show_errors, scan_errors.is_empty(), args.json, args.count_by) {
+            scan_errors::write_human(&scan_errors, out)?;
+        }
+        
+        if let (false, false, None) = (suspicious_files.is_empty(), args.json, args.count_by) {
+            suspicious::write_human(&suspicious_files, out)?;
+        }
+        
+        out.flush()?;
+        Ok(())
+    })?;
+    let formatting_elapsed = formatting.elapsed();
+    let stdout_closed = out.is_closed();
+    drop(out);
+
+    if let Some(ref path) = args.json_array_file {
+        write_atomic(path, |w| JsonFormatter::new().format(&shown, w))?;
+    }
+    
+    if let Some(ref path) = args.html_report {
+        write_atomic(path, |w| HtmlFormatter.format(&shown, w))?;
+    }
+    
+    if let Some(ref path) = args.graph {
+        write_atomic(path, |w| graph::write_dot(&graph_nodes, w))?;
+    }
+
+    if args.stats {
+        print_stats(&ctx, scan_elapsed, formatting_elapsed, deep_stats.as_ref());
     }
 
     // Handle output operations
-    if args.output.is_some() {
-        handle_output(&args, &binaries)?;
+    if args.output.is_some() && !(stdout_closed && args.skip_output_on_broken_pipe) {
+        handle_output(&args, &ctx, &binaries)?;
     }
 
+    Ok(exit_code(&args, &binaries, &policy, !soname_conflicts.is_empty(), !scan_errors.is_empty(), stdout_closed))
+}
+
+/// What the exit status policy saw in binaries as they were scanned,
+/// before the filters dropped any: a stripped binary that embeds a
+/// signature still fails the run
+#[derive(Default)]
+struct Policy {
+    /// Scanned binaries meeting a `--fail-if` condition, reported or not;
+    /// classified as fixtures with the reported ones
+    failing: Vec<BinaryInfo>,
+    /// A scanned binary had warnings
+    warnings: bool,
+}
+
+impl Policy {
+    fn record(&mut self, args: &Args, binary: &BinaryInfo) {
+        if args.fail_if.iter().any(|condition| condition.holds(binary)) {
+            self.failing.push(binary.clone());
+        }
+        self.warnings |= !binary.warnings.is_empty();
+    }
+}
+
+/// Whether `binary` meets a `--fail-if` condition; fixtures are meant to be
+/// odd, so only --include-fixtures holds them to policy
+fn fails_if(args: &Args, binary: &BinaryInfo) -> bool {
+    (args.include_fixtures || !binary.likely_test_fixture) && args.fail_if.iter().any(|condition| condition.holds(binary))
+}
+
+/// Status for a finished run: a `--fail-if` condition, then
+/// `--fail-on-warning`, then `--strict`, then a closed stdout;
+/// `policy` and `soname_conflicts` cover binaries the filters left out of
+/// `binaries`
+fn exit_code(args: &Args, binaries: &[BinaryInfo], policy: &Policy, soname_conflicts: bool, scan_errors: bool, stdout_closed: bool) -> ExitCode {
+    let tree_wide = args.fail_if.contains(&FailCondition::SonameConflict) && soname_conflicts;
+    if tree_wide || policy.failing.iter().chain(binaries).any(|b| fails_if(args, b)) {
+        return ExitCode::from(EXIT_FAIL_IF);
+    }
+    if args.fail_on_warning && (policy.warnings || binaries.iter().any(|b| !b.warnings.is_empty())) {
+        return ExitCode::from(EXIT_WARNINGS);
+    }
+    if args.strict && scan_errors {
+        return ExitCode::from(EXIT_SCAN_ERRORS);
+    }
+    if stdout_closed {
+        return ExitCode::from(EXIT_BROKEN_PIPE);
+    }
+    ExitCode::SUCCESS
+}
+
+fn print_summary(out: &mut dyn Write, binaries: &[BinaryInfo], elapsed: Option<std::time::Duration>, store_coverage: Option<&StoreCoverage>, duplicates_skipped: Option<usize>, scan_errors: usize) -> Result<()> {
+    writeln!(out)?;
+    writeln!(out, "{}", "─".repeat(60).bright_black())?;
+    writeln!(out, "{}", "Summary".bright_cyan().bold())?;
+    writeln!(out)?;
+    
+    let summary = Summary::new(binaries);
+    
+    writeln!(out, "   Total binaries: {}", summary.total.to_string().bright_white())?;
+    writeln!(out, "   ELF binaries: {}", summary.elf.to_string().bright_white())?;
+    writeln!(out, "   Mach-O binaries: {}", summary.macho.to_string().bright_white())?;
+    writeln!(out, "   With embedded debug: {}", summary.with_debug.to_string().bright_green())?;
+    writeln!(out, "   With local symbols: {}", summary.with_local.to_string().bright_green())?;
+    writeln!(out, "   Stripped: {}", summary.stripped.to_string().bright_red())?;
+    
+    if summary.unsupported > 0 {
+        writeln!(out, "   Unsupported formats: {}", summary.unsupported.to_string().yellow())?;
+    }
+    
+    if summary.likely_fixtures > 0 {
+        writeln!(out, "   Likely test fixtures: {}", summary.likely_fixtures.to_string().bright_white())?;
+    }
+    
+    if let Some(skipped) = duplicates_skipped {
+        writeln!(out, "   Duplicate builds skipped: {}", skipped.to_string().bright_white())?;
+    }
+    
+    if summary.debug_files > 0 {
+        writeln!(out, "   Separate debug files: {}", summary.debug_files.to_string().bright_white())?;
+    }
+    
+    if summary.partial > 0 {
+        writeln!(out, "   Header-only (parse failed): {}", summary.partial.to_string().yellow())?;
+    }
+    
+    if scan_errors > 0 {
+        writeln!(out, "   Scan errors: {} {}", scan_errors.to_string().yellow(), "(--show-errors lists them)".bright_black())?;
+    }
+    
+    if !summary.architectures.is_empty() {
+        let architectures: Vec<String> = summary.architectures.iter()
+            .map(|(arch, count)| format!("{} {}", arch, count))
+            .collect();
+        writeln!(out, "   Architectures: {}", architectures.join(", ").bright_white())?;
+    }
+    
+    if !summary.isa_levels.is_empty() {
+        let levels: Vec<String> = summary.isa_levels.iter()
+            .map(|(level, count)| format!("{} {}", level, count))
+            .collect();
+        writeln!(out, "   ISA levels: {}", levels.join(", ").bright_white())?;
+    }
+    
+    if !summary.common_imports.is_empty() {
+        let common: Vec<String> = summary.common_imports.iter()
+            .map(|(name, count)| format!("{} {}", name, count))
+            .collect();
+        writeln!(out, "   Most imported: {}", common.join(", ").bright_white())?;
+    }
+    
+    if summary.relocations > 0 {
+        writeln!(out, "   Relocations: {} ({} IRELATIVE, {} GOT)",
+            summary.relocations.to_string().bright_white(),
+            summary.irelative_relocations,
+            HumanFormatter::format_size(summary.got_bytes)
+        )?;
+    }
+    
+    if summary.obsolete_slices > 0 {
+        writeln!(out, "   Obsolete slices: {} ({} reclaimable)",
+            summary.obsolete_slices.to_string().yellow(),
+            HumanFormatter::format_size(summary.reclaimable_bytes)
+        )?;
+    }
+    
+    if summary.with_remote > 0 {
+        writeln!(out, "   Remote available: {}", summary.with_remote.to_string().bright_blue())?;
+    }
+    
+    if summary.privileged > 0 {
+        writeln!(out, "   Setuid/setgid: {}", summary.privileged.to_string().bright_red())?;
+    }
+    
+    if summary.with_signatures > 0 {
+        writeln!(out, "   Signature matches: {}", summary.with_signatures.to_string().bright_red().bold())?;
+    }
+    
+    if let Some(coverage) = store_coverage {
+        coverage.write_human(out)?;
+    }
+    
+    if let Some(elapsed) = elapsed {
+        writeln!(out)?;
+        writeln!(out, "   Scan time: {:.2}s", elapsed.as_secs_f64())?;
+    }
     Ok(())
 }
 
-fn print_summary(binaries: &[BinaryInfo], elapsed: std::time::Duration) {
-    println!();
-    println!("{}", "─".repeat(60).bright_black());
-    println!("{}", "Summary".bright_cyan().bold());
-    println!();
+fn print_hints(out: &mut dyn Write, summary: &Summary, options: &RunOptions) -> Result<()> {
+    let hints = hints::hints(summary, options);
+    if hints.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(out, "{}", "Hints".bright_cyan().bold())?;
+    for hint in hints {
+        writeln!(out, "   {} {}", "→".bright_black(), hint)?;
+    }
+    Ok(())
+}
+
+fn print_stats(ctx: &ScanContext, elapsed: std::time::Duration, formatting: std::time::Duration, deep: Option<&DeepStats>) {
+    let hits = ctx.dsym_cache.hits();
+    let lookups = hits + ctx.dsym_cache.misses();
+    let hit_rate = if lookups > 0 { hits as f64 * 100.0 / lookups as f64 } else { 0.0 };
+    
+    eprintln!("{}", "Statistics".bright_cyan().bold());
+    eprintln!("   dSYM UUID cache: {} hits / {} lookups ({:.1}%)", hits, lookups, hit_rate);
+    eprintln!("   Scan time: {:.2}s", elapsed.as_secs_f64());
+    eprintln!("   Report formatting: {:.3}s", formatting.as_secs_f64());
+    if let Some(deep) = deep {
+        eprintln!("   Deep analysis: {} binaries in {:.2}s ({:.2}s of work, up to {} at once, peak estimate {})",
+            deep.tasks,
+            deep.wall.as_secs_f64(),
+            deep.busy.as_secs_f64(),
+            deep.peak_tasks,
+            HumanFormatter::format_size(deep.peak_bytes),
+        );
+    }
+}
+
+/// Regular files under each PATH, down to --max-depth levels of
+/// subdirectories, and the files given as PATH themselves
+pub fn candidate_files(args: &Args) -> impl Iterator<Item = PathBuf> + '_ {
+    walk_files(args).filter_map(Result::ok)
+}
+
+/// `candidate_files`, with the entries the walk couldn't read as errors
+pub fn walk_files(args: &Args) -> impl Iterator<Item = Result<PathBuf, ScanError>> + '_ {
+    args.paths.iter().flat_map(|root| walk_path(args, root))
+}
+
+/// One PATH of `walk_files`; WalkDir yields a file given as PATH as it is
+fn walk_path(args: &Args, root: &Path) -> impl Iterator<Item = Result<PathBuf, ScanError>> {
+    let mut walker = WalkDir::new(root);
+    
+    // WalkDir counts the root as depth 0 and its files as depth 1; ours
+    // counts directory levels below the root, hence the off-by-one
+    if let Some(depth) = args.max_depth {
+        walker = walker.max_depth(depth.saturating_add(1));
+    }
+    
+    walker = walker.follow_links(args.follow_symlinks);
+    
+    // Readdir order depends on the filesystem and its history
+    if args.deterministic {
+        walker = walker.sort_by_file_name();
+    }
+
+    let root = root.to_path_buf();
+    let exclude: Vec<GlobMatcher> = args.exclude.iter().map(Glob::compile_matcher).collect();
+    let include: Vec<GlobMatcher> = args.include.iter().map(Glob::compile_matcher).collect();
+    let relative = move |path: &Path| path.strip_prefix(&root).unwrap_or(path).to_path_buf();
+    let excluded = {
+        let relative = relative.clone();
+        // The root itself is never excluded, whatever the globs say
+        move |entry: &walkdir::DirEntry| entry.depth() > 0 && exclude.iter().any(|glob| glob.is_match(relative(entry.path())))
+    };
+
+    walker.into_iter()
+        .filter_entry(move |entry| !excluded(entry))
+        .map(|entry| entry.map(|entry| entry.into_path()).map_err(|error| ScanError::from_walk(&error)))
+        .filter(|entry| entry.as_ref().map_or(true, |path| path.is_file()))
+        .filter(move |entry| match entry {
+            // A file given as PATH is scanned regardless
+            Ok(path) if !include.is_empty() => {
+                let path = relative(path);
+                path.as_os_str().is_empty() || include.iter().any(|glob| glob.is_match(&path))
+            }
+            _ => true,
+        })
+}
+
+/// `*` and `?` stop at `/`; `**` crosses directories. As in `.gitignore`, a
+/// glob without a `/` matches the name at any depth and a leading `/`
+/// anchors it to the PATH being walked
+fn parse_glob(value: &str) -> Result<Glob, String> {
+    let pattern = match value.strip_prefix('/') {
+        Some(anchored) => Cow::Borrowed(anchored),
+        None if value.contains('/') => Cow::Borrowed(value),
+        None => Cow::Owned(format!("**/{}", value)),
+    };
+    GlobBuilder::new(&pattern)
+        .literal_separator(true)
+        .build()
+        .map_err(|error| format!("invalid glob '{}': {}", value, error.kind()))
+}
+
+/// Symbolicate one address, using the debug file the scan would report
+fn reverse_lookup(args: &Args, address: u64) -> Result<ExitCode> {
+    let path = args.single_path("--reverse-lookup")?;
+    if !path.is_file() {
+        anyhow::bail!("--reverse-lookup needs a binary, not a directory: {}", path.display());
+    }
+    
+    let ctx = ScanContext::new(args)?;
+    let info = scan_binary(path, args, &ctx)?;
+    
+    let file = fs::File::open(path)?;
+    let mmap = unsafe { memmap2::Mmap::map(&file)? };
+    let result = symbolicate::symbolicate(&info, &mmap, address)?;
+    
+    let mut out = ReportSink::new(std::io::stdout().lock());
+    out.report(|out| {
+        if args.json {
+            writeln!(out, "{}", serde_json::to_string_pretty(&result)?)?;
+        } else {
+            writeln!(out, "{}", result)?;
+        }
+        Ok(out.flush()?)
+    })?;
     
-    let total = binaries.len();
-    let with_debug = binaries.iter().filter(|b| b.has_debug_info).count();
-    let with_local = binaries.iter().filter(|b| b.has_local_debug_symbols()).count();
-    let with_remote = binaries.iter().filter(|b| b.has_remote_debug_symbols()).count();
-    let stripped = binaries.iter().filter(|b| b.is_stripped).count();
-    let elf_count = binaries.iter().filter(|b| b.binary_type == "ELF").count();
-    let macho_count = binaries.iter().filter(|b| b.binary_type == "Mach-O").count();
+    if out.is_closed() {
+        return Ok(ExitCode::from(EXIT_BROKEN_PIPE));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Leading bytes of a single non-binary PATH checked for the start of a
+/// JSON document (whitespace, then `[` or `{`)
+const REPORT_SNIFF_BYTES: u64 = 4096;
+
+/// The binaries of the symwalker --json report at `path`; None for any
+/// other file, read only when it starts like JSON
+fn saved_report(path: &Path) -> Result<Option<Vec<BinaryInfo>>> {
+    let mut head = Vec::new();
+    fs::File::open(path)?.take(REPORT_SNIFF_BYTES).read_to_end(&mut head)?;
+    if !matches!(head.iter().find(|byte| !byte.is_ascii_whitespace()), Some(b'[' | b'{')) {
+        return Ok(None);
+    }
+    output::read_report(&fs::read(path)?).with_context(|| format!("Can't re-format {}", path.display()))
+}
+
+/// `symwalker scan.json`: print a saved `--json` report in this run's
+/// format instead of scanning. The report's own filtering is kept; this
+/// run's `--local-only`, `--arch` and similar narrow it further
+fn reformat_report(args: &Args, binaries: Vec<BinaryInfo>) -> Result<ExitCode> {
+    let binaries: Vec<BinaryInfo> = binaries.into_iter()
+        .filter(|info| !FILTERS.iter().any(|filter| *filter != Filter::Stripped && filter.rejects(info, args)))
+        .collect();
     
-    println!("   Total binaries: {}", total.to_string().bright_white());
-    println!("   ELF binaries: {}", elf_count.to_string().bright_white());
-    println!("   Mach-O binaries: {}", macho_count.to_string().bright_white());
-    println!("   With embedded debug: {}", with_debug.to_string().bright_green());
-    println!("   With local symbols: {}", with_local.to_string().bright_green());
-    println!("   Stripped: {}", stripped.to_string().bright_red());
+    let formatter = HumanFormatter::new(args.verbose).compact(!atty::is(atty::Stream::Stdout)).time_format(args.time_format)
+        .group_by(args.group_output_by);
+    let mut out = ReportSink::new(BufWriter::new(std::io::stdout().lock()));
+    out.report(|out| {
+        if let Some(field) = args.count_by {
+            let counts = binaries.iter().fold(BTreeMap::new(), |mut counts, b| {
+                *counts.entry(field.key(b)).or_insert(0) += 1;
+                counts
+            });
+            write_counts(field.name(), &counts, None, args.json, out)?;
+        } else if args.json {
+            JsonFormatter::new().format(&binaries, out)?;
+        } else {
+            formatter.format(&binaries, out)?;
+            if !formatter.is_compact() {
+                print_summary(out, &binaries, None, None, None, 0)?;
+            }
+        }
+        Ok(out.flush()?)
+    })?;
     
-    if with_remote > 0 {
-        println!("   Remote available: {}", with_remote.to_string().bright_blue());
+    if let Some(ref path) = args.json_array_file {
+        write_atomic(path, |w| JsonFormatter::new().format(&binaries, w))?;
+    }
+    if let Some(ref path) = args.html_report {
+        write_atomic(path, |w| HtmlFormatter.format(&binaries, w))?;
+    }
+    if let Some(ref path) = args.graph {
+        let nodes: Vec<GraphNode> = binaries.iter().map(|info| GraphNode::from_binary(info, info.file_path.clone())).collect();
+        write_atomic(path, |w| graph::write_dot(&nodes, w))?;
     }
     
-    println!();
-    println!("   Scan time: {:.2}s", elapsed.as_secs_f64());
+    Ok(exit_code(args, &binaries, &Policy::default(), false, false, out.is_closed()))
 }
 
-fn handle_output(args: &Args, binaries: &[BinaryInfo]) -> Result<()> {
+fn self_test(args: &Args) -> Result<ExitCode> {
+    let report = self_test::run(args);
+    
+    let mut out = ReportSink::new(std::io::stdout().lock());
+    out.report(|out| {
+        if args.json {
+            writeln!(out, "{}", serde_json::to_string_pretty(&report)?)?;
+        } else {
+            report.write_human(out)?;
+        }
+        Ok(out.flush()?)
+    })?;
+    
+    if report.failed() {
+        return Ok(ExitCode::from(EXIT_SELF_TEST_FAILED));
+    }
+    if out.is_closed() {
+        return Ok(ExitCode::from(EXIT_BROKEN_PIPE));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn verify_output(dir: &Path, repair: bool, json: bool) -> Result<ExitCode> {
+    let report = manifest::verify(dir, repair)?;
+    
+    let mut out = ReportSink::new(std::io::stdout().lock());
+    out.report(|out| {
+        if json {
+            writeln!(out, "{}", serde_json::to_string_pretty(&report)?)?;
+        } else {
+            let problems = [
+                ("missing", &report.missing),
+                ("modified", &report.modified),
+                ("extra", &report.extra),
+                ("build-id mismatch", &report.build_id_mismatch),
+                ("dSYM without DWARF", &report.incomplete_dsyms),
+            ];
+            for (problem, paths) in problems {
+                for path in paths {
+                    writeln!(out, "{}: {}", problem.red(), path.display())?;
+                }
+            }
+            for path in &report.repaired {
+                writeln!(out, "{}: {}", "repaired".green(), path.display())?;
+            }
+            let status = if report.is_ok() { "OK".bright_green() } else { "FAILED".bright_red() };
+            writeln!(out, "{} files checked: {}", report.checked, status)?;
+        }
+        Ok(out.flush()?)
+    })?;
+    
+    if !report.is_ok() {
+        return Ok(ExitCode::from(EXIT_VERIFY_FAILED));
+    }
+    if out.is_closed() {
+        return Ok(ExitCode::from(EXIT_BROKEN_PIPE));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Copy and download into --output, journaling each binary as it completes
+/// and writing manifest.json once all are done
+pub fn handle_output(args: &Args, ctx: &ScanContext, binaries: &[BinaryInfo]) -> Result<()> {
     let output_dir = args.output.as_ref().unwrap();
     let mut manifest = Vec::new();
+    let mut artifacts: Vec<Artifact> = Vec::new();
+    
+    remove_stale_temp_files(output_dir)?;
+    let completed = if args.resume { manifest::read_journal(output_dir)? } else { Default::default() };
+    let mut journal = Journal::open(output_dir, args.resume)?;
+    // Files of an entry the journal doesn't show as complete may be stale
+    let force = args.force || args.resume;
+    let client = ctx.debuginfod.as_ref().filter(|_| args.download_remote);
+    let executable_client = ctx.debuginfod.as_ref().filter(|_| args.download_executable);
+    
+    // Build-ids whose code is already in the scan; only the rest need fetching
+    let local_executables: std::collections::HashSet<&str> = binaries.iter()
+        .filter(|binary| !binary.is_debug_only)
+        .filter_map(|binary| binary.build_id.as_deref())
+        .collect();
 
     for binary in binaries {
+        let binary_path = report_path(ctx, &binary.file_path).display().to_string();
+        let done = completed.get(&binary_path)
+            .filter(|done| manifest::intact(output_dir, &done.artifacts));
+        if let Some(done) = done {
+            manifest.push(done.entry.clone());
+            artifacts.extend(done.artifacts.iter().cloned());
+            continue;
+        }
+        
+        let mut written = Vec::new();
         let mut entry = serde_json::json!({
-            "binary": binary.file_path.display().to_string(),
+            "binary": binary_path,
             "binary_copied": null,
             "symbols_copied": null,
             "symbols_downloaded": null,
+            "symbols_verified": null,
         });
 
         // Copy binary if requested
@@ -215,10 +1488,16 @@ fn handle_output(args: &Args, binaries: &[BinaryInfo]) -> Result<()> {
             let filename = binary.file_path.file_name().unwrap();
             let dest = output_dir.join(filename);
             
-            if !dest.exists() || args.force {
-                fs::copy(&binary.file_path, &dest)?;
+            if !dest.exists() || force {
+                match binary.carved_from {
+                    Some(ref carved) => extract_atomic(carved, &dest)?,
+                    None => copy_atomic(&binary.file_path, &dest)?,
+                }
                 entry["binary_copied"] = serde_json::json!(dest.display().to_string());
             }
+            // A carved image has no file of its own to repair from
+            let source = binary.carved_from.is_none().then_some(binary.file_path.as_path());
+            written.extend(manifest::artifacts(output_dir, &dest, source)?);
         }
 
         // Copy local debug symbols
@@ -226,33 +1505,207 @@ fn handle_output(args: &Args, binaries: &[BinaryInfo]) -> Result<()> {
             let filename = debug_path.file_name().unwrap();
             let dest = output_dir.join(filename);
             
-            if !dest.exists() || args.force {
-                if debug_path.is_file() {
-                    fs::copy(debug_path, &dest)?;
-                    entry["symbols_copied"] = serde_json::json!(dest.display().to_string());
-                } else if debug_path.is_dir() {
-                    // Handle dSYM bundles
-                    copy_dir_recursive(debug_path, &dest)?;
+            if !dest.exists() || force {
+                // Files, or dSYM bundles
+                if debug_path.is_file() || debug_path.is_dir() {
+                    copy_atomic(debug_path, &dest)?;
                     entry["symbols_copied"] = serde_json::json!(dest.display().to_string());
                 }
             }
+            if dest.exists() {
+                written.extend(manifest::artifacts(output_dir, &dest, Some(debug_path))?);
+            }
         }
 
+        // Download remote debug symbols when nothing was found locally
+        if let (Some(client), Some(ref build_id)) = (client, &binary.build_id) {
+            if binary.debug_file_path.is_none() && binary.has_remote_debug_symbols() {
+                let filename = binary.file_path.file_name().unwrap();
+                let mut debug_name = filename.to_os_string();
+                debug_name.push(".debug");
+                let dest = output_dir.join(debug_name);
+                
+                if !dest.exists() || force {
+                    match download_symbols(client, build_id, &dest, args.verify_downloads) {
+                        Ok(verified) => {
+                            entry["symbols_downloaded"] = serde_json::json!(dest.display().to_string());
+                            entry["symbols_verified"] = serde_json::json!(verified);
+                        }
+                        Err(e) => {
+                            eprintln!("{} {}: {}", "warning:".yellow(), binary.file_path.display(), e);
+                            entry["symbols_verified"] = serde_json::json!(false);
+                            entry["download_error"] = serde_json::json!(e.to_string());
+                        }
+                    }
+                }
+                if dest.exists() {
+                    written.extend(manifest::artifacts(output_dir, &dest, None)?);
+                }
+            }
+        }
+
+        // Fetch the executable a debug-only file belongs to
+        if let (Some(client), Some(ref build_id)) = (executable_client, &binary.build_id) {
+            if binary.is_debug_only && !local_executables.contains(build_id.as_str()) {
+                let dest = output_dir.join(executable_name(&binary.file_path));
+                entry["executable_downloaded"] = serde_json::Value::Null;
+                
+                if !dest.exists() || force {
+                    match download_executable(client, build_id, &dest) {
+                        Ok(()) => {
+                            entry["executable_downloaded"] = serde_json::json!(dest.display().to_string());
+                        }
+                        Err(e) => {
+                            eprintln!("{} {}: {}", "warning:".yellow(), binary.file_path.display(), e);
+                            entry["executable_error"] = serde_json::json!(e.to_string());
+                        }
+                    }
+                }
+                if dest.exists() {
+                    written.extend(manifest::artifacts(output_dir, &dest, None)?);
+                }
+            }
+        }
+
+        for artifact in &mut written {
+            artifact.source = artifact.source.as_deref().map(|source| report_path(ctx, source));
+        }
+        journal.append(&JournalEntry { entry: entry.clone(), artifacts: written.clone() })?;
+        artifacts.extend(written);
         manifest.push(entry);
     }
 
-    // Write manifest
-    let manifest_path = output_dir.join("manifest.json");
-    let manifest_json = serde_json::json!({
+    // Binaries can share a debug file or bundle
+    artifacts.sort_by(|a, b| a.path.cmp(&b.path));
+    artifacts.dedup_by(|a, b| a.path == b.path);
+    
+    // Write manifest; artifacts record what verify-output checks
+    let manifest_path = output_dir.join(manifest::MANIFEST_NAME);
+    let mut manifest_json = serde_json::json!({
         "files": manifest,
         "count": binaries.len(),
+        "symbol_strategies": ctx.symbol_strategies,
+        "artifacts": artifacts,
     });
+    if let Some(ref root) = ctx.scan_root {
+        manifest_json["scan_root"] = serde_json::json!(root.path());
+    }
+    
+    write_atomic(&manifest_path, |w| Ok(serde_json::to_writer_pretty(w, &manifest_json)?))?;
+    drop(journal);
+    fs::remove_file(output_dir.join(manifest::JOURNAL_NAME))?;
+
+    Ok(())
+}
+
+/// `path` as reported: relative to the scan root with --relative-paths
+fn report_path(ctx: &ScanContext, path: &Path) -> PathBuf {
+    match ctx.scan_root {
+        Some(ref root) => root.report_path(path),
+        None => path.to_path_buf(),
+    }
+}
+
+/// Copy a file or directory so that `dest` only ever appears complete
+fn copy_atomic(src: &std::path::Path, dest: &std::path::Path) -> Result<()> {
+    let tmp = temp_path(dest)?;
+    let result = (|| {
+        if src.is_dir() {
+            copy_dir_recursive(src, &tmp)?;
+            if dest.is_dir() {
+                fs::remove_dir_all(dest)?;
+            }
+        } else {
+            fs::copy(src, &tmp)?;
+        }
+        fs::rename(&tmp, dest)?;
+        Ok(())
+    })();
+    
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&tmp).or_else(|_| fs::remove_file(&tmp));
+    }
+    result
+}
+
+/// Write a carved image out as a file of its own, atomically like `copy_atomic`
+fn extract_atomic(carved: &CarvedImage, dest: &std::path::Path) -> Result<()> {
+    let file = fs::File::open(&carved.container)?;
+    let mmap = unsafe { memmap2::Mmap::map(&file)? };
+    let Some(image) = carved.slice(&mmap) else {
+        anyhow::bail!("{} no longer holds the image carved from it", carved.container.display());
+    };
+    write_atomic(dest, |w| Ok(w.write_all(image)?))
+}
+
+/// Remove the temporaries of copies and downloads an interrupted run left behind
+fn remove_stale_temp_files(dir: &std::path::Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if !is_temp_name(&entry.file_name()) {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            fs::remove_dir_all(entry.path())?;
+        } else {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// Download debug symbols for `build_id` to `dest`
+/// With `verify`, the file must be an ELF debug file with the same build-id,
+/// otherwise it is deleted; returns whether the file was verified
+fn download_symbols(client: &DebuginfodClient, build_id: &str, dest: &std::path::Path, verify: bool) -> Result<Option<bool>> {
+    client.download(build_id, dest)?;
+    
+    if !verify {
+        return Ok(None);
+    }
     
-    fs::write(manifest_path, serde_json::to_string_pretty(&manifest_json)?)?;
+    if let Err(e) = check_downloaded(dest, |analyzer| analyzer.verify_debug_file(build_id)) {
+        let _ = fs::remove_file(dest);
+        anyhow::bail!("downloaded debug file failed verification: {}", e);
+    }
+    
+    Ok(Some(true))
+}
 
+/// Download the executable for `build_id` to `dest`, always checking its build-id
+fn download_executable(client: &DebuginfodClient, build_id: &str, dest: &std::path::Path) -> Result<()> {
+    client.download_executable(build_id, dest)?;
+    
+    if let Err(e) = check_downloaded(dest, |analyzer| analyzer.verify_build_id(build_id)) {
+        let _ = fs::remove_file(dest);
+        anyhow::bail!("downloaded executable failed verification: {}", e);
+    }
+    
     Ok(())
 }
 
+fn check_downloaded(dest: &std::path::Path, check: impl FnOnce(&ElfAnalyzer) -> Result<()>) -> Result<()> {
+    let metadata = fs::metadata(dest)?;
+    let file = fs::File::open(dest)?;
+    let mmap = unsafe { memmap2::Mmap::map(&file)? };
+    let analyzer = ElfAnalyzer::new(dest, &mmap, metadata.len(), metadata.modified()?.into())?;
+    check(&analyzer)
+}
+
+/// Output name for the executable of a debug file: `foo.debug` becomes `foo`,
+/// anything else gets `.executable` appended
+fn executable_name(debug_path: &std::path::Path) -> std::ffi::OsString {
+    if debug_path.extension().is_some_and(|ext| ext == "debug") {
+        if let Some(stem) = debug_path.file_stem() {
+            return stem.to_os_string();
+        }
+    }
+    
+    let mut name = debug_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".executable");
+    name
+}
+
 fn copy_dir_recursive(src: &std::path::Path, dst: &std::path::Path) -> Result<()> {
     fs::create_dir_all(dst)?;
     
@@ -0,0 +1,258 @@
+//! Grouping scanned binaries built from the same sources (`--cluster`)
+//!
+//! Two binaries land in the same cluster when they share a signal; every
+//! cluster lists the signals that joined it, so each grouping can be checked
+//! by hand
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use anyhow::Result;
+use colored::*;
+use serde::Serialize;
+
+use crate::binary::BinaryInfo;
+
+/// Shared-source evidence, strongest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Signal {
+    /// Same `.note.package` name
+    Package,
+    /// Same common prefix of the compile units' DW_AT_comp_dir
+    CompDir,
+    /// Same DW_AT_producer set and at least one common source file
+    Producer,
+    /// SONAMEs differing only in version (`libssl.so.1.1`, `libssl.so.3`)
+    SonameFamily,
+}
+
+impl Signal {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Signal::Package => "package",
+            Signal::CompDir => "comp-dir",
+            Signal::Producer => "producer",
+            Signal::SonameFamily => "soname-family",
+        }
+    }
+}
+
+/// One signal value shared by some members of a cluster
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Evidence {
+    pub signal: Signal,
+    pub value: String,
+    pub members: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Cluster {
+    /// Package name, source directory or soname family from the evidence,
+    /// else the first member's file name
+    pub name: String,
+    pub members: Vec<PathBuf>,
+    pub evidence: Vec<Evidence>,
+}
+
+/// Clusters of two or more binaries, ordered by their first member path
+pub fn clusters(binaries: &[BinaryInfo]) -> Vec<Cluster> {
+    let mut evidence: Vec<(Signal, String, Vec<usize>)> = Vec::new();
+
+    let mut by_value = |signal: Signal, key: &dyn Fn(&BinaryInfo) -> Option<String>| {
+        let mut groups: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (index, binary) in binaries.iter().enumerate() {
+            if let Some(value) = key(binary) {
+                groups.entry(value).or_default().push(index);
+            }
+        }
+        evidence.extend(groups.into_iter()
+            .filter(|(_, members)| members.len() > 1)
+            .map(|(value, members)| (signal, value, members)));
+    };
+    by_value(Signal::Package, &|b| b.package_info.as_ref().and_then(|p| p.name.clone()).filter(|name| !name.is_empty()));
+    by_value(Signal::CompDir, &|b| source_root(&b.comp_dirs));
+    by_value(Signal::SonameFamily, &|b| b.soname.as_deref().and_then(soname_family));
+    evidence.extend(producer_evidence(binaries));
+
+    // Union-find over the binaries each piece of evidence connects
+    let mut parent: Vec<usize> = (0..binaries.len()).collect();
+    fn root(parent: &mut [usize], mut index: usize) -> usize {
+        while parent[index] != index {
+            parent[index] = parent[parent[index]];
+            index = parent[index];
+        }
+        index
+    }
+    for (_, _, members) in &evidence {
+        for &member in &members[1..] {
+            let (a, b) = (root(&mut parent, members[0]), root(&mut parent, member));
+            parent[a.max(b)] = a.min(b);
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for index in 0..binaries.len() {
+        let group = root(&mut parent, index);
+        groups.entry(group).or_default().push(index);
+    }
+
+    evidence.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+    let path = |index: usize| binaries[index].file_path.clone();
+
+    let mut clusters: Vec<Cluster> = groups.into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(group, members)| {
+            let evidence: Vec<Evidence> = evidence.iter()
+                .filter(|(_, _, linked)| root(&mut parent, linked[0]) == group)
+                .map(|(signal, value, linked)| Evidence {
+                    signal: *signal,
+                    value: value.clone(),
+                    members: sorted(linked.iter().map(|&index| path(index))),
+                })
+                .collect();
+            let members = sorted(members.into_iter().map(path));
+            let file_name = |path: &Path| path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            let name = evidence.iter()
+                .find(|evidence| evidence.signal != Signal::Producer)
+                .map(|evidence| match evidence.signal {
+                    Signal::CompDir => file_name(Path::new(&evidence.value)),
+                    _ => evidence.value.clone(),
+                })
+                .unwrap_or_else(|| file_name(&members[0]));
+            Cluster { name, members, evidence }
+        })
+        .collect();
+    clusters.sort_by(|a, b| a.members.cmp(&b.members));
+    clusters
+}
+
+fn sorted(paths: impl Iterator<Item = PathBuf>) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = paths.collect();
+    paths.sort();
+    paths
+}
+
+/// Binaries with the same producers joined by common source files; the value
+/// names the producers and one shared file
+fn producer_evidence(binaries: &[BinaryInfo]) -> Vec<(Signal, String, Vec<usize>)> {
+    let mut by_producers: BTreeMap<&[String], Vec<usize>> = BTreeMap::new();
+    for (index, binary) in binaries.iter().enumerate() {
+        if !binary.producers.is_empty() && !binary.source_files.is_empty() {
+            by_producers.entry(&binary.producers).or_default().push(index);
+        }
+    }
+
+    let mut evidence = Vec::new();
+    for (producers, candidates) in by_producers {
+        // Each source file links every candidate compiled from it
+        let mut by_source: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for &index in &candidates {
+            for source in &binaries[index].source_files {
+                by_source.entry(source).or_default().push(index);
+            }
+        }
+
+        let mut linked: Vec<(BTreeSet<usize>, &str)> = Vec::new();
+        for (source, members) in by_source.into_iter().filter(|(_, members)| members.len() > 1) {
+            let members: BTreeSet<usize> = members.into_iter().collect();
+            let (overlapping, mut rest): (Vec<_>, Vec<_>) = linked.into_iter()
+                .partition(|(set, _)| !set.is_disjoint(&members));
+            let mut merged = members;
+            let mut example = source;
+            for (set, first) in overlapping {
+                merged.extend(set);
+                example = example.min(first);
+            }
+            rest.push((merged, example));
+            linked = rest;
+        }
+
+        for (members, example) in linked {
+            let value = format!("{} (shared source {})", producers.join("; "), example);
+            evidence.push((Signal::Producer, value, members.into_iter().collect()));
+        }
+    }
+    evidence
+}
+
+/// Source tree of absolute compile directories: each is cut after its first
+/// `name-version` component (`/build/openssl-3.0.2/crypto` becomes
+/// `/build/openssl-3.0.2`), then their common prefix is taken if it is at
+/// least two components deep (not just `/build`)
+pub fn source_root(comp_dirs: &[String]) -> Option<String> {
+    let mut roots = comp_dirs.iter().map(|dir| {
+        let dir = Path::new(dir);
+        let mut components: Vec<Component> = dir.components().collect();
+        if let Some(versioned) = components.iter().position(is_versioned) {
+            components.truncate(versioned + 1);
+        }
+        dir.is_absolute().then_some(components)
+    });
+    let mut prefix = roots.next()??;
+    for root in roots {
+        let root = root?;
+        let common = prefix.iter().zip(&root).take_while(|(a, b)| a == b).count();
+        prefix.truncate(common);
+    }
+
+    let depth = prefix.iter().filter(|c| matches!(c, Component::Normal(_))).count();
+    (depth >= 2).then(|| prefix.iter().collect::<PathBuf>().display().to_string())
+}
+
+/// `openssl-3.0.2`, `zlib-1.3-1.fc39`: a name, a dash and a digit
+fn is_versioned(component: &Component) -> bool {
+    let Component::Normal(name) = component else {
+        return false;
+    };
+    let name = name.to_string_lossy();
+    name.split_once('-').is_some_and(|(name, version)| {
+        !name.is_empty() && version.starts_with(|c: char| c.is_ascii_digit())
+    })
+}
+
+/// Library name without directory, extension or version:
+/// `libssl.so.3`, `libssl.so.1.1` and `/usr/lib/libssl.48.dylib` are `libssl`,
+/// and `libfoo-2.so.0` is `libfoo`
+pub fn soname_family(soname: &str) -> Option<String> {
+    let file_name = soname.rsplit('/').next()?;
+    let stem = file_name.split('.').next()?;
+    // A version glued on with a dash: `libfoo-2.so.0`
+    let family = match stem.rsplit_once('-') {
+        Some((name, version)) if !name.is_empty() && version.bytes().all(|b| b.is_ascii_digit()) => name,
+        _ => stem,
+    };
+    (!family.is_empty()).then(|| family.to_string())
+}
+
+/// The `Clusters` section of the human output
+pub fn write_human(clusters: &[Cluster], out: &mut dyn Write) -> Result<()> {
+    writeln!(out)?;
+    writeln!(out, "{}", "Clusters".bright_cyan().bold())?;
+    if clusters.is_empty() {
+        writeln!(out, "   {}", "No binaries share a source signal".bright_black())?;
+        return Ok(());
+    }
+
+    for cluster in clusters {
+        writeln!(out)?;
+        writeln!(out, "   {} {}",
+            cluster.name.bright_white().bold(),
+            format!("({} binaries)", cluster.members.len()).bright_black()
+        )?;
+        for member in &cluster.members {
+            writeln!(out, "      {}", member.display().to_string().white())?;
+        }
+        for evidence in &cluster.evidence {
+            let members: Vec<String> = evidence.members.iter()
+                .map(|path| path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned())
+                .collect();
+            writeln!(out, "      {} {}: {}",
+                evidence.signal.name().yellow(),
+                evidence.value,
+                members.join(", ").bright_black()
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,91 @@
+//! Libraries in one tree claiming the same SONAME (`soname-conflict`)
+//!
+//! Two different builds behind one SONAME in different directories leave
+//! the choice to the loader's search order at run time, which differs
+//! between `LD_LIBRARY_PATH`, RUNPATH and `ld.so.cache` setups. Libraries
+//! are identified by build-id (the UUID for Mach-O dylibs, keyed by install
+//! name); copies of the same build are not a conflict.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use colored::*;
+use serde::Serialize;
+
+use crate::binary::{BinaryInfo, ScanWarning};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SonameConflict {
+    pub soname: String,
+    /// Every library claiming the SONAME, by path
+    pub libraries: Vec<ConflictingLibrary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConflictingLibrary {
+    pub path: PathBuf,
+    pub build_id: Option<String>,
+    pub version_definitions: Vec<String>,
+}
+
+impl ConflictingLibrary {
+    /// A scanned library with a SONAME, reported at `path`; None otherwise
+    pub fn from_binary(info: &BinaryInfo, path: PathBuf) -> Option<(String, Self)> {
+        let soname = info.soname.clone().filter(|_| info.is_library)?;
+        let build_id = info.build_id.clone().or_else(|| info.uuid.clone());
+        Some((soname, Self { path, build_id, version_definitions: info.version_definitions.clone() }))
+    }
+}
+
+/// SONAMEs claimed by more than one distinct build, by SONAME; a library
+/// without a build-id counts as distinct from one with
+pub fn soname_conflicts(libraries: Vec<(String, ConflictingLibrary)>) -> Vec<SonameConflict> {
+    let mut by_soname: BTreeMap<String, Vec<ConflictingLibrary>> = BTreeMap::new();
+    for (soname, library) in libraries {
+        by_soname.entry(soname).or_default().push(library);
+    }
+
+    by_soname.into_iter()
+        .filter(|(_, libraries)| libraries.iter().map(|library| &library.build_id).collect::<BTreeSet<_>>().len() > 1)
+        .map(|(soname, mut libraries)| {
+            libraries.sort_by(|a, b| a.path.cmp(&b.path));
+            SonameConflict { soname, libraries }
+        })
+        .collect()
+}
+
+/// The `soname-conflict` warning for the library at `path`, if it's in one
+pub fn warning(conflicts: &[SonameConflict], path: &Path) -> Option<ScanWarning> {
+    let conflict = conflicts.iter().find(|conflict| conflict.libraries.iter().any(|library| library.path == path))?;
+    let others: Vec<String> = conflict.libraries.iter()
+        .filter(|library| library.path != path)
+        .map(|library| format!("{} ({})", library.path.display(), library.build_id.as_deref().unwrap_or("no build-id")))
+        .collect();
+    Some(ScanWarning::new(
+        "soname-conflict",
+        format!("{} is also provided by a different build: {}", conflict.soname, others.join(", ")),
+    ))
+}
+
+pub fn write_human(conflicts: &[SonameConflict], out: &mut dyn Write) -> Result<()> {
+    writeln!(out)?;
+    writeln!(out, "{}", "Conflicts".bright_cyan().bold())?;
+    for conflict in conflicts {
+        writeln!(out)?;
+        writeln!(out, "   {} {}",
+            conflict.soname.bright_white().bold(),
+            format!("({} libraries)", conflict.libraries.len()).bright_black()
+        )?;
+        for library in &conflict.libraries {
+            writeln!(out, "      {} {}",
+                library.path.display().to_string().white(),
+                library.build_id.as_deref().unwrap_or("no build-id").yellow()
+            )?;
+            if !library.version_definitions.is_empty() {
+                writeln!(out, "         {}", format!("defines {}", library.version_definitions.join(", ")).bright_black())?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,413 @@
+//! Self-contained application formats wrapped around a native host:
+//! AppImages (ELF runtime + squashfs) and .NET single-file bundles
+//! (apphost + embedded assemblies)
+
+use std::collections::BTreeMap;
+use std::io::Read;
+use anyhow::{Context, Result};
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContainerFormat {
+    /// Type 1 (ISO 9660) or type 2 (squashfs) AppImage
+    Appimage,
+    /// .NET single-file apphost bundle
+    DotnetBundle,
+}
+
+impl ContainerFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContainerFormat::Appimage => "AppImage",
+            ContainerFormat::DotnetBundle => ".NET single-file bundle",
+        }
+    }
+}
+
+/// A file inside a container payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddedFile {
+    pub path: String,
+    pub size: u64,
+}
+
+/// What `detect` found, and where the payload starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Container {
+    pub format: ContainerFormat,
+    /// Squashfs image (AppImage) or bundle header (.NET) offset, when present
+    pub payload_offset: Option<u64>,
+}
+
+/// `hsqs`, little-endian squashfs 4.0
+const SQUASHFS_MAGIC: &[u8; 4] = b"hsqs";
+
+/// SHA-256 of ".net core bundle", written after the bundle header offset
+const BUNDLE_SIGNATURE: [u8; 32] = [
+    0x8b, 0x12, 0x02, 0xb9, 0x6a, 0x61, 0x20, 0x38, 0x72, 0x7b, 0x93, 0x02, 0x14, 0xd7, 0xa0, 0x32,
+    0x13, 0xf5, 0xb9, 0xe6, 0xef, 0xae, 0x33, 0x18, 0xee, 0x3b, 0x2d, 0xce, 0x24, 0xb3, 0x6a, 0xae,
+];
+
+/// Recognize a container around the native binary in `data`
+pub fn detect(data: &[u8]) -> Option<Container> {
+    // AppImages mark the ELF e_ident padding with "AI" and the type
+    if data.len() > 10 && &data[0..4] == b"\x7fELF" && &data[8..10] == b"AI" && matches!(data[10], 1 | 2) {
+        let payload_offset = if data[10] == 2 { squashfs_offset(data) } else { None };
+        return Some(Container { format: ContainerFormat::Appimage, payload_offset });
+    }
+
+    // The apphost template carries the signature too, with a zero header offset
+    let position = memchr::memmem::find(data, &BUNDLE_SIGNATURE)?;
+    let header_offset = i64::from_le_bytes(data.get(position.checked_sub(8)?..position)?.try_into().ok()?);
+    if header_offset <= 0 || header_offset as u64 >= data.len() as u64 {
+        return None;
+    }
+
+    Some(Container {
+        format: ContainerFormat::DotnetBundle,
+        payload_offset: Some(header_offset as u64),
+    })
+}
+
+/// The AppImage runtime appends the squashfs image right after its section
+/// headers, which are the last thing in the ELF file
+fn squashfs_offset(data: &[u8]) -> Option<u64> {
+    let elf = goblin::elf::Elf::parse_header(data).ok()?;
+    let offset = elf.e_shoff.checked_add(elf.e_shentsize as u64 * elf.e_shnum as u64)?;
+    let magic = data.get(offset as usize..offset as usize + 4)?;
+    (magic == SQUASHFS_MAGIC).then_some(offset)
+}
+
+/// Contents of a container payload
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Payload {
+    pub file_count: u64,
+    pub native_libraries: Vec<EmbeddedFile>,
+}
+
+/// List the container payload without extracting it
+pub fn read_payload(data: &[u8], container: &Container) -> Result<Payload> {
+    let Some(offset) = container.payload_offset else {
+        anyhow::bail!("no {} payload found", container.format.as_str());
+    };
+    let payload = data.get(offset as usize..).context("payload offset past the end of the file")?;
+
+    match container.format {
+        ContainerFormat::Appimage => {
+            let files = list_squashfs(payload)?;
+            Ok(Payload {
+                file_count: files.len() as u64,
+                native_libraries: files.into_iter().filter(|file| is_library_name(&file.path)).collect(),
+            })
+        }
+        ContainerFormat::DotnetBundle => read_bundle(data, offset),
+    }
+}
+
+/// Shared libraries by naming convention (`libfoo.so`, `libfoo.so.1`, `foo.dylib`)
+fn is_library_name(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.ends_with(".so") || name.contains(".so.") || name.ends_with(".dylib")
+}
+
+// .NET bundle manifest
+// https://github.com/dotnet/runtime/blob/main/src/installer/managed/Microsoft.NET.HostModel/Bundle/Manifest.cs
+
+const BUNDLE_NATIVE_BINARY: u8 = 2;
+
+/// Bundle manifest; native libraries are the entries marked `NativeBinary`
+fn read_bundle(data: &[u8], header_offset: u64) -> Result<Payload> {
+    let mut r = Cursor { data, pos: header_offset as usize };
+
+    let major = r.u32()?;
+    let _minor = r.u32()?;
+    let file_count = r.u32()?;
+    let _bundle_id = r.string()?;
+
+    if major >= 2 {
+        // deps.json and runtimeconfig.json locations, then flags
+        r.skip(8 * 5)?;
+    }
+
+    let mut payload = Payload { file_count: file_count as u64, ..Default::default() };
+    for _ in 0..file_count {
+        let _offset = r.u64()?;
+        let size = r.u64()?;
+        if major >= 6 {
+            let _compressed_size = r.u64()?;
+        }
+        let file_type = r.u8()?;
+        let path = r.string()?;
+
+        if file_type == BUNDLE_NATIVE_BINARY {
+            payload.native_libraries.push(EmbeddedFile { path: path.replace('\\', "/"), size });
+        }
+    }
+
+    Ok(payload)
+}
+
+// Squashfs 4.0
+// https://dr-emann.github.io/squashfs/
+
+const COMPRESSION_GZIP: u16 = 1;
+const COMPRESSION_ZSTD: u16 = 6;
+
+const METADATA_UNCOMPRESSED: u16 = 0x8000;
+const INODE_BASIC_DIR: u16 = 1;
+const INODE_BASIC_FILE: u16 = 2;
+const INODE_EXT_DIR: u16 = 8;
+const INODE_EXT_FILE: u16 = 9;
+
+/// Guards against corrupt or hostile images
+const MAX_DEPTH: usize = 64;
+const MAX_ENTRIES: usize = 1 << 20;
+
+/// Walk the directory tree of a squashfs image without mounting it
+fn list_squashfs(image: &[u8]) -> Result<Vec<EmbeddedFile>> {
+    let mut sb = Cursor { data: image, pos: 0 };
+    if sb.bytes(4)? != SQUASHFS_MAGIC {
+        anyhow::bail!("not a little-endian squashfs image");
+    }
+    sb.skip(16)?;
+    let compression = sb.u16()?;
+    sb.skip(6)?;
+    let (major, minor) = (sb.u16()?, sb.u16()?);
+    if (major, minor) != (4, 0) {
+        anyhow::bail!("squashfs {}.{} is not supported", major, minor);
+    }
+    if !matches!(compression, COMPRESSION_GZIP | COMPRESSION_ZSTD) {
+        let name = match compression {
+            2 => "lzma",
+            3 => "lzo",
+            4 => "xz",
+            5 => "lz4",
+            _ => "unknown",
+        };
+        anyhow::bail!("{}-compressed squashfs is not supported", name);
+    }
+    let root_inode = sb.u64()?;
+    let _bytes_used = sb.u64()?;
+    let id_table = sb.u64()?;
+    let xattr_table = sb.u64()?;
+    let inode_table = sb.u64()?;
+    let directory_table = sb.u64()?;
+    let fragment_table = sb.u64()?;
+    let export_table = sb.u64()?;
+
+    // Lookup tables follow the directory table; the nearest one ends it
+    let directory_end = [fragment_table, export_table, id_table, xattr_table]
+        .into_iter()
+        .filter(|&start| start > directory_table && start != u64::MAX)
+        .min()
+        .unwrap_or(image.len() as u64);
+
+    let inodes = MetadataTable::read(image, inode_table, directory_table, compression)?;
+    let directories = MetadataTable::read(image, directory_table, directory_end, compression)?;
+
+    let mut walk = Walk { inodes: &inodes, directories: &directories, files: Vec::new(), entries: 0 };
+    walk.directory(root_inode, "", 0)?;
+    Ok(walk.files)
+}
+
+/// A metadata table decompressed in one piece, with the position of each
+/// block so inode references (block offset << 16 | offset) can be resolved
+struct MetadataTable {
+    data: Vec<u8>,
+    blocks: BTreeMap<u64, usize>,
+}
+
+impl MetadataTable {
+    fn read(image: &[u8], start: u64, end: u64, compression: u16) -> Result<Self> {
+        let mut table = Self { data: Vec::new(), blocks: BTreeMap::new() };
+        let mut pos = start;
+
+        while pos + 2 <= end {
+            let block = pos - start;
+            let mut r = Cursor { data: image, pos: pos as usize };
+            let header = r.u16()?;
+            let size = (header & !METADATA_UNCOMPRESSED) as usize;
+            let raw = r.bytes(size)?;
+
+            let decoded = if header & METADATA_UNCOMPRESSED != 0 {
+                Ok(raw.to_vec())
+            } else {
+                decompress(raw, compression)
+            };
+            match decoded {
+                Ok(decoded) => {
+                    table.blocks.insert(block, table.data.len());
+                    table.data.extend_from_slice(&decoded);
+                }
+                // Past the first block this is another table's metadata
+                Err(_) if !table.blocks.is_empty() => break,
+                Err(e) => return Err(e),
+            }
+
+            pos += 2 + size as u64;
+        }
+
+        Ok(table)
+    }
+
+    /// Cursor at a (block, offset) reference
+    fn at(&self, block: u64, offset: usize) -> Result<Cursor<'_>> {
+        let start = self.blocks.get(&block).context("reference to a missing metadata block")?;
+        Ok(Cursor { data: &self.data, pos: start + offset })
+    }
+}
+
+fn decompress(raw: &[u8], compression: u16) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    match compression {
+        COMPRESSION_GZIP => {
+            flate2::read::ZlibDecoder::new(raw).read_to_end(&mut out)?;
+        }
+        COMPRESSION_ZSTD => {
+            let mut reader = raw;
+            ruzstd::streaming_decoder::StreamingDecoder::new(&mut reader)
+                .map_err(|e| anyhow::anyhow!("zstd: {}", e))?
+                .read_to_end(&mut out)?;
+        }
+        other => anyhow::bail!("unsupported squashfs compression {}", other),
+    }
+    Ok(out)
+}
+
+struct Walk<'a> {
+    inodes: &'a MetadataTable,
+    directories: &'a MetadataTable,
+    files: Vec<EmbeddedFile>,
+    entries: usize,
+}
+
+impl Walk<'_> {
+    fn directory(&mut self, inode_ref: u64, prefix: &str, depth: usize) -> Result<()> {
+        if depth > MAX_DEPTH {
+            anyhow::bail!("directory tree deeper than {}", MAX_DEPTH);
+        }
+
+        let mut inode = self.inodes.at(inode_ref >> 16, (inode_ref & 0xffff) as usize)?;
+        let inode_type = inode.u16()?;
+        inode.skip(14)?;
+        let (start_block, size, offset) = match inode_type {
+            INODE_BASIC_DIR => {
+                let start_block = inode.u32()?;
+                inode.skip(4)?;
+                let size = inode.u16()? as u32;
+                (start_block, size, inode.u16()?)
+            }
+            INODE_EXT_DIR => {
+                inode.skip(4)?;
+                let size = inode.u32()?;
+                let start_block = inode.u32()?;
+                inode.skip(6)?;
+                (start_block, size, inode.u16()?)
+            }
+            other => anyhow::bail!("inode type {} is not a directory", other),
+        };
+
+        // The size counts "." and ".." which aren't stored
+        let mut listing = self.directories.at(start_block as u64, offset as usize)?;
+        let end = listing.pos + (size as usize).saturating_sub(3);
+
+        while listing.pos < end {
+            let count = listing.u32()? as usize + 1;
+            let inode_block = listing.u32()? as u64;
+            listing.skip(4)?;
+
+            for _ in 0..count {
+                self.entries += 1;
+                if self.entries > MAX_ENTRIES {
+                    anyhow::bail!("more than {} entries", MAX_ENTRIES);
+                }
+
+                let inode_offset = listing.u16()? as u64;
+                listing.skip(2)?;
+                let entry_type = listing.u16()?;
+                let name_size = listing.u16()? as usize + 1;
+                let name = String::from_utf8_lossy(listing.bytes(name_size)?);
+                let path = format!("{}{}", prefix, name);
+                let child = inode_block << 16 | inode_offset;
+
+                match entry_type {
+                    INODE_BASIC_DIR => self.directory(child, &format!("{}/", path), depth + 1)?,
+                    INODE_BASIC_FILE => {
+                        let size = self.file_size(child)?;
+                        self.files.push(EmbeddedFile { path, size });
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn file_size(&self, inode_ref: u64) -> Result<u64> {
+        let mut inode = self.inodes.at(inode_ref >> 16, (inode_ref & 0xffff) as usize)?;
+        let inode_type = inode.u16()?;
+        inode.skip(14)?;
+        match inode_type {
+            INODE_BASIC_FILE => {
+                inode.skip(12)?;
+                Ok(inode.u32()? as u64)
+            }
+            INODE_EXT_FILE => {
+                inode.skip(8)?;
+                inode.u64()
+            }
+            other => anyhow::bail!("inode type {} is not a file", other),
+        }
+    }
+}
+
+/// Little-endian reader over a byte slice
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self.pos.checked_add(len)
+            .and_then(|end| self.data.get(self.pos..end))
+            .context("unexpected end of data")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<()> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into()?))
+    }
+
+    /// .NET BinaryWriter string: 7-bit encoded length, then UTF-8
+    fn string(&mut self) -> Result<String> {
+        let mut len = 0usize;
+        for shift in (0..35).step_by(7) {
+            let byte = self.u8()?;
+            len |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(String::from_utf8_lossy(self.bytes(len)?).into_owned());
+            }
+        }
+        anyhow::bail!("malformed string length")
+    }
+}
@@ -1,83 +1,371 @@
 use anyhow::Result;
+use colored::Colorize;
 use reqwest::blocking::Client;
+use reqwest::header::RANGE;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::Duration;
 
+use crate::deep::{self, Limits};
+use crate::elf;
+
+/// Placeholders expanded in templated server URLs
+const BUILD_ID_PLACEHOLDER: &str = "{buildid}";
+const ARTIFACT_PLACEHOLDER: &str = "{artifact}";
+
+/// Consecutive failed lookups after which remote checks stop for the run
+pub const DEFAULT_MAX_FAILURES: usize = 20;
+
+/// Default for `--remote-concurrency`
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// `skipped_reason` of binaries whose remote check the breaker skipped
+pub const SKIPPED_CIRCUIT_OPEN: &str = "circuit-open";
+
+/// Returned instead of issuing requests once the circuit breaker is open
+#[derive(Debug, thiserror::Error)]
+#[error("debuginfod lookups stopped after {0} consecutive failures")]
+pub struct CircuitOpen(pub usize);
+
+/// Files a debuginfod server can serve for a build-id
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Artifact {
+    Debuginfo,
+    Executable,
+}
+
+impl Artifact {
+    /// Path component in the debuginfod protocol
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Artifact::Debuginfo => "debuginfo",
+            Artifact::Executable => "executable",
+        }
+    }
+}
+
+/// One debuginfod server entry
+///
+/// Either a base URL using the standard `/buildid/<id>/debuginfo` layout, or
+/// a template containing `{buildid}` for stores with their own path scheme.
+/// Templates serve only debuginfo unless they also contain `{artifact}`.
+/// `|head-unsupported` makes availability checks use a ranged GET instead of
+/// HEAD, for object stores that reject HEAD.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Server {
+    url: String,
+    template: bool,
+    head_unsupported: bool,
+}
+
+impl Server {
+    /// Parse `URL[|annotation...]`; a URL containing braces is a template
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut parts = spec.split('|');
+        let url = parts.next().unwrap_or_default().trim().to_string();
+        if url.is_empty() {
+            anyhow::bail!("invalid debuginfod server '{}': empty URL", spec);
+        }
+        
+        let mut head_unsupported = false;
+        for annotation in parts {
+            match annotation.trim() {
+                "head-unsupported" => head_unsupported = true,
+                other => anyhow::bail!("invalid debuginfod server '{}': unknown annotation '{}'", spec, other),
+            }
+        }
+        
+        let template = url.contains('{') || url.contains('}');
+        if template {
+            // Anything left after removing the placeholders is a typo like {build_id}
+            let without_id = url.replace(BUILD_ID_PLACEHOLDER, "");
+            let rest = without_id.replace(ARTIFACT_PLACEHOLDER, "");
+            if without_id.len() == url.len() || rest.contains('{') || rest.contains('}') {
+                anyhow::bail!(
+                    "invalid debuginfod server '{}': templates must use {} (and optionally {})",
+                    spec, BUILD_ID_PLACEHOLDER, ARTIFACT_PLACEHOLDER
+                );
+            }
+        }
+        
+        Ok(Self { url, template, head_unsupported })
+    }
+    
+    /// URL of the debug file for `build_id` on this server
+    pub fn debuginfo_url(&self, build_id: &str) -> String {
+        self.artifact_url(build_id, Artifact::Debuginfo)
+            .expect("every server serves debuginfo")
+    }
+    
+    /// URL of `artifact` for `build_id`, or `None` if this server can't serve it
+    pub fn artifact_url(&self, build_id: &str, artifact: Artifact) -> Option<String> {
+        if !self.template {
+            return Some(format!("{}/buildid/{}/{}", self.url.trim_end_matches('/'), build_id, artifact.as_str()));
+        }
+        
+        if self.url.contains(ARTIFACT_PLACEHOLDER) {
+            Some(self.url.replace(BUILD_ID_PLACEHOLDER, build_id).replace(ARTIFACT_PLACEHOLDER, artifact.as_str()))
+        } else if artifact == Artifact::Debuginfo {
+            Some(self.url.replace(BUILD_ID_PLACEHOLDER, build_id))
+        } else {
+            None
+        }
+    }
+    
+    pub fn is_template(&self) -> bool {
+        self.template
+    }
+    
+    pub fn head_unsupported(&self) -> bool {
+        self.head_unsupported
+    }
+}
+
+/// Stops remote lookups when no server has answered for `max_failures`
+/// lookups in a row (blocked proxy, no network), so a large scan doesn't sit
+/// through thousands of timeouts
+///
+/// A lookup fails when every server it tried errored or returned a 5xx; a
+/// 404 is an answer. Atomics so concurrent lookups can share one client.
+struct CircuitBreaker {
+    /// `None` with --no-circuit-breaker
+    max_failures: Option<usize>,
+    consecutive_failures: AtomicUsize,
+    open: AtomicBool,
+}
+
+impl CircuitBreaker {
+    fn check(&self) -> Result<(), CircuitOpen> {
+        match self.max_failures {
+            Some(max_failures) if self.open.load(Ordering::Relaxed) => Err(CircuitOpen(max_failures)),
+            _ => Ok(()),
+        }
+    }
+    
+    fn record(&self, answered: bool) {
+        let Some(max_failures) = self.max_failures else {
+            return;
+        };
+        
+        if answered {
+            self.consecutive_failures.store(0, Ordering::Relaxed);
+            return;
+        }
+        
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        // Only the lookup that opens the circuit reports it
+        if failures >= max_failures && !self.open.swap(true, Ordering::Relaxed) {
+            eprintln!(
+                "{} no debuginfod server answered {} lookups in a row; skipping remote checks for the rest of the run (--no-circuit-breaker to keep trying)",
+                "warning:".yellow().bold(),
+                failures,
+            );
+        }
+    }
+}
+
 /// Client for debuginfod servers (ELF symbol servers)
 pub struct DebuginfodClient {
-    servers: Vec<String>,
+    servers: Vec<Server>,
     client: Client,
+    breaker: CircuitBreaker,
 }
 
 impl DebuginfodClient {
-    pub fn new(custom_servers: Vec<String>) -> Self {
-        let mut servers = custom_servers;
+    pub fn new(custom_servers: Vec<String>) -> Result<Self> {
+        let mut specs = custom_servers;
         
-        // Add default servers if none specified
-        if servers.is_empty() {
-            // Check environment variable
-            if let Ok(env_servers) = std::env::var("DEBUGINFOD_URLS") {
-                servers.extend(env_servers.split_whitespace().map(|s| s.to_string()));
-            }
-            
-            // Add common public servers
-            if servers.is_empty() {
-                servers.push("https://debuginfod.elfutils.org/".to_string());
-                servers.push("https://debuginfod.ubuntu.com/".to_string());
-                servers.push("https://debuginfod.fedoraproject.org/".to_string());
-                servers.push("https://debuginfod.debian.net/".to_string());
-            }
+        // Common public servers if none specified (DEBUGINFOD_URLS is the
+        // caller's business, see `Environment`)
+        if specs.is_empty() {
+            specs.push("https://debuginfod.elfutils.org/".to_string());
+            specs.push("https://debuginfod.ubuntu.com/".to_string());
+            specs.push("https://debuginfod.fedoraproject.org/".to_string());
+            specs.push("https://debuginfod.debian.net/".to_string());
         }
         
+        let servers = specs.iter()
+            .map(|spec| Server::parse(spec))
+            .collect::<Result<Vec<_>>>()?;
+        
         let client = Client::builder()
             .timeout(Duration::from_secs(5))
             .build()
             .unwrap_or_else(|_| Client::new());
         
-        Self { servers, client }
+        let breaker = CircuitBreaker {
+            max_failures: Some(DEFAULT_MAX_FAILURES),
+            consecutive_failures: AtomicUsize::new(0),
+            open: AtomicBool::new(false),
+        };
+        
+        Ok(Self { servers, client, breaker })
+    }
+    
+    /// Stop lookups after `max_failures` consecutive failures; `None` never stops
+    pub fn with_circuit_breaker(mut self, max_failures: Option<usize>) -> Self {
+        self.breaker.max_failures = max_failures.map(|max| max.max(1));
+        self
+    }
+    
+    /// Whether lookups have stopped for the rest of the run
+    pub fn is_circuit_open(&self) -> bool {
+        self.breaker.check().is_err()
     }
     
     /// Check if debug symbols are available for given build-id
     pub fn check_available(&self, build_id: &str) -> Result<(bool, Option<String>)> {
+        self.check_artifact(build_id, Artifact::Debuginfo)
+    }
+    
+    /// Check if any server has `artifact` for the build-id
+    /// Fails with `CircuitOpen` once the circuit breaker has tripped
+    pub fn check_artifact(&self, build_id: &str, artifact: Artifact) -> Result<(bool, Option<String>)> {
+        self.breaker.check()?;
+        
+        let mut attempted = false;
+        let mut answered = false;
         for server in &self.servers {
-            let url = format!("{}/buildid/{}/debuginfo", server.trim_end_matches('/'), build_id);
+            let Some(url) = server.artifact_url(build_id, artifact) else {
+                continue;
+            };
+            attempted = true;
+            
+            // HEAD where supported, otherwise fetch a single byte
+            let request = if server.head_unsupported {
+                self.client.get(&url).header(RANGE, "bytes=0-0")
+            } else {
+                self.client.head(&url)
+            };
             
-            // Send HEAD request to check availability
-            match self.client.head(&url).send() {
+            match request.send() {
                 Ok(response) => {
+                    log::debug!("{} {}: {}", if server.head_unsupported { "GET" } else { "HEAD" }, url, response.status());
                     if response.status().is_success() {
+                        self.breaker.record(true);
                         return Ok((true, Some(url)));
                     }
+                    answered |= !response.status().is_server_error();
                 }
-                Err(_) => continue,
+                Err(e) => log::debug!("{}: {}", url, e),
             }
         }
         
+        if attempted {
+            self.breaker.record(answered);
+        }
         Ok((false, None))
     }
     
+    /// `check_artifact` for each build-id, up to `jobs` at once on one
+    /// connection pool; build-ids the circuit breaker skipped are left out
+    pub fn check_artifacts(&self, build_ids: &[String], artifact: Artifact, jobs: usize) -> HashMap<String, (bool, Option<String>)> {
+        let tasks: Vec<(u64, &String)> = build_ids.iter().map(|id| (0, id)).collect();
+        let limits = Limits { jobs, memory_bytes: u64::MAX };
+        let (answers, _) = deep::run(tasks, limits, |id| self.check_artifact(id, artifact).ok().map(|answer| (id.clone(), answer)));
+        answers.into_iter().flatten().collect()
+    }
+    
     /// Download debug symbols for given build-id
     pub fn download(&self, build_id: &str, output_path: &std::path::Path) -> Result<()> {
+        self.download_artifact(build_id, Artifact::Debuginfo, output_path)
+    }
+    
+    /// Download the original executable for given build-id
+    pub fn download_executable(&self, build_id: &str, output_path: &std::path::Path) -> Result<()> {
+        self.download_artifact(build_id, Artifact::Executable, output_path)
+    }
+    
+    fn download_artifact(&self, build_id: &str, artifact: Artifact, output_path: &std::path::Path) -> Result<()> {
+        self.breaker.check()?;
+        
+        let mut attempted = false;
+        let mut answered = false;
         for server in &self.servers {
-            let url = format!("{}/buildid/{}/debuginfo", server.trim_end_matches('/'), build_id);
+            let Some(url) = server.artifact_url(build_id, artifact) else {
+                continue;
+            };
+            attempted = true;
             
             match self.client.get(&url).send() {
                 Ok(response) => {
+                    log::debug!("GET {}: {}", url, response.status());
                     if response.status().is_success() {
+                        self.breaker.record(true);
                         let bytes = response.bytes()?;
-                        std::fs::write(output_path, bytes)?;
+                        // Never leave a partial download under the final name
+                        crate::output::write_atomic(output_path, |w| Ok(w.write_all(&bytes)?))?;
                         return Ok(());
                     }
+                    answered |= !response.status().is_server_error();
                 }
-                Err(_) => continue,
+                Err(e) => log::debug!("{}: {}", url, e),
             }
         }
         
-        anyhow::bail!("Failed to download debug symbols from any server")
+        if attempted {
+            self.breaker.record(answered);
+        }
+        anyhow::bail!("Failed to download {} from any server", artifact.as_str())
     }
     
     /// Get list of configured servers
-    pub fn servers(&self) -> &[String] {
+    pub fn servers(&self) -> &[Server] {
         &self.servers
     }
 }
 
+/// Debug files downloaded for analysis (`--remote-dwarf`), fetched at most
+/// once per build-id and kept in a temporary directory removed on drop
+pub struct DownloadCache {
+    dir: PathBuf,
+    files: Mutex<HashMap<String, Option<PathBuf>>>,
+}
+
+impl DownloadCache {
+    pub fn new() -> Result<Self> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "symwalker-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir, files: Mutex::new(HashMap::new()) })
+    }
+    
+    /// Local copy of the debug file for `build_id`, downloaded on first use
+    /// `None` if no server had it or it carries another build-id; failures
+    /// aren't retried within the run
+    pub fn debug_file(&self, client: &DebuginfodClient, build_id: &str) -> Option<PathBuf> {
+        let mut files = self.files.lock().unwrap();
+        files.entry(build_id.to_string())
+            .or_insert_with(|| {
+                let path = self.dir.join(format!("{}.debug", build_id));
+                let downloaded = client.download(build_id, &path).is_ok()
+                    && elf::debug_file_build_id(&path).as_deref() == Some(build_id);
+                if !downloaded {
+                    let _ = std::fs::remove_file(&path);
+                }
+                downloaded.then_some(path)
+            })
+            .clone()
+    }
+    
+    /// Downloads attempted so far, successful or not
+    pub fn len(&self) -> usize {
+        self.files.lock().unwrap().len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for DownloadCache {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
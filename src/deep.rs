@@ -0,0 +1,174 @@
+//! Deep analysis (`--dwarf`) on its own bounded worker pool
+//!
+//! Parsing DWARF costs far more time and memory than reading headers, so it
+//! runs after the scan, on `--deep-jobs` workers; a task is only started
+//! while the estimated memory of the running ones stays within
+//! `--deep-memory-bytes`
+
+use std::fs;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+use anyhow::{Context, Result};
+
+use crate::binary::{BinaryInfo, ScanContext};
+use crate::cli::Args;
+use crate::dwarf;
+
+/// Default for `--deep-memory-bytes`
+pub const DEFAULT_MEMORY_BYTES: u64 = 1 << 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Worker threads (at least one)
+    pub jobs: usize,
+    /// Budget for the estimates of the tasks running at once; a task over
+    /// the budget runs alone
+    pub memory_bytes: u64,
+}
+
+impl Limits {
+    pub fn from_args(args: &Args) -> Self {
+        let jobs = args.deep_jobs
+            .or_else(|| std::thread::available_parallelism().ok().map(usize::from))
+            .unwrap_or(1);
+        Self { jobs: jobs.max(1), memory_bytes: args.deep_memory_bytes }
+    }
+}
+
+/// What the pool did, for `--stats`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeepStats {
+    pub tasks: usize,
+    /// From the first task starting to the last one finishing
+    pub wall: Duration,
+    /// Time spent in tasks, summed over workers
+    pub busy: Duration,
+    /// Largest sum of estimates running at once
+    pub peak_bytes: u64,
+    /// Most tasks running at once
+    pub peak_tasks: usize,
+}
+
+struct Queue<T> {
+    pending: std::iter::Peekable<std::iter::Enumerate<std::vec::IntoIter<(u64, T)>>>,
+    running: usize,
+    running_bytes: u64,
+    stats: DeepStats,
+}
+
+/// Run `work` on each task, given as `(estimated bytes, task)`, and return
+/// the results in task order
+///
+/// Tasks start in order: one that doesn't fit the memory budget waits for
+/// running tasks to finish rather than being overtaken by smaller ones.
+pub fn run<T, R, F>(tasks: Vec<(u64, T)>, limits: Limits, work: F) -> (Vec<R>, DeepStats)
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let count = tasks.len();
+    let queue = Mutex::new(Queue {
+        pending: tasks.into_iter().enumerate().peekable(),
+        running: 0,
+        running_bytes: 0,
+        stats: DeepStats { tasks: count, ..DeepStats::default() },
+    });
+    let finished = Condvar::new();
+    let start = Instant::now();
+
+    let next = || -> Option<(usize, u64, T)> {
+        let mut queue = queue.lock().unwrap();
+        loop {
+            let (running, running_bytes) = (queue.running, queue.running_bytes);
+            let fits = match queue.pending.peek() {
+                None => return None,
+                Some((_, (bytes, _))) => running == 0 || running_bytes + bytes <= limits.memory_bytes,
+            };
+            if fits {
+                break;
+            }
+            queue = finished.wait(queue).unwrap();
+        }
+        let (index, (bytes, task)) = queue.pending.next()?;
+        queue.running += 1;
+        queue.running_bytes += bytes;
+        queue.stats.peak_tasks = queue.stats.peak_tasks.max(queue.running);
+        queue.stats.peak_bytes = queue.stats.peak_bytes.max(queue.running_bytes);
+        Some((index, bytes, task))
+    };
+
+    let mut results: Vec<(usize, R)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..limits.jobs.max(1).min(count))
+            .map(|_| scope.spawn(|| {
+                let mut done = Vec::new();
+                while let Some((index, bytes, task)) = next() {
+                    let started = Instant::now();
+                    done.push((index, work(task)));
+
+                    let mut queue = queue.lock().unwrap();
+                    queue.running -= 1;
+                    queue.running_bytes -= bytes;
+                    queue.stats.busy += started.elapsed();
+                    finished.notify_all();
+                }
+                done
+            }))
+            .collect();
+        workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+    });
+
+    let mut stats = queue.into_inner().unwrap().stats;
+    stats.wall = start.elapsed();
+    results.sort_by_key(|(index, _)| *index);
+    (results.into_iter().map(|(_, result)| result).collect(), stats)
+}
+
+/// Run the deep analyses on every binary, merging the results into them
+pub fn annotate_all(binaries: &mut [BinaryInfo], args: &Args, ctx: &ScanContext) -> DeepStats {
+    let tasks: Vec<(u64, &mut BinaryInfo)> = binaries.iter_mut()
+        .filter(|info| wanted(info, args))
+        .map(|info| (estimate(info), info))
+        .collect();
+    // A binary that vanished since the scan keeps its header results
+    let (_, stats) = run(tasks, Limits::from_args(args), |info| {
+        let _ = annotate(info, args, ctx);
+    });
+    stats
+}
+
+/// The deep analyses of one binary, in the calling thread
+pub fn annotate(info: &mut BinaryInfo, args: &Args, ctx: &ScanContext) -> Result<()> {
+    if !wanted(info, args) {
+        return Ok(());
+    }
+    // A carved image is read from the file it was found in
+    let path = info.carved_from.as_ref().map_or(&info.file_path, |carved| &carved.container);
+    let file = fs::File::open(path)?;
+    let mmap = unsafe { memmap2::Mmap::map(&file)? };
+    let data = match info.carved_from {
+        Some(ref carved) => carved.slice(&mmap).context("carved image past the end of its file")?,
+        None => &mmap[..],
+    };
+    let remote = ctx.debuginfod.as_ref().zip(ctx.remote_debug_files.as_ref());
+    dwarf::annotate(info, data, remote);
+    Ok(())
+}
+
+/// Header-only results of damaged or unsupported files have nothing more to read
+fn wanted(info: &BinaryInfo, args: &Args) -> bool {
+    args.dwarf && !info.partial_analysis && !info.is_unsupported()
+}
+
+/// Memory the analyses of `info` are expected to need: the uncompressed
+/// size of the debug sections they will read
+pub fn estimate(info: &BinaryInfo) -> u64 {
+    let path = dwarf::dwarf_source(info).unwrap_or_else(|| info.file_path.clone());
+    let Ok(file) = fs::File::open(&path) else {
+        return 0;
+    };
+    match unsafe { memmap2::Mmap::map(&file) } {
+        Ok(mmap) => dwarf::debug_section_bytes(&mmap),
+        Err(_) => 0,
+    }
+}
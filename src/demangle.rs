@@ -0,0 +1,101 @@
+use std::collections::{BTreeMap, HashSet};
+
+/// Demangle a symbol name (Rust legacy/v0 or Itanium C++)
+/// Returns the input unchanged if it isn't recognized as mangled
+pub fn demangle(name: &str) -> String {
+    if let Ok(demangled) = rustc_demangle::try_demangle(name) {
+        return format!("{:#}", demangled);
+    }
+
+    if name.starts_with("_Z") || name.starts_with("__Z") {
+        if let Ok(symbol) = cpp_demangle::Symbol::new(name) {
+            if let Ok(demangled) = symbol.demangle(&cpp_demangle::DemangleOptions::default()) {
+                return demangled;
+            }
+        }
+    }
+
+    name.to_string()
+}
+
+/// Classify a symbol name by its mangling scheme
+/// Returns one of "rust-v0", "rust-legacy", "itanium", "swift" or "c"
+pub fn mangling_scheme(name: &str) -> &'static str {
+    // Mach-O prefixes every symbol with an extra underscore
+    let bare = name.strip_prefix('_').filter(|rest| {
+        rest.starts_with("_Z") || rest.starts_with("_R") || rest.starts_with('$') || rest.starts_with("_T0")
+    }).unwrap_or(name);
+
+    if bare.starts_with("_R") && rustc_demangle::try_demangle(bare).is_ok() {
+        return "rust-v0";
+    }
+
+    if bare.starts_with("_ZN") && has_rust_hash(bare) && rustc_demangle::try_demangle(bare).is_ok() {
+        return "rust-legacy";
+    }
+
+    if bare.starts_with("_Z") && cpp_demangle::Symbol::new(bare).is_ok() {
+        return "itanium";
+    }
+
+    if ["$s", "$S", "$e", "_T0"].iter().any(|prefix| bare.starts_with(prefix)) {
+        return "swift";
+    }
+
+    "c"
+}
+
+/// Count distinct symbol names per mangling scheme
+pub fn mangling_stats<'a>(names: impl Iterator<Item = &'a str>) -> BTreeMap<String, u64> {
+    let mut seen = HashSet::new();
+    let mut stats = BTreeMap::new();
+
+    for name in names {
+        if name.is_empty() || !seen.insert(name) {
+            continue;
+        }
+        *stats.entry(mangling_scheme(name).to_string()).or_insert(0) += 1;
+    }
+
+    stats
+}
+
+/// Panic runtime a Rust binary links, "abort" or "unwind", from the crate
+/// names in its symbols; None for non-Rust binaries, and when the names
+/// say nothing or contradict each other. `.eh_frame` and
+/// `rust_eh_personality` don't tell: the prebuilt std carries both either way
+pub fn panic_strategy<'a>(names: impl Iterator<Item = &'a str>, stats: &BTreeMap<String, u64>) -> Option<&'static str> {
+    if !stats.contains_key("rust-v0") && !stats.contains_key("rust-legacy") {
+        return None;
+    }
+
+    // Length-prefixed crate names, as both manglings spell them; raising
+    // a panic through libunwind is the unwinding runtime's job, and stays
+    // in the dynamic symbols of a stripped binary. C++ raises the same way
+    let cpp = stats.contains_key("itanium");
+    let (mut abort, mut unwind) = (false, false);
+    for name in names {
+        abort |= name.contains("11panic_abort");
+        unwind |= name.contains("12panic_unwind") || (!cpp && name.trim_start_matches('_') == "Unwind_RaiseException");
+    }
+
+    match (abort, unwind) {
+        (true, false) => Some("abort"),
+        (false, true) => Some("unwind"),
+        _ => None,
+    }
+}
+
+/// Legacy Rust symbols end in a `17h<16 hex digits>E` hash segment,
+/// optionally followed by an LLVM `.llvm.NNNN` suffix
+fn has_rust_hash(name: &str) -> bool {
+    let name = name.split(".llvm.").next().unwrap_or(name);
+    let Some(body) = name.strip_suffix('E') else {
+        return false;
+    };
+
+    body.len() >= 19
+        && body.is_char_boundary(body.len() - 19)
+        && body[body.len() - 19..].starts_with("17h")
+        && body[body.len() - 16..].chars().all(|c| c.is_ascii_hexdigit())
+}
@@ -0,0 +1,184 @@
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+
+use crate::rpath::{self, AuditRoot};
+
+/// Trusted directories the glibc loader falls back to after ld.so.cache
+const DEFAULT_DIRS: &[&str] = &["/lib64", "/usr/lib64", "/lib", "/usr/lib"];
+
+/// Guard against `include` loops in ld.so.conf
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolvedDependency {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// What a library must match to be loadable into the binary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LibraryTarget {
+    pub is_64: bool,
+    pub is_little_endian: bool,
+    pub machine: u16,
+}
+
+/// System library directories: ld.so.conf (following includes), then the
+/// trusted defaults; as host paths under `sysroot` when given
+pub fn system_dirs(sysroot: Option<&Path>) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    read_ld_so_conf(Path::new("/etc/ld.so.conf"), sysroot, &mut dirs, 0);
+
+    for dir in DEFAULT_DIRS {
+        push_unique(&mut dirs, host_path(Path::new(dir), sysroot));
+    }
+
+    dirs
+}
+
+/// Resolve each DT_NEEDED entry like the loader would: DT_RPATH (only without
+/// DT_RUNPATH), DT_RUNPATH, then `system_dirs`
+/// Returns the resolved dependencies and the names that weren't found
+pub fn resolve(
+    needed: &[String],
+    rpath: &[String],
+    runpath: &[String],
+    root: &AuditRoot,
+    system_dirs: &[PathBuf],
+    target: LibraryTarget,
+) -> (Vec<ResolvedDependency>, Vec<String>) {
+    let rpath = if runpath.is_empty() { rpath } else { &[] };
+    let mut search_dirs: Vec<PathBuf> = rpath.iter().chain(runpath)
+        .filter_map(|entry| search_dir(entry, root))
+        .collect();
+    search_dirs.extend_from_slice(system_dirs);
+
+    let mut resolved = Vec::new();
+    let mut missing = Vec::new();
+
+    for name in needed {
+        // Names with a slash are loaded from that path, no search
+        let found = if name.contains('/') {
+            Some(host_path(Path::new(name), root.sysroot)).filter(|path| is_loadable(path, target))
+        } else {
+            search_dirs.iter()
+                .map(|dir| dir.join(name))
+                .find(|path| is_loadable(path, target))
+        };
+
+        match found {
+            Some(path) => resolved.push(ResolvedDependency { name: name.clone(), path }),
+            None => missing.push(name.clone()),
+        }
+    }
+
+    (resolved, missing)
+}
+
+/// Host directory for an RPATH/RUNPATH entry; `None` for entries using
+/// loader-dependent tokens (`$LIB`, `$PLATFORM`)
+fn search_dir(entry: &str, root: &AuditRoot) -> Option<PathBuf> {
+    if entry.is_empty() {
+        return Some(PathBuf::from("."));
+    }
+
+    let origin = std::path::absolute(root.binary.parent().unwrap_or(Path::new(".")))
+        .unwrap_or_else(|_| PathBuf::from("."));
+    let expanded = rpath::expand_origin(entry, &origin)?;
+
+    // $ORIGIN already points at the host location
+    if expanded != entry {
+        Some(rpath::normalize(Path::new(&expanded)))
+    } else {
+        Some(host_path(Path::new(entry), root.sysroot))
+    }
+}
+
+/// Absolute paths are re-rooted under `sysroot`
+fn host_path(path: &Path, sysroot: Option<&Path>) -> PathBuf {
+    match sysroot {
+        Some(sysroot) if path.is_absolute() => {
+            rpath::normalize(&sysroot.join(path.strip_prefix("/").unwrap_or(path)))
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+/// An ELF file of the right class, byte order and machine; the loader skips
+/// anything else (other architectures, linker scripts like libc.so)
+fn is_loadable(path: &Path, target: LibraryTarget) -> bool {
+    let mut header = [0u8; 20];
+    let read = fs::File::open(path).and_then(|mut file| file.read_exact(&mut header));
+    if read.is_err() || &header[..4] != b"\x7fELF" {
+        return false;
+    }
+
+    let is_64 = header[4] == 2;
+    let is_little_endian = header[5] == 1;
+    let machine = if is_little_endian {
+        u16::from_le_bytes([header[18], header[19]])
+    } else {
+        u16::from_be_bytes([header[18], header[19]])
+    };
+
+    LibraryTarget { is_64, is_little_endian, machine } == target
+}
+
+fn read_ld_so_conf(path: &Path, sysroot: Option<&Path>, dirs: &mut Vec<PathBuf>, depth: usize) {
+    if depth > MAX_INCLUDE_DEPTH {
+        return;
+    }
+    let Ok(text) = fs::read_to_string(host_path(path, sysroot)) else {
+        return;
+    };
+
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+
+        if let Some(patterns) = line.strip_prefix("include ").or_else(|| line.strip_prefix("include\t")) {
+            for pattern in patterns.split_whitespace() {
+                let pattern = path.parent().unwrap_or(Path::new("/")).join(pattern);
+                for conf in expand_include(&pattern, sysroot) {
+                    read_ld_so_conf(&conf, sysroot, dirs, depth + 1);
+                }
+            }
+        } else if !line.starts_with("hwcap") {
+            for dir in line.split(|c: char| c == ':' || c == ',' || c.is_whitespace()) {
+                if dir.starts_with('/') {
+                    push_unique(dirs, host_path(Path::new(dir), sysroot));
+                }
+            }
+        }
+    }
+}
+
+/// Expand a `*` in the last component of an include pattern, sorted like glob(3)
+fn expand_include(pattern: &Path, sysroot: Option<&Path>) -> Vec<PathBuf> {
+    let file_pattern = pattern.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let Some((prefix, suffix)) = file_pattern.split_once('*') else {
+        return vec![pattern.to_path_buf()];
+    };
+    let dir = pattern.parent().unwrap_or(Path::new("/"));
+
+    let Ok(entries) = fs::read_dir(host_path(dir, sysroot)) else {
+        return Vec::new();
+    };
+    let mut matches: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| {
+            name.len() >= prefix.len() + suffix.len() && name.starts_with(prefix) && name.ends_with(suffix)
+        })
+        .map(|name| dir.join(name))
+        .collect();
+    matches.sort();
+    matches
+}
+
+fn push_unique(dirs: &mut Vec<PathBuf>, dir: PathBuf) {
+    if !dirs.contains(&dir) {
+        dirs.push(dir);
+    }
+}
@@ -0,0 +1,305 @@
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use object::{Object, ObjectSection};
+use serde::{Serialize, Deserialize};
+
+use crate::binary::{BinaryInfo, thin_slice};
+use crate::debuginfod::{DebuginfodClient, DownloadCache};
+use crate::demangle::demangle;
+
+/// Number of functions reported in `largest_functions`
+const TOP_FUNCTIONS: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionSize {
+    pub name: String,
+    pub size: u64,
+}
+
+/// Where the DWARF behind `function_count`/`largest_functions` came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DwarfOrigin {
+    /// The scanned file itself
+    Binary,
+    /// The local debug file or dSYM bundle
+    DebugFile,
+    /// Downloaded from debuginfod (`--remote-dwarf`)
+    Remote,
+}
+
+pub struct FunctionReport {
+    pub count: u64,
+    pub largest: Vec<FunctionSize>,
+    /// DW_AT_comp_dir, DW_AT_producer and DW_AT_name of the compile units,
+    /// sorted and deduplicated
+    pub comp_dirs: Vec<String>,
+    pub producers: Vec<String>,
+    pub source_files: Vec<String>,
+}
+
+/// Populate the DWARF-derived fields of `info`
+/// Prefers the detached debug file when one was found, since the binary
+/// itself is often stripped of its DWARF sections; with `remote`, debug info
+/// only a debuginfod server has is downloaded and used as a last resort
+pub fn annotate(info: &mut BinaryInfo, binary_data: &[u8], remote: Option<(&DebuginfodClient, &DownloadCache)>) {
+    let report = dwarf_source(info)
+        .and_then(|path| file_report(&path))
+        .map(|report| (report, DwarfOrigin::DebugFile));
+
+    // Fall back to the binary itself if the debug file had nothing usable
+    let report = report.or_else(|| {
+        function_report(binary_data).ok().flatten().map(|report| (report, DwarfOrigin::Binary))
+    });
+
+    let report = report.or_else(|| {
+        let (client, cache) = remote?;
+        let build_id = info.build_id.as_ref().filter(|_| info.has_remote_debug_symbols())?;
+        let path = cache.debug_file(client, build_id)?;
+        file_report(&path).map(|report| (report, DwarfOrigin::Remote))
+    });
+
+    if let Some((report, origin)) = report {
+        info.function_count = Some(report.count);
+        info.largest_functions = Some(report.largest);
+        info.comp_dirs = report.comp_dirs;
+        info.producers = report.producers;
+        info.source_files = report.source_files;
+        info.dwarf_origin = Some(origin);
+    }
+}
+
+/// Uncompressed size of the `.debug_*` sections, 0 if `data` isn't an object
+pub fn debug_section_bytes(data: &[u8]) -> u64 {
+    let Ok(file) = thin_slice(data).and_then(|data| Ok(object::File::parse(data)?)) else {
+        return 0;
+    };
+    file.sections()
+        .filter(|section| section.name().is_ok_and(|name| name.starts_with(".debug_") || name.starts_with("__debug_")))
+        .map(|section| section.compressed_data().map_or(section.size(), |data| data.uncompressed_size))
+        .sum()
+}
+
+fn file_report(path: &Path) -> Option<FunctionReport> {
+    let file = fs::File::open(path).ok()?;
+    let mmap = unsafe { memmap2::Mmap::map(&file) }.ok()?;
+    function_report(&mmap).ok().flatten()
+}
+
+/// Locate the file holding DWARF for a binary with a detached debug file
+/// For dSYM bundles this is the object inside Contents/Resources/DWARF
+pub(crate) fn dwarf_source(info: &BinaryInfo) -> Option<PathBuf> {
+    let debug_path = info.debug_file_path.as_ref()?;
+
+    if debug_path.is_file() {
+        return Some(debug_path.clone());
+    }
+
+    let dwarf_dir = debug_path.join("Contents/Resources/DWARF");
+    fs::read_dir(dwarf_dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| path.is_file())
+}
+
+/// Count `DW_TAG_subprogram` entries with code ranges and collect the largest
+/// Returns `None` if the object carries no `.debug_info`
+pub fn function_report(data: &[u8]) -> Result<Option<FunctionReport>> {
+    let data = thin_slice(data)?;
+    let file = object::File::parse(data)?;
+
+    let Some((dwarf_sections, endian)) = load_sections(&file)? else {
+        return Ok(None);
+    };
+    let dwarf = dwarf_sections.borrow(|section| gimli::EndianSlice::new(section, endian));
+
+    let mut count = 0u64;
+    let mut functions = Vec::new();
+    let (mut comp_dirs, mut producers, mut source_files) = (BTreeSet::new(), BTreeSet::new(), BTreeSet::new());
+    let lossy = |value: &Reader| value.to_string_lossy().into_owned();
+
+    let mut units = dwarf.units();
+    while let Some(header) = units.next()? {
+        let unit = match dwarf.unit(header) {
+            Ok(unit) => unit,
+            Err(_) => continue,
+        };
+        comp_dirs.extend(unit.comp_dir.as_ref().map(lossy));
+        source_files.extend(unit.name.as_ref().map(lossy));
+
+        let mut entries = unit.entries();
+        while let Ok(Some((_, entry))) = entries.next_dfs() {
+            if entry.tag() == gimli::DW_TAG_compile_unit {
+                let producer = entry.attr_value(gimli::DW_AT_producer).ok().flatten()
+                    .and_then(|value| dwarf.attr_string(&unit, value).ok());
+                producers.extend(producer.as_ref().map(lossy));
+                continue;
+            }
+            if entry.tag() != gimli::DW_TAG_subprogram {
+                continue;
+            }
+
+            let size = match dwarf.die_ranges(&unit, entry) {
+                Ok(mut ranges) => {
+                    let mut size = 0u64;
+                    while let Ok(Some(range)) = ranges.next() {
+                        size += range.end.saturating_sub(range.begin);
+                    }
+                    size
+                }
+                Err(_) => 0,
+            };
+
+            // Declarations and abstract inline instances carry no code
+            if size == 0 {
+                continue;
+            }
+
+            count += 1;
+            let name = subprogram_name(&dwarf, &unit, entry, 0)
+                .map(|name| demangle(&name))
+                .unwrap_or_else(|| "<anonymous>".to_string());
+            functions.push(FunctionSize { name, size });
+        }
+    }
+
+    functions.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    functions.truncate(TOP_FUNCTIONS);
+
+    Ok(Some(FunctionReport {
+        count,
+        largest: functions,
+        comp_dirs: comp_dirs.into_iter().collect(),
+        producers: producers.into_iter().collect(),
+        source_files: source_files.into_iter().collect(),
+    }))
+}
+
+/// DW_AT_comp_dir of the compile units, sorted and deduplicated; empty
+/// without `.debug_info`
+pub fn comp_dirs(data: &[u8]) -> Result<Vec<String>> {
+    let data = thin_slice(data)?;
+    let file = object::File::parse(data)?;
+
+    let Some((dwarf_sections, endian)) = load_sections(&file)? else {
+        return Ok(Vec::new());
+    };
+    let dwarf = dwarf_sections.borrow(|section| gimli::EndianSlice::new(section, endian));
+
+    let mut dirs = BTreeSet::new();
+    let mut units = dwarf.units();
+    while let Some(header) = units.next()? {
+        let Ok(unit) = dwarf.unit(header) else {
+            continue;
+        };
+        dirs.extend(unit.comp_dir.map(|dir| dir.to_string_lossy().into_owned()));
+    }
+    Ok(dirs.into_iter().collect())
+}
+
+/// `.dwo` files named by the skeleton units of split DWARF, in unit order
+/// Returns `None` if no unit is a skeleton (the DWARF is all here)
+pub fn dwo_references(data: &[u8]) -> Result<Option<Vec<String>>> {
+    let data = thin_slice(data)?;
+    let file = object::File::parse(data)?;
+
+    let Some((dwarf_sections, endian)) = load_sections(&file)? else {
+        return Ok(None);
+    };
+    let dwarf = dwarf_sections.borrow(|section| gimli::EndianSlice::new(section, endian));
+
+    let mut is_skeleton = false;
+    let mut names = Vec::new();
+
+    let mut units = dwarf.units();
+    while let Some(header) = units.next()? {
+        let Ok(unit) = dwarf.unit(header) else {
+            continue;
+        };
+        // DW_UT_skeleton, or a DWARF 4 compile unit with DW_AT_GNU_dwo_id
+        if unit.dwo_id.is_none() {
+            continue;
+        }
+        is_skeleton = true;
+
+        let name = unit.dwo_name().ok().flatten()
+            .and_then(|value| dwarf.attr_string(&unit, value).ok())
+            .map(|name| name.to_string_lossy().into_owned());
+        if let Some(name) = name {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+
+    Ok(is_skeleton.then_some(names))
+}
+
+/// DWARF sections, decompressed where needed
+pub(crate) type Sections<'data> = gimli::DwarfSections<Cow<'data, [u8]>>;
+
+/// Decompressed DWARF sections of `file` and its byte order
+/// Returns `None` if the object carries no `.debug_info`
+pub(crate) fn load_sections<'data>(
+    file: &object::File<'data>,
+) -> Result<Option<(Sections<'data>, gimli::RunTimeEndian)>> {
+    if file.section_by_name(".debug_info").is_none() {
+        return Ok(None);
+    }
+
+    let endian = if file.is_little_endian() {
+        gimli::RunTimeEndian::Little
+    } else {
+        gimli::RunTimeEndian::Big
+    };
+
+    let load_section = |id: gimli::SectionId| -> Result<Cow<'data, [u8]>, gimli::Error> {
+        Ok(file
+            .section_by_name(id.name())
+            .and_then(|section| section.uncompressed_data().ok())
+            .unwrap_or(Cow::Borrowed(&[])))
+    };
+
+    Ok(Some((gimli::DwarfSections::load(load_section)?, endian)))
+}
+
+type Reader<'a> = gimli::EndianSlice<'a, gimli::RunTimeEndian>;
+
+/// Resolve a subprogram's name, preferring the linkage name so it can be
+/// demangled, and following DW_AT_specification/DW_AT_abstract_origin
+fn subprogram_name(
+    dwarf: &gimli::Dwarf<Reader>,
+    unit: &gimli::Unit<Reader>,
+    entry: &gimli::DebuggingInformationEntry<Reader>,
+    depth: u8,
+) -> Option<String> {
+    for attr in [gimli::DW_AT_linkage_name, gimli::DW_AT_MIPS_linkage_name, gimli::DW_AT_name] {
+        if let Ok(Some(value)) = entry.attr_value(attr) {
+            if let Ok(name) = dwarf.attr_string(unit, value) {
+                return Some(name.to_string_lossy().into_owned());
+            }
+        }
+    }
+
+    // Bound the reference chain so malformed DWARF can't loop forever
+    if depth >= 4 {
+        return None;
+    }
+
+    for attr in [gimli::DW_AT_specification, gimli::DW_AT_abstract_origin] {
+        if let Ok(Some(gimli::AttributeValue::UnitRef(offset))) = entry.attr_value(attr) {
+            if let Ok(origin) = unit.entry(offset) {
+                if let Some(name) = subprogram_name(dwarf, unit, &origin, depth + 1) {
+                    return Some(name);
+                }
+            }
+        }
+    }
+
+    None
+}
+
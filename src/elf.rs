@@ -1,12 +1,42 @@
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
-use goblin::elf::{Elf, header::*, program_header::*};
+use serde::{Deserialize, Serialize};
+use goblin::elf::sym::{STB_GLOBAL, STB_GNU_UNIQUE, STB_LOCAL, STB_WEAK, STT_FILE, STT_FUNC, STT_SECTION};
+use goblin::elf::{Elf, header::*, program_header::*, section_header::{SHF_ALLOC, SHF_TLS, SHN_UNDEF, SHN_XINDEX, SHT_GNU_HASH, SHT_HASH, SHT_NOBITS, SHT_NOTE, SHT_STRTAB, SHT_SYMTAB}};
 
-use crate::binary::BinaryInfo;
+use crate::arch;
+use crate::binary::{BinaryInfo, ScanContext, ScanWarning};
 use crate::cli::Args;
-use crate::symbol_finder::SymbolFinder;
-use crate::debuginfod::DebuginfodClient;
+use crate::manifest;
+use crate::symbol_finder::{DebugCandidate, Strategy, SymbolFinder};
+use crate::demangle;
+use crate::go;
+use crate::hardening::{self, CheckResult, HardeningReport};
+use crate::imports::{self, ImportedSymbol};
+use crate::deps::{self, LibraryTarget, ResolvedDependency};
+use crate::rpath::{self, AuditRoot};
+
+/// Most bytes a `.gnu_debugdata` section may decompress to; real ones hold
+/// a symbol table of a few hundred KiB
+const MAX_MINIDEBUGINFO_BYTES: usize = 64 << 20;
+
+/// Bytes of each note section/segment searched by default
+pub const DEFAULT_MAX_NOTE_BYTES: usize = 4 << 20;
+
+/// Consecutive empty notes after which a region is treated as padding
+const MAX_EMPTY_NOTES: usize = 256;
+
+/// Why a note search gave up before the end of its region
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum NoteLimit {
+    Bytes(usize),
+    EmptyEntries,
+}
 
 pub struct ElfAnalyzer<'a> {
     path: &'a Path,
@@ -14,6 +44,12 @@ pub struct ElfAnalyzer<'a> {
     elf: Elf<'a>,
     file_size: u64,
     file_modified: DateTime<Utc>,
+    max_note_bytes: usize,
+    /// `e_shstrndx` names no string table, so sections are only known by
+    /// type and flags
+    section_names_unavailable: bool,
+    /// First limit a note search hit, reported as a warning
+    note_limit: Cell<Option<NoteLimit>>,
 }
 
 impl<'a> ElfAnalyzer<'a> {
@@ -23,23 +59,45 @@ impl<'a> ElfAnalyzer<'a> {
         file_size: u64,
         file_modified: DateTime<Utc>,
     ) -> Result<Self> {
-        let elf = Elf::parse(data)?;
+        let mut elf = Elf::parse(data)?;
+        let section_names_unavailable = !has_section_name_table(&elf);
+        if section_names_unavailable {
+            // Names read from whatever e_shstrndx points at would be garbage
+            elf.shdr_strtab = Default::default();
+        }
         Ok(Self {
             path,
             data,
             elf,
             file_size,
             file_modified,
+            max_note_bytes: DEFAULT_MAX_NOTE_BYTES,
+            section_names_unavailable,
+            note_limit: Cell::new(None),
         })
     }
     
-    pub fn analyze(&self, args: &Args) -> Result<BinaryInfo> {
-        let architecture = self.get_architecture();
+    /// Cap on the bytes of each note region searched for build-ids
+    pub fn with_max_note_bytes(mut self, max_note_bytes: usize) -> Self {
+        self.max_note_bytes = max_note_bytes;
+        self
+    }
+    
+    pub fn analyze(&self, args: &Args, ctx: &ScanContext) -> Result<BinaryInfo> {
+        let architecture = arch::from_elf(self.elf.header.e_machine, self.elf.is_64, self.elf.little_endian).to_string();
+        let architecture_raw = self.get_architecture();
         let is_64bit = self.elf.is_64;
         let is_stripped = self.is_stripped();
         let debug_sections = self.find_debug_sections();
-        let has_debug_info = !debug_sections.is_empty();
+        // Debug sections are only recognized by name
+        let has_debug_info = (!self.section_names_unavailable).then_some(!debug_sections.is_empty());
+        let is_debug_only = has_debug_info == Some(true) && self.allocated_sections_emptied();
         let build_id = self.extract_build_id();
+        let go_build_id = self.extract_go_build_id();
+        let go_pclntab = self.go_pclntab();
+        let minidebuginfo = self.minidebuginfo();
+        let package_info = self.extract_package_info();
+        let (abi_os, min_kernel_version) = self.extract_abi_tag().unzip();
         let gnu_debuglink = self.extract_gnu_debuglink();
         let (is_pie, is_executable, is_library) = self.get_binary_type();
         let entry_point = if self.elf.entry > 0 {
@@ -47,22 +105,60 @@ impl<'a> ElfAnalyzer<'a> {
         } else {
             None
         };
+        let entry_section = self.entry_section();
         let interpreter = self.get_interpreter();
+        let relocations = self.relocation_stats();
+        let tls_size = self.tls_size();
+        let libc = libc_flavor(&interpreter, &self.elf.libraries);
+        let notes = (args.verbose >= 2).then(|| self.notes());
+        let isa_level = if args.check_isa { self.isa_level() } else { None };
         
         // Security features
-        let (has_nx, has_relro) = self.check_security_features();
-        let has_canary = self.check_stack_canary();
-        let has_fortify = self.check_fortify();
+        let hardening = self.check_hardening(is_pie);
+        let mangling_stats = demangle::mangling_stats(self.symbol_names());
+        let panic_strategy = demangle::panic_strategy(self.symbol_names(), &mangling_stats);
+        let symbol_bindings = self.symbol_bindings();
         
-        // Find local debug symbols
-        let debug_file_path = self.find_local_debug_file(&build_id, &gnu_debuglink);
+        // Find local debug symbols; the first candidate is the preferred one
+        let (mut candidates, resolve_warning) = self.find_local_debug_files(&build_id, &gnu_debuglink, args, ctx);
+        let conflict_warning = build_id.as_deref().and_then(|id| conflicting_debug_files(id, &candidates));
+        let chosen = (!candidates.is_empty()).then(|| candidates.remove(0));
+        let (debug_file_path, debug_file_source) = chosen.map(|chosen| (chosen.path, chosen.source)).unzip();
         
-        // Check remote symbols via debuginfod
-        let (debuginfod_available, debuginfod_url) = if args.check_remote {
-            self.check_debuginfod(&build_id, args)
+        let mut warnings = self.collect_warnings(&interpreter, &build_id, &debug_file_path);
+        warnings.extend(resolve_warning);
+        warnings.extend(conflict_warning);
+        if let Some(warning) = self.entry_warning(&entry_section) {
+            warnings.push(warning);
+        }
+        if let Some(limit) = self.note_limit.get() {
+            let message = match limit {
+                NoteLimit::Bytes(bytes) => format!("stopped searching notes after {} bytes (--max-note-bytes)", bytes),
+                NoteLimit::EmptyEntries => format!("stopped searching notes after {} empty entries", MAX_EMPTY_NOTES),
+            };
+            warnings.push(ScanWarning::new("truncated-notes", message));
+        }
+        if self.section_names_unavailable {
+            warnings.push(ScanWarning::new(
+                "section-names-unavailable",
+                format!("e_shstrndx {} is not a string table; sections are identified by type only", self.elf.header.e_shstrndx),
+            ));
+        }
+        
+        let rpath = rpath::split_entries(self.elf.rpaths.iter().copied());
+        let runpath = rpath::split_entries(self.elf.runpaths.iter().copied());
+        
+        let (resolved_dependencies, missing_dependencies) = if args.check_deps {
+            self.resolve_dependencies(&rpath, &runpath, args, ctx)
         } else {
-            (None, None)
+            (Vec::new(), Vec::new())
         };
+        if !missing_dependencies.is_empty() {
+            warnings.push(ScanWarning::new(
+                "missing-dependency",
+                format!("unresolved shared libraries: {}", missing_dependencies.join(", ")),
+            ));
+        }
         
         Ok(BinaryInfo {
             file_path: self.path.to_path_buf(),
@@ -70,53 +166,237 @@ impl<'a> ElfAnalyzer<'a> {
             file_modified: self.file_modified,
             binary_type: "ELF".to_string(),
             architecture,
+            architecture_raw,
             is_64bit,
+            is_big_endian: !self.elf.little_endian,
             is_stripped,
             has_debug_info,
+            machine_code: Some(self.elf.header.e_machine),
+            os_abi: Some(os_abi_name(self.elf.header.e_ident[EI_OSABI])),
+            abi_version: Some(self.elf.header.e_ident[EI_ABIVERSION]),
+            elf_version: Some(self.elf.header.e_version),
+            abi_os,
+            min_kernel_version,
+            build_id_type: build_id.as_deref().map(|id| build_id_type(id).to_string()),
             build_id,
+            go_build_id,
+            has_go_pclntab: go_pclntab.is_some(),
+            go_function_count: go_pclntab.map(|(_, functions)| functions),
+            has_minidebuginfo: minidebuginfo.is_some(),
+            minidebuginfo_symbols: minidebuginfo.flatten(),
+            package_info,
             gnu_debuglink,
+            rpath,
+            runpath,
             debug_sections,
+            is_debug_only,
+            section_names_unavailable: self.section_names_unavailable,
             uuid: None,
             dsym_bundle: None,
             debug_file_path,
-            debuginfod_available,
-            debuginfod_url,
+            alternate_debug_files: candidates,
+            debug_file_source,
             entry_point,
+            entry_section,
             interpreter,
-            is_pie,
+            libc,
+            is_pie: hardening.pie.enabled(),
             is_executable,
             is_library,
-            has_nx,
-            has_canary,
-            has_relro,
-            has_fortify,
+            has_nx: hardening.nx.enabled(),
+            has_canary: hardening.canary.enabled(),
+            has_relro: hardening.relro.enabled(),
+            has_fortify: hardening.fortify.enabled(),
+            dangerous_imports: self.dangerous_imports(),
+            imports: args.imports.then(|| self.imports()),
+            soname: self.elf.soname.map(str::to_string),
+            hash_style: self.hash_style().map(str::to_string),
+            isa_level: isa_level.map(str::to_string),
+            has_dynamic_section: self.elf.dynamic.is_some(),
+            largest_load_bytes: Some(self.largest_load_bytes()),
+            version_definitions: self.version_definitions(),
+            hardening,
+            mangling_stats,
+            panic_strategy: panic_strategy.map(str::to_string),
+            global_symbol_count: symbol_bindings.map(|(global, _)| global),
+            local_symbol_count: symbol_bindings.map(|(_, local)| local),
+            relocation_count: Some(relocations.count),
+            irelative_count: Some(relocations.irelative),
+            plt_entries: Some(relocations.plt_entries),
+            got_bytes: Some(relocations.got_bytes),
+            stapsdt_probes: notes.as_ref().map(|notes| {
+                notes.iter().filter(|note| note.owner == "stapsdt" && note.note_type == NT_STAPSDT && note.malformed.is_none()).count() as u64
+            }),
+            notes,
+            has_tls: tls_size.is_some(),
+            tls_size: tls_size.unwrap_or(0),
+            needed: self.elf.libraries.iter().map(|name| name.to_string()).collect(),
+            resolved_dependencies,
+            missing_dependencies,
+            warnings,
+            ..Default::default()
         })
     }
     
-    fn get_architecture(&self) -> String {
-        match self.elf.header.e_machine {
-            EM_X86_64 => "x86_64".to_string(),
-            EM_386 => "i386".to_string(),
-            EM_ARM => "ARM".to_string(),
-            EM_AARCH64 => "AArch64".to_string(),
-            EM_RISCV => "RISC-V".to_string(),
-            EM_PPC => "PowerPC".to_string(),
-            EM_PPC64 => "PowerPC64".to_string(),
-            EM_MIPS => "MIPS".to_string(),
-            EM_S390 => "S390".to_string(),
-            _ => format!("Unknown (0x{:x})", self.elf.header.e_machine),
+    /// Check that this file is a debug file for `expected_build_id`
+    /// Guards against servers answering 200 with an HTML error page
+    pub fn verify_debug_file(&self, expected_build_id: &str) -> Result<()> {
+        self.verify_build_id(expected_build_id)?;
+        
+        if self.find_debug_sections().is_empty() {
+            anyhow::bail!("no .debug_* sections");
+        }
+        
+        Ok(())
+    }
+    
+    /// Check that this file carries `expected_build_id`
+    pub fn verify_build_id(&self, expected_build_id: &str) -> Result<()> {
+        match self.extract_build_id() {
+            Some(ref build_id) if build_id.eq_ignore_ascii_case(expected_build_id) => Ok(()),
+            Some(build_id) => anyhow::bail!("build-id mismatch (got {})", build_id),
+            None => anyhow::bail!("no build-id note"),
         }
     }
     
+    fn get_architecture(&self) -> String {
+        machine_name(self.elf.header.e_machine)
+    }
+    
     fn is_stripped(&self) -> bool {
-        // Check if .symtab section exists
-        !self.elf.section_headers.iter().any(|sh| {
-            if let Some(name) = self.elf.shdr_strtab.get_at(sh.sh_name) {
-                name == ".symtab"
-            } else {
-                false
+        // Check if a .symtab exists, by type so it doesn't need section names
+        !self.elf.section_headers.iter().any(|sh| sh.sh_type == SHT_SYMTAB)
+    }
+    
+    /// Relocation counts and GOT size, for startup-cost audits
+    ///
+    /// Totals come from the REL/RELA section sizes (or the DT_*SZ tags when
+    /// there are no section headers), not from reading entries; IRELATIVE
+    /// needs each entry's type, so only the dynamic relocations are walked.
+    /// RELR sections are bitmaps and are decoded, as `readelf -r` does.
+    fn relocation_stats(&self) -> RelocationStats {
+        let irelative_type = irelative_type(self.elf.header.e_machine);
+        let count_irelative = |relocs: &goblin::elf::RelocSection| -> u64 {
+            match irelative_type {
+                Some(r_type) => relocs.iter().filter(|reloc| reloc.r_type == r_type).count() as u64,
+                None => 0,
             }
-        })
+        };
+        
+        let mut stats = RelocationStats::default();
+        
+        if self.elf.shdr_relocs.is_empty() {
+            for relocs in [&self.elf.dynrelas, &self.elf.dynrels, &self.elf.pltrelocs] {
+                stats.count += relocs.len() as u64;
+                stats.irelative += count_irelative(relocs);
+            }
+            stats.plt_entries = self.elf.pltrelocs.len() as u64;
+        } else {
+            for (index, relocs) in &self.elf.shdr_relocs {
+                let Some(sh) = self.elf.section_headers.get(*index) else {
+                    continue;
+                };
+                stats.count += relocs.len() as u64;
+                
+                // Non-alloc relocations (.rela.text in objects) never run at load time
+                if sh.sh_flags & SHF_ALLOC as u64 != 0 {
+                    stats.irelative += count_irelative(relocs);
+                }
+                if matches!(self.elf.shdr_strtab.get_at(sh.sh_name), Some(".rela.plt" | ".rel.plt")) {
+                    stats.plt_entries += relocs.len() as u64;
+                }
+            }
+        }
+        
+        for sh in self.elf.section_headers.iter().filter(|sh| sh.sh_type == SHT_RELR) {
+            let words = usize::try_from(sh.sh_offset).ok()
+                .zip(usize::try_from(sh.sh_size).ok())
+                .and_then(|(offset, size)| self.data.get(offset..offset.checked_add(size)?));
+            stats.count += words.map_or(0, |words| relr_count(words, self.elf.is_64, self.elf.little_endian));
+        }
+        
+        stats.got_bytes = self.elf.section_headers.iter()
+            .filter(|sh| matches!(self.elf.shdr_strtab.get_at(sh.sh_name), Some(".got" | ".got.plt")))
+            .map(|sh| sh.sh_size)
+            .sum();
+        
+        stats
+    }
+    
+    /// Size of the `PT_TLS` segment, allocated for every thread
+    fn tls_size(&self) -> Option<u64> {
+        self.elf.program_headers.iter()
+            .find(|ph| ph.p_type == PT_TLS)
+            .map(|ph| ph.p_memsz)
+    }
+    
+    /// Symbol hash tables for the dynamic loader: `sysv` (`.hash`), `gnu`
+    /// (`.gnu.hash`) or `both`, by section type, or by dynamic tag when the
+    /// section headers are gone
+    fn hash_style(&self) -> Option<&'static str> {
+        let has = |sh_type| self.elf.section_headers.iter().any(|sh| sh.sh_type == sh_type);
+        let info = self.elf.dynamic.as_ref().map(|dynamic| &dynamic.info);
+        let sysv = has(SHT_HASH) || info.is_some_and(|info| info.hash.is_some());
+        let gnu = has(SHT_GNU_HASH) || info.is_some_and(|info| info.gnu_hash.is_some());
+        match (sysv, gnu) {
+            (true, true) => Some("both"),
+            (true, false) => Some("sysv"),
+            (false, true) => Some("gnu"),
+            (false, false) => None,
+        }
+    }
+    
+    /// Allocated section containing the entry point; TLS sections are
+    /// templates whose addresses overlap the real ones, so they're skipped
+    fn entry_section(&self) -> Option<String> {
+        let entry = self.elf.entry;
+        if entry == 0 {
+            return None;
+        }
+        
+        self.elf.section_headers.iter()
+            .filter(|sh| sh.sh_flags & SHF_ALLOC as u64 != 0 && sh.sh_flags & SHF_TLS as u64 == 0)
+            .find(|sh| entry >= sh.sh_addr && entry - sh.sh_addr < sh.sh_size)
+            .and_then(|sh| self.elf.shdr_strtab.get_at(sh.sh_name))
+            .map(str::to_string)
+    }
+    
+    /// Entry points normally sit in `.text` (or a `.text.*` input section
+    /// kept separate); in a file that maps one, an entry anywhere else is how
+    /// packers and injected stubs look
+    fn entry_warning(&self, entry_section: &Option<String>) -> Option<ScanWarning> {
+        let has_text = self.elf.section_headers.iter()
+            .any(|sh| sh.sh_flags & SHF_ALLOC as u64 != 0 && self.elf.shdr_strtab.get_at(sh.sh_name) == Some(".text"));
+        if self.elf.entry == 0 || !has_text {
+            return None;
+        }
+        
+        let message = match entry_section.as_deref() {
+            Some(name) if name == ".text" || name.starts_with(".text.") => return None,
+            Some(name) => format!("entry point 0x{:x} is in {}, not .text", self.elf.entry, name),
+            None => format!("entry point 0x{:x} is outside every section", self.elf.entry),
+        };
+        Some(ScanWarning::new("unusual-entry-point", message))
+    }
+    
+    /// File bytes of the largest PT_LOAD segment
+    fn largest_load_bytes(&self) -> u64 {
+        self.elf.program_headers.iter()
+            .filter(|ph| ph.p_type == PT_LOAD)
+            .map(|ph| ph.p_filesz)
+            .max()
+            .unwrap_or(0)
+    }
+    
+    /// The shape `objcopy --only-keep-debug` leaves: every allocated section
+    /// but the notes (which carry the build-id) turned into `SHT_NOBITS`.
+    /// An object file with DWARF and nothing emptied isn't one
+    fn allocated_sections_emptied(&self) -> bool {
+        let headers = &self.elf.section_headers;
+        headers.iter().any(|sh| sh.sh_type == SHT_NOBITS)
+            && headers.iter()
+                .filter(|sh| sh.sh_flags & SHF_ALLOC as u64 != 0)
+                .all(|sh| sh.sh_type == SHT_NOBITS || sh.sh_type == SHT_NOTE || sh.sh_size == 0)
     }
     
     fn find_debug_sections(&self) -> Vec<String> {
@@ -134,19 +414,93 @@ impl<'a> ElfAnalyzer<'a> {
         sections
     }
     
+    /// `.note.ABI-tag`: the OS and the oldest kernel the binary runs on
+    fn extract_abi_tag(&self) -> Option<(String, String)> {
+        let desc = self.find_note(".note.ABI-tag", b"GNU\0", NT_GNU_ABI_TAG)?;
+        decode_abi_tag(desc, self.elf.little_endian)
+    }
+    
     fn extract_build_id(&self) -> Option<String> {
-        // Look for .note.gnu.build-id section
+        self.find_note(".note.gnu.build-id", b"GNU\0", NT_GNU_BUILD_ID)
+            .map(hex::encode)
+    }
+    
+    /// Go toolchain build id, distinct from the GNU build-id
+    /// Stored as text: `<action id>/<content id>` hashes
+    fn extract_go_build_id(&self) -> Option<String> {
+        let desc = self.find_note(".note.go.buildid", b"Go\0\0", NT_GO_BUILD_ID)?;
+        let end = desc.iter().position(|&b| b == 0).unwrap_or(desc.len());
+        std::str::from_utf8(&desc[..end]).ok()
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+    }
+    
+    /// Go runtime symbol table: `.gopclntab`, or merged into `.noptrdata`
+    /// or `.data.rel.ro` when the section was dropped (external linking)
+    fn go_pclntab(&self) -> Option<(go::PclntabVersion, u64)> {
+        let contents = |name: &str| self.elf.section_headers.iter()
+            .filter(|sh| sh.sh_type != SHT_NOBITS && self.elf.shdr_strtab.get_at(sh.sh_name) == Some(name))
+            .find_map(|sh| {
+                let offset = usize::try_from(sh.sh_offset).ok()?;
+                self.data.get(offset..offset.checked_add(usize::try_from(sh.sh_size).ok()?)?)
+            });
+        
+        if let Some(data) = contents(".gopclntab") {
+            return go::parse(data, self.elf.little_endian);
+        }
+        [".noptrdata", ".data.rel.ro"].into_iter()
+            .filter_map(contents)
+            .find_map(|data| go::search(data, self.elf.little_endian))
+    }
+    
+    /// `.gnu_debugdata` (MiniDebugInfo), with the number of symbols in it
+    /// when it decompresses
+    fn minidebuginfo(&self) -> Option<Option<u64>> {
+        let sh = self.elf.section_headers.iter()
+            .find(|sh| sh.sh_type != SHT_NOBITS && self.elf.shdr_strtab.get_at(sh.sh_name) == Some(".gnu_debugdata"))?;
+        let data = usize::try_from(sh.sh_offset).ok()
+            .and_then(|offset| self.data.get(offset..offset.checked_add(usize::try_from(sh.sh_size).ok()?)?));
+        Some(data.and_then(minidebuginfo_symbols))
+    }
+    
+    /// x86-64 microarchitecture level from `.note.gnu.property`; newer
+    /// toolchains (GCC 11, binutils 2.36) record the levels the code uses
+    fn isa_level(&self) -> Option<&'static str> {
+        if self.elf.header.e_machine != EM_X86_64 {
+            return None;
+        }
+        let desc = self.find_note(".note.gnu.property", b"GNU\0", NT_GNU_PROPERTY_TYPE_0)?;
+        x86_isa_level(desc, self.elf.is_64, self.elf.little_endian)
+    }
+    
+    /// Package metadata from `.note.package`; the descriptor is a
+    /// NUL-terminated JSON object
+    fn extract_package_info(&self) -> Option<PackageInfo> {
+        let desc = self.find_note(".note.package", b"FDO\0", NT_FDO_PACKAGING_METADATA)?;
+        let end = desc.iter().position(|&b| b == 0).unwrap_or(desc.len());
+        serde_json::from_slice(&desc[..end]).ok()
+    }
+    
+    /// `size` bytes at file `offset`; None past the end of the file, or for
+    /// header values whose sum wraps
+    fn file_bytes(&self, offset: u64, size: u64) -> Option<&'a [u8]> {
+        let offset = usize::try_from(offset).ok()?;
+        self.data.get(offset..offset.checked_add(usize::try_from(size).ok()?)?)
+    }
+    
+    /// Descriptor of the first note with `owner` and `note_type`, looking in
+    /// the named section first (every SHT_NOTE section when names are
+    /// unavailable) and then in PT_NOTE segments
+    fn find_note(&self, section: &str, owner: &[u8], note_type: u32) -> Option<&'a [u8]> {
         for sh in &self.elf.section_headers {
-            if let Some(name) = self.elf.shdr_strtab.get_at(sh.sh_name) {
-                if name == ".note.gnu.build-id" {
-                    let offset = sh.sh_offset as usize;
-                    let size = sh.sh_size as usize;
-                    
-                    if offset + size <= self.data.len() {
-                        if let Some(build_id) = self.parse_build_id_note(&self.data[offset..offset + size]) {
-                            return Some(build_id);
-                        }
-                    }
+            let candidate = if self.section_names_unavailable {
+                sh.sh_type == SHT_NOTE
+            } else {
+                self.elf.shdr_strtab.get_at(sh.sh_name) == Some(section)
+            };
+            if candidate {
+                if let Some(desc) = self.file_bytes(sh.sh_offset, sh.sh_size).and_then(|data| self.search_notes(data, owner, note_type)) {
+                    return Some(desc);
                 }
             }
         }
@@ -154,13 +508,8 @@ impl<'a> ElfAnalyzer<'a> {
         // Also check PT_NOTE segments
         for ph in &self.elf.program_headers {
             if ph.p_type == PT_NOTE {
-                let offset = ph.p_offset as usize;
-                let size = ph.p_filesz as usize;
-                
-                if offset + size <= self.data.len() {
-                    if let Some(build_id) = self.parse_build_id_note(&self.data[offset..offset + size]) {
-                        return Some(build_id);
-                    }
+                if let Some(desc) = self.file_bytes(ph.p_offset, ph.p_filesz).and_then(|data| self.search_notes(data, owner, note_type)) {
+                    return Some(desc);
                 }
             }
         }
@@ -168,51 +517,90 @@ impl<'a> ElfAnalyzer<'a> {
         None
     }
     
-    fn parse_build_id_note(&self, data: &[u8]) -> Option<String> {
-        if data.len() < 12 {
-            return None;
+    /// Every note, from the SHT_NOTE sections and then the PT_NOTE segments
+    /// no note section lies in (those repeat the sections' bytes)
+    fn notes(&self) -> Vec<NoteInfo> {
+        let mut regions = Vec::new();
+        let note_sections: Vec<_> = self.elf.section_headers.iter().enumerate()
+            .filter(|(_, sh)| sh.sh_type == SHT_NOTE)
+            .collect();
+        for (index, sh) in &note_sections {
+            let location = match self.elf.shdr_strtab.get_at(sh.sh_name) {
+                Some(name) if !name.is_empty() => name.to_string(),
+                _ => format!("section {}", index),
+            };
+            regions.push((location, sh.sh_offset, sh.sh_size));
+        }
+        for ph in self.elf.program_headers.iter().filter(|ph| ph.p_type == PT_NOTE) {
+            let end = ph.p_offset.saturating_add(ph.p_filesz);
+            if !note_sections.iter().any(|(_, sh)| sh.sh_offset >= ph.p_offset && sh.sh_offset < end) {
+                regions.push((format!("PT_NOTE@0x{:x}", ph.p_offset), ph.p_offset, ph.p_filesz));
+            }
         }
         
-        let mut offset = 0;
-        while offset + 12 <= data.len() {
-            let namesz = u32::from_ne_bytes([data[offset], data[offset+1], data[offset+2], data[offset+3]]) as usize;
-            let descsz = u32::from_ne_bytes([data[offset+4], data[offset+5], data[offset+6], data[offset+7]]) as usize;
-            let note_type = u32::from_ne_bytes([data[offset+8], data[offset+9], data[offset+10], data[offset+11]]);
-            
-            offset += 12;
-            
-            // Align to 4 bytes
-            let namesz_aligned = (namesz + 3) & !3;
-            let descsz_aligned = (descsz + 3) & !3;
-            
-            if offset + namesz_aligned + descsz_aligned > data.len() {
-                break;
+        let mut notes = Vec::new();
+        for (location, offset, size) in regions {
+            let Some(data) = self.file_bytes(offset, size) else {
+                notes.push(NoteInfo {
+                    location,
+                    owner: String::new(),
+                    note_type: 0,
+                    type_name: None,
+                    desc_size: 0,
+                    malformed: Some(format!("{} bytes at 0x{:x} are past the end of the file", size, offset)),
+                });
+                continue;
+            };
+            for entry in NoteEntries::new(data, self.max_note_bytes, self.elf.little_endian) {
+                let (owner, note_type, desc_size, malformed) = match entry {
+                    NoteEntry::Note { name, note_type, desc } => {
+                        let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+                        (String::from_utf8_lossy(&name[..end]).into_owned(), note_type, desc.len() as u64, None)
+                    }
+                    NoteEntry::Malformed { namesz, descsz, note_type } => (
+                        String::new(),
+                        note_type,
+                        descsz as u64,
+                        Some(format!("name size {} and descriptor size {} run past the end of the region", namesz, descsz)),
+                    ),
+                    NoteEntry::Limit(limit) => {
+                        if self.note_limit.get().is_none() {
+                            self.note_limit.set(Some(limit));
+                        }
+                        continue;
+                    }
+                };
+                notes.push(NoteInfo {
+                    location: location.clone(),
+                    type_name: note_type_name(&owner, note_type).map(str::to_string),
+                    owner,
+                    note_type,
+                    desc_size,
+                    malformed,
+                });
             }
-            
-            // NT_GNU_BUILD_ID = 3
-            if note_type == 3 && namesz >= 4 && &data[offset..offset+4] == b"GNU\0" {
-                let build_id_offset = offset + namesz_aligned;
-                if build_id_offset + descsz <= data.len() {
-                    let build_id_bytes = &data[build_id_offset..build_id_offset + descsz];
-                    return Some(hex::encode(build_id_bytes));
+        }
+        notes
+    }
+    
+    /// `parse_note` within `max_note_bytes`, remembering a limit it hit
+    fn search_notes(&self, data: &'a [u8], owner: &[u8], note_type: u32) -> Option<&'a [u8]> {
+        match parse_note(data, owner, note_type, self.max_note_bytes, self.elf.little_endian) {
+            Ok(desc) => desc,
+            Err(limit) => {
+                if self.note_limit.get().is_none() {
+                    self.note_limit.set(Some(limit));
                 }
+                None
             }
-            
-            offset += namesz_aligned + descsz_aligned;
         }
-        
-        None
     }
     
     fn extract_gnu_debuglink(&self) -> Option<String> {
         for sh in &self.elf.section_headers {
             if let Some(name) = self.elf.shdr_strtab.get_at(sh.sh_name) {
                 if name == ".gnu_debuglink" {
-                    let offset = sh.sh_offset as usize;
-                    let size = sh.sh_size as usize;
-                    
-                    if offset + size <= self.data.len() {
-                        let data = &self.data[offset..offset + size];
+                    if let Some(data) = self.file_bytes(sh.sh_offset, sh.sh_size) {
                         // Find null terminator
                         if let Some(null_pos) = data.iter().position(|&b| b == 0) {
                             if let Ok(filename) = std::str::from_utf8(&data[..null_pos]) {
@@ -242,11 +630,7 @@ impl<'a> ElfAnalyzer<'a> {
     fn get_interpreter(&self) -> Option<String> {
         for ph in &self.elf.program_headers {
             if ph.p_type == PT_INTERP {
-                let offset = ph.p_offset as usize;
-                let size = ph.p_filesz as usize;
-                
-                if offset + size <= self.data.len() {
-                    let data = &self.data[offset..offset + size];
+                if let Some(data) = self.file_bytes(ph.p_offset, ph.p_filesz) {
                     if let Some(null_pos) = data.iter().position(|&b| b == 0) {
                         if let Ok(interp) = std::str::from_utf8(&data[..null_pos]) {
                             return Some(interp.to_string());
@@ -258,76 +642,687 @@ impl<'a> ElfAnalyzer<'a> {
         None
     }
     
-    fn check_security_features(&self) -> (bool, bool) {
-        let mut has_nx = false;
-        let mut has_relro = false;
+    fn check_hardening(&self, is_pie: bool) -> HardeningReport {
+        let pie = if is_pie {
+            CheckResult::pass("ET_DYN with PT_INTERP")
+        } else if self.elf.header.e_type == ET_DYN {
+            CheckResult::not_applicable("ET_DYN without PT_INTERP (shared library)")
+        } else {
+            CheckResult::fail(format!("e_type is {}", et_to_str(self.elf.header.e_type)))
+        };
         
-        // Check for GNU_STACK (NX)
-        for ph in &self.elf.program_headers {
-            if ph.p_type == PT_GNU_STACK {
-                has_nx = (ph.p_flags & PF_X) == 0;
-            }
-            if ph.p_type == PT_GNU_RELRO {
-                has_relro = true;
+        let nx = match self.elf.program_headers.iter().find(|ph| ph.p_type == PT_GNU_STACK) {
+            Some(ph) if ph.p_flags & PF_X == 0 => CheckResult::pass("PT_GNU_STACK without PF_X"),
+            Some(_) => CheckResult::fail("PT_GNU_STACK has PF_X (executable stack)"),
+            None => CheckResult::fail("PT_GNU_STACK absent"),
+        };
+        
+        let relro = if self.elf.program_headers.iter().any(|ph| ph.p_type == PT_GNU_RELRO) {
+            CheckResult::pass("PT_GNU_RELRO present")
+        } else {
+            CheckResult::fail("PT_GNU_RELRO absent")
+        };
+        
+        let dynsym_names = || self.elf.dynsyms.iter()
+            .filter_map(|sym| self.elf.dynstrtab.get_at(sym.st_name));
+        
+        HardeningReport {
+            pie,
+            nx,
+            canary: hardening::check_canary(dynsym_names(), "dynsym"),
+            relro,
+            fortify: hardening::check_fortify(dynsym_names(), "dynsym"),
+        }
+    }
+    
+    /// Defined global (and weak) and local `.symtab` symbols, leaving out
+    /// section and file symbols; None without a `.symtab`
+    fn symbol_bindings(&self) -> Option<(u64, u64)> {
+        if self.elf.syms.is_empty() {
+            return None;
+        }
+        let (mut global, mut local) = (0, 0);
+        // Index 0 is the null symbol
+        for sym in self.elf.syms.iter().skip(1) {
+            match sym.st_bind() {
+                STB_LOCAL if !matches!(sym.st_type(), STT_SECTION | STT_FILE) => local += 1,
+                STB_GLOBAL | STB_WEAK | STB_GNU_UNIQUE if sym.st_shndx != SHN_UNDEF as usize => global += 1,
+                _ => {}
             }
         }
-        
-        (has_nx, has_relro)
+        Some((global, local))
+    }
+    
+    /// Undefined dynamic symbols from `hardening::DANGEROUS_FUNCTIONS`
+    fn dangerous_imports(&self) -> Vec<String> {
+        let imports = self.elf.dynsyms.iter()
+            .filter(|sym| sym.st_shndx == goblin::elf::section_header::SHN_UNDEF as usize)
+            .filter_map(|sym| self.elf.dynstrtab.get_at(sym.st_name));
+        hardening::dangerous_imports(imports)
     }
     
-    fn check_stack_canary(&self) -> bool {
-        // Look for __stack_chk_fail symbol
-        for sym in &self.elf.dynsyms {
-            if let Some(name) = self.elf.dynstrtab.get_at(sym.st_name) {
-                if name.contains("stack_chk_fail") || name.contains("stack_chk_guard") {
-                    return true;
+    /// Versions `.gnu.version_d` defines (`LIBFOO_1.0`), leaving out the
+    /// base entry, which names the file itself
+    fn version_definitions(&self) -> Vec<String> {
+        const VER_FLG_BASE: u16 = 1;
+        self.elf.verdef.iter()
+            .flat_map(|verdef| verdef.iter())
+            .filter(|definition| definition.vd_flags & VER_FLG_BASE == 0)
+            .filter_map(|definition| definition.iter().next())
+            .filter_map(|aux| self.elf.dynstrtab.get_at(aux.vda_name).map(str::to_string))
+            .collect()
+    }
+    
+    /// Undefined `STT_FUNC` symbols in `.dynsym`, with the version and
+    /// file `.gnu.version_r` names for them
+    fn imports(&self) -> Vec<ImportedSymbol> {
+        let mut needed: HashMap<u16, (&str, &str)> = HashMap::new();
+        for file in self.elf.verneed.iter().flat_map(|verneed| verneed.iter()) {
+            let Some(file_name) = self.elf.dynstrtab.get_at(file.vn_file) else { continue };
+            for version in file.iter() {
+                if let Some(name) = self.elf.dynstrtab.get_at(version.vna_name) {
+                    needed.insert(version.vna_other, (name, file_name));
                 }
             }
         }
-        false
+        
+        let imports = self.elf.dynsyms.iter().enumerate()
+            .filter(|(_, sym)| sym.st_shndx == SHN_UNDEF as usize && sym.st_type() == STT_FUNC)
+            .filter_map(|(index, sym)| {
+                let name = self.elf.dynstrtab.get_at(sym.st_name).filter(|name| !name.is_empty())?;
+                let version = self.elf.versym.as_ref()
+                    .and_then(|versym| versym.get_at(index))
+                    .and_then(|versym| needed.get(&versym.version()).copied());
+                Some(ImportedSymbol::elf(name, version))
+            })
+            .collect();
+        imports::sorted(imports)
     }
     
-    fn check_fortify(&self) -> bool {
-        // Look for fortified functions like __memcpy_chk
-        for sym in &self.elf.dynsyms {
-            if let Some(name) = self.elf.dynstrtab.get_at(sym.st_name) {
-                if name.ends_with("_chk") {
-                    return true;
+    fn collect_warnings(
+        &self,
+        interpreter: &Option<String>,
+        build_id: &Option<String>,
+        debug_file_path: &Option<PathBuf>,
+    ) -> Vec<ScanWarning> {
+        let mut warnings = Vec::new();
+        
+        if let Some(ref interp) = interpreter {
+            if !Path::new(interp).exists() {
+                warnings.push(ScanWarning::new("missing-interpreter", format!("interpreter {} not found", interp)));
+            }
+        }
+        
+        let wx = self.elf.program_headers.iter()
+            .any(|ph| ph.p_type == PT_LOAD && ph.p_flags & PF_W != 0 && ph.p_flags & PF_X != 0);
+        if wx {
+            warnings.push(ScanWarning::new("wx-segment", "PT_LOAD segment is both writable and executable"));
+        }
+        
+        warnings.extend(self.structure_warnings());
+        
+        if let (Some(expected), Some(path)) = (build_id, debug_file_path) {
+            if let Some(found) = debug_file_build_id(path) {
+                if !found.eq_ignore_ascii_case(expected) {
+                    warnings.push(ScanWarning::new(
+                        "debug-file-mismatch",
+                        format!("{} has build-id {}, expected {}", path.display(), found, expected),
+                    ));
                 }
             }
         }
-        false
+        
+        warnings
     }
     
-    fn find_local_debug_file(&self, build_id: &Option<String>, gnu_debuglink: &Option<String>) -> Option<PathBuf> {
-        let finder = SymbolFinder::new(self.path);
+    /// Header combinations the toolchain never produces; a sign of damage
+    /// or hand-crafting
+    fn structure_warnings(&self) -> Vec<ScanWarning> {
+        let mut problems = Vec::new();
+        let count = |p_type: u32| self.elf.program_headers.iter().filter(|ph| ph.p_type == p_type).count();
+        let has_section = |name: &str| self.elf.section_headers.iter()
+            .any(|sh| self.elf.shdr_strtab.get_at(sh.sh_name) == Some(name));
+        let e_type = self.elf.header.e_type;
         
-        // Try multiple strategies
-        if let Some(ref bid) = build_id {
-            if let Some(path) = finder.find_by_build_id(bid) {
-                return Some(path);
+        if matches!(e_type, ET_EXEC | ET_DYN) {
+            if count(PT_LOAD) == 0 {
+                problems.push(format!("ET_{} without PT_LOAD segments", et_to_str(e_type)));
+            }
+            if count(PT_INTERP) > 0 && count(PT_DYNAMIC) == 0 {
+                problems.push("PT_INTERP without PT_DYNAMIC".to_string());
             }
         }
         
-        if let Some(ref link) = gnu_debuglink {
-            if let Some(path) = finder.find_by_debuglink(link) {
-                return Some(path);
+        for (p_type, name) in [(PT_INTERP, "PT_INTERP"), (PT_DYNAMIC, "PT_DYNAMIC")] {
+            if count(p_type) > 1 {
+                problems.push(format!("{} {} segments", count(p_type), name));
             }
         }
         
-        // Look for .debug file next to binary
-        finder.find_adjacent_debug()
+        // Fully stripped section headers (sstrip) or their names leave nothing to compare against
+        if !self.elf.section_headers.is_empty() && !self.section_names_unavailable
+            && count(PT_DYNAMIC) > 0 && !has_section(".dynamic")
+        {
+            problems.push("PT_DYNAMIC without a .dynamic section".to_string());
+        }
+        
+        let file_len = self.data.len() as u64;
+        let truncated = self.elf.program_headers.iter()
+            .any(|ph| ph.p_offset.saturating_add(ph.p_filesz) > file_len);
+        if truncated {
+            problems.push("segment extends past the end of the file".to_string());
+        }
+        
+        problems.into_iter()
+            .map(|problem| ScanWarning::new("inconsistent-structure", problem))
+            .collect()
     }
     
-    fn check_debuginfod(&self, build_id: &Option<String>, args: &Args) -> (Option<bool>, Option<String>) {
-        if let Some(ref bid) = build_id {
-            let client = DebuginfodClient::new(args.debuginfod_urls.clone());
-            
-            if let Ok((available, url)) = client.check_available(bid) {
-                return (Some(available), url);
+    /// .symtab and .dynsym names, leaving out file and section symbols
+    fn symbol_names(&self) -> impl Iterator<Item = &str> + '_ {
+        let static_names = self.elf.syms.iter()
+            .filter(|sym| sym.st_type() != goblin::elf::sym::STT_FILE && sym.st_type() != goblin::elf::sym::STT_SECTION)
+            .filter_map(|sym| self.elf.strtab.get_at(sym.st_name));
+        let dynamic_names = self.elf.dynsyms.iter()
+            .filter_map(|sym| self.elf.dynstrtab.get_at(sym.st_name));
+        
+        static_names.chain(dynamic_names)
+    }
+    
+    /// Local debug files the strategies found, preferred first, plus a
+    /// warning if `--resolve-timeout` cut the search short
+    fn find_local_debug_files(
+        &self,
+        build_id: &Option<String>,
+        gnu_debuglink: &Option<String>,
+        args: &Args,
+        ctx: &ScanContext,
+    ) -> (Vec<DebugCandidate>, Option<ScanWarning>) {
+        let finder = SymbolFinder::new(self.path)
+            .with_strategies(&ctx.symbol_strategies)
+            .with_sysroot(args.sysroot.as_deref())
+            .with_timeout(args.resolve_timeout.map(Duration::from_secs));
+        let found = self.search_local_debug_files(&finder, build_id, gnu_debuglink);
+        (found, finder.timeout_warning())
+    }
+    
+    /// Every strategy is tried, in the finder's order; a file two
+    /// strategies find is listed once, under the first
+    fn search_local_debug_files(
+        &self,
+        finder: &SymbolFinder,
+        build_id: &Option<String>,
+        gnu_debuglink: &Option<String>,
+    ) -> Vec<DebugCandidate> {
+        let mut found: Vec<DebugCandidate> = Vec::new();
+        for &strategy in finder.strategies() {
+            let path = match strategy {
+                Strategy::BuildId => build_id.as_deref().and_then(|id| finder.find_by_build_id(id)),
+                Strategy::Debuglink => gnu_debuglink.as_deref().and_then(|link| finder.find_by_debuglink(link)),
+                // Stripping can drop .gnu_debuglink along with the symbols
+                Strategy::PathMirror => finder.find_by_path_mirror(build_id.as_deref()),
+                Strategy::Adjacent => finder.find_adjacent_debug(),
+                Strategy::DsymAdjacent | Strategy::DerivedData => None,
+            };
+            let Some(path) = path else { continue };
+            let canonical = fs::canonicalize(&path).ok();
+            let seen = found.iter().any(|candidate| {
+                candidate.path == path || (canonical.is_some() && fs::canonicalize(&candidate.path).ok() == canonical)
+            });
+            if !seen {
+                found.push(DebugCandidate { path, source: strategy });
             }
         }
-        (Some(false), None)
+        found
+    }
+    
+    fn resolve_dependencies(
+        &self,
+        rpath: &[String],
+        runpath: &[String],
+        args: &Args,
+        ctx: &ScanContext,
+    ) -> (Vec<ResolvedDependency>, Vec<String>) {
+        let needed: Vec<String> = self.elf.libraries.iter().map(|name| name.to_string()).collect();
+        let root = AuditRoot {
+            binary: self.path,
+            prefix: args.scan_root(self.path),
+            sysroot: args.sysroot.as_deref(),
+        };
+        let target = LibraryTarget {
+            is_64: self.elf.is_64,
+            is_little_endian: self.elf.little_endian,
+            machine: self.elf.header.e_machine,
+        };
+        deps::resolve(&needed, rpath, runpath, &root, &ctx.library_dirs, target)
     }
 }
 
+/// The format's own name for an `e_machine` (`architecture_raw`)
+fn machine_name(e_machine: u16) -> String {
+    match e_machine {
+        EM_X86_64 => "x86_64".to_string(),
+        EM_386 => "i386".to_string(),
+        EM_ARM => "ARM".to_string(),
+        EM_AARCH64 => "AArch64".to_string(),
+        EM_RISCV => "RISC-V".to_string(),
+        EM_PPC => "PowerPC".to_string(),
+        EM_PPC64 => "PowerPC64".to_string(),
+        EM_MIPS => "MIPS".to_string(),
+        EM_S390 => "S390".to_string(),
+        _ => format!("Unknown (0x{:x})", e_machine),
+    }
+}
+
+#[derive(Default)]
+struct RelocationStats {
+    count: u64,
+    irelative: u64,
+    /// JMPREL relocations, one per PLT slot
+    plt_entries: u64,
+    got_bytes: u64,
+}
+
+/// SHT_RELR, packed relative relocations (not in goblin's table yet)
+const SHT_RELR: u32 = 19;
+
+/// Relocations encoded in a RELR section: an even word is one address, an
+/// odd word a bitmap of the following words to relocate
+fn relr_count(data: &[u8], is_64: bool, little_endian: bool) -> u64 {
+    let word_size = if is_64 { 8 } else { 4 };
+    data.chunks_exact(word_size)
+        .map(|bytes| {
+            let mut word = [0u8; 8];
+            if little_endian {
+                word[..word_size].copy_from_slice(bytes);
+                u64::from_le_bytes(word)
+            } else {
+                word[8 - word_size..].copy_from_slice(bytes);
+                u64::from_be_bytes(word)
+            }
+        })
+        .map(|word| if word & 1 == 0 { 1 } else { (word >> 1).count_ones() as u64 })
+        .sum()
+}
+
+/// `R_*_IRELATIVE` for the machine; `None` where the ABI has no IFUNCs
+fn irelative_type(e_machine: u16) -> Option<u32> {
+    match e_machine {
+        EM_X86_64 => Some(37),
+        EM_386 => Some(42),
+        EM_AARCH64 => Some(1032),
+        EM_ARM => Some(160),
+        EM_PPC | EM_PPC64 => Some(248),
+        EM_S390 => Some(61),
+        EM_RISCV => Some(58),
+        EM_SPARC | EM_SPARCV9 => Some(249),
+        _ => None,
+    }
+}
+
+/// `EI_OSABI` as readelf names it
+fn os_abi_name(os_abi: u8) -> String {
+    match os_abi {
+        ELFOSABI_NONE => "SYSV".to_string(),
+        ELFOSABI_HPUX => "HP-UX".to_string(),
+        ELFOSABI_NETBSD => "NetBSD".to_string(),
+        ELFOSABI_GNU => "GNU/Linux".to_string(),
+        ELFOSABI_SOLARIS => "Solaris".to_string(),
+        ELFOSABI_AIX => "AIX".to_string(),
+        ELFOSABI_FREEBSD => "FreeBSD".to_string(),
+        ELFOSABI_OPENBSD => "OpenBSD".to_string(),
+        ELFOSABI_ARM => "ARM".to_string(),
+        ELFOSABI_STANDALONE => "Standalone".to_string(),
+        _ => format!("Unknown (0x{:x})", os_abi),
+    }
+}
+
+/// C library the binary was linked against, from its interpreter and
+/// DT_NEEDED entries; `static` without either
+fn libc_flavor(interpreter: &Option<String>, needed: &[&str]) -> Option<String> {
+    let interpreter = interpreter.as_deref().unwrap_or("");
+    let flavor = if interpreter.contains("ld-musl") || needed.iter().any(|lib| lib.starts_with("libc.musl")) {
+        "musl"
+    } else if interpreter.starts_with("/system/bin/linker") || interpreter.starts_with("/apex/") {
+        "bionic"
+    } else if interpreter.contains("ld-linux") || interpreter.contains("ld64.so") || needed.contains(&"libc.so.6") {
+        "glibc"
+    } else if needed.iter().any(|lib| lib.starts_with("libc.so")) {
+        "libc"
+    } else if interpreter.is_empty() && needed.is_empty() {
+        "static"
+    } else {
+        return None;
+    };
+    Some(flavor.to_string())
+}
+
+/// What the ELF header alone says, for files the full parse rejects
+pub fn header_only(data: &[u8]) -> Option<BinaryInfo> {
+    let header = Elf::parse_header(data).ok()?;
+    let is_64 = header.e_ident[EI_CLASS] == ELFCLASS64;
+    let is_little_endian = header.e_ident[EI_DATA] == ELFDATA2LSB;
+
+    Some(BinaryInfo {
+        binary_type: "ELF".to_string(),
+        architecture: arch::from_elf(header.e_machine, is_64, is_little_endian).to_string(),
+        architecture_raw: machine_name(header.e_machine),
+        is_64bit: is_64,
+        is_big_endian: !is_little_endian,
+        machine_code: Some(header.e_machine),
+        abi_version: Some(header.e_ident[EI_ABIVERSION]),
+        elf_version: Some(header.e_version),
+        entry_point: (header.e_entry != 0).then(|| format!("0x{:x}", header.e_entry)),
+        is_executable: header.e_type == ET_EXEC || header.e_type == ET_DYN,
+        is_library: header.e_type == ET_DYN,
+        ..Default::default()
+    })
+}
+
+/// Whether `e_shstrndx` points at an SHT_STRTAB section; files without
+/// section headers need none
+fn has_section_name_table(elf: &Elf) -> bool {
+    if elf.section_headers.is_empty() {
+        return true;
+    }
+    let mut index = elf.header.e_shstrndx as u32;
+    if index == SHN_XINDEX {
+        index = elf.section_headers[0].sh_link;
+    }
+    index != SHN_UNDEF
+        && elf.section_headers.get(index as usize).is_some_and(|sh| sh.sh_type == SHT_STRTAB)
+}
+
+/// Build-id of the ELF file at `path`, if it has one
+pub(crate) fn debug_file_build_id(path: &Path) -> Option<String> {
+    let file = std::fs::File::open(path).ok()?;
+    let mmap = unsafe { memmap2::Mmap::map(&file).ok()? };
+    let analyzer = ElfAnalyzer::new(path, &mmap, 0, DateTime::<Utc>::default()).ok()?;
+    analyzer.extract_build_id()
+}
+
+/// Debug files that all carry `build_id` but differ in content: at most
+/// one of them matches the binary
+fn conflicting_debug_files(build_id: &str, candidates: &[DebugCandidate]) -> Option<ScanWarning> {
+    let matching: Vec<&DebugCandidate> = candidates.iter()
+        .filter(|candidate| debug_file_build_id(&candidate.path).is_some_and(|found| found.eq_ignore_ascii_case(build_id)))
+        .collect();
+    if matching.len() < 2 {
+        return None;
+    }
+    let hashes: HashSet<String> = matching.iter()
+        .filter_map(|candidate| manifest::hash_file(&candidate.path).ok())
+        .map(|(_, hash)| hash)
+        .collect();
+    (hashes.len() > 1).then(|| {
+        let paths: Vec<String> = matching.iter()
+            .map(|candidate| format!("{} ({})", candidate.path.display(), candidate.source.name()))
+            .collect();
+        ScanWarning::new("conflicting-debug-files", format!(
+            "{} debug files have build-id {} but different contents: {}", matching.len(), build_id, paths.join(", ")
+        ))
+    })
+}
+
+/// First four bytes of `data` in the file's byte order
+fn read_u32(data: &[u8], little_endian: bool) -> u32 {
+    let bytes = [data[0], data[1], data[2], data[3]];
+    if little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) }
+}
+
+/// OS and minimum kernel (or OS) version from an NT_GNU_ABI_TAG descriptor:
+/// four words, the OS code then major, minor and patch
+pub fn decode_abi_tag(desc: &[u8], little_endian: bool) -> Option<(String, String)> {
+    if desc.len() < 16 {
+        return None;
+    }
+    let word = |index: usize| read_u32(&desc[index * 4..], little_endian);
+    let os = match word(0) {
+        0 => "Linux".to_string(),
+        1 => "GNU/Hurd".to_string(),
+        2 => "Solaris".to_string(),
+        3 => "FreeBSD".to_string(),
+        4 => "NetBSD".to_string(),
+        5 => "Syllable".to_string(),
+        os => format!("Unknown ({})", os),
+    };
+    Some((os, format!("{}.{}.{}", word(1), word(2), word(3))))
+}
+
+/// How the linker made a hex build-id, judged by its length (`--build-id=`
+/// sha1, md5, uuid, lld's fast xxhash, sha256); `hex` for any other length,
+/// as `--build-id=0x...` gives. A 16-byte id is `uuid` when its version and
+/// variant bits say so, which one md5 in 64 also does.
+pub fn build_id_type(build_id: &str) -> &'static str {
+    let nibble = |index: usize| build_id.as_bytes().get(index).copied();
+    match build_id.len() / 2 {
+        20 => "sha1",
+        16 if nibble(12) == Some(b'4') && matches!(nibble(16), Some(b'8' | b'9' | b'a' | b'b' | b'A' | b'B')) => "uuid",
+        16 => "md5",
+        8 => "xxhash",
+        32 => "sha256",
+        _ => "hex",
+    }
+}
+
+const NT_GNU_ABI_TAG: u32 = 1;
+const NT_GNU_BUILD_ID: u32 = 3;
+const NT_GO_BUILD_ID: u32 = 4;
+const NT_GNU_PROPERTY_TYPE_0: u32 = 5;
+const NT_FDO_PACKAGING_METADATA: u32 = 0xcafe_1a7e;
+
+const GNU_PROPERTY_X86_ISA_1_NEEDED: u32 = 0xc000_8002;
+const GNU_PROPERTY_X86_ISA_1_USED: u32 = 0xc001_0002;
+
+/// `GNU_PROPERTY_X86_ISA_1_*` bits, highest level first
+const X86_ISA_LEVELS: [(u32, &str); 4] = [(8, "x86-64-v4"), (4, "x86-64-v3"), (2, "x86-64-v2"), (1, "x86-64")];
+
+/// Symbols in a MiniDebugInfo section: an xz-compressed ELF whose
+/// `.symtab` keeps the functions `strip` removed
+pub fn minidebuginfo_symbols(compressed: &[u8]) -> Option<u64> {
+    let mut elf = Capped { data: Vec::new(), limit: MAX_MINIDEBUGINFO_BYTES };
+    lzma_rs::xz_decompress(&mut &compressed[..], &mut elf).ok()?;
+    let elf = Elf::parse(&elf.data).ok()?;
+    Some(elf.syms.iter().filter(|sym| sym.st_name != 0).count() as u64)
+}
+
+/// A buffer that refuses to grow past `limit`, against decompression bombs
+struct Capped {
+    data: Vec<u8>,
+    limit: usize,
+}
+
+impl std::io::Write for Capped {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.data.len() + buf.len() > self.limit {
+            return Err(std::io::Error::other("decompressed past the limit"));
+        }
+        self.data.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Highest x86-64 level the ISA_1_NEEDED and ISA_1_USED properties of an
+/// NT_GNU_PROPERTY_TYPE_0 descriptor name; properties are padded to 8
+/// bytes in 64-bit files and 4 in 32-bit ones
+pub fn x86_isa_level(desc: &[u8], is_64: bool, little_endian: bool) -> Option<&'static str> {
+    let align = if is_64 { 8 } else { 4 };
+    let mut bits = 0;
+    let mut offset = 0;
+    while offset + 8 <= desc.len() {
+        let pr_type = read_u32(&desc[offset..], little_endian);
+        let datasz = read_u32(&desc[offset + 4..], little_endian) as usize;
+        offset += 8;
+        if datasz > desc.len() - offset {
+            break;
+        }
+        if matches!(pr_type, GNU_PROPERTY_X86_ISA_1_NEEDED | GNU_PROPERTY_X86_ISA_1_USED) && datasz >= 4 {
+            bits |= read_u32(&desc[offset..], little_endian);
+        }
+        offset += datasz.next_multiple_of(align);
+    }
+    X86_ISA_LEVELS.iter().find(|(bit, _)| bits & bit != 0).map(|(_, level)| *level)
+}
+
+/// Owning package from a `.note.package` note (the systemd/Fedora packaging
+/// metadata spec); the note's camelCase keys are accepted on input
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PackageInfo {
+    /// rpm, deb, alpm, ...
+    #[serde(alias = "type")]
+    pub package_type: Option<String>,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub architecture: Option<String>,
+    #[serde(alias = "osCpe")]
+    pub os_cpe: Option<String>,
+    #[serde(alias = "debugInfoUrl")]
+    pub debug_info_url: Option<String>,
+}
+
+/// A note entry, as listed at `-vv`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteInfo {
+    /// Section name, or `PT_NOTE@0x<offset>` for a segment without note sections
+    pub location: String,
+    pub owner: String,
+    pub note_type: u32,
+    /// NT_* name for the owner's known types
+    pub type_name: Option<String>,
+    pub desc_size: u64,
+    /// Why the entry couldn't be read; the rest of its region is skipped
+    pub malformed: Option<String>,
+}
+
+const NT_STAPSDT: u32 = 3;
+
+/// NT_* name of a note type; the numbers only mean something per owner
+pub fn note_type_name(owner: &str, note_type: u32) -> Option<&'static str> {
+    Some(match (owner, note_type) {
+        ("GNU", 1) => "NT_GNU_ABI_TAG",
+        ("GNU", 2) => "NT_GNU_HWCAP",
+        ("GNU", 3) => "NT_GNU_BUILD_ID",
+        ("GNU", 4) => "NT_GNU_GOLD_VERSION",
+        ("GNU", 5) => "NT_GNU_PROPERTY_TYPE_0",
+        ("Go", 4) => "NT_GO_BUILD_ID",
+        ("stapsdt", 3) => "NT_STAPSDT",
+        ("FDO", 0xcafe_1a7e) => "NT_FDO_PACKAGING_METADATA",
+        ("Android", 1) => "NT_ANDROID_TYPE_IDENT",
+        ("Android", 4) => "NT_ANDROID_TYPE_MEMTAG",
+        ("FreeBSD", 1) => "NT_FREEBSD_ABI_TAG",
+        ("FreeBSD", 2) => "NT_FREEBSD_NOINIT_TAG",
+        ("FreeBSD", 3) => "NT_FREEBSD_ARCH_TAG",
+        ("FreeBSD", 4) => "NT_FREEBSD_FEATURE_CTL",
+        ("NetBSD", 1) => "NT_NETBSD_IDENT",
+        ("OpenBSD", 1) => "NT_OPENBSD_IDENT",
+        ("Linux", 0x100) => "NT_LINUX_VERSION",
+        ("Xen", 0) => "XEN_ELFNOTE_INFO",
+        ("Xen", 1) => "XEN_ELFNOTE_ENTRY",
+        ("Xen", 2) => "XEN_ELFNOTE_HYPERCALL_PAGE",
+        ("Xen", 3) => "XEN_ELFNOTE_VIRT_BASE",
+        ("Xen", 4) => "XEN_ELFNOTE_PADDR_OFFSET",
+        ("Xen", 5) => "XEN_ELFNOTE_XEN_VERSION",
+        ("Xen", 6) => "XEN_ELFNOTE_GUEST_OS",
+        ("Xen", 7) => "XEN_ELFNOTE_GUEST_VERSION",
+        ("Xen", 8) => "XEN_ELFNOTE_LOADER",
+        ("Xen", 9) => "XEN_ELFNOTE_PAE_MODE",
+        ("Xen", 10) => "XEN_ELFNOTE_FEATURES",
+        ("Xen", 18) => "XEN_ELFNOTE_PHYS32_ENTRY",
+        ("CORE", 1) => "NT_PRSTATUS",
+        ("CORE", 2) => "NT_PRFPREG",
+        ("CORE", 3) => "NT_PRPSINFO",
+        ("CORE", 6) => "NT_AUXV",
+        ("CORE", 0x4649_4c45) => "NT_FILE",
+        ("CORE", 0x5349_4749) => "NT_SIGINFO",
+        _ => return None,
+    })
+}
+
+/// Walk a note section/segment for a note with `owner` and `note_type`
+/// Gives up after `max_bytes`, or on a run of empty entries (zero padding
+/// would otherwise be walked 12 bytes at a time)
+fn parse_note<'d>(data: &'d [u8], owner: &[u8], note_type: u32, max_bytes: usize, little_endian: bool) -> Result<Option<&'d [u8]>, NoteLimit> {
+    for entry in NoteEntries::new(data, max_bytes, little_endian) {
+        match entry {
+            NoteEntry::Note { name, note_type: ntype, desc } if ntype == note_type && name == owner => return Ok(Some(desc)),
+            NoteEntry::Note { .. } => {}
+            NoteEntry::Malformed { .. } => return Ok(None),
+            NoteEntry::Limit(limit) => return Err(limit),
+        }
+    }
+    Ok(None)
+}
+
+/// An entry of a note region
+enum NoteEntry<'d> {
+    Note { name: &'d [u8], note_type: u32, desc: &'d [u8] },
+    /// Sizes that run past the region; nothing after it can be read
+    Malformed { namesz: usize, descsz: usize, note_type: u32 },
+    Limit(NoteLimit),
+}
+
+/// The entries of a note region, ending after a malformed entry or a limit
+struct NoteEntries<'d> {
+    data: &'d [u8],
+    end: usize,
+    max_bytes: usize,
+    little_endian: bool,
+    offset: usize,
+    done: bool,
+}
+
+impl<'d> NoteEntries<'d> {
+    fn new(data: &'d [u8], max_bytes: usize, little_endian: bool) -> Self {
+        Self { data, end: data.len().min(max_bytes), max_bytes, little_endian, offset: 0, done: false }
+    }
+}
+
+impl<'d> Iterator for NoteEntries<'d> {
+    type Item = NoteEntry<'d>;
+
+    fn next(&mut self) -> Option<NoteEntry<'d>> {
+        if self.done {
+            return None;
+        }
+        let data = self.data;
+        let mut empty_run = 0;
+        while self.offset + 12 <= self.end {
+            let offset = self.offset;
+            let namesz = read_u32(&data[offset..], self.little_endian) as usize;
+            let descsz = read_u32(&data[offset + 4..], self.little_endian) as usize;
+            let note_type = read_u32(&data[offset + 8..], self.little_endian);
+            self.offset += 12;
+            
+            if namesz == 0 && descsz == 0 {
+                empty_run += 1;
+                if empty_run >= MAX_EMPTY_NOTES {
+                    self.done = true;
+                    return Some(NoteEntry::Limit(NoteLimit::EmptyEntries));
+                }
+                continue;
+            }
+            
+            // Align to 4 bytes; garbage sizes must not overflow
+            let namesz_aligned = namesz.saturating_add(3) & !3;
+            let descsz_aligned = descsz.saturating_add(3) & !3;
+            
+            if namesz_aligned.saturating_add(descsz_aligned) > data.len() - self.offset {
+                self.done = true;
+                return Some(NoteEntry::Malformed { namesz, descsz, note_type });
+            }
+            
+            let name = &data[self.offset..self.offset + namesz];
+            let desc_offset = self.offset + namesz_aligned;
+            self.offset += namesz_aligned + descsz_aligned;
+            return Some(NoteEntry::Note { name, note_type, desc: &data[desc_offset..desc_offset + descsz] });
+        }
+        
+        self.done = true;
+        (data.len() > self.max_bytes && self.offset < data.len()).then_some(NoteEntry::Limit(NoteLimit::Bytes(self.max_bytes)))
+    }
+}
@@ -0,0 +1,109 @@
+//! Environment variables that change what a scan finds
+//!
+//! They are read in one place, into `Environment`, which the rest of the
+//! scan asks instead of the process environment. `--hermetic` makes every
+//! lookup come back empty, so two machines given the same flags scan the
+//! same way; `--record-env` lists what was set.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use anyhow::Result;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, FromArgMatches};
+use serde::Serialize;
+
+use crate::cli::Args;
+
+/// Every variable symwalker reads, and what it changes
+pub const VARIABLES: [(&str, &str); 6] = [
+    ("SYMWALKER_DIR", "directory to scan when none is given"),
+    ("DEBUGINFOD_URLS", "debuginfod servers when --debuginfod-urls isn't given"),
+    ("HOME", "Xcode DerivedData searched for dSYM bundles"),
+    ("DEBUGINFOD_CACHE_PATH", "--prefetch cache when --cache-dir isn't given"),
+    ("XDG_CACHE_HOME", "--prefetch cache when neither is set"),
+    ("USERPROFILE", "home directory on Windows, where HOME is usually unset"),
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    hermetic: bool,
+    /// The `VARIABLES` that are set, even under `--hermetic`, for the record
+    values: BTreeMap<&'static str, String>,
+}
+
+/// One variable in the `--record-env` list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RecordedVariable {
+    pub name: &'static str,
+    pub set: bool,
+    /// Set, but `--hermetic` kept it from being used
+    pub ignored: bool,
+    /// Only with `--record-env-values`
+    pub value: Option<String>,
+}
+
+impl Environment {
+    /// Read `VARIABLES` from the process environment
+    pub fn capture(hermetic: bool) -> Self {
+        Self::from_vars(hermetic, VARIABLES.iter().filter_map(|&(name, _)| {
+            std::env::var_os(name).map(|value| (name, value.to_string_lossy().into_owned()))
+        }))
+    }
+
+    /// An environment with these variables set; names outside `VARIABLES` are dropped
+    pub fn from_vars<'a>(hermetic: bool, vars: impl IntoIterator<Item = (&'a str, String)>) -> Self {
+        let values = vars.into_iter()
+            .filter_map(|(name, value)| VARIABLES.iter().find(|&&(known, _)| known == name).map(|&(known, _)| (known, value)))
+            .collect();
+        Self { hermetic, values }
+    }
+
+    pub fn is_hermetic(&self) -> bool {
+        self.hermetic
+    }
+
+    /// A variable's value, or None under `--hermetic`
+    pub fn var(&self, name: &str) -> Option<&str> {
+        if self.hermetic {
+            return None;
+        }
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// `DEBUGINFOD_URLS` split into server URLs
+    pub fn debuginfod_urls(&self) -> Vec<String> {
+        self.var("DEBUGINFOD_URLS").map_or_else(Vec::new, |urls| urls.split_whitespace().map(str::to_string).collect())
+    }
+
+    /// `HOME`, else `USERPROFILE`
+    pub fn home_dir(&self) -> Option<PathBuf> {
+        ["HOME", "USERPROFILE"].iter()
+            .find_map(|&name| self.var(name).filter(|home| !home.is_empty()))
+            .map(PathBuf::from)
+    }
+
+    /// Each of `VARIABLES`, in order, with values redacted unless `values`
+    pub fn record(&self, values: bool) -> Vec<RecordedVariable> {
+        VARIABLES.iter()
+            .map(|&(name, _)| {
+                let value = self.values.get(name);
+                RecordedVariable {
+                    name,
+                    set: value.is_some(),
+                    ignored: value.is_some() && self.hermetic,
+                    value: value.filter(|_| values).cloned(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Arguments from the command line; under `--hermetic` the paths must be
+/// given there too, not taken from `SYMWALKER_DIR`
+pub fn args_from(matches: &ArgMatches) -> Result<Args> {
+    let args = Args::from_arg_matches(matches)?;
+    if args.hermetic && matches.value_source("paths") == Some(ValueSource::EnvVariable) {
+        anyhow::bail!("--hermetic doesn't read SYMWALKER_DIR; give the paths to scan");
+    }
+    Ok(args)
+}
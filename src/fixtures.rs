@@ -0,0 +1,189 @@
+//! Guessing which binaries are test fixtures (`likely_test_fixture`)
+//!
+//! Repositories check in small, deliberately odd binaries for their tests:
+//! stripped, hand-assembled, without a dynamic section. They trip coverage
+//! and hardening policies without being anything to fix. This is a
+//! heuristic: each signal that fired is recorded with what triggered it,
+//! and `--fixture-config` tunes or turns off the signals.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::binary::BinaryInfo;
+
+/// Source files bigger than this aren't searched for binary names
+const MAX_SOURCE_BYTES: u64 = 1024 * 1024;
+
+/// Fixture config file (TOML); every key is optional:
+///
+/// ```toml
+/// signals = ["path", "tiny", "empty-load", "source-reference"]
+/// path_components = ["test", "tests", "testdata", "fixtures"]
+/// tiny_bytes = 4096
+/// min_load_bytes = 256
+/// source_extensions = ["c", "rs", "go", "py"]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FixtureConfig {
+    /// Signals that mark a binary, by name (see `SIGNALS`)
+    pub signals: Vec<String>,
+    /// Directory names, matched case-insensitively below the scan root
+    pub path_components: Vec<String>,
+    /// `tiny`: smaller than this and without a dynamic section
+    pub tiny_bytes: u64,
+    /// `empty-load`: an executable or library whose largest PT_LOAD has
+    /// fewer file bytes than this
+    pub min_load_bytes: u64,
+    /// `source-reference`: extensions of the sibling files searched for the
+    /// binary's name
+    pub source_extensions: Vec<String>,
+}
+
+/// Every signal, in the order they're checked
+pub const SIGNALS: [&str; 4] = ["path", "tiny", "empty-load", "source-reference"];
+
+impl Default for FixtureConfig {
+    fn default() -> Self {
+        let strings = |items: &[&str]| items.iter().map(|item| item.to_string()).collect();
+        Self {
+            signals: strings(&SIGNALS),
+            path_components: strings(&["test", "tests", "testdata", "test-data", "test_data", "fixture", "fixtures", "__fixtures__"]),
+            tiny_bytes: 4096,
+            min_load_bytes: 256,
+            source_extensions: strings(&[
+                "c", "h", "cc", "cpp", "rs", "go", "py", "sh", "js", "ts", "java", "rb", "mk", "cmake", "txt", "json", "toml", "yaml", "yml",
+            ]),
+        }
+    }
+}
+
+impl FixtureConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read fixture config: {}", path.display()))?;
+        Self::parse(&content)
+            .with_context(|| format!("Invalid fixture config: {}", path.display()))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: Self = toml::from_str(content)?;
+        if let Some(unknown) = config.signals.iter().find(|signal| !SIGNALS.contains(&signal.as_str())) {
+            anyhow::bail!("unknown signal '{}' (expected one of {})", unknown, SIGNALS.join(", "));
+        }
+        Ok(config)
+    }
+
+    fn enabled(&self, signal: &str) -> bool {
+        self.signals.iter().any(|enabled| enabled == signal)
+    }
+}
+
+/// A signal that fired, and what it saw
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixtureSignal {
+    pub signal: String,
+    pub detail: String,
+}
+
+impl FixtureSignal {
+    fn new(signal: &str, detail: String) -> Self {
+        Self { signal: signal.to_string(), detail }
+    }
+}
+
+/// Sibling source files, read once per directory
+#[derive(Default)]
+struct SourceCache {
+    directories: HashMap<PathBuf, Vec<(String, String)>>,
+}
+
+impl SourceCache {
+    /// (file name, contents) of the source files in `dir`
+    fn sources(&mut self, dir: &Path, config: &FixtureConfig) -> &[(String, String)] {
+        self.directories.entry(dir.to_path_buf()).or_insert_with(|| {
+            let Ok(entries) = fs::read_dir(dir) else {
+                return Vec::new();
+            };
+            let mut sources: Vec<(String, String)> = entries.flatten()
+                .filter(|entry| entry.metadata().is_ok_and(|metadata| metadata.is_file() && metadata.len() <= MAX_SOURCE_BYTES))
+                .filter(|entry| {
+                    let path = entry.path();
+                    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+                    config.source_extensions.iter().any(|wanted| wanted.eq_ignore_ascii_case(extension))
+                })
+                .filter_map(|entry| {
+                    let content = fs::read(entry.path()).ok()?;
+                    Some((entry.file_name().to_string_lossy().into_owned(), String::from_utf8_lossy(&content).into_owned()))
+                })
+                .collect();
+            sources.sort();
+            sources
+        })
+    }
+}
+
+/// The signals `info` trips, for a binary found under `root`
+fn signals(info: &BinaryInfo, root: Option<&Path>, config: &FixtureConfig, sources: &mut SourceCache) -> Vec<FixtureSignal> {
+    let mut signals = Vec::new();
+
+    if config.enabled("path") {
+        let parents = info.file_path.parent().map(|dir| root.and_then(|root| dir.strip_prefix(root).ok()).unwrap_or(dir));
+        let component = parents.into_iter()
+            .flat_map(Path::components)
+            .filter_map(|component| component.as_os_str().to_str())
+            .find(|component| config.path_components.iter().any(|wanted| wanted.eq_ignore_ascii_case(component)));
+        if let Some(component) = component {
+            signals.push(FixtureSignal::new("path", format!("in a {} directory", component)));
+        }
+    }
+
+    if config.enabled("tiny") && info.file_size < config.tiny_bytes && !info.has_dynamic_section {
+        signals.push(FixtureSignal::new("tiny", format!("{} bytes, no dynamic section", info.file_size)));
+    }
+
+    if config.enabled("empty-load") && (info.is_executable || info.is_library) {
+        if let Some(bytes) = info.largest_load_bytes.filter(|&bytes| bytes < config.min_load_bytes) {
+            signals.push(FixtureSignal::new("empty-load", format!("largest PT_LOAD has {} file bytes", bytes)));
+        }
+    }
+
+    if config.enabled("source-reference") {
+        let name = info.file_path.file_name().and_then(|name| name.to_str());
+        if let (Some(dir), Some(name)) = (info.file_path.parent(), name) {
+            let referencing = sources.sources(dir, config).iter()
+                .find(|(source, content)| source != name && mentions(content, name));
+            if let Some((source, _)) = referencing {
+                signals.push(FixtureSignal::new("source-reference", format!("named in {}", source)));
+            }
+        }
+    }
+
+    signals
+}
+
+/// Whether `content` refers to a file called `name`, as a path or a quoted
+/// string: `./app`, `testdata/app`, `"app"`; not `app.c`, `myapp` or the
+/// word app in prose
+fn mentions(content: &str, name: &str) -> bool {
+    let part_of_name = |c: char| c.is_alphanumeric() || matches!(c, '.' | '_' | '-');
+    content.match_indices(name).any(|(start, _)| {
+        let before = content[..start].chars().next_back();
+        let after = content[start + name.len()..].chars().next();
+        matches!(before, Some('/' | '"' | '\'' | '`')) && !after.is_some_and(part_of_name)
+    })
+}
+
+/// Mark the binaries found under `roots` that look like test fixtures;
+/// only directories below the root a binary was found under count
+pub fn classify_all(binaries: &mut [BinaryInfo], roots: &[PathBuf], config: &FixtureConfig) {
+    let mut sources = SourceCache::default();
+    for info in binaries {
+        let root = roots.iter().find(|root| info.file_path.starts_with(root)).map(PathBuf::as_path);
+        info.fixture_signals = signals(info, root, config, &mut sources);
+        info.likely_test_fixture = !info.fixture_signals.is_empty();
+    }
+}
@@ -0,0 +1,92 @@
+//! Go's runtime symbol table (pclntab)
+//!
+//! Go binaries carry function names and line tables for the runtime's own
+//! stack traces, so they stay symbolizable after `strip` or `-ldflags=-s`.
+//! Only the header and function table size are read here.
+
+/// pclntab layouts, named for the Go release that introduced them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PclntabVersion {
+    Go12,
+    Go116,
+    Go118,
+    Go120,
+}
+
+impl PclntabVersion {
+    pub const ALL: [PclntabVersion; 4] = [
+        PclntabVersion::Go12,
+        PclntabVersion::Go116,
+        PclntabVersion::Go118,
+        PclntabVersion::Go120,
+    ];
+
+    pub fn magic(&self) -> u32 {
+        match self {
+            PclntabVersion::Go12 => 0xffff_fffb,
+            PclntabVersion::Go116 => 0xffff_fffa,
+            PclntabVersion::Go118 => 0xffff_fff0,
+            PclntabVersion::Go120 => 0xffff_fff1,
+        }
+    }
+
+    /// Header words (pointer-sized) before the one holding the function
+    /// table offset; Go 1.2 has no offsets, the table follows `nfunc`
+    fn function_table_word(&self) -> Option<usize> {
+        match self {
+            PclntabVersion::Go12 => None,
+            // nfunc, nfiles, funcnametab, cutab, filetab, pctab, pclntab
+            PclntabVersion::Go116 => Some(6),
+            // The same with textStart after nfiles
+            PclntabVersion::Go118 | PclntabVersion::Go120 => Some(7),
+        }
+    }
+
+    /// Bytes per function table entry (entry PC, function offset)
+    fn entry_size(&self, ptr_size: usize) -> usize {
+        match self {
+            PclntabVersion::Go12 | PclntabVersion::Go116 => 2 * ptr_size,
+            // Entry PCs became 32-bit offsets from textStart
+            PclntabVersion::Go118 | PclntabVersion::Go120 => 8,
+        }
+    }
+}
+
+/// The pclntab at the start of `data`: its layout and function count, or
+/// None when the header or function table doesn't hold up
+pub fn parse(data: &[u8], little_endian: bool) -> Option<(PclntabVersion, u64)> {
+    let magic = read(data, 0, 4, little_endian)? as u32;
+    let version = PclntabVersion::ALL.into_iter().find(|version| version.magic() == magic)?;
+
+    // Two zero pad bytes, the instruction size quantum and the pointer size
+    let header = data.get(4..8)?;
+    let (quantum, ptr_size) = (header[2], header[3] as usize);
+    if header[..2] != [0, 0] || !matches!(quantum, 1 | 2 | 4) || !matches!(ptr_size, 4 | 8) {
+        return None;
+    }
+
+    let word = |index: usize| read(data, 8 + index * ptr_size, ptr_size, little_endian);
+    let functions = word(0)?;
+    let table = match version.function_table_word() {
+        Some(index) => usize::try_from(word(index)?).ok().filter(|&offset| offset >= 8 + (index + 1) * ptr_size)?,
+        None => 8 + ptr_size,
+    };
+    let table_size = usize::try_from(functions).ok()?.checked_mul(version.entry_size(ptr_size))?;
+    (table.checked_add(table_size)? <= data.len()).then_some((version, functions))
+}
+
+/// A valid pclntab inside `data`, for linkers that merge it into another
+/// section instead of keeping `.gopclntab`; it is pointer-aligned, so only
+/// 4-byte boundaries are tried
+pub fn search(data: &[u8], little_endian: bool) -> Option<(PclntabVersion, u64)> {
+    (0..data.len().saturating_sub(8))
+        .step_by(4)
+        .filter(|&offset| data[offset + 4] == 0 && data[offset + 5] == 0)
+        .find_map(|offset| parse(&data[offset..], little_endian))
+}
+
+fn read(data: &[u8], offset: usize, size: usize, little_endian: bool) -> Option<u64> {
+    let bytes = data.get(offset..offset.checked_add(size)?)?;
+    let fold = |value: u64, byte: &u8| value << 8 | *byte as u64;
+    Some(if little_endian { bytes.iter().rev().fold(0, fold) } else { bytes.iter().fold(0, fold) })
+}
@@ -0,0 +1,100 @@
+//! Library dependency graph of a scanned tree, in Graphviz DOT (`--graph`)
+//!
+//! Nodes are the scanned binaries, edges their `DT_NEEDED` / `LC_LOAD_DYLIB`
+//! entries. A dependency is matched to a scanned library by the path
+//! `--check-deps` resolved it to, then by SONAME (install name for Mach-O),
+//! then by file name; the rest become dashed external nodes, usually
+//! system libraries.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+
+use crate::binary::BinaryInfo;
+use crate::deps::ResolvedDependency;
+
+#[derive(Debug, Clone)]
+pub struct GraphNode {
+    /// Path shown in the graph (relative with `--relative-paths`)
+    pub label: PathBuf,
+    file_path: PathBuf,
+    soname: Option<String>,
+    is_library: bool,
+    needed: Vec<String>,
+    resolved: Vec<ResolvedDependency>,
+}
+
+impl GraphNode {
+    /// A scanned binary, shown as `label`
+    pub fn from_binary(info: &BinaryInfo, label: PathBuf) -> Self {
+        Self {
+            label,
+            file_path: info.file_path.clone(),
+            soname: info.soname.clone().filter(|_| info.is_library),
+            is_library: info.is_library,
+            needed: info.needed.clone(),
+            resolved: info.resolved_dependencies.clone(),
+        }
+    }
+
+    /// The scanned node `name` refers to, if any
+    fn provider(&self, name: &str, nodes: &[GraphNode]) -> Option<usize> {
+        let resolved = self.resolved.iter().find(|dependency| dependency.name == name);
+        if let Some(index) = resolved.and_then(|dependency| nodes.iter().position(|node| node.file_path == dependency.path)) {
+            return Some(index);
+        }
+        if let Some(index) = nodes.iter().position(|node| node.soname.as_deref() == Some(name)) {
+            return Some(index);
+        }
+        let file_name = Path::new(name).file_name()?;
+        nodes.iter().position(|node| node.is_library && node.label.file_name() == Some(file_name))
+    }
+}
+
+/// DOT for `nodes`; nodes are sorted by label so the output is stable
+pub fn write_dot(nodes: &[GraphNode], out: &mut dyn Write) -> Result<()> {
+    let mut nodes = nodes.to_vec();
+    nodes.sort_by(|a, b| a.label.cmp(&b.label));
+
+    let mut edges = Vec::new();
+    let mut external: BTreeMap<&str, usize> = BTreeMap::new();
+    for (index, node) in nodes.iter().enumerate() {
+        for name in &node.needed {
+            let target = match node.provider(name, &nodes) {
+                Some(provider) if provider == index => continue,
+                Some(provider) => format!("n{}", provider),
+                None => {
+                    let next = external.len();
+                    format!("x{}", external.entry(name).or_insert(next))
+                }
+            };
+            let edge = (format!("n{}", index), target);
+            if !edges.contains(&edge) {
+                edges.push(edge);
+            }
+        }
+    }
+
+    writeln!(out, "digraph dependencies {{")?;
+    writeln!(out, "    rankdir=LR;")?;
+    writeln!(out, "    node [shape=box];")?;
+    for (index, node) in nodes.iter().enumerate() {
+        let shape = if node.is_library { ", shape=ellipse" } else { "" };
+        writeln!(out, "    n{} [label={}{}];", index, quote(&node.label.display().to_string()), shape)?;
+    }
+    for (name, index) in &external {
+        writeln!(out, "    x{} [label={}, shape=ellipse, style=dashed, color=gray50, fontcolor=gray50];", index, quote(name))?;
+    }
+    for (from, to) in &edges {
+        let style = if to.starts_with('x') { " [style=dashed, color=gray50]" } else { "" };
+        writeln!(out, "    {} -> {}{};", from, to, style)?;
+    }
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// A DOT string literal
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
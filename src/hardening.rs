@@ -0,0 +1,97 @@
+use serde::{Serialize, Deserialize};
+
+/// Outcome of a single hardening check
+/// `value` is `None` when the check doesn't apply to the format
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub value: Option<bool>,
+    pub evidence: String,
+}
+
+impl CheckResult {
+    pub fn pass(evidence: impl Into<String>) -> Self {
+        Self { value: Some(true), evidence: evidence.into() }
+    }
+
+    pub fn fail(evidence: impl Into<String>) -> Self {
+        Self { value: Some(false), evidence: evidence.into() }
+    }
+
+    pub fn not_applicable(evidence: impl Into<String>) -> Self {
+        Self { value: None, evidence: evidence.into() }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.value.unwrap_or(false)
+    }
+}
+
+/// Hardening checks shared by the ELF and Mach-O analyzers
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HardeningReport {
+    pub pie: CheckResult,
+    pub nx: CheckResult,
+    pub canary: CheckResult,
+    pub relro: CheckResult,
+    pub fortify: CheckResult,
+}
+
+impl HardeningReport {
+    /// Checks in display order, with their labels
+    pub fn checks(&self) -> [(&'static str, &CheckResult); 5] {
+        [
+            ("PIE", &self.pie),
+            ("NX", &self.nx),
+            ("Canary", &self.canary),
+            ("RELRO", &self.relro),
+            ("Fortify", &self.fortify),
+        ]
+    }
+    
+    /// Labels of applicable checks that failed
+    pub fn failures(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.checks().into_iter()
+            .filter(|(_, check)| check.value == Some(false))
+            .map(|(label, _)| label)
+    }
+}
+
+/// Stack protector: any `__stack_chk_fail`/`__stack_chk_guard` reference
+/// `table` names where the symbols came from, for the evidence string
+pub fn check_canary<'a>(names: impl Iterator<Item = &'a str>, table: &str) -> CheckResult {
+    for name in names {
+        if name.contains("stack_chk_fail") || name.contains("stack_chk_guard") {
+            return CheckResult::pass(format!("found {} in {}", name, table));
+        }
+    }
+    CheckResult::fail(format!("no __stack_chk_* symbol in {}", table))
+}
+
+/// Classically unsafe libc functions reported in `dangerous_imports`
+pub const DANGEROUS_FUNCTIONS: &[&str] = &[
+    "gets", "strcpy", "strcat", "sprintf", "vsprintf", "system", "popen",
+    "execl", "execle", "execlp", "execv", "execve", "execvp", "execvpe",
+];
+
+/// `DANGEROUS_FUNCTIONS` among imported `names`, sorted and deduplicated;
+/// Mach-O's leading underscore is ignored
+pub fn dangerous_imports<'a>(names: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut found: Vec<String> = names
+        .map(|name| name.strip_prefix('_').filter(|rest| DANGEROUS_FUNCTIONS.contains(rest)).unwrap_or(name))
+        .filter(|name| DANGEROUS_FUNCTIONS.contains(name))
+        .map(str::to_string)
+        .collect();
+    found.sort();
+    found.dedup();
+    found
+}
+
+/// FORTIFY_SOURCE: any fortified `*_chk` libc variant such as `__memcpy_chk`
+pub fn check_fortify<'a>(names: impl Iterator<Item = &'a str>, table: &str) -> CheckResult {
+    for name in names {
+        if name.ends_with("_chk") {
+            return CheckResult::pass(format!("found {} in {}", name, table));
+        }
+    }
+    CheckResult::fail(format!("no fortified *_chk symbol in {}", table))
+}
@@ -0,0 +1,78 @@
+//! Next-step hints after the human summary
+//!
+//! Each rule looks at the summary counts and what the run already did; the
+//! first `MAX_HINTS` that apply are shown, in rule order.
+
+use crate::cli::Args;
+use crate::output::Summary;
+
+/// Most hints shown after one run
+pub const MAX_HINTS: usize = 3;
+
+/// Options the hints would otherwise suggest again
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunOptions {
+    pub check_remote: bool,
+    pub download_remote: bool,
+}
+
+impl RunOptions {
+    pub fn from_args(args: &Args) -> Self {
+        Self { check_remote: args.check_remote, download_remote: args.download_remote }
+    }
+}
+
+/// One rule: its hint when it applies to the run
+pub type Rule = fn(&Summary, &RunOptions) -> Option<String>;
+
+pub const RULES: [Rule; 4] = [check_remote, download_remote, stale_debug_files, missing_build_ids];
+
+/// Hints for a run, at most `MAX_HINTS`
+pub fn hints(summary: &Summary, options: &RunOptions) -> Vec<String> {
+    RULES.iter().filter_map(|rule| rule(summary, options)).take(MAX_HINTS).collect()
+}
+
+/// Stripped binaries with build-ids may have debug info on a debuginfod server
+pub fn check_remote(summary: &Summary, options: &RunOptions) -> Option<String> {
+    (summary.stripped_with_build_id > 0 && !options.check_remote).then(|| format!(
+        "{} stripped {} without local symbols {} a build-id: --check-remote looks {} up on debuginfod servers",
+        summary.stripped_with_build_id,
+        plural(summary.stripped_with_build_id, "binary", "binaries"),
+        plural(summary.stripped_with_build_id, "has", "have"),
+        plural(summary.stripped_with_build_id, "it", "them"),
+    ))
+}
+
+/// Remote debug info was found but nothing fetched it
+pub fn download_remote(summary: &Summary, options: &RunOptions) -> Option<String> {
+    (summary.with_remote > 0 && !options.download_remote).then(|| format!(
+        "debug info for {} {} is available remotely: add --download-remote --output DIR to fetch it",
+        summary.with_remote,
+        plural(summary.with_remote, "binary", "binaries"),
+    ))
+}
+
+/// Adjacent debug files whose build-id doesn't match the binary
+pub fn stale_debug_files(summary: &Summary, _options: &RunOptions) -> Option<String> {
+    (summary.debug_file_mismatches > 0).then(|| format!(
+        "{} debug {} no longer {} {} binary (warning debug-file-mismatch): --problems lists them",
+        summary.debug_file_mismatches,
+        plural(summary.debug_file_mismatches, "file", "files"),
+        plural(summary.debug_file_mismatches, "matches", "match"),
+        plural(summary.debug_file_mismatches, "its", "their"),
+    ))
+}
+
+/// Most ELF binaries were linked without a build-id, so no symbol server or
+/// debug directory can be searched for them
+pub fn missing_build_ids(summary: &Summary, _options: &RunOptions) -> Option<String> {
+    (summary.elf_without_build_id > 0 && summary.elf_without_build_id * 2 > summary.elf).then(|| format!(
+        "{} of {} ELF binaries have no build-id, so their debug info can't be looked up: link with -Wl,--build-id",
+        summary.elf_without_build_id,
+        summary.elf,
+    ))
+}
+
+fn plural(count: usize, one: &'static str, many: &'static str) -> &'static str {
+    if count == 1 { one } else { many }
+}
@@ -0,0 +1,162 @@
+//! Self-contained HTML report (`--html-report`): summary, a sortable and
+//! filterable table, and the `-vv` human block of each binary on demand
+
+use std::fmt::Write as _;
+use std::io::Write;
+use anyhow::Result;
+
+use crate::binary::BinaryInfo;
+use crate::output::{HumanFormatter, OutputFormatter, Summary};
+
+const STYLE: &str = r#"
+body { font: 14px/1.4 system-ui, sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.5em; }
+table { border-collapse: collapse; }
+th, td { padding: 4px 10px; text-align: left; border-bottom: 1px solid #ddd; vertical-align: top; }
+#summary th { font-weight: normal; color: #666; }
+#binaries th { cursor: pointer; user-select: none; background: #f4f4f4; }
+#binaries th[aria-sort=ascending]::after { content: " \25B2"; }
+#binaries th[aria-sort=descending]::after { content: " \25BC"; }
+#filter { margin: 1em 0; padding: 4px; width: 30em; }
+summary { cursor: pointer; font-family: monospace; }
+pre { background: #f8f8f8; padding: 8px; }
+.stripped { color: #b00; }
+.debug, .local-debug, .remote-debug, .go-runtime-symbols, .minidebuginfo { color: #070; }
+.header-only { color: #a60; }
+"#;
+
+const SCRIPT: &str = r#"
+const table = document.getElementById("binaries");
+const rows = Array.from(table.tBodies[0].rows);
+document.getElementById("filter").addEventListener("input", (e) => {
+  const needle = e.target.value.toLowerCase();
+  for (const row of rows) row.hidden = !row.textContent.toLowerCase().includes(needle);
+});
+table.tHead.querySelectorAll("th").forEach((th, column) => {
+  th.addEventListener("click", () => {
+    const ascending = th.getAttribute("aria-sort") !== "ascending";
+    table.tHead.querySelectorAll("th").forEach((other) => other.removeAttribute("aria-sort"));
+    th.setAttribute("aria-sort", ascending ? "ascending" : "descending");
+    const key = (row) => row.cells[column].dataset.sort ?? row.cells[column].textContent;
+    const compare = (a, b) => key(a).localeCompare(key(b), undefined, { numeric: true });
+    rows.sort((a, b) => ascending ? compare(a, b) : compare(b, a));
+    table.tBodies[0].append(...rows);
+  });
+});
+"#;
+
+/// Renders the report from the same results as the other formatters
+pub struct HtmlFormatter;
+
+impl OutputFormatter for HtmlFormatter {
+    fn format(&self, binaries: &[BinaryInfo], out: &mut dyn Write) -> Result<()> {
+        let mut html = String::new();
+
+        html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str("<title>Symbol Walker report</title>\n");
+        writeln!(html, "<style>{}</style>", STYLE)?;
+        html.push_str("</head>\n<body>\n<h1>Symbol Walker report</h1>\n");
+
+        write_summary(&mut html, &Summary::new(binaries))?;
+
+        html.push_str("<input id=\"filter\" type=\"search\" placeholder=\"Filter binaries\">\n");
+        html.push_str("<table id=\"binaries\">\n<thead><tr><th>Path</th><th>Architecture</th><th>Type</th><th>Symbols</th><th>Hardening</th></tr></thead>\n<tbody>\n");
+
+        let details = HumanFormatter::new(2);
+        for (index, binary) in binaries.iter().enumerate() {
+            let mut block = Vec::new();
+            details.format_binary(index + 1, binary, &mut block)?;
+            let block = strip_ansi(&String::from_utf8_lossy(&block));
+
+            let (passed, applicable) = binary.hardening.checks().iter()
+                .filter_map(|(_, check)| check.value)
+                .fold((0, 0), |(passed, applicable), value| (passed + value as usize, applicable + 1));
+            let (grade, sort_key) = if applicable > 0 {
+                (format!("{}/{}", passed, applicable), format!("{:.3}", passed as f64 / applicable as f64))
+            } else {
+                ("n/a".to_string(), "-1".to_string())
+            };
+            let status = binary.debug_status();
+
+            writeln!(
+                html,
+                "<tr><td><details><summary>{}</summary><pre>{}</pre></details></td><td>{}</td><td>{} {}</td><td class=\"{}\">{}</td><td data-sort=\"{}\">{}</td></tr>",
+                escape(&binary.file_path.display().to_string()),
+                escape(block.trim_end()),
+                escape(&binary.architecture),
+                escape(&binary.binary_type),
+                binary.kind_label(),
+                status,
+                status,
+                sort_key,
+                grade,
+            )?;
+        }
+
+        html.push_str("</tbody>\n</table>\n");
+        writeln!(html, "<script>{}</script>", SCRIPT)?;
+        html.push_str("</body>\n</html>\n");
+
+        out.write_all(html.as_bytes())?;
+        Ok(())
+    }
+}
+
+fn write_summary(html: &mut String, summary: &Summary) -> Result<()> {
+    let architectures: Vec<String> = summary.architectures.iter()
+        .map(|(arch, count)| format!("{} {}", arch, count))
+        .collect();
+    let rows = [
+        ("Total binaries", summary.total.to_string()),
+        ("ELF binaries", summary.elf.to_string()),
+        ("Mach-O binaries", summary.macho.to_string()),
+        ("With embedded debug", summary.with_debug.to_string()),
+        ("With local symbols", summary.with_local.to_string()),
+        ("Remote available", summary.with_remote.to_string()),
+        ("Stripped", summary.stripped.to_string()),
+        ("Setuid/setgid", summary.privileged.to_string()),
+        ("Architectures", architectures.join(", ")),
+    ];
+
+    html.push_str("<table id=\"summary\">\n");
+    for (label, value) in rows {
+        writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", label, escape(&value))?;
+    }
+    html.push_str("</table>\n");
+    Ok(())
+}
+
+/// Escape text for element content and double-quoted attributes
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Drop the color escapes `colored` adds when stdout is a terminal
+fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // CSI: ESC [ parameters final-byte
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) && c != '[' {
+                    break;
+                }
+            }
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
@@ -0,0 +1,94 @@
+//! Imported function symbols (`--imports`), an approximation of the libc
+//! and system-library surface a binary uses when planning a seccomp or
+//! landlock policy
+//!
+//! ELF imports are the undefined `STT_FUNC` symbols in `.dynsym`, with the
+//! library named by their symbol version (`memcpy@GLIBC_2.14` comes from
+//! glibc); unversioned ones have no library, since any `DT_NEEDED` entry
+//! could provide them. Mach-O imports are the undefined symbols, with the
+//! dylib their two-level namespace ordinal names.
+
+use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::binary::BinaryInfo;
+use crate::demangle::demangle;
+
+/// Imports listed by the summary's most-imported line
+pub const COMMON_LIMIT: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportedSymbol {
+    /// As the source names it: Mach-O's leading underscore is dropped
+    pub name: String,
+    /// Only for mangled names
+    pub demangled: Option<String>,
+    /// Symbol version (`GLIBC_2.14`), ELF only
+    pub version: Option<String>,
+    /// `glibc`, `libstdc++`, `libgcc`, else the file the version or
+    /// ordinal names (`libz.so.1`, `libSystem.B.dylib`)
+    pub library: Option<String>,
+}
+
+impl ImportedSymbol {
+    /// An ELF import; `needed` is the version and the file `.gnu.version_r` names
+    pub fn elf(name: &str, needed: Option<(&str, &str)>) -> Self {
+        let library = needed.map(|(version, file)| library_for_version(version).unwrap_or(file).to_string());
+        Self::new(name, needed.map(|(version, _)| version.to_string()), library)
+    }
+
+    /// A Mach-O import from `dylib` (an install name), if known
+    pub fn macho(name: &str, dylib: Option<&str>) -> Self {
+        let name = name.strip_prefix('_').unwrap_or(name);
+        let library = dylib.map(|path| path.rsplit('/').next().unwrap_or(path).to_string());
+        Self::new(name, None, library)
+    }
+
+    fn new(name: &str, version: Option<String>, library: Option<String>) -> Self {
+        let demangled = Some(demangle(name)).filter(|demangled| demangled != name);
+        Self { name: name.to_string(), demangled, version, library }
+    }
+}
+
+/// The runtime a GNU symbol version belongs to, whatever its soname
+pub fn library_for_version(version: &str) -> Option<&'static str> {
+    const PREFIXES: [(&str, &str); 4] = [
+        ("GLIBC_", "glibc"),
+        ("GLIBCXX_", "libstdc++"),
+        ("CXXABI_", "libstdc++"),
+        ("GCC_", "libgcc"),
+    ];
+    PREFIXES.iter().find(|(prefix, _)| version.starts_with(prefix)).map(|&(_, library)| library)
+}
+
+/// Sorted by library, then name, without duplicates
+pub fn sorted(mut imports: Vec<ImportedSymbol>) -> Vec<ImportedSymbol> {
+    imports.sort_by(|a, b| (&a.library, &a.name, &a.version).cmp(&(&b.library, &b.name, &b.version)));
+    imports.dedup();
+    imports
+}
+
+/// Imports per library; `None` counts those no version or ordinal names
+pub fn by_library(imports: &[ImportedSymbol]) -> BTreeMap<Option<&str>, usize> {
+    imports.iter().fold(BTreeMap::new(), |mut counts, import| {
+        *counts.entry(import.library.as_deref()).or_insert(0) += 1;
+        counts
+    })
+}
+
+/// Names imported by the most binaries, most first, then by name
+pub fn most_common(binaries: &[BinaryInfo], limit: usize) -> Vec<(String, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for imports in binaries.iter().filter_map(|b| b.imports.as_ref()) {
+        let mut names: Vec<&str> = imports.iter().map(|import| import.name.as_str()).collect();
+        names.sort_unstable();
+        names.dedup();
+        for name in names {
+            *counts.entry(name).or_insert(0) += 1;
+        }
+    }
+    let mut common: Vec<(String, usize)> = counts.into_iter().map(|(name, count)| (name.to_string(), count)).collect();
+    common.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    common.truncate(limit);
+    common
+}
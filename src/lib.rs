@@ -0,0 +1,42 @@
+pub mod arch;
+pub mod binary;
+pub mod carve;
+pub mod cli;
+pub mod cluster;
+pub mod conflicts;
+pub mod container;
+pub mod deps;
+pub mod elf;
+pub mod environment;
+pub mod fixtures;
+pub mod go;
+pub mod graph;
+pub mod hardening;
+pub mod hints;
+pub mod html;
+pub mod imports;
+pub mod lock;
+pub mod logging;
+pub mod macho;
+pub mod manifest;
+pub mod orphans;
+pub mod output;
+pub mod plugin;
+pub mod prefetch;
+pub mod relative;
+pub mod repro;
+pub mod rpath;
+pub mod scan_errors;
+pub mod self_test;
+pub mod signatures;
+pub mod source_dirs;
+pub mod suspicious;
+pub mod symbol_finder;
+pub mod symbolicate;
+pub mod debuginfod;
+pub mod deep;
+pub mod demangle;
+pub mod dwarf;
+
+#[cfg(feature = "testutil")]
+pub mod testutil;
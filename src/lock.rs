@@ -0,0 +1,154 @@
+//! Advisory lock keeping two runs from writing the same `--output` directory
+
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Lock file created in the output directory for the length of a run
+pub const LOCK_NAME: &str = ".symwalker.lock";
+
+/// Locks older than this are stale even when their owner can't be checked
+/// (it runs on another host)
+pub const STALE_AFTER: Duration = Duration::hours(24);
+
+/// Exit status after an interrupt, as for a shell killed by SIGINT
+const EXIT_INTERRUPTED: i32 = 130;
+
+/// Lock path of this process, removed by the interrupt handler
+static HELD: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Owner recorded in the lock file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockInfo {
+    pub pid: u32,
+    pub hostname: String,
+    pub started: DateTime<Utc>,
+}
+
+impl LockInfo {
+    pub fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+            hostname: hostname(),
+            started: Utc::now(),
+        }
+    }
+
+    /// Too old, or its process on this host has exited
+    pub fn is_stale(&self) -> bool {
+        Utc::now() - self.started > STALE_AFTER
+            || (self.hostname == hostname() && !process_alive(self.pid))
+    }
+
+    fn describe(&self) -> String {
+        format!("PID {} on {} since {}", self.pid, self.hostname, self.started.format("%Y-%m-%d %H:%M:%S UTC"))
+    }
+}
+
+/// Held lock on an output directory; dropping it removes the lock file
+#[derive(Debug)]
+pub struct OutputLock {
+    path: PathBuf,
+}
+
+impl OutputLock {
+    /// Create the lock in `dir`, failing if another run holds it; with
+    /// `force`, a stale lock is taken over
+    pub fn acquire(dir: &Path, force: bool) -> Result<Self> {
+        let path = dir.join(LOCK_NAME);
+        if !create(&path)? {
+            // An unreadable lock names no owner that could still be running
+            let owner = fs::read_to_string(&path).ok()
+                .and_then(|text| serde_json::from_str::<LockInfo>(&text).ok());
+            let stale = owner.as_ref().is_none_or(LockInfo::is_stale);
+            let held_by = owner.as_ref().map_or("an unreadable lock".to_string(), LockInfo::describe);
+
+            if !stale {
+                anyhow::bail!("{} is in use by another run ({})", dir.display(), held_by);
+            }
+            if !force {
+                anyhow::bail!("{} has a stale lock ({}); pass --force-lock to take it over", dir.display(), held_by);
+            }
+            fs::remove_file(&path).with_context(|| format!("removing {}", path.display()))?;
+            if !create(&path)? {
+                anyhow::bail!("{} was locked by another run while taking over a stale lock", dir.display());
+            }
+        }
+
+        *HELD.lock().unwrap_or_else(|e| e.into_inner()) = Some(path.clone());
+        Ok(Self { path })
+    }
+}
+
+impl Drop for OutputLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+        HELD.lock().unwrap_or_else(|e| e.into_inner()).take();
+    }
+}
+
+/// Create the lock file exclusively; false if it already exists
+fn create(path: &Path) -> Result<bool> {
+    let mut file = match fs::OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => return Ok(false),
+        Err(e) => return Err(e).with_context(|| format!("creating {}", path.display())),
+    };
+    let result = serde_json::to_writer(&mut file, &LockInfo::current())
+        .map_err(anyhow::Error::from)
+        .and_then(|_| Ok(file.write_all(b"\n")?));
+    if let Err(e) = result {
+        let _ = fs::remove_file(path);
+        return Err(e).with_context(|| format!("writing {}", path.display()));
+    }
+    Ok(true)
+}
+
+/// Remove the held lock on Ctrl-C or SIGTERM before exiting; the journal
+/// already lets `--resume` finish the run
+pub fn release_on_interrupt() {
+    // A second call only fails to replace the identical handler
+    let _ = ctrlc::set_handler(|| {
+        if let Some(path) = HELD.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            let _ = fs::remove_file(path);
+        }
+        std::process::exit(EXIT_INTERRUPTED);
+    });
+}
+
+#[cfg(unix)]
+fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    // SAFETY: the buffer is valid for its length; the name may lack its NUL
+    // when truncated, so the last byte is left as one
+    if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len() - 1) } != 0 {
+        return "unknown".to_string();
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..len]).into_owned()
+}
+
+#[cfg(not(unix))]
+fn hostname() -> String {
+    std::env::var("COMPUTERNAME").unwrap_or_else(|_| "unknown".to_string())
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let Some(pid) = libc::pid_t::try_from(pid).ok().filter(|&pid| pid > 0) else {
+        return false;
+    };
+    // SAFETY: signal 0 only checks that the process exists
+    let signalled = unsafe { libc::kill(pid, 0) } == 0;
+    signalled || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Without a portable check, only the lock's age makes it stale
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
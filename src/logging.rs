@@ -0,0 +1,114 @@
+//! Diagnostics about symwalker itself (`--log-level`, `RUST_LOG`)
+//!
+//! Which paths a symbol strategy probed, which debuginfod servers a lookup
+//! tried, why a file was skipped: written to stderr through the `log`
+//! facade, apart from the report. `--verbose` adds result detail; this
+//! explains how the results came about.
+
+use std::io::Write;
+use clap::ValueEnum;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => LevelFilter::Off,
+            LogLevel::Error => LevelFilter::Error,
+            LogLevel::Warn => LevelFilter::Warn,
+            LogLevel::Info => LevelFilter::Info,
+            LogLevel::Debug => LevelFilter::Debug,
+            LogLevel::Trace => LevelFilter::Trace,
+        }
+    }
+}
+
+/// Which records get written: a default level plus per-module levels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    default: LevelFilter,
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl Filter {
+    /// Everything at `level` or more severe
+    pub fn level(level: LevelFilter) -> Self {
+        Self { default: level, modules: Vec::new() }
+    }
+
+    /// `RUST_LOG` directives, comma-separated: a bare level sets the default
+    /// and `module=level` one module and its children
+    /// (`warn,symwalker::debuginfod=trace`); anything unparseable is ignored
+    pub fn parse(spec: &str) -> Self {
+        let mut filter = Self::level(LevelFilter::Off);
+        for directive in spec.split(',').map(str::trim).filter(|directive| !directive.is_empty()) {
+            match directive.split_once('=') {
+                Some((module, level)) => {
+                    if let Ok(level) = level.trim().parse() {
+                        filter.modules.push((module.trim().to_string(), level));
+                    }
+                }
+                None => match directive.parse() {
+                    Ok(level) => filter.default = level,
+                    // A module on its own logs everything
+                    Err(_) => filter.modules.push((directive.to_string(), LevelFilter::Trace)),
+                },
+            }
+        }
+        filter
+    }
+
+    /// Whether a record from `target` at `level` passes; the longest
+    /// matching module wins
+    pub fn enabled(&self, target: &str, level: Level) -> bool {
+        let module = self.modules.iter()
+            .filter(|(module, _)| target == module || target.strip_prefix(module.as_str()).is_some_and(|rest| rest.starts_with("::")))
+            .max_by_key(|(module, _)| module.len());
+        level <= module.map_or(self.default, |&(_, level)| level)
+    }
+
+    /// The most verbose level anything passes at
+    fn max(&self) -> LevelFilter {
+        self.modules.iter().map(|&(_, level)| level).fold(self.default, Ord::max)
+    }
+}
+
+struct StderrLogger {
+    filter: Filter,
+}
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.filter.enabled(metadata.target(), metadata.level())
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let _ = writeln!(std::io::stderr().lock(), "[{} {}] {}", record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Install the stderr logger: `--log-level` wins over `RUST_LOG`, and with
+/// neither nothing is logged
+pub fn init(level: Option<LogLevel>, rust_log: Option<&str>) {
+    let filter = match (level, rust_log) {
+        (Some(level), _) => Filter::level(level.into()),
+        (None, Some(spec)) => Filter::parse(spec),
+        (None, None) => return,
+    };
+    log::set_max_level(filter.max());
+    // Only fails when a logger is already installed
+    let _ = log::set_boxed_logger(Box::new(StderrLogger { filter }));
+}
@@ -1,13 +1,40 @@
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
-use goblin::mach::{Mach, MachO};
+use serde::{Deserialize, Serialize};
+use goblin::mach::{Mach, MachO, MultiArch};
+use goblin::mach::fat::{SIZEOF_FAT_ARCH, SIZEOF_FAT_HEADER};
 use goblin::mach::constants::cputype::*;
 use goblin::mach::load_command::CommandVariant;
+use goblin::mach::symbols::N_STAB;
 
-use crate::binary::BinaryInfo;
+use crate::arch;
+use crate::binary::{BinaryInfo, ScanContext, ScanWarning};
 use crate::cli::Args;
-use crate::symbol_finder::SymbolFinder;
+use crate::demangle;
+use crate::go;
+use crate::hardening::{self, CheckResult, HardeningReport};
+use crate::imports::{self, ImportedSymbol};
+use crate::symbol_finder::{Strategy, SymbolFinder};
+
+/// Every `mach_header` flag `<mach-o/loader.h>` defines, `MH_NOUNDEFS`
+/// (0x1) through `MH_SIM_SUPPORT` (0x8000000), and `MH_DYLIB_IN_CACHE`
+pub const KNOWN_HEADER_FLAGS: u32 = 0x8fff_ffff;
+
+/// One architecture slice of a universal binary
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SliceInfo {
+    pub architecture: String,
+    pub offset: u64,
+    pub size: u64,
+    pub align: u64,  // bytes; the fat header stores the power of two
+    pub obsolete: bool,  // no current Apple OS runs it (i386, 32-bit ARM, PowerPC)
+    // The slice's own analysis; None when it doesn't parse
+    pub uuid: Option<String>,
+    pub is_stripped: Option<bool>,  // slices are often stripped differently
+    pub has_debug_info: Option<bool>,
+}
 
 pub struct MachoAnalyzer<'a> {
     path: &'a Path,
@@ -31,48 +58,73 @@ impl<'a> MachoAnalyzer<'a> {
         })
     }
     
-    pub fn analyze(&self, args: &Args) -> Result<BinaryInfo> {
+    pub fn analyze(&self, args: &Args, ctx: &ScanContext) -> Result<BinaryInfo> {
         let mach = Mach::parse(self.data)?;
         
-        // Handle universal/fat binaries - analyze first architecture
-        let macho = match mach {
-            Mach::Binary(m) => m,
+        // Universal binaries report their first slice; the others are
+        // analyzed into the slice list
+        let mut fat_layout = None;
+        let (macho, bytes) = match mach {
+            Mach::Binary(m) => (m, self.data),
             Mach::Fat(fat) => {
-                // Get first architecture
-                if let Some(arch) = fat.iter_arches().next() {
-                    let arch = arch?;
-                    let offset = arch.offset as usize;
-                    let size = arch.size as usize;
-                    if offset + size <= self.data.len() {
-                        MachO::parse(&self.data[offset..offset + size], 0)?
-                    } else {
-                        anyhow::bail!("Invalid fat binary");
+                let (mut slices, padding) = slice_layout(&fat)?;
+                let mut first = None;
+                for (index, slice) in slices.iter_mut().enumerate() {
+                    let end = slice.offset.checked_add(slice.size).filter(|&end| end <= self.data.len() as u64);
+                    let parsed = end.map(|end| &self.data[slice.offset as usize..end as usize])
+                        .map(|bytes| MachO::parse(bytes, 0).map(|macho| (macho, bytes)));
+                    let (macho, bytes) = match (index, parsed) {
+                        (_, Some(Ok(parsed))) => parsed,
+                        // Only the first slice has to parse
+                        (0, Some(Err(e))) => return Err(e.into()),
+                        (0, None) => anyhow::bail!("Invalid fat binary"),
+                        _ => continue,
+                    };
+                    slice.uuid = self.extract_uuid(&macho);
+                    slice.is_stripped = Some(self.is_stripped(&macho));
+                    slice.has_debug_info = Some(self.has_debug_info(&macho));
+                    if index == 0 {
+                        first = Some((macho, bytes));
                     }
-                } else {
-                    anyhow::bail!("Empty fat binary");
                 }
+                fat_layout = Some((slices, padding));
+                first.ok_or_else(|| anyhow::anyhow!("Empty fat binary"))?
             }
         };
         
-        let architecture = self.get_architecture(&macho);
+        let architecture = arch::from_macho(macho.header.cputype()).to_string();
+        let architecture_raw = self.get_architecture(&macho);
         let is_64bit = macho.is_64;
         let uuid = self.extract_uuid(&macho);
         let is_stripped = self.is_stripped(&macho);
         let has_debug_info = self.has_debug_info(&macho);
+        let go_pclntab = self.go_pclntab(&macho);
+        let (has_objc, has_swift) = self.language_runtimes(&macho);
         let (_, is_executable, is_library) = self.get_binary_type(&macho);
         let entry_point = self.get_entry_point(&macho);
         
         // Security features
-        let (has_nx, has_canary, has_pie) = self.check_security_features(&macho);
+        let hardening = self.check_hardening(&macho);
+        let mangling_stats = demangle::mangling_stats(symbol_names(&macho));
+        let panic_strategy = demangle::panic_strategy(symbol_names(&macho), &mangling_stats);
+        let dyld_environment = self.dyld_environment(&macho, bytes);
+        let mut warnings = self.collect_warnings(&macho, &dyld_environment);
         
-        // Find dSYM bundle
-        let dsym_bundle = if args.check_dsym {
-            self.find_dsym_bundle(&uuid)
+        // Find dSYM bundle, matching any slice's UUID
+        let uuids: Vec<String> = match fat_layout {
+            Some((ref slices, _)) => slices.iter().filter_map(|slice| slice.uuid.clone()).collect(),
+            None => uuid.iter().cloned().collect(),
+        };
+        let (dsym_bundle, debug_file_source) = if args.check_dsym {
+            let (found, resolve_warning) = self.find_dsym_bundle(&uuids, args, ctx);
+            warnings.extend(resolve_warning);
+            found.unzip()
         } else {
-            None
+            (None, None)
         };
         
         let debug_file_path = dsym_bundle.clone();
+        let (fat_slices, fat_padding_bytes) = fat_layout.unzip();
         
         Ok(BinaryInfo {
             file_path: self.path.to_path_buf(),
@@ -80,40 +132,53 @@ impl<'a> MachoAnalyzer<'a> {
             file_modified: self.file_modified,
             binary_type: "Mach-O".to_string(),
             architecture,
+            architecture_raw,
             is_64bit,
+            is_big_endian: !macho.little_endian,
             is_stripped,
-            has_debug_info,
+            has_debug_info: Some(has_debug_info),
+            has_go_pclntab: go_pclntab.is_some(),
+            go_function_count: go_pclntab.map(|(_, functions)| functions),
+            has_objc,
+            has_swift,
             build_id: None,
             gnu_debuglink: None,
             debug_sections: Vec::new(),
             uuid,
             dsym_bundle,
+            dyld_environment,
+            unknown_header_flags: macho.header.flags & !KNOWN_HEADER_FLAGS,
+            fat_slices,
+            fat_padding_bytes,
             debug_file_path,
+            debug_file_source,
             debuginfod_available: None,
             debuginfod_url: None,
             entry_point,
             interpreter: None,
-            is_pie: has_pie,
+            libc: macho.libs.iter().any(|lib| lib.contains("/libSystem.")).then(|| "libSystem".to_string()),
+            is_pie: hardening.pie.enabled(),
             is_executable,
             is_library,
-            has_nx,
-            has_canary,
-            has_relro: false,  // Not applicable to Mach-O
-            has_fortify: false,  // Check this separately
+            has_nx: hardening.nx.enabled(),
+            has_canary: hardening.canary.enabled(),
+            has_relro: hardening.relro.enabled(),
+            has_fortify: hardening.fortify.enabled(),
+            dangerous_imports: self.dangerous_imports(&macho),
+            imports: args.imports.then(|| self.imports(&macho)),
+            soname: macho.name.map(str::to_string),
+            // goblin lists the binary itself first, as "self"
+            needed: macho.libs.iter().skip(1).map(|lib| lib.to_string()).collect(),
+            hardening,
+            mangling_stats,
+            panic_strategy: panic_strategy.map(str::to_string),
+            warnings,
+            ..Default::default()
         })
     }
     
     fn get_architecture(&self, macho: &MachO) -> String {
-        match macho.header.cputype() {
-            CPU_TYPE_X86_64 => "x86_64".to_string(),
-            CPU_TYPE_X86 => "i386".to_string(),
-            CPU_TYPE_ARM => "ARM".to_string(),
-            CPU_TYPE_ARM64 => "ARM64".to_string(),
-            CPU_TYPE_ARM64_32 => "ARM64_32".to_string(),
-            CPU_TYPE_POWERPC => "PowerPC".to_string(),
-            CPU_TYPE_POWERPC64 => "PowerPC64".to_string(),
-            _ => format!("Unknown (0x{:x})", macho.header.cputype()),
-        }
+        cputype_name(macho.header.cputype())
     }
     
     fn extract_uuid(&self, macho: &MachO) -> Option<String> {
@@ -158,6 +223,37 @@ impl<'a> MachoAnalyzer<'a> {
         false
     }
     
+    /// Objective-C (`__objc_*` sections, or the 32-bit `__OBJC` segment)
+    /// and Swift (`__swift5_*`) runtime metadata
+    fn language_runtimes(&self, macho: &MachO) -> (bool, bool) {
+        let (mut has_objc, mut has_swift) = (false, false);
+        for segment in &macho.segments {
+            has_objc |= segment.name().ok() == Some("__OBJC");
+            for (section, _) in segment.sections().into_iter().flatten() {
+                let name = section.name().unwrap_or_default();
+                has_objc |= name.starts_with("__objc_");
+                has_swift |= name.starts_with("__swift5_");
+            }
+        }
+        (has_objc, has_swift)
+    }
+    
+    /// Go runtime symbol table: `__gopclntab`, or merged into `__noptrdata`
+    fn go_pclntab(&self, macho: &MachO) -> Option<(go::PclntabVersion, u64)> {
+        let sections: Vec<_> = macho.segments.iter()
+            .filter_map(|segment| segment.sections().ok())
+            .flatten()
+            .collect();
+        let contents = |name: &str| sections.iter()
+            .find(|(section, _)| section.name().ok() == Some(name))
+            .map(|(_, data)| *data);
+        
+        match contents("__gopclntab") {
+            Some(data) => go::parse(data, macho.little_endian),
+            None => go::search(contents("__noptrdata")?, macho.little_endian),
+        }
+    }
+    
     fn get_binary_type(&self, macho: &MachO) -> (bool, bool, bool) {
         use goblin::mach::header::*;
         
@@ -185,39 +281,232 @@ impl<'a> MachoAnalyzer<'a> {
         None
     }
     
-    fn check_security_features(&self, macho: &MachO) -> (bool, bool, bool) {
+    fn check_hardening(&self, macho: &MachO) -> HardeningReport {
         use goblin::mach::header::*;
         
-        let has_nx = (macho.header.flags & MH_NO_HEAP_EXECUTION) != 0;
-        let has_pie = (macho.header.flags & MH_PIE) != 0;
+        let pie = if macho.header.flags & MH_PIE != 0 {
+            CheckResult::pass("MH_PIE flag set")
+        } else if macho.header.filetype == MH_DYLIB {
+            CheckResult::not_applicable("MH_DYLIB (PIE applies to executables)")
+        } else {
+            CheckResult::fail("MH_PIE flag not set")
+        };
         
-        // Check for stack canary by looking for symbols
-        let mut has_canary = false;
-        for symbol in macho.symbols() {
-            if let Ok((name, _)) = symbol {
-                if name.contains("stack_chk") {
-                    has_canary = true;
-                    break;
+        let nx = if macho.header.flags & MH_NO_HEAP_EXECUTION != 0 {
+            CheckResult::pass("MH_NO_HEAP_EXECUTION flag set")
+        } else {
+            CheckResult::fail("MH_NO_HEAP_EXECUTION flag not set")
+        };
+        
+        let symbol_names = || macho.symbols()
+            .flatten()
+            .map(|(name, _)| name);
+        
+        HardeningReport {
+            pie,
+            nx,
+            canary: hardening::check_canary(symbol_names(), "symbol table"),
+            relro: CheckResult::not_applicable("not applicable to Mach-O"),
+            fortify: hardening::check_fortify(symbol_names(), "symbol table"),
+        }
+    }
+    
+    /// Undefined symbols from `hardening::DANGEROUS_FUNCTIONS`
+    fn dangerous_imports(&self, macho: &MachO) -> Vec<String> {
+        let imports = macho.symbols()
+            .flatten()
+            .filter(|(_, nlist)| nlist.is_undefined())
+            .map(|(name, _)| name);
+        hardening::dangerous_imports(imports)
+    }
+    
+    /// Undefined symbols with the dylib their library ordinal names, or
+    /// the dyld bind names when there is no symbol table
+    fn imports(&self, macho: &MachO) -> Vec<ImportedSymbol> {
+        // Ordinal 0 is this image; 0xfe the main executable, 0xff any image (flat lookup)
+        let dylib = |n_desc: u16| match usize::from(n_desc >> 8) {
+            ordinal @ 1..=0xfd => macho.libs.get(ordinal).copied(),
+            _ => None,
+        };
+        let mut imports: Vec<ImportedSymbol> = macho.symbols()
+            .flatten()
+            .filter(|(name, nlist)| nlist.is_undefined() && nlist.n_type & N_STAB == 0 && !name.is_empty())
+            .map(|(name, nlist)| ImportedSymbol::macho(name, dylib(nlist.n_desc)))
+            .collect();
+        if imports.is_empty() {
+            imports = macho.imports().unwrap_or_default().iter()
+                .map(|import| ImportedSymbol::macho(import.name, Some(import.dylib)))
+                .collect();
+        }
+        imports::sorted(imports)
+    }
+    
+    /// `LC_DYLD_ENVIRONMENT` strings, which dyld applies as if set in the
+    /// environment; `bytes` is the Mach-O the load command offsets refer to
+    fn dyld_environment(&self, macho: &MachO, bytes: &[u8]) -> Vec<String> {
+        macho.load_commands.iter()
+            .filter_map(|lc| match lc.command {
+                CommandVariant::DyldEnvironment(cmd) => {
+                    let start = lc.offset + cmd.name as usize;
+                    let end = lc.offset + cmd.cmdsize as usize;
+                    let raw = bytes.get(start..end.min(bytes.len()))?;
+                    let raw = raw.split(|&b| b == 0).next().unwrap_or(raw);
+                    Some(String::from_utf8_lossy(raw).into_owned())
                 }
+                _ => None,
+            })
+            .collect()
+    }
+    
+    fn collect_warnings(&self, macho: &MachO, dyld_environment: &[String]) -> Vec<ScanWarning> {
+        const VM_PROT_WRITE: u32 = 0x2;
+        const VM_PROT_EXECUTE: u32 = 0x4;
+        
+        let mut warnings = Vec::new();
+        
+        let wx = macho.segments.iter()
+            .any(|seg| seg.initprot & VM_PROT_WRITE != 0 && seg.initprot & VM_PROT_EXECUTE != 0);
+        if wx {
+            warnings.push(ScanWarning::new("wx-segment", "segment is both writable and executable"));
+        }
+        
+        // Libraries injected into every process started from this binary
+        for entry in dyld_environment {
+            if entry.starts_with("DYLD_INSERT_LIBRARIES=") {
+                warnings.push(ScanWarning::new("dyld-insert-libraries", format!("embeds {}", entry)));
             }
         }
         
-        (has_nx, has_canary, has_pie)
+        warnings
     }
     
-    fn find_dsym_bundle(&self, uuid: &Option<String>) -> Option<PathBuf> {
-        let finder = SymbolFinder::new(self.path);
-        
+    /// dSYM bundle and the strategy that found it, plus a warning if
+    /// `--resolve-timeout` cut the search short
+    fn find_dsym_bundle(&self, uuids: &[String], args: &Args, ctx: &ScanContext) -> (Option<(PathBuf, Strategy)>, Option<ScanWarning>) {
+        let finder = SymbolFinder::new(self.path)
+            .with_dsym_cache(&ctx.dsym_cache)
+            .with_home(ctx.environment.home_dir())
+            .follow_symlinks(args.include_symlinked_dsyms)
+            .with_strategies(&ctx.symbol_strategies)
+            .with_timeout(args.resolve_timeout.map(Duration::from_secs));
+        let found = self.search_dsym_bundle(&finder, uuids);
+        (found, finder.timeout_warning())
+    }
+    
+    fn search_dsym_bundle(&self, finder: &SymbolFinder, uuids: &[String]) -> Option<(PathBuf, Strategy)> {
         // Try multiple strategies
-        if let Some(ref uuid_str) = uuid {
+        for uuid in uuids {
             // Look for dSYM bundle in standard locations
-            if let Some(path) = finder.find_dsym_by_uuid(uuid_str) {
-                return Some(path);
+            if let Some(found) = finder.find_dsym_by_uuid(uuid) {
+                return Some(found);
             }
         }
         
         // Look for adjacent dSYM bundle
-        finder.find_adjacent_dsym()
+        finder.find_adjacent_dsym().map(|path| (path, Strategy::DsymAdjacent))
     }
 }
 
+/// The slices of a universal binary in header order, and the padding
+/// between the end of the fat header and the slices and between slices
+fn slice_layout(fat: &MultiArch) -> Result<(Vec<SliceInfo>, u64)> {
+    let mut slices = Vec::with_capacity(fat.narches);
+    for arch in fat.iter_arches() {
+        let arch = arch?;
+        slices.push(SliceInfo {
+            architecture: arch::from_macho(arch.cputype).to_string(),
+            offset: arch.offset.into(),
+            size: arch.size.into(),
+            align: 1u64.checked_shl(arch.align).unwrap_or(0),
+            obsolete: matches!(arch.cputype, CPU_TYPE_X86 | CPU_TYPE_ARM | CPU_TYPE_POWERPC | CPU_TYPE_POWERPC64),
+            uuid: None,
+            is_stripped: None,
+            has_debug_info: None,
+        });
+    }
+
+    let mut by_offset: Vec<&SliceInfo> = slices.iter().collect();
+    by_offset.sort_by_key(|slice| slice.offset);
+    let mut end = (SIZEOF_FAT_HEADER + fat.narches * SIZEOF_FAT_ARCH) as u64;
+    let mut padding = 0;
+    for slice in by_offset {
+        padding += slice.offset.saturating_sub(end);
+        end = end.max(slice.offset + slice.size);
+    }
+    Ok((slices, padding))
+}
+
+/// The format's own name for a `cputype` (`architecture_raw`)
+fn cputype_name(cputype: u32) -> String {
+    match cputype {
+        CPU_TYPE_X86_64 => "x86_64".to_string(),
+        CPU_TYPE_X86 => "i386".to_string(),
+        CPU_TYPE_ARM => "ARM".to_string(),
+        CPU_TYPE_ARM64 => "ARM64".to_string(),
+        CPU_TYPE_ARM64_32 => "ARM64_32".to_string(),
+        CPU_TYPE_POWERPC => "PowerPC".to_string(),
+        CPU_TYPE_POWERPC64 => "PowerPC64".to_string(),
+        _ => format!("Unknown (0x{:x})", cputype),
+    }
+}
+
+/// What the Mach-O header alone says, for files the full parse rejects
+/// Universal binaries use their first slice, or just its cputype when the
+/// slice itself is out of bounds
+pub fn header_only(data: &[u8]) -> Option<BinaryInfo> {
+    use goblin::mach::fat::FAT_MAGIC;
+    use goblin::mach::header::*;
+    
+    let word = |offset: usize, big_endian: bool| -> Option<u32> {
+        let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+    };
+
+    let (is_64, big_endian) = match word(0, true)? {
+        FAT_MAGIC => {
+            let cputype = word(8, true)?;
+            let offset = word(16, true)? as usize;
+            // A slice at offset 0 would be this fat header again
+            let slice = data.get(offset..).filter(|_| offset > 0);
+            return slice.and_then(header_only).or_else(|| {
+                Some(BinaryInfo {
+                    binary_type: "Mach-O".to_string(),
+                    architecture: arch::from_macho(cputype).to_string(),
+                    architecture_raw: cputype_name(cputype),
+                    is_64bit: cputype & CPU_ARCH_ABI64 != 0,
+                    ..Default::default()
+                })
+            });
+        }
+        MH_MAGIC => (false, true),
+        MH_MAGIC_64 => (true, true),
+        MH_CIGAM => (false, false),
+        MH_CIGAM_64 => (true, false),
+        _ => return None,
+    };
+
+    let cputype = word(4, big_endian)?;
+    let filetype = word(12, big_endian)?;
+    let flags = word(24, big_endian).unwrap_or(0);
+
+    Some(BinaryInfo {
+        binary_type: "Mach-O".to_string(),
+        architecture: arch::from_macho(cputype).to_string(),
+        architecture_raw: cputype_name(cputype),
+        is_64bit: is_64,
+        is_big_endian: big_endian,
+        is_executable: filetype == MH_EXECUTE,
+        is_library: filetype == MH_DYLIB,
+        unknown_header_flags: flags & !KNOWN_HEADER_FLAGS,
+        ..Default::default()
+    })
+}
+
+/// Symbol table names, skipping debugger stabs, which carry file and
+/// object names
+fn symbol_names<'a>(macho: &'a MachO) -> impl Iterator<Item = &'a str> + 'a {
+    macho.symbols()
+        .flatten()
+        .filter(|(_, nlist)| !nlist.is_stab())
+        .map(|(name, _)| name)
+}
@@ -1,26 +1,22 @@
-mod binary;
-mod cli;
-mod elf;
-mod macho;
-mod output;
-mod symbol_finder;
-mod debuginfod;
-
+use std::process::ExitCode;
 use anyhow::Result;
-use cli::Args;
-use clap::Parser;
+use clap::CommandFactory;
+use symwalker::cli::{self, Args};
+use symwalker::{environment, logging};
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+fn main() -> Result<ExitCode> {
+    let args = environment::args_from(&Args::command().get_matches())?;
+    // About symwalker itself, not the scan, so --hermetic leaves RUST_LOG alone
+    logging::init(args.log_level, std::env::var("RUST_LOG").ok().as_deref());
     
     // Disable colors if not a TTY or JSON output
     if !atty::is(atty::Stream::Stdout) || args.json {
         colored::control::set_override(false);
     }
+    // Older Windows consoles only render ANSI colors once asked to
+    #[cfg(windows)]
+    let _ = colored::control::set_virtual_terminal(true);
     
     // Run the scanner
-    cli::run(args)?;
-    
-    Ok(())
+    cli::run(args)
 }
-
@@ -0,0 +1,254 @@
+//! Checksums of the files written to an `--output` directory, and checking
+//! a copied directory against them (`symwalker verify-output`)
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use crate::elf;
+use crate::lock::LOCK_NAME;
+
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// A file in the output directory as it was written
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Artifact {
+    /// Relative to the output directory
+    pub path: PathBuf,
+    pub size: u64,
+    pub sha256: String,
+    /// File it was copied from; downloads have none
+    pub source: Option<PathBuf>,
+    /// GNU build-id the file carried when written (ELF only)
+    pub build_id: Option<String>,
+}
+
+/// Artifacts for `dest` inside `output_dir`, one per file when it's a
+/// directory (a dSYM bundle)
+pub fn artifacts(output_dir: &Path, dest: &Path, source: Option<&Path>) -> Result<Vec<Artifact>> {
+    let mut artifacts = Vec::new();
+
+    for entry in WalkDir::new(dest).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path();
+        let (size, sha256) = hash_file(path)?;
+        let within = path.strip_prefix(dest)?;
+        artifacts.push(Artifact {
+            path: path.strip_prefix(output_dir)?.to_path_buf(),
+            size,
+            sha256,
+            source: source.map(|source| if within.as_os_str().is_empty() { source.to_path_buf() } else { source.join(within) }),
+            build_id: elf::debug_file_build_id(path),
+        });
+    }
+
+    Ok(artifacts)
+}
+
+/// Size and hex SHA-256 of a file's contents
+pub fn hash_file(path: &Path) -> Result<(u64, String)> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut size = 0;
+
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+
+    Ok((size, hex::encode(hasher.finalize())))
+}
+
+/// Differences between an output directory and its manifest
+#[derive(Debug, Default, Serialize)]
+pub struct VerifyReport {
+    pub checked: usize,
+    pub missing: Vec<PathBuf>,
+    pub modified: Vec<PathBuf>,
+    /// Files the manifest doesn't list
+    pub extra: Vec<PathBuf>,
+    /// ELF files that no longer carry the build-id they were written with
+    pub build_id_mismatch: Vec<PathBuf>,
+    /// dSYM bundles without a file in `Contents/Resources/DWARF`
+    pub incomplete_dsyms: Vec<PathBuf>,
+    /// Missing or modified files copied again from their source
+    pub repaired: Vec<PathBuf>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty()
+            && self.modified.is_empty()
+            && self.extra.is_empty()
+            && self.build_id_mismatch.is_empty()
+            && self.incomplete_dsyms.is_empty()
+    }
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    artifacts: Option<Vec<Artifact>>,
+    /// Root that relative sources are under (`--relative-paths`)
+    scan_root: Option<PathBuf>,
+}
+
+/// Check every file `manifest.json` in `dir` lists; with `repair`, missing
+/// or modified files are copied again from their source when it still has
+/// the recorded contents
+pub fn verify(dir: &Path, repair: bool) -> Result<VerifyReport> {
+    let manifest_path = dir.join(MANIFEST_NAME);
+    let manifest: Manifest = serde_json::from_slice(&fs::read(&manifest_path)
+        .with_context(|| format!("reading {}", manifest_path.display()))?)
+        .with_context(|| format!("parsing {}", manifest_path.display()))?;
+    let Some(artifacts) = manifest.artifacts else {
+        anyhow::bail!("{} has no checksums; write it again with --output", manifest_path.display());
+    };
+
+    let mut report = VerifyReport { checked: artifacts.len(), ..Default::default() };
+
+    for artifact in &artifacts {
+        let path = dir.join(&artifact.path);
+        let intact = match hash_file(&path) {
+            Ok((size, sha256)) => size == artifact.size && sha256 == artifact.sha256,
+            Err(_) => false,
+        };
+
+        if !intact {
+            if repair && restore(artifact, &path, manifest.scan_root.as_deref()) {
+                report.repaired.push(artifact.path.clone());
+            } else if path.is_file() {
+                report.modified.push(artifact.path.clone());
+            } else {
+                report.missing.push(artifact.path.clone());
+            }
+            continue;
+        }
+
+        if artifact.build_id.is_some() && elf::debug_file_build_id(&path) != artifact.build_id {
+            report.build_id_mismatch.push(artifact.path.clone());
+        }
+    }
+
+    let listed: BTreeSet<&Path> = artifacts.iter().map(|artifact| artifact.path.as_path()).collect();
+    for entry in WalkDir::new(dir).sort_by_file_name().into_iter().filter_map(|e| e.ok()) {
+        let relative = entry.path().strip_prefix(dir)?;
+        let unlisted = relative != Path::new(MANIFEST_NAME) && relative != Path::new(LOCK_NAME) && !listed.contains(relative);
+        if entry.file_type().is_file() && unlisted {
+            report.extra.push(relative.to_path_buf());
+        }
+    }
+
+    // Bundles are recorded file by file; each needs its DWARF file
+    let bundles: BTreeSet<&Path> = listed.iter()
+        .filter_map(|path| path.components().next())
+        .map(|first| Path::new(first.as_os_str()))
+        .filter(|first| first.extension().is_some_and(|ext| ext == "dSYM"))
+        .collect();
+    for bundle in bundles {
+        let has_dwarf = fs::read_dir(dir.join(bundle).join("Contents/Resources/DWARF"))
+            .map(|entries| entries.filter_map(|e| e.ok()).any(|e| e.path().is_file()))
+            .unwrap_or(false);
+        if !has_dwarf {
+            report.incomplete_dsyms.push(bundle.to_path_buf());
+        }
+    }
+
+    Ok(report)
+}
+
+/// Copy `artifact` back from its source if that still has the recorded contents
+fn restore(artifact: &Artifact, dest: &Path, scan_root: Option<&Path>) -> bool {
+    let Some(ref source) = artifact.source else {
+        return false;
+    };
+    let source = match scan_root {
+        Some(root) if source.is_relative() => root.join(source),
+        _ => source.clone(),
+    };
+    if !matches!(hash_file(&source), Ok((size, ref sha256)) if size == artifact.size && *sha256 == artifact.sha256) {
+        return false;
+    }
+
+    if let Some(parent) = dest.parent() {
+        if fs::create_dir_all(parent).is_err() {
+            return false;
+        }
+    }
+    fs::copy(&source, dest).is_ok()
+}
+
+/// Entries of an `--output` run appended as each binary completes, so an
+/// interrupted run can be resumed; removed once `manifest.json` is written
+pub const JOURNAL_NAME: &str = "manifest.partial.jsonl";
+
+/// One completed binary: its `files` entry and the artifacts written for it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub entry: serde_json::Value,
+    pub artifacts: Vec<Artifact>,
+}
+
+pub struct Journal {
+    file: fs::File,
+}
+
+impl Journal {
+    /// Open the journal in `dir`, keeping its entries when `resume`
+    pub fn open(dir: &Path, resume: bool) -> Result<Self> {
+        let path = dir.join(JOURNAL_NAME);
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(resume)
+            .write(true)
+            .truncate(!resume)
+            .open(&path)
+            .with_context(|| format!("opening {}", path.display()))?;
+        Ok(Self { file })
+    }
+
+    /// Append an entry and sync it, so it survives the process being killed
+    pub fn append(&mut self, entry: &JournalEntry) -> Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+/// Completed entries of an earlier run by binary path, the last one winning;
+/// a line torn by the interruption is ignored
+pub fn read_journal(dir: &Path) -> Result<HashMap<String, JournalEntry>> {
+    let path = dir.join(JOURNAL_NAME);
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+    };
+
+    Ok(text.lines()
+        .filter_map(|line| serde_json::from_str::<JournalEntry>(line).ok())
+        .filter_map(|entry| Some((entry.entry.get("binary")?.as_str()?.to_string(), entry)))
+        .collect())
+}
+
+/// Whether every artifact is still in `dir` with its recorded contents
+pub fn intact(dir: &Path, artifacts: &[Artifact]) -> bool {
+    artifacts.iter().all(|artifact| {
+        matches!(hash_file(&dir.join(&artifact.path)), Ok((size, ref sha256)) if size == artifact.size && *sha256 == artifact.sha256)
+    })
+}
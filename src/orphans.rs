@@ -0,0 +1,102 @@
+//! Debug files in a symbol store that no scanned binary needs (`--symbol-store`)
+//!
+//! Store files are identified by their contents, not their names, so any
+//! layout works: `.build-id/XX/YYYY.debug` trees, debuginfod caches, dSYM
+//! bundles. A file is an orphan when its build-id (or Mach-O UUID) belongs
+//! to none of the scanned binaries; those are safe to garbage-collect.
+
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use clap::Parser;
+use colored::*;
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::binary::{scan_headers, BinaryInfo, ScanContext};
+use crate::cli::Args;
+use crate::output::HumanFormatter;
+use crate::symbol_finder::Strategy;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreOrphans {
+    pub store: PathBuf,
+    /// ELF and Mach-O files in the store with a build-id or UUID
+    pub identified: usize,
+    /// Other files, which are never reported as orphans
+    pub unidentified: usize,
+    /// Orphans in walk order
+    pub orphans: Vec<OrphanFile>,
+    pub orphan_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanFile {
+    pub path: PathBuf,
+    /// Build-id, or the UUID of a Mach-O file
+    pub id: String,
+    pub size: u64,
+}
+
+/// The build-id or UUID a scanned binary's debug files are stored under
+pub fn referenced_id(binary: &BinaryInfo) -> Option<&str> {
+    binary.build_id.as_deref().or(binary.uuid.as_deref())
+}
+
+impl StoreOrphans {
+    /// Walk `store` and keep the files whose id isn't in `referenced`
+    pub fn find(store: &Path, referenced: &HashSet<String>) -> Result<Self> {
+        if !store.is_dir() {
+            anyhow::bail!("Symbol store is not a directory: {}", store.display());
+        }
+
+        // Only the ids are needed, so don't look for the store files' own debug files
+        let mut argv = vec!["symwalker".into(), store.as_os_str().to_os_string()];
+        for strategy in Strategy::ALL {
+            argv.extend(["--no-symbol-strategy".into(), strategy.name().into()]);
+        }
+        let args = Args::parse_from(argv);
+        let ctx = ScanContext::new(&args)?;
+
+        let mut found = Self { store: store.to_path_buf(), identified: 0, unidentified: 0, orphans: Vec::new(), orphan_bytes: 0 };
+        // .build-id trees link each file under its id; the links aren't counted again
+        let files = WalkDir::new(store).sort_by_file_name().into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file());
+        for entry in files {
+            let info = scan_headers(entry.path(), &args, &ctx).ok();
+            let Some(id) = info.as_ref().and_then(referenced_id) else {
+                found.unidentified += 1;
+                continue;
+            };
+            found.identified += 1;
+            if !referenced.contains(id) {
+                let size = info.as_ref().map_or(0, |info| info.file_size);
+                found.orphan_bytes += size;
+                found.orphans.push(OrphanFile { path: entry.into_path(), id: id.to_string(), size });
+            }
+        }
+        Ok(found)
+    }
+
+    /// The orphan total, then each orphan by id
+    pub fn write_human(&self, out: &mut dyn Write) -> Result<()> {
+        writeln!(out)?;
+        writeln!(out, "{}", "Symbol Store Orphans".bright_cyan().bold())?;
+        writeln!(out, "   {} of {} debug files in {} {}",
+            self.orphans.len().to_string().bright_white().bold(),
+            self.identified,
+            self.store.display(),
+            format!("({} reclaimable)", HumanFormatter::format_size(self.orphan_bytes)).bright_black()
+        )?;
+        for orphan in &self.orphans {
+            writeln!(out, "      {} {} {}",
+                orphan.id.yellow(),
+                orphan.path.display(),
+                HumanFormatter::format_size(orphan.size).bright_black()
+            )?;
+        }
+        Ok(())
+    }
+}
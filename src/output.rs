@@ -1,21 +1,148 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
 use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
+use clap::ValueEnum;
 use colored::*;
-use crate::binary::BinaryInfo;
+use serde::Serialize;
+use crate::binary::{BinaryInfo, SCHEMA_VERSION};
+use crate::cli::CountField;
+use crate::cluster::Cluster;
+use crate::dwarf::DwarfOrigin;
+use crate::environment::RecordedVariable;
+use crate::imports;
+use crate::orphans::StoreOrphans;
+use crate::prefetch::PrefetchReport;
+use crate::conflicts::SonameConflict;
+use crate::scan_errors::ScanError;
+use crate::source_dirs::SourceDir;
+use crate::suspicious::SuspiciousFile;
+
+/// Between the blocks of the human report
+const SEPARATOR: &str = "────────────────────────────────────────────────────────────";
+
+/// `value` in `color`, written straight to the output: the bytes
+/// `value.to_string().color()` would give, without the two Strings
+pub(crate) struct Painted<T> {
+    value: T,
+    color: Color,
+    bold: bool,
+}
+
+pub(crate) fn paint<T: Display>(value: T, color: Color) -> Painted<T> {
+    Painted { value, color, bold: false }
+}
+
+impl<T> Painted<T> {
+    pub(crate) fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+}
+
+impl<T: Display> Display for Painted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !colored::control::SHOULD_COLORIZE.should_colorize() {
+            return self.value.fmt(f);
+        }
+        write!(f, "\x1b[{}{}m", if self.bold { "1;" } else { "" }, self.color.to_fg_str())?;
+        self.value.fmt(f)?;
+        f.write_str("\x1b[0m")
+    }
+}
+
+/// How the human report shows times (`--time-format`)
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeFormat {
+    /// 2024-05-01 12:00:00 UTC
+    Utc,
+    /// In the local time zone, with its offset (UTC when none is configured)
+    Local,
+    /// Age, like "3 days ago"
+    Relative,
+    /// Seconds since the Unix epoch
+    Epoch,
+}
+
+impl TimeFormat {
+    /// `time` as shown in the report; `now` is what relative times count from
+    pub fn render(&self, time: &DateTime<Utc>, now: &DateTime<Utc>) -> String {
+        match self {
+            TimeFormat::Utc => time.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+            TimeFormat::Local => time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S %:z").to_string(),
+            TimeFormat::Relative => relative_time(now.signed_duration_since(time).num_seconds()),
+            TimeFormat::Epoch => time.timestamp().to_string(),
+        }
+    }
+}
+
+/// "3 days ago" for `seconds` in the past, "in 2 hours" for the future
+fn relative_time(seconds: i64) -> String {
+    const UNITS: [(i64, &str); 5] = [(365 * 86400, "year"), (30 * 86400, "month"), (86400, "day"), (3600, "hour"), (60, "minute")];
+    let Some(&(size, unit)) = UNITS.iter().find(|(size, _)| seconds.abs() >= *size) else {
+        return "just now".to_string();
+    };
+    let count = seconds.abs() / size;
+    let plural = if count == 1 { "" } else { "s" };
+    if seconds < 0 {
+        format!("in {} {}{}", count, unit, plural)
+    } else {
+        format!("{} {}{} ago", count, unit, plural)
+    }
+}
 
 pub trait OutputFormatter {
-    fn format(&self, binaries: &[BinaryInfo]) -> Result<()>;
+    fn format(&self, binaries: &[BinaryInfo], out: &mut dyn Write) -> Result<()>;
 }
 
 pub struct HumanFormatter {
-    verbose: bool,
+    verbosity: u8,
+    /// One tab-separated line per binary instead of a block
+    compact: bool,
+    time_format: TimeFormat,
+    /// Sort binaries by this field, with a header before each value
+    group_by: Option<CountField>,
+    /// What relative times count from: when the formatter was made
+    now: DateTime<Utc>,
 }
 
 impl HumanFormatter {
-    pub fn new(verbose: bool) -> Self {
-        Self { verbose }
+    pub fn new(verbosity: u8) -> Self {
+        Self { verbosity, compact: false, time_format: TimeFormat::Utc, group_by: None, now: Utc::now() }
+    }
+    
+    pub fn time_format(mut self, time_format: TimeFormat) -> Self {
+        self.time_format = time_format;
+        self
+    }
+    
+    /// Count relative times from `now` instead
+    pub fn relative_to(mut self, now: DateTime<Utc>) -> Self {
+        self.now = now;
+        self
+    }
+    
+    /// Line per binary, for pipes; ignored with `-v`, which asks for detail
+    pub fn compact(mut self, compact: bool) -> Self {
+        self.compact = compact && self.verbosity == 0;
+        self
+    }
+    
+    /// Group the report by `field`; compact lines are only reordered,
+    /// so they stay one binary per line
+    pub fn group_by(mut self, field: Option<CountField>) -> Self {
+        self.group_by = field;
+        self
     }
     
-    fn format_size(bytes: u64) -> String {
+    pub fn is_compact(&self) -> bool {
+        self.compact
+    }
+    
+    pub(crate) fn format_size(bytes: u64) -> String {
         const KB: u64 = 1024;
         const MB: u64 = KB * 1024;
         const GB: u64 = MB * 1024;
@@ -33,20 +160,36 @@ impl HumanFormatter {
 }
 
 impl OutputFormatter for HumanFormatter {
-    fn format(&self, binaries: &[BinaryInfo]) -> Result<()> {
+    fn format(&self, binaries: &[BinaryInfo], out: &mut dyn Write) -> Result<()> {
+        let groups = self.groups(binaries);
+        if self.compact {
+            for binary in groups.iter().flat_map(|(_, group)| group) {
+                writeln!(out, "{}", Self::compact_line(binary))?;
+            }
+            return Ok(());
+        }
+        
         if binaries.is_empty() {
-            println!("{}", "No binaries found.".yellow());
+            writeln!(out, "{}", "No binaries found.".yellow())?;
             return Ok(());
         }
         
-        println!("Found {} binar{}", 
+        writeln!(out, "Found {} binar{}", 
             binaries.len().to_string().bright_white().bold(),
             if binaries.len() == 1 { "y" } else { "ies" }
-        );
-        println!();
+        )?;
+        writeln!(out)?;
         
-        for (idx, binary) in binaries.iter().enumerate() {
-            self.format_binary(idx + 1, binary)?;
+        let mut index = 0;
+        for (key, group) in &groups {
+            if let (Some(field), Some(key)) = (self.group_by, key) {
+                writeln!(out, "{}", format!("{}: {} ({})", field.name(), key, group.len()).bold())?;
+                writeln!(out)?;
+            }
+            for binary in group {
+                index += 1;
+                self.format_binary(index, binary, out)?;
+            }
         }
         
         Ok(())
@@ -54,208 +197,1285 @@ impl OutputFormatter for HumanFormatter {
 }
 
 impl HumanFormatter {
-    fn format_binary(&self, index: usize, binary: &BinaryInfo) -> Result<()> {
-        // Header
-        let type_label = if binary.is_executable {
-            "EXE"
-        } else if binary.is_library {
-            "LIB"
-        } else {
-            "BIN"
+    /// `binaries` split by the `group_by` field, groups in key order and
+    /// binaries in scan order within each; one unkeyed group without it
+    fn groups<'a>(&self, binaries: &'a [BinaryInfo]) -> Vec<(Option<String>, Vec<&'a BinaryInfo>)> {
+        let Some(field) = self.group_by else {
+            return vec![(None, binaries.iter().collect())];
         };
+        let groups = binaries.iter().fold(BTreeMap::new(), |mut groups: BTreeMap<String, Vec<&BinaryInfo>>, binary| {
+            groups.entry(field.key(binary)).or_default().push(binary);
+            groups
+        });
+        groups.into_iter().map(|(key, group)| (Some(key), group)).collect()
+    }
+    
+    /// `path  format  kind  arch  debug-status`, tab-separated
+    fn compact_line(binary: &BinaryInfo) -> String {
+        format!("{}\t{}\t{}\t{}\t{}",
+            binary.file_path.display(),
+            binary.binary_type,
+            binary.kind_label(),
+            binary.architecture,
+            binary.debug_status()
+        )
+    }
+    
+    pub(crate) fn format_binary(&self, index: usize, binary: &BinaryInfo, out: &mut dyn Write) -> Result<()> {
+        // Header
+        let type_label = binary.kind_label();
         
-        println!("{} Binary #{} ({})", 
-            "●".bright_cyan(),
-            index.to_string().bright_white(),
-            type_label.bright_yellow()
-        );
+        writeln!(out, "{} Binary #{} ({})", 
+            paint("●", Color::BrightCyan),
+            paint(index, Color::BrightWhite),
+            paint(&type_label, Color::BrightYellow)
+        )?;
         
         // Basic info
-        println!("   {}: {}", 
-            "Path".bright_black(),
-            binary.file_path.display().to_string().white()
-        );
-        println!("   {}: {}", 
-            "Size".bright_black(),
-            Self::format_size(binary.file_size).white()
-        );
-        println!("   {}: {} {}", 
-            "Architecture".bright_black(),
-            binary.architecture.white(),
-            if binary.is_64bit { "(64-bit)" } else { "(32-bit)" }.bright_black()
-        );
-        println!("   {}: {}", 
-            "Type".bright_black(),
-            binary.binary_type.white()
-        );
-        println!("   {}: {}", 
-            "Modified".bright_black(),
-            binary.file_modified.format("%Y-%m-%d %H:%M:%S UTC").to_string().white()
-        );
+        writeln!(out, "   {}: {}", 
+            paint("Path", Color::BrightBlack),
+            paint(binary.file_path.display(), Color::White)
+        )?;
+        writeln!(out, "   {}: {}", 
+            paint("Size", Color::BrightBlack),
+            paint(Self::format_size(binary.file_size), Color::White)
+        )?;
+        let bits = if binary.is_64bit { "64-bit" } else { "32-bit" };
+        let byte_order = match (self.verbosity >= 1, binary.is_big_endian) {
+            (false, _) => "",
+            (true, true) => ", big-endian",
+            (true, false) => ", little-endian",
+        };
+        match binary.fat_slices {
+            // The fields below describe the first slice
+            Some(ref slices) => writeln!(out, "   {}: {} {}", 
+                paint("Architecture", Color::BrightBlack),
+                paint(format_args!("Universal ({})", slices.iter().map(|slice| slice.architecture.as_str()).collect::<Vec<_>>().join(", ")), Color::White),
+                paint(format_args!("(reporting {})", binary.architecture), Color::BrightBlack)
+            )?,
+            None => writeln!(out, "   {}: {} {}", 
+                paint("Architecture", Color::BrightBlack),
+                paint(&binary.architecture, Color::White),
+                paint(format_args!("({}{})", bits, byte_order), Color::BrightBlack)
+            )?,
+        }
+        if binary.is_debug_only {
+            writeln!(out, "   {}: {} {}", 
+                paint("Type", Color::BrightBlack),
+                paint(&binary.binary_type, Color::White),
+                paint("(separate debug file)", Color::BrightBlack)
+            )?;
+        } else {
+            writeln!(out, "   {}: {}", 
+                paint("Type", Color::BrightBlack),
+                paint(&binary.binary_type, Color::White)
+            )?;
+        }
+        writeln!(out, "   {}: {}", 
+            paint("Modified", Color::BrightBlack),
+            paint(self.time_format.render(&binary.file_modified, &self.now), Color::White)
+        )?;
+        
+        if binary.is_privileged() {
+            let bits: Vec<&str> = [(binary.setuid, "SETUID"), (binary.setgid, "SETGID")]
+                .iter()
+                .filter(|(set, _)| *set)
+                .map(|(_, label)| *label)
+                .collect();
+            writeln!(out, "   {}: {}", 
+                paint("Privileges", Color::BrightBlack),
+                bits.join(" + ").bright_red().bold()
+            )?;
+        }
+        
+        if binary.likely_test_fixture {
+            let signals: Vec<String> = binary.fixture_signals.iter()
+                .map(|signal| format!("{}: {}", signal.signal, signal.detail))
+                .collect();
+            writeln!(out, "   {}: {} {}", 
+                paint("Test Fixture", Color::BrightBlack),
+                paint("likely", Color::Yellow),
+                paint(format_args!("({})", signals.join("; ")), Color::BrightBlack)
+            )?;
+        }
+        
+        for warning in &binary.warnings {
+            writeln!(out, "   {} {} {}", 
+                paint("⚠", Color::Yellow),
+                paint(&warning.message, Color::Yellow),
+                paint(format_args!("[{}]", warning.code), Color::BrightBlack)
+            )?;
+        }
+        
+        if !binary.dangerous_imports.is_empty() {
+            writeln!(out, "   {} {} {}", 
+                paint("⚠", Color::Yellow),
+                paint(format_args!("imports {}", binary.dangerous_imports.join(", ")), Color::Yellow),
+                paint("[dangerous-imports]", Color::BrightBlack)
+            )?;
+        }
+        
+        if let Some(ref imports) = binary.imports {
+            let libraries: Vec<String> = imports::by_library(imports).into_iter()
+                .map(|(library, count)| format!("{} {}", library.unwrap_or("other"), count))
+                .collect();
+            writeln!(out, "   {}: {} {}",
+                paint("Imports", Color::BrightBlack),
+                paint(imports.len(), Color::White),
+                paint(if libraries.is_empty() { String::new() } else { format!("({})", libraries.join(", ")) }, Color::BrightBlack)
+            )?;
+            if self.verbosity >= 2 {
+                for import in imports {
+                    let name = import.demangled.as_deref().unwrap_or(&import.name);
+                    let version = import.version.as_ref().map(|version| format!("@{}", version)).unwrap_or_default();
+                    writeln!(out, "      {}{} {}",
+                        paint(&name, Color::White),
+                        paint(&version, Color::BrightBlack),
+                        paint(import.library.as_deref().unwrap_or("other"), Color::BrightBlack)
+                    )?;
+                }
+            }
+        }
+        
+        // Nothing beyond file metadata for formats we don't analyze
+        if binary.is_unsupported() {
+            writeln!(out)?;
+            return Ok(());
+        }
         
         // Verbose mode
-        if self.verbose {
+        if self.verbosity >= 1 {
             if let Some(ref entry) = binary.entry_point {
-                println!("   {}: {}", "Entry Point".bright_black(), entry.white());
+                match binary.entry_section {
+                    Some(ref section) => writeln!(out, "   {}: {} ({})", paint("Entry Point", Color::BrightBlack), paint(&entry, Color::White), section)?,
+                    None => writeln!(out, "   {}: {}", paint("Entry Point", Color::BrightBlack), paint(&entry, Color::White))?,
+                }
             }
             
             if let Some(ref interp) = binary.interpreter {
-                println!("   {}: {}", "Interpreter".bright_black(), interp.white());
+                writeln!(out, "   {}: {}", paint("Interpreter", Color::BrightBlack), paint(&interp, Color::White))?;
             }
             
-            // Security features
-            println!();
-            println!("   {}", "Security Features:".bright_cyan());
-            println!("      PIE: {}", if binary.is_pie { "✓".green() } else { "✗".red() });
-            println!("      NX: {}", if binary.has_nx { "✓".green() } else { "✗".red() });
-            println!("      Canary: {}", if binary.has_canary { "✓".green() } else { "✗".red() });
+            if let Some(ref package) = binary.package_info {
+                let fields = [&package.name, &package.version, &package.architecture];
+                let mut label: Vec<&str> = fields.iter().filter_map(|field| field.as_deref()).collect();
+                let kind = package.package_type.as_deref().map(|kind| format!("({})", kind));
+                label.extend(kind.as_deref());
+                writeln!(out, "   {}: {}", paint("Package", Color::BrightBlack), paint(label.join(" "), Color::White))?;
+            }
             
-            if binary.binary_type == "ELF" {
-                println!("      RELRO: {}", if binary.has_relro { "✓".green() } else { "✗".red() });
-                println!("      Fortify: {}", if binary.has_fortify { "✓".green() } else { "✗".red() });
+            // Security features; a separate debug file has no code to harden
+            if !binary.is_debug_only {
+                writeln!(out)?;
+                writeln!(out, "   {}", paint("Security Features:", Color::BrightCyan))?;
+                for (label, check) in binary.hardening.checks() {
+                    // Checks that don't apply to the format are omitted
+                    let Some(value) = check.value else {
+                        continue;
+                    };
+                    let mark = if value { paint("✓", Color::Green) } else { paint("✗", Color::Red) };
+                
+                    if self.verbosity >= 2 {
+                        writeln!(out, "      {}: {} {}", label, mark, paint(format_args!("({})", check.evidence), Color::BrightBlack))?;
+                    } else {
+                        writeln!(out, "      {}: {}", label, mark)?;
+                    }
+                }
+            
+                for finding in &binary.rpath_findings {
+                    writeln!(out, "      Search Path: {} {} {}", 
+                        paint("✗", Color::Red),
+                        paint(finding.entry.escape_debug(), Color::White),
+                        paint(format_args!("({})", finding.issue.as_str()), Color::BrightBlack)
+                    )?;
+                }
             }
         }
         
-        println!();
+        writeln!(out)?;
         
         // Debug info status
-        if binary.is_stripped {
-            println!("   {}: {}", 
-                "Symbols".bright_black(),
-                "Stripped".red()
-            );
+        if binary.partial_analysis {
+            writeln!(out, "   {}: {}", 
+                paint("Symbols", Color::BrightBlack),
+                paint("Unknown (header-only analysis)", Color::Yellow)
+            )?;
+        } else if binary.is_stripped && binary.has_go_pclntab {
+            let functions = binary.go_function_count.map_or(String::new(), |count| format!(", {} functions", count));
+            writeln!(out, "   {}: {} {}", 
+                paint("Symbols", Color::BrightBlack),
+                paint("Stripped", Color::Yellow),
+                paint(format_args!("(Go runtime symbol table{})", functions), Color::BrightBlack)
+            )?;
+        } else if binary.is_stripped && binary.has_minidebuginfo {
+            writeln!(out, "   {}: {} {}", 
+                paint("Symbols", Color::BrightBlack),
+                paint("Stripped", Color::Yellow),
+                paint("(function symbols in MiniDebugInfo)", Color::BrightBlack)
+            )?;
+        } else if binary.is_stripped {
+            writeln!(out, "   {}: {}", 
+                paint("Symbols", Color::BrightBlack),
+                paint("Stripped", Color::Red)
+            )?;
         } else {
-            println!("   {}: {}", 
-                "Symbols".bright_black(),
-                "Present".green()
-            );
+            writeln!(out, "   {}: {}", 
+                paint("Symbols", Color::BrightBlack),
+                paint("Present", Color::Green)
+            )?;
+        }
+        
+        if binary.has_minidebuginfo {
+            let symbols = match binary.minidebuginfo_symbols {
+                Some(count) => format!("present ({} symbols)", count),
+                None => "present (doesn't decompress)".to_string(),
+            };
+            writeln!(out, "   {}: {}", 
+                paint("MiniDebugInfo", Color::BrightBlack),
+                paint(symbols, Color::Green)
+            )?;
+        }
+        
+        if binary.is_debug_only {
+            writeln!(out, "   {}: {} {}", 
+                paint("Debug Info", Color::BrightBlack),
+                paint("✓", Color::Green),
+                paint("Debug-only file (no code)", Color::BrightBlack)
+            )?;
+        } else if binary.has_debug_info == Some(true) {
+            writeln!(out, "   {}: {} {}", 
+                paint("Debug Info", Color::BrightBlack),
+                paint("✓", Color::Green),
+                paint("Embedded", Color::BrightBlack)
+            )?;
+        } else if binary.section_names_unavailable {
+            writeln!(out, "   {}: {} {}", 
+                paint("Debug Info", Color::BrightBlack),
+                paint("?", Color::Yellow),
+                paint("Unknown (no section names)", Color::BrightBlack)
+            )?;
+        }
+        
+        if self.verbosity >= 1 && !binary.resolution_method.is_empty() {
+            writeln!(out, "   {}: {}", paint("Resolved by", Color::BrightBlack), paint(&binary.resolution_method, Color::White))?;
+        }
+        
+        if binary.is_skeleton {
+            writeln!(out, "   {}: {} {}", 
+                paint("Split DWARF", Color::BrightBlack),
+                paint("⚠", Color::Yellow),
+                paint(format_args!("Skeleton units only, {} .dwo file(s) referenced", binary.dwo_names.len()), Color::BrightBlack)
+            )?;
+            if self.verbosity >= 1 {
+                for name in &binary.dwo_names {
+                    writeln!(out, "      {}", paint(&name, Color::White))?;
+                }
+            }
+        }
+        
+        if self.verbosity >= 1 && !binary.mangling_stats.is_empty() {
+            let stats: Vec<String> = binary.mangling_stats.iter()
+                .map(|(scheme, count)| format!("{} {}", scheme, count))
+                .collect();
+            writeln!(out, "   {}: {}", 
+                paint("Mangling", Color::BrightBlack),
+                paint(stats.join(", "), Color::White)
+            )?;
+        }
+        
+        if let (Some(ref strategy), true) = (&binary.panic_strategy, self.verbosity >= 1) {
+            writeln!(out, "   {}: {}", paint("Panic Strategy", Color::BrightBlack), paint(strategy, Color::White))?;
+        }
+        
+        if let (Some(global), Some(local), true) = (binary.global_symbol_count, binary.local_symbol_count, self.verbosity >= 1) {
+            writeln!(out, "   {}: {}",
+                paint("Symbol Table", Color::BrightBlack),
+                paint(format_args!("{} global, {} local", global, local), Color::White)
+            )?;
+        }
+        
+        if self.verbosity >= 1 {
+            if let Some(count) = binary.function_count {
+                let origin = if binary.dwarf_origin == Some(DwarfOrigin::Remote) { " (remote debug info)" } else { "" };
+                writeln!(out, "   {}: {}{}", 
+                    paint("Functions", Color::BrightBlack),
+                    paint(count, Color::White),
+                    paint(&origin, Color::BrightBlack)
+                )?;
+            }
+        }
+        
+        if self.verbosity >= 2 {
+            if let Some(ref functions) = binary.largest_functions {
+                if !functions.is_empty() {
+                    writeln!(out, "   {}:", paint("Largest Functions", Color::BrightBlack))?;
+                    for function in functions {
+                        writeln!(out, "      {:>10}  {}", 
+                            paint(Self::format_size(function.size), Color::BrightWhite),
+                            paint(&function.name, Color::White)
+                        )?;
+                    }
+                }
+            }
         }
         
-        if binary.has_debug_info {
-            println!("   {}: {} {}", 
-                "Debug Info".bright_black(),
-                "✓".green(),
-                "Embedded".bright_black()
-            );
+        if self.verbosity >= 1 && !(binary.resolved_dependencies.is_empty() && binary.missing_dependencies.is_empty()) {
+            writeln!(out, "   {}:", paint("Dependencies", Color::BrightBlack))?;
+            for dependency in &binary.resolved_dependencies {
+                writeln!(out, "      {} {} {}", 
+                    paint("✓", Color::Green),
+                    paint(&dependency.name, Color::White),
+                    paint(format_args!("=> {}", dependency.path.display()), Color::BrightBlack)
+                )?;
+            }
+            for name in &binary.missing_dependencies {
+                writeln!(out, "      {} {} {}", 
+                    paint("✗", Color::Red),
+                    paint(&name, Color::White),
+                    paint("(not found)", Color::BrightBlack)
+                )?;
+            }
+        }
+        
+        if let Some(format) = binary.container_format {
+            let detail = match binary.payload_files {
+                Some(files) => format!(" ({} files, {} native libraries)", files, binary.embedded_libraries.len()),
+                None => String::new(),
+            };
+            writeln!(out, "   {}: {}{}", 
+                paint("Container", Color::BrightBlack),
+                format.as_str().cyan(),
+                paint(&detail, Color::BrightBlack)
+            )?;
+            if self.verbosity >= 1 {
+                for library in &binary.embedded_libraries {
+                    writeln!(out, "      {:>10}  {}", 
+                        paint(Self::format_size(library.size), Color::BrightWhite),
+                        paint(&library.path, Color::White)
+                    )?;
+                }
+            }
+        }
+        
+        if !binary.signature_matches.is_empty() {
+            writeln!(out, "   {}: {}", 
+                paint("Signature Matches", Color::BrightBlack),
+                paint(binary.signature_matches.len(), Color::BrightRed).bold()
+            )?;
+            for m in &binary.signature_matches {
+                writeln!(out, "      {} {} ({} @ 0x{:x})", 
+                    paint("⚠", Color::BrightRed),
+                    paint(&m.signature, Color::BrightWhite),
+                    paint(m.pattern.escape_debug(), Color::White),
+                    m.offset
+                )?;
+            }
         }
         
         // ELF-specific
         if binary.binary_type == "ELF" {
             if let Some(ref build_id) = binary.build_id {
-                println!("   {}: {}", 
-                    "Build ID".bright_black(),
-                    build_id.bright_white()
-                );
+                writeln!(out, "   {}: {} {}", 
+                    paint("Build ID", Color::BrightBlack),
+                    paint(&build_id, Color::BrightWhite),
+                    paint(format_args!("({}, {} bytes)", binary.build_id_type.as_deref().unwrap_or("hex"), build_id.len() / 2), Color::BrightBlack)
+                )?;
+            }
+            
+            if let Some(ref go_build_id) = binary.go_build_id {
+                writeln!(out, "   {}: {}", 
+                    paint("Go Build ID", Color::BrightBlack),
+                    paint(&go_build_id, Color::White)
+                )?;
             }
             
             if let Some(ref debuglink) = binary.gnu_debuglink {
-                println!("   {}: {}", 
-                    "GNU Debuglink".bright_black(),
-                    debuglink.white()
-                );
+                writeln!(out, "   {}: {}", 
+                    paint("GNU Debuglink", Color::BrightBlack),
+                    paint(&debuglink, Color::White)
+                )?;
+            }
+            
+            if let (Some(os_abi), Some(abi_version), Some(elf_version), true) =
+                (&binary.os_abi, binary.abi_version, binary.elf_version, self.verbosity >= 1)
+            {
+                writeln!(out, "   {}: {}",
+                    paint("ELF Header", Color::BrightBlack),
+                    paint(format_args!("OS/ABI {}, ABI version {}, ELF version {}", os_abi, abi_version, elf_version), Color::White)
+                )?;
+            }
+            
+            if let (Some(level), true) = (&binary.isa_level, self.verbosity >= 1) {
+                writeln!(out, "   {}: {}", paint("ISA Level", Color::BrightBlack), paint(&level, Color::White))?;
+            }
+            
+            if let (Some(hash_style), true) = (&binary.hash_style, self.verbosity >= 1) {
+                writeln!(out, "   {}: {}", paint("Hash Style", Color::BrightBlack), paint(&hash_style, Color::White))?;
+            }
+            
+            if let (Some(os), Some(version), true) = (&binary.abi_os, &binary.min_kernel_version, self.verbosity >= 1) {
+                writeln!(out, "   {}: {}", paint("ABI Tag", Color::BrightBlack), paint(format_args!("{} {} or later", os, version), Color::White))?;
             }
             
-            if !binary.debug_sections.is_empty() && self.verbose {
-                println!("   {}: {}", 
-                    "Debug Sections".bright_black(),
-                    binary.debug_sections.join(", ").white()
-                );
+            if binary.has_tls && self.verbosity >= 1 {
+                writeln!(out, "   {}: {}",
+                    paint("Thread-Local Storage", Color::BrightBlack),
+                    paint(format_args!("{} per thread", Self::format_size(binary.tls_size)), Color::White)
+                )?;
+            }
+            
+            if !binary.debug_sections.is_empty() && self.verbosity >= 1 {
+                writeln!(out, "   {}: {}", 
+                    paint("Debug Sections", Color::BrightBlack),
+                    paint(binary.debug_sections.join(", "), Color::White)
+                )?;
+            }
+            
+            if self.verbosity >= 2 {
+                if let Some(count) = binary.relocation_count {
+                    writeln!(out, "   {}: {} {}", 
+                        paint("Relocations", Color::BrightBlack),
+                        paint(count, Color::White),
+                        paint(format_args!("({} IRELATIVE, {} PLT entries, {} GOT)",
+                            binary.irelative_count.unwrap_or(0),
+                            binary.plt_entries.unwrap_or(0),
+                            Self::format_size(binary.got_bytes.unwrap_or(0))
+                        ), Color::BrightBlack)
+                    )?;
+                }
+                
+                if let Some(ref notes) = binary.notes {
+                    let probes = match binary.stapsdt_probes {
+                        Some(probes) if probes > 0 => format!(" ({} stapsdt probes)", probes),
+                        _ => String::new(),
+                    };
+                    writeln!(out, "   {}: {}", paint("Notes", Color::BrightBlack), paint(format_args!("{}{}", notes.len(), probes), Color::White))?;
+                    for note in notes {
+                        if let Some(ref reason) = note.malformed {
+                            writeln!(out, "      {}  {}", paint(&note.location, Color::White), paint(format_args!("malformed: {}", reason), Color::Yellow))?;
+                            continue;
+                        }
+                        let note_type = match note.type_name {
+                            Some(ref name) => format!("{} ({})", name, note.note_type),
+                            None => format!("type 0x{:x}", note.note_type),
+                        };
+                        writeln!(out, "      {}  {}  {}  {}",
+                            paint(&note.location, Color::White),
+                            paint(&note.owner, Color::BrightWhite),
+                            paint(note_type, Color::White),
+                            paint(format_args!("{} bytes", note.desc_size), Color::BrightBlack)
+                        )?;
+                    }
+                }
             }
         }
         
         // Mach-O specific
         if binary.binary_type == "Mach-O" {
             if let Some(ref uuid) = binary.uuid {
-                println!("   {}: {}", 
-                    "UUID".bright_black(),
-                    uuid.bright_white()
-                );
+                writeln!(out, "   {}: {}", 
+                    paint("UUID", Color::BrightBlack),
+                    paint(&uuid, Color::BrightWhite)
+                )?;
+            }
+            
+            // Slices can differ in UUID and strip state; the lines above
+            // describe the first
+            if let Some(ref slices) = binary.fat_slices {
+                writeln!(out, "   {}:", paint("Slices", Color::BrightBlack))?;
+                for slice in slices {
+                    let state = match (slice.is_stripped, slice.has_debug_info) {
+                        (None, _) => paint("unparsed", Color::Yellow),
+                        (Some(true), _) => paint("stripped", Color::Red),
+                        (Some(false), Some(true)) => paint("symbols, debug info", Color::Green),
+                        (Some(false), _) => paint("symbols", Color::Green),
+                    };
+                    writeln!(out, "      {:<8} {}  {}",
+                        paint(&slice.architecture, Color::White),
+                        paint(slice.uuid.as_deref().unwrap_or("no UUID"), Color::BrightWhite),
+                        state
+                    )?;
+                }
+            }
+            
+            // Reserved bits: hand-crafted or tampered headers
+            if binary.unknown_header_flags != 0 && self.verbosity >= 1 {
+                writeln!(out, "   {}: {}",
+                    paint("Unknown Header Flags", Color::BrightBlack),
+                    paint(format_args!("{:#010x}", binary.unknown_header_flags), Color::Yellow)
+                )?;
+            }
+            
+            if self.verbosity >= 1 && (binary.has_objc || binary.has_swift) {
+                let runtimes: Vec<&str> = [(binary.has_objc, "Objective-C"), (binary.has_swift, "Swift")].into_iter()
+                    .filter_map(|(present, name)| present.then_some(name))
+                    .collect();
+                writeln!(out, "   {}: {}", paint("Runtime", Color::BrightBlack), paint(runtimes.join(", "), Color::White))?;
+            }
+            
+            if let (Some(slices), Some(padding), true) = (&binary.fat_slices, binary.fat_padding_bytes, self.verbosity >= 1) {
+                writeln!(out, "   {}: {}",
+                    paint("Fat Slices", Color::BrightBlack),
+                    paint(format_args!("{}, {} padding", slices.len(), Self::format_size(padding)), Color::White)
+                )?;
+                for slice in slices {
+                    writeln!(out, "      {:<8} {:>10} {:>10}  align {:<6}{}",
+                        paint(&slice.architecture, Color::White),
+                        format!("{:#x}", slice.offset),
+                        Self::format_size(slice.size),
+                        slice.align,
+                        if slice.obsolete { paint("obsolete", Color::Yellow).to_string() } else { String::new() }
+                    )?;
+                }
+            }
+            
+            if self.verbosity >= 1 && !binary.dyld_environment.is_empty() {
+                writeln!(out, "   {}:", paint("Dyld Environment", Color::BrightBlack))?;
+                for entry in &binary.dyld_environment {
+                    writeln!(out, "      {}", paint(&entry, Color::White))?;
+                }
             }
             
             if let Some(ref dsym) = binary.dsym_bundle {
-                println!();
-                println!("   {}: {} {}", 
-                    "dSYM Bundle".bright_black(),
-                    "✓".green(),
-                    "Found".bright_black()
-                );
-                println!("      {}: {}", 
-                    "Path".bright_black(),
-                    dsym.display().to_string().white()
-                );
+                writeln!(out)?;
+                writeln!(out, "   {}: {} {}", 
+                    paint("dSYM Bundle", Color::BrightBlack),
+                    paint("✓", Color::Green),
+                    paint("Found", Color::BrightBlack)
+                )?;
+                writeln!(out, "      {}: {}", 
+                    paint("Path", Color::BrightBlack),
+                    paint(dsym.display(), Color::White)
+                )?;
+                if let Some(source) = binary.debug_file_source {
+                    writeln!(out, "      {}: {}", paint("Found by", Color::BrightBlack), source.name())?;
+                }
             } else {
-                println!();
-                println!("   {}: {} {}", 
-                    "dSYM Bundle".bright_black(),
-                    "✗".red(),
-                    "Not found".bright_black()
-                );
+                writeln!(out)?;
+                writeln!(out, "   {}: {} {}", 
+                    paint("dSYM Bundle", Color::BrightBlack),
+                    paint("✗", Color::Red),
+                    paint("Not found", Color::BrightBlack)
+                )?;
             }
         }
         
         // Local debug file
         if let Some(ref debug_path) = binary.debug_file_path {
             if binary.binary_type == "ELF" {
-                println!();
-                println!("   {}: {} {}", 
-                    "Local Debug".bright_black(),
-                    "✓".green(),
-                    "Found".bright_black()
-                );
-                println!("      {}: {}", 
-                    "Path".bright_black(),
-                    debug_path.display().to_string().white()
-                );
-            }
-        } else if binary.binary_type == "ELF" && !binary.has_debug_info {
-            println!();
-            println!("   {}: {} {}", 
-                "Local Debug".bright_black(),
-                "✗".red(),
-                "Not found".bright_black()
-            );
+                writeln!(out)?;
+                writeln!(out, "   {}: {} {}", 
+                    paint("Local Debug", Color::BrightBlack),
+                    paint("✓", Color::Green),
+                    paint("Found", Color::BrightBlack)
+                )?;
+                writeln!(out, "      {}: {}", 
+                    paint("Path", Color::BrightBlack),
+                    paint(debug_path.display(), Color::White)
+                )?;
+                if let Some(source) = binary.debug_file_source {
+                    writeln!(out, "      {}: {}", paint("Found by", Color::BrightBlack), source.name())?;
+                }
+                if self.verbosity >= 1 && !binary.alternate_debug_files.is_empty() {
+                    writeln!(out, "      {}:", paint("Also found", Color::BrightBlack))?;
+                    for alternate in &binary.alternate_debug_files {
+                        writeln!(out, "         {} {}",
+                            paint(alternate.path.display(), Color::White),
+                            paint(format_args!("({})", alternate.source.name()), Color::BrightBlack)
+                        )?;
+                    }
+                }
+            }
+        } else if binary.binary_type == "ELF" && binary.has_debug_info != Some(true) {
+            writeln!(out)?;
+            writeln!(out, "   {}: {} {}", 
+                paint("Local Debug", Color::BrightBlack),
+                paint("✗", Color::Red),
+                paint("Not found", Color::BrightBlack)
+            )?;
         }
         
         // Remote availability (debuginfod)
         if let Some(available) = binary.debuginfod_available {
-            println!();
+            writeln!(out)?;
             if available {
-                println!("   {}: {} {}", 
-                    "Remote Debug".bright_black(),
-                    "✓".green(),
-                    "Available".bright_black()
-                );
+                writeln!(out, "   {}: {} {}", 
+                    paint("Remote Debug", Color::BrightBlack),
+                    paint("✓", Color::Green),
+                    paint("Available", Color::BrightBlack)
+                )?;
                 if let Some(ref url) = binary.debuginfod_url {
-                    if self.verbose {
-                        println!("      {}: {}", 
-                            "URL".bright_black(),
-                            url.white()
-                        );
+                    if self.verbosity >= 1 {
+                        writeln!(out, "      {}: {}", 
+                            paint("URL", Color::BrightBlack),
+                            paint(&url, Color::White)
+                        )?;
+                    }
+                }
+            } else {
+                writeln!(out, "   {}: {} {}", 
+                    paint("Remote Debug", Color::BrightBlack),
+                    paint("✗", Color::Red),
+                    paint("Not available", Color::BrightBlack)
+                )?;
+            }
+        }
+        
+        if let Some(ref reason) = binary.skipped_reason {
+            writeln!(out)?;
+            writeln!(out, "   {}: {} {}", 
+                paint("Remote Debug", Color::BrightBlack),
+                paint("-", Color::Yellow),
+                paint(format_args!("Not checked ({})", reason), Color::BrightBlack)
+            )?;
+        }
+        
+        // Remote executable for debug-only files
+        if let Some(available) = binary.executable_available {
+            writeln!(out)?;
+            if available {
+                writeln!(out, "   {}: {} {}", 
+                    paint("Remote Executable", Color::BrightBlack),
+                    paint("✓", Color::Green),
+                    paint("Available", Color::BrightBlack)
+                )?;
+                if let Some(ref url) = binary.executable_url {
+                    if self.verbosity >= 1 {
+                        writeln!(out, "      {}: {}", 
+                            paint("URL", Color::BrightBlack),
+                            paint(&url, Color::White)
+                        )?;
                     }
                 }
             } else {
-                println!("   {}: {} {}", 
-                    "Remote Debug".bright_black(),
-                    "✗".red(),
-                    "Not available".bright_black()
-                );
+                writeln!(out, "   {}: {} {}", 
+                    paint("Remote Executable", Color::BrightBlack),
+                    paint("✗", Color::Red),
+                    paint("Not available", Color::BrightBlack)
+                )?;
             }
         }
         
-        println!();
-        println!("{}", "─".repeat(60).bright_black());
+        writeln!(out)?;
+        writeln!(out, "{}", paint(SEPARATOR, Color::BrightBlack))?;
         
         Ok(())
     }
 }
 
-pub struct JsonFormatter;
+/// Aggregate counts over a set of results
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub total: usize,
+    pub elf: usize,
+    pub macho: usize,
+    pub with_debug: usize,
+    pub with_local: usize,
+    pub with_remote: usize,
+    pub stripped: usize,
+    pub privileged: usize,
+    pub with_signatures: usize,
+    pub unsupported: usize,
+    /// Separate debug files (`objcopy --only-keep-debug` output)
+    pub debug_files: usize,
+    /// Binaries the test fixture heuristic flagged
+    pub likely_fixtures: usize,
+    /// Binaries only analyzed from their header
+    pub partial: usize,
+    /// Stripped binaries without local symbols that have a build-id to look up
+    pub stripped_with_build_id: usize,
+    pub elf_without_build_id: usize,
+    /// Binaries whose adjacent debug file has another build-id
+    pub debug_file_mismatches: usize,
+    /// Sums over ELF binaries
+    pub relocations: u64,
+    pub irelative_relocations: u64,
+    pub got_bytes: u64,
+    /// Universal-binary slices for architectures no current Apple OS runs
+    pub obsolete_slices: usize,
+    pub reclaimable_bytes: u64,
+    /// Binaries per canonical architecture
+    pub architectures: BTreeMap<String, usize>,
+    /// With `--check-isa`: binaries per x86-64 level
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub isa_levels: BTreeMap<String, usize>,
+    /// With `--imports`: the functions most binaries import, with how many do
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub common_imports: Vec<(String, usize)>,
+}
+
+impl Summary {
+    pub fn new(binaries: &[BinaryInfo]) -> Self {
+        let count = |pred: &dyn Fn(&BinaryInfo) -> bool| binaries.iter().filter(|b| pred(b)).count();
+        
+        Self {
+            total: binaries.len(),
+            elf: count(&|b| b.binary_type == "ELF"),
+            macho: count(&|b| b.binary_type == "Mach-O"),
+            with_debug: count(&|b| b.has_debug_info == Some(true)),
+            with_local: count(&|b| b.has_local_debug_symbols()),
+            with_remote: count(&|b| b.has_remote_debug_symbols()),
+            stripped: count(&|b| b.is_stripped),
+            privileged: count(&|b| b.is_privileged()),
+            with_signatures: count(&|b| !b.signature_matches.is_empty()),
+            unsupported: count(&|b| b.is_unsupported()),
+            debug_files: count(&|b| b.is_debug_only),
+            likely_fixtures: count(&|b| b.likely_test_fixture),
+            partial: count(&|b| b.partial_analysis),
+            stripped_with_build_id: count(&|b| b.is_stripped && !b.has_local_debug_symbols() && b.build_id.is_some()),
+            elf_without_build_id: count(&|b| b.binary_type == "ELF" && b.build_id.is_none()),
+            debug_file_mismatches: count(&|b| b.warnings.iter().any(|w| w.code == "debug-file-mismatch")),
+            relocations: binaries.iter().filter_map(|b| b.relocation_count).sum(),
+            irelative_relocations: binaries.iter().filter_map(|b| b.irelative_count).sum(),
+            got_bytes: binaries.iter().filter_map(|b| b.got_bytes).sum(),
+            obsolete_slices: binaries.iter().flat_map(|b| b.fat_slices.iter().flatten()).filter(|slice| slice.obsolete).count(),
+            reclaimable_bytes: binaries.iter().map(BinaryInfo::reclaimable_bytes).sum(),
+            architectures: binaries.iter().fold(BTreeMap::new(), |mut architectures, b| {
+                *architectures.entry(b.architecture.clone()).or_insert(0) += 1;
+                architectures
+            }),
+            isa_levels: binaries.iter().filter_map(|b| b.isa_level.clone()).fold(BTreeMap::new(), |mut levels, level| {
+                *levels.entry(level).or_insert(0) += 1;
+                levels
+            }),
+            common_imports: imports::most_common(binaries, imports::COMMON_LIMIT),
+        }
+    }
+}
+
+/// Share of the scanned binaries whose debug info the debuginfod servers
+/// have, for `--compare-to-store`
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreCoverage {
+    pub total: usize,
+    pub present: usize,
+    /// `present` as a percentage of `total` (100 when nothing was scanned)
+    pub percent: f64,
+    /// Binaries without debug info on the servers, in scan order
+    pub missing: Vec<MissingSymbols>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MissingSymbols {
+    pub path: PathBuf,
+    /// Build-id, or the UUID of a Mach-O binary
+    pub id: Option<String>,
+    /// `absent` (no server has it), `no-id` (nothing to look up), or why
+    /// the lookup didn't run (`not-checked`, `circuit-open`)
+    pub reason: String,
+}
+
+impl StoreCoverage {
+    pub fn new(binaries: &[BinaryInfo]) -> Self {
+        let missing: Vec<MissingSymbols> = binaries.iter()
+            .filter(|b| b.debuginfod_available != Some(true))
+            .map(|b| {
+                let id = b.build_id.clone().or_else(|| b.uuid.clone());
+                let reason = match (b.debuginfod_available, &id, &b.skipped_reason) {
+                    (_, None, _) => "no-id".to_string(),
+                    (Some(false), _, _) => "absent".to_string(),
+                    (_, _, Some(reason)) => reason.clone(),
+                    // debuginfod only serves ELF build-ids
+                    _ => "not-checked".to_string(),
+                };
+                MissingSymbols { path: b.file_path.clone(), id, reason }
+            })
+            .collect();
+        let total = binaries.len();
+        let present = total - missing.len();
+        let percent = if total == 0 { 100.0 } else { present as f64 * 100.0 / total as f64 };
+        Self { total, present, percent, missing }
+    }
+    
+    /// The coverage line, then each missing binary by id
+    pub fn write_human(&self, out: &mut dyn Write) -> Result<()> {
+        writeln!(out, "   Store coverage: {} {}",
+            format!("{:.1}%", self.percent).bright_white().bold(),
+            format!("({} of {} binaries)", self.present, self.total).bright_black()
+        )?;
+        for missing in &self.missing {
+            writeln!(out, "      {} {} {}",
+                missing.id.as_deref().unwrap_or("-").yellow(),
+                missing.path.display(),
+                format!("[{}]", missing.reason).bright_black()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Behavior when JSON output would exceed `--max-output-bytes`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Emit as many binaries as fit, plus a `truncated` marker and counts
+    TruncateList,
+    /// Emit only the summary block
+    Summarize,
+    /// Fail without printing anything
+    Error,
+}
+
+/// Binaries dropped by a filter, for `--explain-filtered`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FilteredReport {
+    pub total: usize,
+    /// Dropped binaries per filter reason
+    pub by_filter: BTreeMap<&'static str, usize>,
+    /// The first `limit` dropped binaries
+    pub binaries: Vec<FilteredBinary>,
+    pub truncated: bool,
+    #[serde(skip)]
+    limit: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FilteredBinary {
+    pub path: PathBuf,
+    pub filter: &'static str,
+}
+
+impl FilteredReport {
+    pub fn new(limit: usize) -> Self {
+        Self { limit, ..Self::default() }
+    }
+    
+    pub fn record(&mut self, path: &Path, filter: &'static str) {
+        self.total += 1;
+        *self.by_filter.entry(filter).or_insert(0) += 1;
+        if self.binaries.len() < self.limit {
+            self.binaries.push(FilteredBinary { path: path.to_path_buf(), filter });
+        } else {
+            self.truncated = true;
+        }
+    }
+    
+    /// Counts per filter, largest first, then the listed binaries
+    pub fn write_human(&self, out: &mut dyn Write) -> Result<()> {
+        writeln!(out)?;
+        writeln!(out, "{}", format!("Filtered out: {} binaries", self.total).bright_cyan().bold())?;
+        if self.total == 0 {
+            return Ok(());
+        }
+        
+        let mut rows: Vec<_> = self.by_filter.iter().collect();
+        rows.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        for (filter, count) in rows {
+            writeln!(out, "   {:>6}  {}", count.to_string().bright_white(), filter)?;
+        }
+        
+        writeln!(out)?;
+        for binary in &self.binaries {
+            writeln!(out, "   {}  {}", binary.path.display(), binary.filter.bright_black())?;
+        }
+        if self.truncated {
+            let more = self.total - self.binaries.len();
+            writeln!(out, "   {}", format!("... and {} more (raise --explain-limit)", more).bright_black())?;
+        }
+        
+        Ok(())
+    }
+}
+
+/// Optional sections that turn the `--json` array into an object; every
+/// document shape flattens the same set
+#[derive(Default, Serialize)]
+struct ReportSections {
+    /// Emit `{"binaries": [...], "filtered": {...}}` instead of a bare array
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filtered: Option<FilteredReport>,
+    /// Likewise with `"clusters": [...]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clusters: Option<Vec<Cluster>>,
+    /// Likewise with `"source_dirs": [...]`, from `--resolve-source-dirs`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_dirs: Option<Vec<SourceDir>>,
+    /// Likewise with `"store_coverage": {...}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    store_coverage: Option<StoreCoverage>,
+    /// Likewise with `"store_orphans": {...}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    store_orphans: Option<StoreOrphans>,
+    /// Likewise with `"scan_root": "..."`, which relative paths are under
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scan_root: Option<PathBuf>,
+    /// Likewise with `"environment": [...]`, from `--record-env`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    environment: Option<Vec<RecordedVariable>>,
+    /// Likewise with `"prefetch": {...}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prefetch: Option<PrefetchReport>,
+    /// Likewise with `"conflicts": [...]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    conflicts: Option<Vec<SonameConflict>>,
+    /// Likewise with `"duplicates_skipped": N`, from `--dedup-by-id`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duplicates_skipped: Option<usize>,
+    /// Likewise with `"errors": [...]`, from `--show-errors`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errors: Option<Vec<ScanError>>,
+    /// Likewise with `"suspicious_files": [...]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suspicious_files: Option<Vec<SuspiciousFile>>,
+}
+
+impl ReportSections {
+    fn is_empty(&self) -> bool {
+        self.filtered.is_none() && self.clusters.is_none() && self.source_dirs.is_none() && self.store_coverage.is_none()
+            && self.store_orphans.is_none() && self.scan_root.is_none() && self.environment.is_none() && self.prefetch.is_none()
+            && self.conflicts.is_none() && self.duplicates_skipped.is_none() && self.errors.is_none() && self.suspicious_files.is_none()
+    }
+}
+
+#[derive(Default)]
+pub struct JsonFormatter {
+    budget: Option<(u64, OverflowPolicy)>,
+    sections: ReportSections,
+}
+
+impl JsonFormatter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn with_budget(mut self, max_bytes: u64, policy: OverflowPolicy) -> Self {
+        self.budget = Some((max_bytes, policy));
+        self
+    }
+    
+    pub fn with_filtered(mut self, report: FilteredReport) -> Self {
+        self.sections.filtered = Some(report);
+        self
+    }
+    
+    pub fn with_clusters(mut self, clusters: Vec<Cluster>) -> Self {
+        self.sections.clusters = Some(clusters);
+        self
+    }
+    
+    pub fn with_source_dirs(mut self, dirs: Vec<SourceDir>) -> Self {
+        self.sections.source_dirs = Some(dirs);
+        self
+    }
+    
+    pub fn with_store_coverage(mut self, coverage: StoreCoverage) -> Self {
+        self.sections.store_coverage = Some(coverage);
+        self
+    }
+    
+    pub fn with_store_orphans(mut self, orphans: StoreOrphans) -> Self {
+        self.sections.store_orphans = Some(orphans);
+        self
+    }
+    
+    pub fn with_scan_root(mut self, root: PathBuf) -> Self {
+        self.sections.scan_root = Some(root);
+        self
+    }
+    
+    pub fn with_environment(mut self, environment: Vec<RecordedVariable>) -> Self {
+        self.sections.environment = Some(environment);
+        self
+    }
+    
+    pub fn with_prefetch(mut self, report: PrefetchReport) -> Self {
+        self.sections.prefetch = Some(report);
+        self
+    }
+    
+    pub fn with_conflicts(mut self, conflicts: Vec<SonameConflict>) -> Self {
+        self.sections.conflicts = Some(conflicts);
+        self
+    }
+    
+    pub fn with_duplicates_skipped(mut self, skipped: usize) -> Self {
+        self.sections.duplicates_skipped = Some(skipped);
+        self
+    }
+    
+    pub fn with_errors(mut self, errors: Vec<ScanError>) -> Self {
+        self.sections.errors = Some(errors);
+        self
+    }
+    
+    pub fn with_suspicious_files(mut self, files: Vec<SuspiciousFile>) -> Self {
+        self.sections.suspicious_files = Some(files);
+        self
+    }
+}
+
+/// Write sink that only counts bytes, for sizing output before emitting it
+#[derive(Default)]
+struct CountingWriter {
+    count: u64,
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.count += buf.len() as u64;
+        Ok(buf.len())
+    }
+    
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Size of `value` as pretty JSON plus the trailing newline
+fn json_size<T: Serialize + ?Sized>(value: &T) -> Result<u64> {
+    let mut counter = CountingWriter::default();
+    serde_json::to_writer_pretty(&mut counter, value)?;
+    Ok(counter.count + 1)
+}
+
+#[derive(Serialize)]
+struct TruncatedList<'a> {
+    truncated: bool,
+    total: usize,
+    emitted: usize,
+    binaries: &'a [BinaryInfo],
+    #[serde(flatten)]
+    sections: &'a ReportSections,
+}
+
+#[derive(Serialize)]
+struct SummaryOnly<'a> {
+    truncated: bool,
+    summary: Summary,
+    #[serde(flatten)]
+    sections: &'a ReportSections,
+}
+
+#[derive(Serialize)]
+struct ExplainedList<'a> {
+    binaries: &'a [BinaryInfo],
+    #[serde(flatten)]
+    sections: &'a ReportSections,
+}
+
+fn write_json<T: Serialize + ?Sized>(value: &T, out: &mut dyn Write) -> Result<()> {
+    serde_json::to_writer_pretty(&mut *out, value)?;
+    writeln!(out)?;
+    Ok(())
+}
 
 impl OutputFormatter for JsonFormatter {
-    fn format(&self, binaries: &[BinaryInfo]) -> Result<()> {
-        let json = serde_json::to_string_pretty(&binaries)?;
-        println!("{}", json);
+    fn format(&self, binaries: &[BinaryInfo], out: &mut dyn Write) -> Result<()> {
+        let sections = &self.sections;
+        let explained = (!sections.is_empty()).then_some(ExplainedList { binaries, sections });
+        let write_all = |out: &mut dyn Write| match explained {
+            Some(ref explained) => write_json(explained, out),
+            None => write_json(binaries, out),
+        };
+        let Some((max_bytes, policy)) = self.budget else {
+            return write_all(out);
+        };
+        
+        let size = match explained {
+            Some(ref explained) => json_size(explained)?,
+            None => json_size(binaries)?,
+        };
+        if size <= max_bytes {
+            return write_all(out);
+        }
+        
+        match policy {
+            OverflowPolicy::Error => {
+                anyhow::bail!("JSON output would be {} bytes, exceeding --max-output-bytes {}", size, max_bytes);
+            }
+            OverflowPolicy::Summarize => {
+                write_json(&SummaryOnly { truncated: true, summary: Summary::new(binaries), sections }, out)
+            }
+            OverflowPolicy::TruncateList => {
+                let document = |emitted: usize| TruncatedList {
+                    truncated: true,
+                    total: binaries.len(),
+                    emitted,
+                    binaries: &binaries[..emitted],
+                    sections,
+                };
+                
+                // Document size grows with the prefix length, so binary
+                // search for the longest prefix that fits
+                let (mut lo, mut hi) = (0, binaries.len());
+                while lo < hi {
+                    let mid = (lo + hi).div_ceil(2);
+                    if json_size(&document(mid))? <= max_bytes {
+                        lo = mid;
+                    } else {
+                        hi = mid - 1;
+                    }
+                }
+                
+                write_json(&document(lo), out)
+            }
+        }
+    }
+}
+
+/// The binaries in a saved `--json` report, either the plain list or the
+/// object other options wrap it in; None when `data` isn't a symwalker report
+pub fn read_report(data: &[u8]) -> Result<Option<Vec<BinaryInfo>>> {
+    let Ok(document) = serde_json::from_slice::<serde_json::Value>(data) else {
+        return Ok(None);
+    };
+    let list = match document {
+        serde_json::Value::Array(list) => list,
+        serde_json::Value::Object(mut fields) => match fields.remove("binaries") {
+            Some(serde_json::Value::Array(list)) => list,
+            _ if fields.contains_key("summary") && fields.contains_key("truncated") => {
+                anyhow::bail!("the report only has a summary (written with --overflow summarize), not the binaries");
+            }
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+    
+    let versions: Option<Vec<u64>> = list.iter()
+        .map(|binary| binary.get("schema_version")?.as_u64())
+        .collect();
+    let Some(versions) = versions else {
+        return Ok(None);
+    };
+    if let Some(version) = versions.into_iter().find(|&version| version != u64::from(SCHEMA_VERSION)) {
+        anyhow::bail!("the report has schema_version {}, this symwalker reads {}: scan again instead", version, SCHEMA_VERSION);
+    }
+    Ok(Some(serde_json::from_value(serde_json::Value::Array(list))?))
+}
+
+/// Grouped counts for `--count-by`, largest group first
+/// JSON is `{"field": ..., "total": N, "counts": {"group": n, ...}}`, plus
+/// `filtered` with `--explain-filtered`
+pub fn write_counts(
+    field: &str,
+    counts: &BTreeMap<String, usize>,
+    filtered: Option<&FilteredReport>,
+    json: bool,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let total: usize = counts.values().sum();
+    
+    if json {
+        let mut value = serde_json::json!({ "field": field, "total": total, "counts": counts });
+        if let Some(filtered) = filtered {
+            value["filtered"] = serde_json::to_value(filtered)?;
+        }
+        serde_json::to_writer_pretty(&mut *out, &value)?;
+        writeln!(out)?;
+        return Ok(());
+    }
+    
+    let mut rows: Vec<(&String, &usize)> = counts.iter().collect();
+    rows.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    let width = rows.iter().map(|(key, _)| key.chars().count()).max().unwrap_or(0).max("Total".len());
+    
+    writeln!(out, "{}", format!("Binaries by {}", field).bright_cyan().bold())?;
+    writeln!(out)?;
+    for (key, count) in rows {
+        writeln!(out, "   {:<width$}  {:>6}", key.white(), count.to_string().bright_white(), width = width)?;
+    }
+    writeln!(out, "   {:<width$}  {:>6}", "Total".bright_black(), total.to_string().bright_white(), width = width)?;
+    
+    Ok(())
+}
+
+/// Hidden sibling `path` is written as before being renamed into place:
+/// `.<name>.tmp.<pid>`
+pub fn temp_path(path: &Path) -> Result<PathBuf> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let filename = path.file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid output path: {}", path.display()))?;
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(filename);
+    tmp_name.push(format!(".tmp.{}", std::process::id()));
+    Ok(dir.join(tmp_name))
+}
+
+/// Whether `name` is a `temp_path` left behind by an interrupted write
+pub fn is_temp_name(name: &std::ffi::OsStr) -> bool {
+    let name = name.to_string_lossy();
+    name.starts_with('.') && name.rsplit_once(".tmp.").is_some_and(|(_, pid)| !pid.is_empty() && pid.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Write a file so readers never observe a partial document: output goes to
+/// a temporary file in the same directory which is renamed over `path` only
+/// once fully written and synced
+pub fn write_atomic(path: &Path, write: impl FnOnce(&mut dyn Write) -> Result<()>) -> Result<()> {
+    let tmp_path = temp_path(path)?;
+    
+    let result = (|| {
+        let file = fs::File::create(&tmp_path)?;
+        let mut writer = BufWriter::new(file);
+        write(&mut writer)?;
+        let file = writer.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
         Ok(())
+    })();
+    
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
     }
+    result
 }
 
+
+/// Stdout for the reports, noticing when the reader goes away (`| head`)
+///
+/// Once a write fails with EPIPE every later one fails the same way without
+/// touching the pipe, so a report stops at its next `?`; `report` turns that
+/// into an early, quiet end of the reports.
+pub struct ReportSink<W> {
+    inner: W,
+    closed: bool,
+}
+
+impl<W: Write> ReportSink<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, closed: false }
+    }
+    
+    /// Whether the reader has gone away
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+    
+    /// Run one report step; after the reader went away the step is skipped,
+    /// and its failure to write is not an error
+    pub fn report(&mut self, step: impl FnOnce(&mut dyn Write) -> Result<()>) -> Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        match step(self) {
+            Err(_) if self.closed => Ok(()),
+            result => result,
+        }
+    }
+    
+    fn check<T>(&mut self, result: std::io::Result<T>) -> std::io::Result<T> {
+        if let Err(ref e) = result {
+            self.closed |= e.kind() == std::io::ErrorKind::BrokenPipe;
+        }
+        result
+    }
+}
+
+impl<W: Write> Write for ReportSink<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.closed {
+            return Err(std::io::ErrorKind::BrokenPipe.into());
+        }
+        let result = self.inner.write(buf);
+        self.check(result)
+    }
+    
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.closed {
+            return Err(std::io::ErrorKind::BrokenPipe.into());
+        }
+        let result = self.inner.flush();
+        self.check(result)
+    }
+}
@@ -0,0 +1,98 @@
+//! External analyzers for formats symwalker can't classify (`--plugin`)
+//!
+//! The plugin runs once per unrecognized file, with the file's path as its
+//! only argument and a request on stdin:
+//!
+//! ```json
+//! {"protocol": 1, "path": "/fw/blob.img", "size": 1048576}
+//! ```
+//!
+//! It answers on stdout with `null` (or nothing) when the file isn't a
+//! format it knows, or with an object of `BinaryInfo` fields; `binary_type`
+//! is required, anything left out keeps its default. `file_path`,
+//! `file_size`, `file_modified`, `file_modified_epoch` and `schema_version`
+//! are always set by symwalker. A non-zero exit status or malformed answer
+//! is an error.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use crate::arch;
+use crate::binary::BinaryInfo;
+
+/// Version of the request and answer layout
+pub const PROTOCOL_VERSION: u32 = 1;
+
+pub struct Plugin {
+    program: PathBuf,
+}
+
+impl Plugin {
+    pub fn new(program: &Path) -> Result<Self> {
+        if !program.is_file() {
+            anyhow::bail!("plugin not found: {}", program.display());
+        }
+        Ok(Self { program: program.to_path_buf() })
+    }
+
+    /// Ask the plugin about `path`; None when it doesn't recognize the file
+    pub fn analyze(&self, path: &Path, size: u64) -> Result<Option<BinaryInfo>> {
+        let mut child = Command::new(&self.program)
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("running {}", self.program.display()))?;
+
+        let request = serde_json::json!({ "protocol": PROTOCOL_VERSION, "path": path, "size": size });
+        let mut stdin = child.stdin.take().context("plugin stdin")?;
+        // Plugins that only look at their argument may exit without reading it
+        if let Err(e) = writeln!(stdin, "{}", request) {
+            if e.kind() != std::io::ErrorKind::BrokenPipe {
+                return Err(e.into());
+            }
+        }
+        drop(stdin);
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            anyhow::bail!("{} exited with {}", self.program.display(), output.status);
+        }
+        parse_answer(&output.stdout)
+    }
+}
+
+/// The plugin's answer as a `BinaryInfo`, normalizing its architecture name
+pub fn parse_answer(stdout: &[u8]) -> Result<Option<BinaryInfo>> {
+    if stdout.iter().all(u8::is_ascii_whitespace) {
+        return Ok(None);
+    }
+    let fields = match serde_json::from_slice(stdout).context("plugin answer is not JSON")? {
+        Value::Null => return Ok(None),
+        Value::Object(fields) => fields,
+        _ => anyhow::bail!("plugin answer is neither null nor an object"),
+    };
+    let binary_type = fields.get("binary_type").and_then(Value::as_str).unwrap_or_default();
+    if binary_type.is_empty() {
+        anyhow::bail!("plugin answer has no binary_type");
+    }
+
+    let mut info = serde_json::to_value(BinaryInfo::default())?;
+    let defaults = info.as_object_mut().unwrap();
+    for (key, value) in fields {
+        if defaults.contains_key(&key) {
+            defaults.insert(key, value);
+        }
+    }
+    let mut info: BinaryInfo = serde_json::from_value(info).context("plugin answer doesn't fit BinaryInfo")?;
+
+    if info.architecture_raw.is_empty() {
+        info.architecture_raw = info.architecture.clone();
+    }
+    info.architecture = arch::normalize(&info.architecture).unwrap_or(arch::UNKNOWN).to_string();
+    Ok(Some(info))
+}
@@ -0,0 +1,133 @@
+//! Fill the local debuginfod cache for a whole tree (`--prefetch`)
+//!
+//! Files go where debuginfod clients (gdb, eu-stack, ...) look for them,
+//! `<cache>/<build-id>/debuginfo` and `<cache>/<build-id>/executable`, so
+//! the tools work offline afterwards. Downloads share the run's client, and
+//! with it the circuit breaker, on `--prefetch-jobs` workers.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use colored::*;
+use serde::Serialize;
+
+use crate::debuginfod::{Artifact, CircuitOpen, DebuginfodClient};
+use crate::deep::{self, Limits};
+use crate::elf;
+use crate::environment::Environment;
+use crate::output::HumanFormatter;
+
+/// Default for `--prefetch-jobs`
+pub const DEFAULT_JOBS: usize = 4;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PrefetchReport {
+    pub cache: PathBuf,
+    /// Distinct build-ids among the scanned binaries
+    pub build_ids: usize,
+    /// Counts below are per file: one per build-id, two with `--prefetch-executables`
+    pub fetched: usize,
+    pub fetched_bytes: u64,
+    pub already_cached: usize,
+    /// No server had it, or what came back was for another build-id
+    pub unavailable: usize,
+    /// Not tried because the circuit breaker had stopped remote lookups
+    pub skipped: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Fetched(u64),
+    Cached,
+    Unavailable,
+    Skipped,
+}
+
+/// Where debuginfod clients keep their cache: `DEBUGINFOD_CACHE_PATH`, else
+/// `debuginfod_client` under `XDG_CACHE_HOME` or `~/.cache`; `None` under
+/// `--hermetic`
+pub fn default_cache_dir(environment: &Environment) -> Option<PathBuf> {
+    if let Some(path) = environment.var("DEBUGINFOD_CACHE_PATH").filter(|path| !path.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    let cache = environment.var("XDG_CACHE_HOME")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| environment.home_dir().map(|home| home.join(".cache")))?;
+    Some(cache.join("debuginfod_client"))
+}
+
+/// The cache file for `artifact` of `build_id`
+pub fn cache_path(cache: &Path, build_id: &str, artifact: Artifact) -> PathBuf {
+    cache.join(build_id).join(artifact.as_str())
+}
+
+/// Download every artifact of `build_ids` that isn't cached yet
+pub fn run(client: &DebuginfodClient, cache: &Path, build_ids: &[String], executables: bool, jobs: usize) -> PrefetchReport {
+    let artifacts: &[Artifact] = if executables { &[Artifact::Debuginfo, Artifact::Executable] } else { &[Artifact::Debuginfo] };
+    let tasks: Vec<(u64, (&str, Artifact))> = build_ids.iter()
+        .flat_map(|id| artifacts.iter().map(move |&artifact| (0, (id.as_str(), artifact))))
+        .collect();
+    let limits = Limits { jobs, memory_bytes: u64::MAX };
+    let (outcomes, _) = deep::run(tasks, limits, |(id, artifact)| fetch(client, cache, id, artifact));
+
+    let mut report = PrefetchReport { cache: cache.to_path_buf(), build_ids: build_ids.len(), ..PrefetchReport::default() };
+    for outcome in outcomes {
+        match outcome {
+            Outcome::Fetched(bytes) => {
+                report.fetched += 1;
+                report.fetched_bytes += bytes;
+            }
+            Outcome::Cached => report.already_cached += 1,
+            Outcome::Unavailable => report.unavailable += 1,
+            Outcome::Skipped => report.skipped += 1,
+        }
+    }
+    report
+}
+
+fn fetch(client: &DebuginfodClient, cache: &Path, build_id: &str, artifact: Artifact) -> Outcome {
+    let path = cache_path(cache, build_id, artifact);
+    // debuginfod clients leave empty files behind for failed lookups
+    if path.metadata().is_ok_and(|metadata| metadata.len() > 0) {
+        return Outcome::Cached;
+    }
+    if let Some(parent) = path.parent() {
+        if std::fs::create_dir_all(parent).is_err() {
+            return Outcome::Unavailable;
+        }
+    }
+
+    let downloaded = match artifact {
+        Artifact::Debuginfo => client.download(build_id, &path),
+        Artifact::Executable => client.download_executable(build_id, &path),
+    };
+    match downloaded {
+        Err(e) if e.is::<CircuitOpen>() => Outcome::Skipped,
+        Err(_) => Outcome::Unavailable,
+        // A store answering with the wrong file must not poison the cache
+        Ok(()) if elf::debug_file_build_id(&path).is_none_or(|found| !found.eq_ignore_ascii_case(build_id)) => {
+            let _ = std::fs::remove_file(&path);
+            Outcome::Unavailable
+        }
+        Ok(()) => Outcome::Fetched(path.metadata().map_or(0, |metadata| metadata.len())),
+    }
+}
+
+impl PrefetchReport {
+    pub fn write_human(&self, out: &mut dyn Write) -> Result<()> {
+        writeln!(out)?;
+        writeln!(out, "{}", "Prefetch".bright_cyan().bold())?;
+        writeln!(out, "   {} build-ids into {}", self.build_ids, self.cache.display())?;
+        writeln!(out, "   Fetched: {} {}",
+            self.fetched.to_string().bright_green(),
+            format!("({})", HumanFormatter::format_size(self.fetched_bytes)).bright_black()
+        )?;
+        writeln!(out, "   Already cached: {}", self.already_cached.to_string().bright_white())?;
+        writeln!(out, "   Unavailable: {}", self.unavailable.to_string().yellow())?;
+        if self.skipped > 0 {
+            writeln!(out, "   Skipped (remote lookups stopped): {}", self.skipped.to_string().yellow())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+//! Paths relative to the scan root (`--relative-paths`)
+//!
+//! Reports of a staged tree name its files as they will be deployed, not
+//! where the stage happened to sit; files outside the root (system debug
+//! files) keep their absolute paths
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+
+use crate::binary::BinaryInfo;
+
+/// The scanned directory, as given and canonicalized
+#[derive(Debug, Clone)]
+pub struct ScanRoot {
+    given: PathBuf,
+    canonical: PathBuf,
+}
+
+impl ScanRoot {
+    pub fn new(directory: &Path) -> Result<Self> {
+        let canonical = fs::canonicalize(directory)
+            .with_context(|| format!("resolving {}", directory.display()))?;
+        Ok(Self { given: directory.to_path_buf(), canonical })
+    }
+
+    /// Canonical root, recorded once in the output
+    pub fn path(&self) -> &Path {
+        &self.canonical
+    }
+
+    /// `path` relative to the root, or None when it lies outside
+    ///
+    /// Paths are matched as found before being canonicalized, so a symlink
+    /// inside the tree stays relative even when it points elsewhere.
+    pub fn relative(&self, path: &Path) -> Option<PathBuf> {
+        path.strip_prefix(&self.given).ok()
+            .or_else(|| path.strip_prefix(&self.canonical).ok())
+            .map(Path::to_path_buf)
+            .or_else(|| fs::canonicalize(path).ok()?.strip_prefix(&self.canonical).ok().map(Path::to_path_buf))
+    }
+
+    /// `path` relative to the root when it lies inside, otherwise as it is
+    pub fn report_path(&self, path: &Path) -> PathBuf {
+        self.relative(path).unwrap_or_else(|| path.to_path_buf())
+    }
+
+    /// Make `file_path`, `debug_file_path`, `alternate_debug_files`,
+    /// `dsym_bundle` and the file an image was carved from relative, listing
+    /// the ones outside the root in `external_paths`
+    pub fn relativize(&self, info: &mut BinaryInfo) {
+        let mut external = Vec::new();
+        let mut relativize = |name: &str, path: &mut PathBuf| match self.relative(path) {
+            Some(relative) => *path = relative,
+            None => external.push(name.to_string()),
+        };
+        relativize("file_path", &mut info.file_path);
+        if let Some(ref mut path) = info.debug_file_path {
+            relativize("debug_file_path", path);
+        }
+        for alternate in &mut info.alternate_debug_files {
+            relativize("alternate_debug_files", &mut alternate.path);
+        }
+        if let Some(ref mut path) = info.dsym_bundle {
+            relativize("dsym_bundle", path);
+        }
+        if let Some(ref mut carved) = info.carved_from {
+            relativize("carved_from", &mut carved.container);
+        }
+        info.external_paths = external;
+    }
+}
+
+/// Undo `ScanRoot::relativize` given the recorded root, for reading
+/// relative-path JSON back
+pub fn resolve(info: &mut BinaryInfo, root: &Path) {
+    let resolve = |path: &mut PathBuf| {
+        if path.is_relative() {
+            *path = root.join(&*path);
+        }
+    };
+    resolve(&mut info.file_path);
+    if let Some(ref mut path) = info.debug_file_path {
+        resolve(path);
+    }
+    for alternate in &mut info.alternate_debug_files {
+        resolve(&mut alternate.path);
+    }
+    if let Some(ref mut path) = info.dsym_bundle {
+        resolve(path);
+    }
+    if let Some(ref mut carved) = info.carved_from {
+        resolve(&mut carved.container);
+    }
+    info.external_paths.clear();
+}
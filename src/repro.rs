@@ -0,0 +1,116 @@
+//! Signs a binary wasn't built reproducibly (`--repro-check`)
+//!
+//! Heuristics, not proof: an absolute `DW_AT_comp_dir` (no
+//! `-fdebug-prefix-map`), a `__DATE__`/`__TIME__` expansion in read-only
+//! data, and home or temporary directories of the build host in the DWARF
+//! string tables. Each kind of finding is one warning on the binary.
+
+use object::{Object, ObjectSection};
+
+use crate::binary::{thin_slice, ScanWarning};
+use crate::dwarf;
+
+/// Read-only data a `__DATE__` or `__TIME__` string ends up in
+const DATA_SECTIONS: [&str; 3] = [".rodata", "__cstring", "__const"];
+
+/// DWARF string tables; `.debug_str` is `__debug_str` in Mach-O
+const STRING_SECTIONS: [&str; 2] = [".debug_str", ".debug_line_str"];
+
+/// Directories that only exist on the machine that ran the build
+const BUILD_HOST_PREFIXES: [&str; 7] = ["/home/", "/Users/", "/root/", "/tmp/", "/var/tmp/", "/private/var/folders/", "C:\\Users\\"];
+
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// Warnings for the non-determinism markers in `data`, an ELF or Mach-O file
+pub fn check(data: &[u8]) -> Vec<ScanWarning> {
+    let mut warnings = Vec::new();
+
+    let comp_dirs: Vec<String> = dwarf::comp_dirs(data).unwrap_or_default().into_iter()
+        .filter(|dir| is_absolute(dir))
+        .collect();
+    if let Some(first) = comp_dirs.first() {
+        warnings.push(ScanWarning::new("repro-comp-dir", format!("absolute DW_AT_comp_dir {}{}", first, more(comp_dirs.len()))));
+    }
+
+    let Ok(file) = thin_slice(data).and_then(|data| Ok(object::File::parse(data)?)) else {
+        return warnings;
+    };
+    let sections = |names: &[&str]| -> Vec<Vec<u8>> {
+        names.iter()
+            .filter_map(|name| file.section_by_name(name))
+            .filter_map(|section| section.uncompressed_data().ok())
+            .map(|data| data.into_owned())
+            .collect()
+    };
+
+    let read_only = sections(&DATA_SECTIONS);
+    let dates: Vec<String> = read_only.iter().flat_map(|data| build_dates(data)).collect();
+    if let Some(date) = dates.first() {
+        let time = read_only.iter().flat_map(|data| build_times(data)).next();
+        let time = time.map_or(String::new(), |time| format!(" and __TIME__-style \"{}\"", time));
+        warnings.push(ScanWarning::new("repro-timestamp", format!("__DATE__-style \"{}\"{} in read-only data", date, time)));
+    }
+
+    let paths: Vec<String> = sections(&STRING_SECTIONS).iter()
+        .flat_map(|table| table.split(|&byte| byte == 0).map(|string| String::from_utf8_lossy(string).into_owned()).collect::<Vec<_>>())
+        .filter(|string| is_build_host_path(string))
+        .collect();
+    if let Some(first) = paths.first() {
+        warnings.push(ScanWarning::new("repro-build-path", format!("build-host path {}{} in the DWARF strings", first, more(paths.len()))));
+    }
+
+    warnings
+}
+
+fn more(count: usize) -> String {
+    if count > 1 { format!(" (and {} more)", count - 1) } else { String::new() }
+}
+
+fn is_absolute(dir: &str) -> bool {
+    dir.starts_with('/') || dir.as_bytes().get(1..3) == Some(b":\\")
+}
+
+/// Under a home or temporary directory
+pub fn is_build_host_path(path: &str) -> bool {
+    BUILD_HOST_PREFIXES.iter().any(|prefix| path.starts_with(prefix))
+}
+
+/// `__DATE__` expansions (`Mmm dd yyyy`, the day space-padded) in `data`
+pub fn build_dates(data: &[u8]) -> Vec<String> {
+    let mut dates = Vec::new();
+    for (start, window) in data.windows(11).enumerate() {
+        if !window.is_ascii() {
+            continue;
+        }
+        let month = MONTHS.iter().any(|month| window.starts_with(month.as_bytes())) && window[3] == b' ';
+        let day = matches!(window[4], b' ' | b'0'..=b'3') && window[5].is_ascii_digit() && window[6] == b' ';
+        let year = matches!(&window[7..9], b"19" | b"20") && window[9..].iter().all(u8::is_ascii_digit);
+        if month && day && year && !boundary_is_alphanumeric(data, start, 11) {
+            dates.push(String::from_utf8_lossy(window).into_owned());
+        }
+    }
+    dates
+}
+
+/// `__TIME__` expansions (`hh:mm:ss`) in `data`
+pub fn build_times(data: &[u8]) -> Vec<String> {
+    let mut times = Vec::new();
+    for (start, window) in data.windows(8).enumerate() {
+        let digits = |at: usize, max: u8| window[at].is_ascii_digit() && window[at + 1].is_ascii_digit()
+            && (window[at] - b'0') * 10 + (window[at + 1] - b'0') <= max;
+        if digits(0, 23) && window[2] == b':' && digits(3, 59) && window[5] == b':' && digits(6, 59)
+            && !boundary_is_alphanumeric(data, start, 8)
+            && !data.get(start + 8).is_some_and(|&byte| byte == b':' || byte == b'.')
+        {
+            times.push(String::from_utf8_lossy(window).into_owned());
+        }
+    }
+    times
+}
+
+/// Whether the match at `start..start + len` runs into a longer word
+fn boundary_is_alphanumeric(data: &[u8], start: usize, len: usize) -> bool {
+    let before = start.checked_sub(1).and_then(|index| data.get(index));
+    let after = data.get(start + len);
+    [before, after].into_iter().flatten().any(|byte| byte.is_ascii_alphanumeric())
+}
@@ -0,0 +1,163 @@
+use std::path::{Component, Path, PathBuf};
+use serde::{Serialize, Deserialize};
+
+/// What's wrong with an RPATH/RUNPATH entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RpathIssue {
+    /// Empty entry, searched as the current working directory
+    Empty,
+    /// Resolved against the working directory at load time
+    Relative,
+    /// `$ORIGIN` expansion leaves the scanned directory
+    OriginEscapes,
+    /// Directory doesn't exist under the scan root
+    Missing,
+    /// Anyone can drop a library into the directory
+    WorldWritable,
+}
+
+impl RpathIssue {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RpathIssue::Empty => "empty",
+            RpathIssue::Relative => "relative",
+            RpathIssue::OriginEscapes => "origin-escapes",
+            RpathIssue::Missing => "missing",
+            RpathIssue::WorldWritable => "world-writable",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpathFinding {
+    pub entry: String,
+    pub issue: RpathIssue,
+}
+
+/// Where the audited binary lives, for resolving entries
+pub struct AuditRoot<'a> {
+    /// The binary as found on disk
+    pub binary: &'a Path,
+    /// Directory being scanned; `$ORIGIN` entries must stay inside it
+    pub prefix: &'a Path,
+    /// Root for absolute entries (`--sysroot`), `/` when unset
+    pub sysroot: Option<&'a Path>,
+}
+
+/// Split DT_RPATH/DT_RUNPATH strings into their colon-separated entries
+pub fn split_entries<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    values.into_iter()
+        .flat_map(|value| value.split(':'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Check every entry, reporting at most one issue per entry
+pub fn audit(entries: &[String], root: &AuditRoot) -> Vec<RpathFinding> {
+    entries.iter()
+        .filter_map(|entry| {
+            audit_entry(entry, root).map(|issue| RpathFinding { entry: entry.clone(), issue })
+        })
+        .collect()
+}
+
+fn audit_entry(entry: &str, root: &AuditRoot) -> Option<RpathIssue> {
+    if entry.is_empty() {
+        return Some(RpathIssue::Empty);
+    }
+
+    let prefix = normalize(&absolute(root.prefix));
+    let origin = absolute(root.binary.parent().unwrap_or(Path::new(".")));
+
+    // Other dynamic string tokens ($LIB, $PLATFORM) depend on the loader
+    let expanded = expand_origin(entry, &origin)?;
+    let uses_origin = expanded != entry;
+    let path = Path::new(&expanded);
+
+    if !path.is_absolute() {
+        return Some(RpathIssue::Relative);
+    }
+
+    let host_path = if uses_origin {
+        let resolved = normalize(path);
+        if !resolved.starts_with(&prefix) {
+            return Some(RpathIssue::OriginEscapes);
+        }
+        resolved
+    } else {
+        match root.sysroot {
+            Some(sysroot) => normalize(&sysroot.join(path.strip_prefix("/").unwrap_or(path))),
+            None => normalize(path),
+        }
+    };
+
+    let Ok(metadata) = std::fs::metadata(&host_path) else {
+        return Some(RpathIssue::Missing);
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.is_dir() && metadata.permissions().mode() & 0o002 != 0 {
+            return Some(RpathIssue::WorldWritable);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = metadata;
+
+    None
+}
+
+/// Replace `$ORIGIN` and `${ORIGIN}` with `origin`
+/// Returns `None` for entries using other tokens (`$LIB`, `$PLATFORM`)
+pub fn expand_origin(entry: &str, origin: &Path) -> Option<String> {
+    let origin = origin.to_string_lossy();
+    let mut expanded = String::with_capacity(entry.len());
+    let mut rest = entry;
+
+    while let Some(pos) = rest.find('$') {
+        expanded.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+
+        let (name, remainder) = if let Some(braced) = after.strip_prefix('{') {
+            let end = braced.find('}')?;
+            (&braced[..end], &braced[end + 1..])
+        } else {
+            let end = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+
+        if name != "ORIGIN" {
+            return None;
+        }
+        expanded.push_str(&origin);
+        rest = remainder;
+    }
+
+    expanded.push_str(rest);
+    Some(expanded)
+}
+
+/// Lexically resolve `.` and `..`; `..` never climbs above the root
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if normalized.file_name().is_some() {
+                    normalized.pop();
+                } else if !path.is_absolute() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,111 @@
+//! Files the scan couldn't read or parse (`--show-errors`)
+//!
+//! Files that aren't ELF or Mach-O are skipped without a word, as before;
+//! everything else that keeps a file out of the report is recorded here:
+//! unreadable files and directories, broken links and loops under
+//! `--follow-symlinks`, failed memory maps, and binaries whose format was
+//! recognized but which didn't parse even for their header fields.
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use colored::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorKind {
+    /// EACCES on a file or directory
+    PermissionDenied,
+    /// A link whose target doesn't exist (only followed with `--follow-symlinks`)
+    BrokenSymlink,
+    /// A directory link pointing back up the tree
+    SymlinkLoop,
+    /// The file opened but couldn't be memory mapped
+    Mmap,
+    /// Any other I/O failure
+    Io,
+    /// ELF/Mach-O magic, but no usable analysis
+    Malformed,
+}
+
+impl ErrorKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorKind::PermissionDenied => "permission-denied",
+            ErrorKind::BrokenSymlink => "broken-symlink",
+            ErrorKind::SymlinkLoop => "symlink-loop",
+            ErrorKind::Mmap => "mmap",
+            ErrorKind::Io => "io",
+            ErrorKind::Malformed => "malformed",
+        }
+    }
+
+    fn from_io(error: &io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            _ => ErrorKind::Io,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanError {
+    pub path: PathBuf,
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+/// Context on a failed memory map, so it isn't taken for an open failure
+#[derive(Debug, thiserror::Error)]
+#[error("memory map failed")]
+pub struct MapFailed;
+
+/// A file in no format symwalker knows; skipped quietly
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct NotABinary(pub &'static str);
+
+impl ScanError {
+    /// Why scanning `path` failed; None for files that just aren't binaries
+    pub fn from_scan(path: &Path, error: &anyhow::Error) -> Option<Self> {
+        if error.downcast_ref::<NotABinary>().is_some() {
+            return None;
+        }
+        let kind = if error.downcast_ref::<MapFailed>().is_some() {
+            ErrorKind::Mmap
+        } else if let Some(io_error) = error.downcast_ref::<io::Error>() {
+            ErrorKind::from_io(io_error)
+        } else {
+            ErrorKind::Malformed
+        };
+        Some(Self { path: path.to_path_buf(), kind, message: format!("{:#}", error) })
+    }
+
+    /// A directory entry the walk couldn't get past
+    pub fn from_walk(error: &walkdir::Error) -> Self {
+        let path = error.path().map(Path::to_path_buf).unwrap_or_default();
+        let kind = match error.io_error() {
+            _ if error.loop_ancestor().is_some() => ErrorKind::SymlinkLoop,
+            Some(io_error) if io_error.kind() == io::ErrorKind::NotFound && path.is_symlink() => ErrorKind::BrokenSymlink,
+            Some(io_error) => ErrorKind::from_io(io_error),
+            None => ErrorKind::Io,
+        };
+        Self { path, kind, message: error.to_string() }
+    }
+}
+
+/// One line per error, after the report
+pub fn write_human(errors: &[ScanError], out: &mut dyn Write) -> Result<()> {
+    writeln!(out)?;
+    writeln!(out, "{}", "Scan Errors".bright_cyan().bold())?;
+    writeln!(out)?;
+    for error in errors {
+        writeln!(out, "   {} {} {}",
+            error.path.display().to_string().white(),
+            format!("[{}]", error.kind.name()).yellow(),
+            error.message.bright_black()
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,353 @@
+//! Environment checks for support tickets (`--self-test`)
+//!
+//! Most "symwalker finds nothing" reports come down to the machine: no
+//! route to the debuginfod servers, no /usr/lib/debug, a scan root that
+//! can't be read. Each check runs on its own thread and is given up on
+//! after `--self-test-timeout`, so a dead mount or a black-holed network
+//! costs seconds, not minutes.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use chrono::Utc;
+use colored::*;
+use reqwest::header::RANGE;
+use serde::Serialize;
+
+use crate::cli::Args;
+use crate::debuginfod::{Artifact, DebuginfodClient};
+use crate::elf::ElfAnalyzer;
+use crate::environment::Environment;
+use crate::prefetch;
+
+/// Every check, in the order they run; names for `--skip-check`
+pub const CHECKS: [&str; 8] = ["scan-root", "debug-dirs", "derived-data", "debuginfod", "cache-dir", "output-dir", "locale", "parse"];
+
+/// Build-id asked of each server; any HTTP answer, 404 included, means it's reachable
+const PROBE_BUILD_ID: &str = "0000000000000000000000000000000000000000";
+
+/// Answers slower than this are a warning: a scan makes one request per build-id
+const SLOW_SERVER: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+    Skip,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub check: &'static str,
+    pub status: Status,
+    pub detail: String,
+    /// What to do about a warning or failure
+    pub hint: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+/// The JSON form, with enough about the build to attach to a ticket
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestReport {
+    pub version: &'static str,
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub checked_at: String,
+    pub checks: Vec<CheckResult>,
+}
+
+impl SelfTestReport {
+    pub fn failed(&self) -> bool {
+        self.checks.iter().any(|check| check.status == Status::Fail)
+    }
+
+    pub fn write_human(&self, out: &mut dyn Write) -> Result<()> {
+        writeln!(out, "{}", "Self-test".bright_cyan().bold())?;
+        writeln!(out, "{}", format!("symwalker {} on {}/{}", self.version, self.os, self.arch).bright_black())?;
+        writeln!(out)?;
+        for check in &self.checks {
+            let mark = match check.status {
+                Status::Pass => "✓".green(),
+                Status::Warn => "!".yellow(),
+                Status::Fail => "✗".red(),
+                Status::Skip => "-".bright_black(),
+            };
+            writeln!(out, "   {} {:<13} {} {}",
+                mark,
+                check.check,
+                check.detail,
+                format!("({} ms)", check.elapsed_ms).bright_black()
+            )?;
+            if let Some(ref hint) = check.hint {
+                writeln!(out, "     {} {}", "→".bright_black(), hint.bright_black())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What a check found
+struct Outcome {
+    status: Status,
+    detail: String,
+    hint: Option<String>,
+}
+
+impl Outcome {
+    fn new(status: Status, detail: impl Into<String>) -> Self {
+        Self { status, detail: detail.into(), hint: None }
+    }
+
+    fn hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+/// Run every check not named in `--skip-check`
+pub fn run(args: &Args) -> SelfTestReport {
+    let timeout = Duration::from_secs(args.self_test_timeout.max(1));
+    let environment = Environment::capture(args.hermetic);
+    let mut checks = Vec::new();
+    let mut check = |name: &'static str, task: Box<dyn FnOnce() -> Outcome + Send>| {
+        if args.skip_check.iter().any(|skipped| skipped == name) {
+            checks.push(CheckResult { check: name, status: Status::Skip, detail: "skipped with --skip-check".to_string(), hint: None, elapsed_ms: 0 });
+        } else {
+            checks.push(bounded(name, timeout, task));
+        }
+    };
+
+    for root in &args.paths {
+        let root = root.clone();
+        check("scan-root", Box::new(move || scan_root(&root)));
+    }
+
+    let sysroot = args.sysroot.clone();
+    check("debug-dirs", Box::new(move || debug_dirs(sysroot.as_deref())));
+
+    let home = environment.home_dir();
+    check("derived-data", Box::new(move || derived_data(home)));
+
+    let specs = if args.debuginfod_urls.is_empty() { environment.debuginfod_urls() } else { args.debuginfod_urls.clone() };
+    match DebuginfodClient::new(specs) {
+        Ok(client) => {
+            for server in client.servers() {
+                let url = server.artifact_url(PROBE_BUILD_ID, Artifact::Debuginfo).unwrap_or_default();
+                let head = !server.head_unsupported();
+                check("debuginfod", Box::new(move || debuginfod_server(&url, head, timeout)));
+            }
+        }
+        Err(e) => {
+            let message = format!("{:#}", e);
+            check("debuginfod", Box::new(move || {
+                Outcome::new(Status::Fail, message).hint("Fix the entry in --debuginfod-urls or DEBUGINFOD_URLS")
+            }));
+        }
+    }
+
+    let cache = args.cache_dir.clone().or_else(|| prefetch::default_cache_dir(&environment));
+    check("cache-dir", Box::new(move || match cache {
+        Some(cache) => writable(&cache),
+        None => Outcome::new(Status::Warn, "no debuginfod cache directory")
+            .hint("Set DEBUGINFOD_CACHE_PATH, XDG_CACHE_HOME or HOME, or pass --cache-dir with --prefetch"),
+    }));
+
+    let output = args.output.clone();
+    check("output-dir", Box::new(move || match output {
+        Some(output) => writable(&output),
+        None => Outcome::new(Status::Skip, "no --output given"),
+    }));
+
+    check("locale", Box::new(locale));
+    check("parse", Box::new(parse));
+
+    SelfTestReport {
+        version: env!("CARGO_PKG_VERSION"),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        checked_at: Utc::now().to_rfc3339(),
+        checks,
+    }
+}
+
+/// `task` on its own thread, failed if it hasn't answered within `timeout`;
+/// a check stuck in the kernel is left behind rather than waited for
+fn bounded(name: &'static str, timeout: Duration, task: Box<dyn FnOnce() -> Outcome + Send>) -> CheckResult {
+    let start = Instant::now();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(task());
+    });
+    let outcome = receiver.recv_timeout(timeout).unwrap_or_else(|_| {
+        Outcome::new(Status::Fail, format!("no answer within {} s", timeout.as_secs()))
+            .hint("Something is hanging (network, automounted or network file system); --self-test-timeout allows more time")
+    });
+    CheckResult {
+        check: name,
+        status: outcome.status,
+        detail: outcome.detail,
+        hint: outcome.hint,
+        elapsed_ms: start.elapsed().as_millis() as u64,
+    }
+}
+
+fn scan_root(root: &Path) -> Outcome {
+    if !root.exists() {
+        return Outcome::new(Status::Fail, format!("{} doesn't exist", root.display()))
+            .hint("Check the path given as PATH (or SYMWALKER_DIR)");
+    }
+    if !root.is_dir() {
+        return Outcome::new(Status::Pass, format!("{} is a file; it's scanned on its own, or re-formatted if it's a saved --json report", root.display()));
+    }
+    match fs::read_dir(root) {
+        Ok(_) => Outcome::new(Status::Pass, format!("{} is readable", root.display())),
+        Err(e) => Outcome::new(Status::Fail, format!("{} can't be listed: {}", root.display(), e))
+            .hint("symwalker needs read and search (x) permission on every directory it scans"),
+    }
+}
+
+/// /usr/lib/debug, under the sysroot first, where the build-id and
+/// debuglink strategies look
+fn debug_dirs(sysroot: Option<&Path>) -> Outcome {
+    if cfg!(any(target_os = "macos", windows)) {
+        return Outcome::new(Status::Skip, "no /usr/lib/debug on this platform");
+    }
+    let dirs: Vec<PathBuf> = sysroot.map(|sysroot| sysroot.join("usr/lib/debug")).into_iter()
+        .chain([PathBuf::from("/usr/lib/debug")])
+        .collect();
+    let mut found = Vec::new();
+    for dir in &dirs {
+        match fs::read_dir(dir) {
+            Ok(_) => found.push(dir.display().to_string()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Outcome::new(Status::Fail, format!("{} can't be listed: {}", dir.display(), e))
+                    .hint("Debug files there can't be found without read permission");
+            }
+        }
+    }
+    if found.is_empty() {
+        return Outcome::new(Status::Warn, "no /usr/lib/debug")
+            .hint("Install debug packages (-dbgsym, -debuginfo) or look up symbols remotely with --check-remote");
+    }
+    let build_ids = dirs.iter().any(|dir| dir.join(".build-id").is_dir());
+    Outcome::new(Status::Pass, format!("{}{}", found.join(", "), if build_ids { "" } else { " (no .build-id index)" }))
+}
+
+fn derived_data(home: Option<PathBuf>) -> Outcome {
+    if !cfg!(target_os = "macos") {
+        return Outcome::new(Status::Skip, "Xcode DerivedData is macOS only");
+    }
+    let Some(home) = home else {
+        return Outcome::new(Status::Warn, "no home directory (HOME)")
+            .hint("dSYM bundles in Xcode DerivedData are only found with HOME set");
+    };
+    let derived_data = home.join("Library/Developer/Xcode/DerivedData");
+    if derived_data.is_dir() {
+        Outcome::new(Status::Pass, derived_data.display().to_string())
+    } else {
+        Outcome::new(Status::Warn, format!("{} doesn't exist", derived_data.display()))
+            .hint("Only needed for dSYM bundles of local Xcode builds")
+    }
+}
+
+fn debuginfod_server(url: &str, head: bool, timeout: Duration) -> Outcome {
+    let client = match reqwest::blocking::Client::builder().timeout(timeout).build() {
+        Ok(client) => client,
+        Err(e) => return Outcome::new(Status::Fail, format!("no HTTP client: {}", e)),
+    };
+    let request = if head { client.head(url) } else { client.get(url).header(RANGE, "bytes=0-0") };
+    let start = Instant::now();
+    match request.send() {
+        Ok(response) if response.status().is_server_error() => {
+            Outcome::new(Status::Fail, format!("{} answered {}", url, response.status()))
+                .hint("The server is up but failing; lookups against it will count as misses")
+        }
+        Ok(_) if start.elapsed() > SLOW_SERVER => {
+            Outcome::new(Status::Warn, format!("{} answered in {} ms", url, start.elapsed().as_millis()))
+                .hint("Slow servers make --check-remote slow; consider a closer mirror")
+        }
+        Ok(_) => Outcome::new(Status::Pass, format!("{} answered in {} ms", url, start.elapsed().as_millis())),
+        Err(e) => Outcome::new(Status::Fail, format!("{} unreachable: {}", url, e))
+            .hint("Check network access and proxy settings (HTTPS_PROXY); local scans still work"),
+    }
+}
+
+/// Whether files can be created in `dir`, or in the nearest existing
+/// parent it would be created under
+fn writable(dir: &Path) -> Outcome {
+    let Some(existing) = dir.ancestors().find(|ancestor| ancestor.is_dir()) else {
+        return Outcome::new(Status::Fail, format!("{} has no existing parent", dir.display()));
+    };
+    let probe = existing.join(format!(".symwalker-self-test-{}", std::process::id()));
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            if existing == dir {
+                Outcome::new(Status::Pass, format!("{} is writable", dir.display()))
+            } else {
+                Outcome::new(Status::Pass, format!("{} can be created in {}", dir.display(), existing.display()))
+            }
+        }
+        Err(e) => Outcome::new(Status::Fail, format!("{} isn't writable: {}", existing.display(), e))
+            .hint("Pick a directory you own, or fix its permissions"),
+    }
+}
+
+/// LC_ALL, LC_CTYPE, then LANG, as the C library resolves them; read from
+/// the process, not `Environment`, since they don't change what a scan finds
+fn locale() -> Outcome {
+    if cfg!(windows) {
+        return Outcome::new(Status::Skip, "no POSIX locale on Windows");
+    }
+    let setting = ["LC_ALL", "LC_CTYPE", "LANG"].iter()
+        .find_map(|&name| std::env::var(name).ok().filter(|value| !value.is_empty()).map(|value| (name, value)));
+    match setting {
+        Some((name, value)) if value.to_ascii_lowercase().replace('-', "").contains("utf8") => {
+            Outcome::new(Status::Pass, format!("{}={}", name, value))
+        }
+        Some((name, value)) => Outcome::new(Status::Warn, format!("{}={} isn't UTF-8", name, value))
+            .hint("Non-ASCII paths may show as replacement characters; try LANG=C.UTF-8"),
+        None => Outcome::new(Status::Warn, "no locale set (LC_ALL, LC_CTYPE, LANG)")
+            .hint("Non-ASCII paths may show as replacement characters; try LANG=C.UTF-8"),
+    }
+}
+
+/// Parse a built-in x86-64 executable: one PT_LOAD, no sections
+fn parse() -> Outcome {
+    let elf = tiny_elf();
+    match ElfAnalyzer::new(Path::new("self-test"), &elf, elf.len() as u64, Utc::now()) {
+        Ok(_) => Outcome::new(Status::Pass, format!("parsed a built-in {}-byte ELF", elf.len())),
+        Err(e) => Outcome::new(Status::Fail, format!("built-in ELF rejected: {:#}", e))
+            .hint("The binary itself is broken; reinstall symwalker"),
+    }
+}
+
+fn tiny_elf() -> Vec<u8> {
+    let mut elf = b"\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0".to_vec();
+    // e_type, e_machine, e_version, e_entry, e_phoff, e_shoff, e_flags
+    elf.extend_from_slice(&2u16.to_le_bytes());
+    elf.extend_from_slice(&0x3eu16.to_le_bytes());
+    elf.extend_from_slice(&1u32.to_le_bytes());
+    elf.extend_from_slice(&0x400078u64.to_le_bytes());
+    elf.extend_from_slice(&64u64.to_le_bytes());
+    elf.extend_from_slice(&0u64.to_le_bytes());
+    elf.extend_from_slice(&0u32.to_le_bytes());
+    // e_ehsize, e_phentsize, e_phnum, e_shentsize, e_shnum, e_shstrndx
+    for half in [64u16, 56, 1, 64, 0, 0] {
+        elf.extend_from_slice(&half.to_le_bytes());
+    }
+    // PT_LOAD, R+X, covering the file
+    elf.extend_from_slice(&1u32.to_le_bytes());
+    elf.extend_from_slice(&5u32.to_le_bytes());
+    for word in [0u64, 0x400000, 0x400000, 120, 120, 0x1000] {
+        elf.extend_from_slice(&word.to_le_bytes());
+    }
+    elf
+}
@@ -0,0 +1,198 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result};
+use memchr::memmem::Finder;
+use object::{Object, ObjectSection, ObjectSymbol, SectionKind};
+use serde::{Serialize, Deserialize};
+
+use crate::binary::thin_slice;
+
+/// Upper bound on matches recorded per binary
+const MAX_MATCHES: usize = 64;
+
+/// Signature file format (TOML):
+///
+/// ```toml
+/// [[signature]]
+/// name = "liblzma-5.6"
+/// strings = ["5.6.0", "5.6.1"]   # searched in read-only data and string tables
+/// bytes = ["f30f1efa4883ec08"]   # hex, searched in read-only data and code
+/// symbols = ["lzma_code"]         # exact symbol names
+/// ```
+///
+/// The string tables are ELF `.dynstr` and `.strtab`, so strings also match
+/// library and symbol names, including in stripped binaries
+#[derive(Debug, Deserialize)]
+struct SignatureFile {
+    #[serde(default)]
+    signature: Vec<SignatureSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SignatureSpec {
+    name: String,
+    #[serde(default)]
+    strings: Vec<String>,
+    #[serde(default)]
+    bytes: Vec<String>,
+    #[serde(default)]
+    symbols: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureMatch {
+    pub signature: String,
+    pub pattern: String,
+    pub kind: String,  // "string", "bytes" or "symbol"
+    pub offset: u64,   // file offset, or symbol address for "symbol"
+}
+
+struct Pattern {
+    signature: String,
+    display: String,
+    kind: &'static str,
+    finder: Finder<'static>,
+}
+
+/// Compiled signature list, loaded once per run
+pub struct SignatureSet {
+    data_patterns: Vec<Pattern>,
+    code_patterns: Vec<Pattern>,
+    symbols: Vec<(String, String)>,
+}
+
+impl SignatureSet {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read signature file: {}", path.display()))?;
+        Self::parse(&content)
+            .with_context(|| format!("Invalid signature file: {}", path.display()))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let file: SignatureFile = toml::from_str(content)?;
+
+        let mut data_patterns = Vec::new();
+        let mut code_patterns = Vec::new();
+        let mut symbols = Vec::new();
+
+        for spec in file.signature {
+            for string in &spec.strings {
+                if string.is_empty() {
+                    anyhow::bail!("Empty string pattern in signature '{}'", spec.name);
+                }
+                data_patterns.push(Pattern {
+                    signature: spec.name.clone(),
+                    display: string.clone(),
+                    kind: "string",
+                    finder: Finder::new(string.as_bytes()).into_owned(),
+                });
+            }
+
+            for bytes in &spec.bytes {
+                let needle = hex::decode(bytes.replace(' ', ""))
+                    .with_context(|| format!("Invalid hex pattern '{}' in signature '{}'", bytes, spec.name))?;
+                if needle.is_empty() {
+                    anyhow::bail!("Empty byte pattern in signature '{}'", spec.name);
+                }
+                code_patterns.push(Pattern {
+                    signature: spec.name.clone(),
+                    display: bytes.clone(),
+                    kind: "bytes",
+                    finder: Finder::new(&needle).into_owned(),
+                });
+            }
+
+            for symbol in spec.symbols {
+                symbols.push((spec.name.clone(), symbol));
+            }
+        }
+
+        Ok(Self { data_patterns, code_patterns, symbols })
+    }
+
+    /// Search a binary's read-only data, code and symbol tables
+    /// Each pattern reports at most its first hit per section
+    pub fn scan(&self, data: &[u8]) -> Vec<SignatureMatch> {
+        let mut matches = Vec::new();
+
+        let Ok(slice) = thin_slice(data) else {
+            return matches;
+        };
+        let Ok(file) = object::File::parse(slice) else {
+            return matches;
+        };
+        // Offsets are reported relative to the whole file, not the slice
+        let base = slice.as_ptr() as u64 - data.as_ptr() as u64;
+
+        for section in file.sections() {
+            let searchable_data = matches!(
+                section.kind(),
+                SectionKind::ReadOnlyData
+                    | SectionKind::ReadOnlyDataWithRel
+                    | SectionKind::ReadOnlyString
+                    | SectionKind::OtherString
+            ) || matches!(section.name(), Ok(".dynstr" | ".strtab"));
+            let searchable_code = searchable_data || section.kind() == SectionKind::Text;
+
+            if !searchable_code {
+                continue;
+            }
+
+            let Some((file_offset, _)) = section.file_range() else {
+                continue;
+            };
+            let Ok(contents) = section.data() else {
+                continue;
+            };
+
+            let patterns = self.code_patterns.iter()
+                .chain(self.data_patterns.iter().filter(|_| searchable_data));
+
+            for pattern in patterns {
+                if let Some(pos) = pattern.finder.find(contents) {
+                    matches.push(SignatureMatch {
+                        signature: pattern.signature.clone(),
+                        pattern: pattern.display.clone(),
+                        kind: pattern.kind.to_string(),
+                        offset: base + file_offset + pos as u64,
+                    });
+                    if matches.len() >= MAX_MATCHES {
+                        return matches;
+                    }
+                }
+            }
+        }
+
+        if !self.symbols.is_empty() {
+            for symbol in file.symbols().chain(file.dynamic_symbols()) {
+                let Ok(name) = symbol.name() else {
+                    continue;
+                };
+                // Mach-O symbols carry a leading underscore
+                let bare = name.strip_prefix('_').unwrap_or(name);
+
+                for (signature, wanted) in &self.symbols {
+                    if name == wanted || bare == wanted {
+                        let already = matches.iter()
+                            .any(|m| m.kind == "symbol" && &m.signature == signature && &m.pattern == wanted);
+                        if already {
+                            continue;
+                        }
+                        matches.push(SignatureMatch {
+                            signature: signature.clone(),
+                            pattern: wanted.clone(),
+                            kind: "symbol".to_string(),
+                            offset: symbol.address(),
+                        });
+                        if matches.len() >= MAX_MATCHES {
+                            return matches;
+                        }
+                    }
+                }
+            }
+        }
+
+        matches
+    }
+}
@@ -0,0 +1,56 @@
+//! Source roots the scanned binaries were built in (`--resolve-source-dirs`)
+//!
+//! Each `DW_AT_comp_dir` is cut back to its source tree the way `--cluster`
+//! does (`/build/openssl-3.0.2/crypto` becomes `/build/openssl-3.0.2`), so
+//! the list is the set of prefixes a debugger's source map has to cover.
+//! Relative directories, as `-fdebug-prefix-map` leaves them, are kept as
+//! they are. Only binaries whose DWARF `--dwarf` could read contribute.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use anyhow::Result;
+use colored::*;
+use serde::Serialize;
+
+use crate::binary::BinaryInfo;
+use crate::cluster;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceDir {
+    pub path: String,
+    /// Binaries with a compile unit under `path`
+    pub binaries: usize,
+}
+
+/// Distinct source roots, the most used first, then by path
+pub fn source_dirs(binaries: &[BinaryInfo]) -> Vec<SourceDir> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for info in binaries {
+        let roots: BTreeSet<String> = info.comp_dirs.iter()
+            .map(|dir| cluster::source_root(std::slice::from_ref(dir)).unwrap_or_else(|| dir.clone()))
+            .collect();
+        for root in roots {
+            *counts.entry(root).or_default() += 1;
+        }
+    }
+
+    let mut dirs: Vec<SourceDir> = counts.into_iter().map(|(path, binaries)| SourceDir { path, binaries }).collect();
+    dirs.sort_by(|a, b| b.binaries.cmp(&a.binaries).then_with(|| a.path.cmp(&b.path)));
+    dirs
+}
+
+pub fn write_human(dirs: &[SourceDir], out: &mut dyn Write) -> Result<()> {
+    writeln!(out)?;
+    writeln!(out, "{}", "Source Directories".bright_cyan().bold())?;
+    if dirs.is_empty() {
+        writeln!(out, "   {}", "No reported binary has readable DWARF compile directories".bright_black())?;
+        return Ok(());
+    }
+
+    writeln!(out)?;
+    for dir in dirs {
+        let binaries = if dir.binaries == 1 { "1 binary".to_string() } else { format!("{} binaries", dir.binaries) };
+        writeln!(out, "   {} {}", dir.path.bright_white(), format!("({})", binaries).bright_black())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,174 @@
+//! Files whose name promises a binary they don't contain, and the reverse
+//!
+//! A `.so` that is really a linker script (glibc installs `libc.so` as
+//! `GROUP ( libc.so.6 libc_nonshared.a ... )`), an empty `.o` or a `.ko`
+//! of text would otherwise be skipped without a word. Only files with a
+//! `WATCHED` extension are sniffed, so the walk stays cheap. Static
+//! archives named `.a` and LLVM bitcode objects are what they claim.
+
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use colored::*;
+use serde::{Deserialize, Serialize};
+
+use crate::binary::ScanWarning;
+
+/// Extensions that promise ELF or Mach-O content (`.so.6` counts as `.so`)
+pub const WATCHED: [&str; 5] = ["so", "dylib", "o", "a", "ko"];
+
+/// Extensions that promise anything but a binary
+const DATA_EXTENSIONS: [&str; 2] = ["txt", "dat"];
+
+/// Bytes read to classify a file; linker scripts are a few hundred
+const SNIFF_BYTES: u64 = 64 << 10;
+
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+const BITCODE_MAGIC: &[u8] = b"BC\xc0\xde";
+const BITCODE_WRAPPER_MAGIC: &[u8] = b"\xde\xc0\x17\x0b";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SuspiciousKind {
+    /// GROUP/INPUT directives for the linker, not a library
+    LinkerScript,
+    /// Any other text
+    Text,
+    Empty,
+    /// A static archive under a shared-library or object name
+    Archive,
+    /// Binary content in no format symwalker knows
+    Data,
+}
+
+impl SuspiciousKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            SuspiciousKind::LinkerScript => "linker-script",
+            SuspiciousKind::Text => "text",
+            SuspiciousKind::Empty => "empty",
+            SuspiciousKind::Archive => "archive",
+            SuspiciousKind::Data => "data",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SuspiciousFile {
+    pub path: PathBuf,
+    pub kind: SuspiciousKind,
+    pub message: String,
+    /// Libraries a linker script's GROUP and INPUT directives name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub libraries: Vec<String>,
+}
+
+/// The watched extension `path` has, if any
+fn watched_extension(path: &Path) -> Option<&'static str> {
+    let name = path.file_name()?.to_str()?;
+    WATCHED.into_iter().find(|extension| {
+        let suffix = format!(".{}", extension);
+        name.ends_with(&suffix) || (*extension == "so" && name.contains(".so."))
+    })
+}
+
+/// What `path`, which isn't ELF or Mach-O, holds instead, when its name
+/// says it should be one; None for names that don't and for honest
+/// archives and bitcode
+pub fn check(path: &Path) -> Option<SuspiciousFile> {
+    let extension = watched_extension(path)?;
+    let mut head = Vec::new();
+    fs::File::open(path).ok()?.take(SNIFF_BYTES).read_to_end(&mut head).ok()?;
+
+    let suspicious = |kind: SuspiciousKind, what: &str, libraries: Vec<String>| Some(SuspiciousFile {
+        path: path.to_path_buf(),
+        kind,
+        message: format!("{} named like a .{} binary", what, extension),
+        libraries,
+    });
+    if head.is_empty() {
+        return suspicious(SuspiciousKind::Empty, "empty file", Vec::new());
+    }
+    if head.starts_with(AR_MAGIC) {
+        return match extension {
+            "a" => None,
+            _ => suspicious(SuspiciousKind::Archive, "static archive", Vec::new()),
+        };
+    }
+    // LTO objects
+    if head.starts_with(BITCODE_MAGIC) || head.starts_with(BITCODE_WRAPPER_MAGIC) {
+        return None;
+    }
+    if head.contains(&0) {
+        return suspicious(SuspiciousKind::Data, "unrecognized binary data", Vec::new());
+    }
+
+    let text = String::from_utf8_lossy(&head);
+    match linker_script_libraries(&text) {
+        Some(libraries) => suspicious(SuspiciousKind::LinkerScript, "linker script", libraries),
+        None => suspicious(SuspiciousKind::Text, "text file", Vec::new()),
+    }
+}
+
+/// The files a linker script's GROUP and INPUT directives list, AS_NEEDED
+/// ones included; None when `script` has neither directive
+pub fn linker_script_libraries(script: &str) -> Option<Vec<String>> {
+    let mut uncommented = String::new();
+    let mut rest = script;
+    while let Some(start) = rest.find("/*") {
+        uncommented.push_str(&rest[..start]);
+        rest = rest[start..].find("*/").map_or("", |end| &rest[start + end + 2..]);
+    }
+    uncommented.push_str(rest);
+    let spaced = uncommented.replace('(', " ( ").replace(')', " ) ").replace(',', " ");
+
+    let mut tokens = spaced.split_whitespace().peekable();
+    let mut libraries = Vec::new();
+    let mut found = false;
+    while let Some(token) = tokens.next() {
+        if !matches!(token, "GROUP" | "INPUT") || tokens.peek() != Some(&"(") {
+            continue;
+        }
+        found = true;
+        tokens.next();
+        let mut depth = 1;
+        for token in tokens.by_ref() {
+            match token {
+                "(" => depth += 1,
+                ")" => depth -= 1,
+                "AS_NEEDED" => {}
+                library => libraries.push(library.to_string()),
+            }
+            if depth == 0 {
+                break;
+            }
+        }
+    }
+    found.then_some(libraries)
+}
+
+/// For an analyzed binary whose name says it's data
+pub fn extension_warning(path: &Path, binary_type: &str) -> Option<ScanWarning> {
+    let extension = path.extension()?.to_str()?;
+    DATA_EXTENSIONS.iter().find(|data| extension.eq_ignore_ascii_case(data))?;
+    Some(ScanWarning::new("misleading-extension", format!("{} binary named like a .{} file", binary_type, extension)))
+}
+
+/// One line per file, after the report
+pub fn write_human(files: &[SuspiciousFile], out: &mut dyn Write) -> Result<()> {
+    writeln!(out)?;
+    writeln!(out, "{}", "Suspicious Files".bright_cyan().bold())?;
+    writeln!(out)?;
+    for file in files {
+        writeln!(out, "   {} {} {}",
+            file.path.display().to_string().white(),
+            format!("[{}]", file.kind.name()).yellow(),
+            file.message.bright_black()
+        )?;
+        if !file.libraries.is_empty() {
+            writeln!(out, "      {} {}", "->".bright_black(), file.libraries.join(" "))?;
+        }
+    }
+    Ok(())
+}
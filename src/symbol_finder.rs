@@ -1,69 +1,211 @@
-use std::path::{Path, PathBuf};
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
 use std::fs;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::binary::ScanWarning;
+
+/// Ways the finder looks for debug symbols, selectable with
+/// `--symbol-strategy`/`--no-symbol-strategy`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Strategy {
+    /// /usr/lib/debug/.build-id/XX/YYYY.debug
+    BuildId,
+    /// .gnu_debuglink name next to the binary, in .debug/ or under /usr/lib/debug
+    Debuglink,
+    /// /usr/lib/debug/<binary path>.debug, with or without a .gnu_debuglink
+    PathMirror,
+    /// <binary>.debug or .debug/<binary> next to the binary
+    Adjacent,
+    /// <binary>.dSYM next to the binary
+    DsymAdjacent,
+    /// dSYM bundles under ~/Library/Developer/Xcode/DerivedData
+    DerivedData,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 6] = [
+        Strategy::BuildId,
+        Strategy::Debuglink,
+        Strategy::PathMirror,
+        Strategy::Adjacent,
+        Strategy::DsymAdjacent,
+        Strategy::DerivedData,
+    ];
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            Strategy::BuildId => "build-id",
+            Strategy::Debuglink => "debuglink",
+            Strategy::PathMirror => "path-mirror",
+            Strategy::Adjacent => "adjacent",
+            Strategy::DsymAdjacent => "dsym-adjacent",
+            Strategy::DerivedData => "derived-data",
+        }
+    }
+}
+
+/// A debug file one of the strategies found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DebugCandidate {
+    pub path: PathBuf,
+    pub source: Strategy,
+}
 
 /// Intelligent heuristics for finding debug symbols
 pub struct SymbolFinder<'a> {
     binary_path: &'a Path,
+    sysroot: Option<&'a Path>,
+    dsym_cache: Option<&'a DsymUuidCache>,
+    follow_symlinks: bool,
+    strategies: &'a [Strategy],
+    /// Home directory whose Xcode DerivedData is searched
+    home: Option<PathBuf>,
+    /// `--resolve-timeout` from when the finder was set up
+    timeout: Option<(Duration, Instant)>,
+    /// Set once a search found the deadline passed
+    timed_out: Cell<bool>,
 }
 
 impl<'a> SymbolFinder<'a> {
     pub fn new(binary_path: &'a Path) -> Self {
-        Self { binary_path }
+        Self {
+            binary_path,
+            sysroot: None,
+            dsym_cache: None,
+            follow_symlinks: false,
+            strategies: &Strategy::ALL,
+            home: None,
+            timeout: None,
+            timed_out: Cell::new(false),
+        }
+    }
+    
+    /// Only use these strategies; the `find_*` methods of the others find nothing
+    pub fn with_strategies(mut self, strategies: &'a [Strategy]) -> Self {
+        self.strategies = strategies;
+        self
+    }
+    
+    /// Search DerivedData under this home directory; without one it isn't searched
+    pub fn with_home(mut self, home: Option<PathBuf>) -> Self {
+        self.home = home;
+        self
+    }
+    
+    /// Enabled strategies, in the order their finds are preferred
+    pub fn strategies(&self) -> &[Strategy] {
+        self.strategies
+    }
+    
+    fn enabled(&self, strategy: Strategy) -> bool {
+        self.strategies.contains(&strategy) && !self.expired()
+    }
+    
+    /// Give up once `timeout` has passed: every search started after that
+    /// finds nothing, and a DerivedData walk stops between entries. A single
+    /// probe stuck on a hung mount can't be interrupted.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout.map(|timeout| (timeout, Instant::now() + timeout));
+        self
+    }
+    
+    fn expired(&self) -> bool {
+        let expired = self.timeout.is_some_and(|(_, deadline)| Instant::now() >= deadline);
+        if expired {
+            self.timed_out.set(true);
+        }
+        expired
+    }
+    
+    /// Warning for a search the timeout cut short
+    pub fn timeout_warning(&self) -> Option<ScanWarning> {
+        let (timeout, _) = self.timeout.filter(|_| self.timed_out.get())?;
+        Some(ScanWarning::new(
+            "resolve-timeout",
+            format!("gave up looking for local debug files after {}s (--resolve-timeout)", timeout.as_secs()),
+        ))
+    }
+    
+    /// Also search the debug directory of this root filesystem, for binaries
+    /// scanned from an extracted image
+    pub fn with_sysroot(mut self, sysroot: Option<&'a Path>) -> Self {
+        self.sysroot = sysroot;
+        self
+    }
+    
+    /// Share dSYM UUID lookups with other finders in the same run
+    pub fn with_dsym_cache(mut self, cache: &'a DsymUuidCache) -> Self {
+        self.dsym_cache = Some(cache);
+        self
+    }
+    
+    /// Follow symlinks while searching DerivedData, for dSYMs linked in
+    /// from a build cache; loops are detected and skipped
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
     }
     
     /// Find debug file using build-id (ELF)
-    /// Standard locations:
+    /// Standard locations, under the sysroot first when there is one:
     /// - /usr/lib/debug/.build-id/XX/YYYYYYYY.debug
     /// - /usr/lib/debug/.build-id/XX/YYYYYYYY
     pub fn find_by_build_id(&self, build_id: &str) -> Option<PathBuf> {
-        if build_id.len() < 3 {
+        if !self.enabled(Strategy::BuildId) || build_id.len() < 3 {
             return None;
         }
         
         let (prefix, suffix) = build_id.split_at(2);
         
-        let standard_paths = vec![
-            format!("/usr/lib/debug/.build-id/{}/{}.debug", prefix, suffix),
-            format!("/usr/lib/debug/.build-id/{}/{}", prefix, suffix),
-            format!("/lib/debug/.build-id/{}/{}.debug", prefix, suffix),
-            format!("/lib/debug/.build-id/{}/{}", prefix, suffix),
-        ];
-        
-        for path_str in standard_paths {
-            let path = PathBuf::from(path_str);
-            if path.exists() && path.is_file() {
-                return Some(path);
-            }
-        }
+        let file = |dir: PathBuf, debug: bool| {
+            let dir = dir.join(".build-id").join(prefix);
+            if debug { dir.join(format!("{}.debug", suffix)) } else { dir.join(suffix) }
+        };
         
-        None
+        let roots = self.sysroot.into_iter().chain(host_root());
+        roots.flat_map(|root| [
+                file(debug_root(root), true),
+                file(debug_root(root), false),
+                file(root.join("lib").join("debug"), true),
+                file(root.join("lib").join("debug"), false),
+            ])
+            .find(|path| probe(Strategy::BuildId, path))
     }
     
     /// Find debug file using .gnu_debuglink (ELF)
     /// Looks in:
     /// - Same directory as binary
     /// - Same directory/.debug/
-    /// - /usr/lib/debug/<path>
+    /// - <sysroot>/usr/lib/debug/<dir inside sysroot>/, then /usr/lib/debug/<dir>/
     pub fn find_by_debuglink(&self, debuglink: &str) -> Option<PathBuf> {
+        if !self.enabled(Strategy::Debuglink) {
+            return None;
+        }
+        
         if let Some(parent) = self.binary_path.parent() {
             // Same directory
             let same_dir = parent.join(debuglink);
-            if same_dir.exists() && same_dir.is_file() {
+            if probe(Strategy::Debuglink, &same_dir) {
                 return Some(same_dir);
             }
             
             // .debug subdirectory
             let debug_subdir = parent.join(".debug").join(debuglink);
-            if debug_subdir.exists() && debug_subdir.is_file() {
+            if probe(Strategy::Debuglink, &debug_subdir) {
                 return Some(debug_subdir);
             }
             
-            // /usr/lib/debug/<full-path>
-            let full_path = self.binary_path.to_string_lossy();
-            if full_path.starts_with('/') {
-                let debug_path = format!("/usr/lib/debug{}", full_path);
-                let debug_file = PathBuf::from(debug_path).with_file_name(debuglink);
-                if debug_file.exists() && debug_file.is_file() {
+            // /usr/lib/debug/<dir>/<debuglink>, under the sysroot first
+            for (root, inside) in self.mirror_roots(self.binary_path) {
+                let debug_file = mirror_path(&root, &inside).with_file_name(debuglink);
+                if probe(Strategy::Debuglink, &debug_file) {
                     return Some(debug_file);
                 }
             }
@@ -72,21 +214,67 @@ impl<'a> SymbolFinder<'a> {
         None
     }
     
+    /// Find the debug file mirroring the binary's path (ELF), for binaries
+    /// stripped of their .gnu_debuglink as well; a file with a different
+    /// build-id than `build_id` is skipped
+    /// Looks in:
+    /// - <sysroot>/usr/lib/debug/<path inside sysroot>.debug
+    /// - /usr/lib/debug/<canonical path>.debug
+    pub fn find_by_path_mirror(&self, build_id: Option<&str>) -> Option<PathBuf> {
+        if !self.enabled(Strategy::PathMirror) {
+            return None;
+        }
+        
+        let canonical = fs::canonicalize(self.binary_path).ok()?;
+        let candidates = self.mirror_roots(&canonical).into_iter().map(|(root, inside)| mirror_path(&root, &inside));
+        
+        candidates.into_iter().find(|path| {
+            probe(Strategy::PathMirror, path) && match (build_id, crate::elf::debug_file_build_id(path)) {
+                (Some(expected), Some(found)) if !found.eq_ignore_ascii_case(expected) => {
+                    log::debug!("path-mirror: skipped {}, build-id {} isn't {}", path.display(), found, expected);
+                    false
+                }
+                _ => true,
+            }
+        })
+    }
+    
+    /// The roots whose `/usr/lib/debug` mirrors `path`, each with the path
+    /// inside it: the sysroot when `path` lies in it, then the host root
+    fn mirror_roots(&self, path: &Path) -> Vec<(PathBuf, PathBuf)> {
+        let mut roots = Vec::new();
+        if let Some(sysroot) = self.sysroot.and_then(|root| fs::canonicalize(root).ok()) {
+            let inside = fs::canonicalize(path).ok()
+                .and_then(|canonical| canonical.strip_prefix(&sysroot).ok().map(Path::to_path_buf));
+            if let Some(inside) = inside {
+                roots.push((sysroot, inside));
+            }
+        }
+        if let Some(host) = host_root().filter(|_| path.has_root()) {
+            roots.push((host.to_path_buf(), path.strip_prefix(host).unwrap_or(path).to_path_buf()));
+        }
+        roots
+    }
+    
     /// Find .debug file adjacent to binary (ELF)
     pub fn find_adjacent_debug(&self) -> Option<PathBuf> {
+        if !self.enabled(Strategy::Adjacent) {
+            return None;
+        }
+        
         if let Some(parent) = self.binary_path.parent() {
             if let Some(filename) = self.binary_path.file_name() {
                 let mut debug_name = filename.to_os_string();
                 debug_name.push(".debug");
                 
                 let debug_path = parent.join(debug_name);
-                if debug_path.exists() && debug_path.is_file() {
+                if probe(Strategy::Adjacent, &debug_path) {
                     return Some(debug_path);
                 }
                 
                 // Try in .debug subdirectory
                 let debug_subdir = parent.join(".debug").join(filename);
-                if debug_subdir.exists() && debug_subdir.is_file() {
+                if probe(Strategy::Adjacent, &debug_subdir) {
                     return Some(debug_subdir);
                 }
             }
@@ -95,25 +283,26 @@ impl<'a> SymbolFinder<'a> {
         None
     }
     
-    /// Find dSYM bundle by UUID (Mach-O)
+    /// Find dSYM bundle by UUID (Mach-O), with the strategy that found it
     /// Standard locations:
     /// - <binary>.dSYM
     /// - <binary-dir>/<binary>.dSYM
     /// - ~/Library/Developer/Xcode/DerivedData/*/Build/Products/*/*.dSYM
-    pub fn find_dsym_by_uuid(&self, uuid: &str) -> Option<PathBuf> {
+    pub fn find_dsym_by_uuid(&self, uuid: &str) -> Option<(PathBuf, Strategy)> {
         // Try adjacent dSYM first
         if let Some(dsym) = self.find_adjacent_dsym() {
             if self.verify_dsym_uuid(&dsym, uuid) {
-                return Some(dsym);
+                return Some((dsym, Strategy::DsymAdjacent));
             }
+            log::debug!("dsym-adjacent: skipped {}, no slice has UUID {}", dsym.display(), uuid);
         }
         
         // Search in common Xcode locations
-        if let Some(home) = dirs::home_dir() {
+        if let Some(ref home) = self.home {
             let derived_data = home.join("Library/Developer/Xcode/DerivedData");
             if derived_data.exists() {
                 if let Some(dsym) = self.search_derived_data(&derived_data, uuid) {
-                    return Some(dsym);
+                    return Some((dsym, Strategy::DerivedData));
                 }
             }
         }
@@ -123,13 +312,18 @@ impl<'a> SymbolFinder<'a> {
     
     /// Find adjacent dSYM bundle (Mach-O)
     pub fn find_adjacent_dsym(&self) -> Option<PathBuf> {
+        if !self.enabled(Strategy::DsymAdjacent) {
+            return None;
+        }
+        
         if let Some(parent) = self.binary_path.parent() {
             if let Some(filename) = self.binary_path.file_name() {
                 let mut dsym_name = filename.to_os_string();
                 dsym_name.push(".dSYM");
                 
                 let dsym_path = parent.join(dsym_name);
-                if dsym_path.exists() && dsym_path.is_dir() {
+                log::trace!("dsym-adjacent: probed {}", dsym_path.display());
+                if dsym_path.is_dir() {
                     return Some(dsym_path);
                 }
             }
@@ -139,61 +333,40 @@ impl<'a> SymbolFinder<'a> {
     }
     
     fn verify_dsym_uuid(&self, dsym_path: &Path, expected_uuid: &str) -> bool {
-        // Look for DWARF file inside dSYM bundle
-        // Structure: <name>.dSYM/Contents/Resources/DWARF/<name>
-        let contents = dsym_path.join("Contents/Resources/DWARF");
-        
-        if !contents.exists() {
-            return false;
+        match self.dsym_cache {
+            Some(cache) => cache.uuids(dsym_path).contains(expected_uuid),
+            None => read_dsym_uuids(dsym_path).contains(expected_uuid),
         }
-        
-        // Read directory and check first file (usually matches binary name)
-        if let Ok(entries) = fs::read_dir(contents) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_file() {
-                    // Try to extract UUID from this file and compare
-                    if let Ok(file) = fs::File::open(&path) {
-                        if let Ok(mmap) = unsafe { memmap2::Mmap::map(&file) } {
-                            if let Ok(mach) = goblin::mach::Mach::parse(&mmap) {
-                                let macho = match mach {
-                                    goblin::mach::Mach::Binary(m) => m,
-                                    _ => return false,
-                                };
-                                
-                                for lc in &macho.load_commands {
-                                    if let goblin::mach::load_command::CommandVariant::Uuid(uuid_cmd) = lc.command {
-                                        let uuid = uuid::Uuid::from_bytes(uuid_cmd.uuid).to_string().to_uppercase();
-                                        return uuid == expected_uuid;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        
-        false
     }
     
-    fn search_derived_data(&self, derived_data: &Path, uuid: &str) -> Option<PathBuf> {
+    /// Search a DerivedData-style tree for a dSYM bundle covering `uuid`
+    pub fn search_derived_data(&self, derived_data: &Path, uuid: &str) -> Option<PathBuf> {
+        if !self.enabled(Strategy::DerivedData) {
+            return None;
+        }
+        
         // This is a simplified search - could be made more sophisticated
         // Walk through DerivedData looking for matching dSYM bundles
         
         use walkdir::WalkDir;
         
+        // With links followed, WalkDir reports a loop as an error entry,
+        // which is skipped like any other unreadable entry
         for entry in WalkDir::new(derived_data)
             .max_depth(5)
-            .follow_links(false)
+            .follow_links(self.follow_symlinks)
             .into_iter()
             .filter_map(|e| e.ok())
         {
+            if self.expired() {
+                return None;
+            }
             let path = entry.path();
-            if path.is_dir() && path.extension().and_then(|s| s.to_str()) == Some("dSYM") {
-                if self.verify_dsym_uuid(path, uuid) {
-                    return Some(path.to_path_buf());
-                }
+            if path.is_dir()
+                && path.extension().and_then(|s| s.to_str()) == Some("dSYM")
+                && self.verify_dsym_uuid(path, uuid)
+            {
+                return Some(path.to_path_buf());
             }
         }
         
@@ -201,13 +374,156 @@ impl<'a> SymbolFinder<'a> {
     }
 }
 
-// Add dirs crate for home_dir
-mod dirs {
-    use std::path::PathBuf;
-    use std::env;
+/// Whether `path` is a file, logged as one of `strategy`'s probes
+fn probe(strategy: Strategy, path: &Path) -> bool {
+    let found = path.is_file();
+    log::trace!("{}: probed {} ({})", strategy.name(), path.display(), if found { "found" } else { "missing" });
+    found
+}
+
+/// The host's own `/usr/lib/debug` tree; Windows and other non-Unix hosts
+/// have none, so only the sysroot is searched there
+fn host_root() -> Option<&'static Path> {
+    cfg!(unix).then(|| Path::new("/"))
+}
+
+/// <root>/usr/lib/debug, joined per component so verbatim (`\\?\`) Windows
+/// roots stay valid
+fn debug_root(root: &Path) -> PathBuf {
+    root.join("usr").join("lib").join("debug")
+}
+
+/// <root>/usr/lib/debug/<inside>.debug for a path `inside` relative to `root`
+fn mirror_path(root: &Path, inside: &Path) -> PathBuf {
+    let mut mirrored = debug_root(root);
+    mirrored.extend(inside.components().filter(|component| matches!(component, Component::Normal(_))));
+    let mut mirrored = mirrored.into_os_string();
+    mirrored.push(".debug");
+    PathBuf::from(mirrored)
+}
+
+/// Bundle → UUID set mappings, kept for the lifetime of a run so
+/// DerivedData searches don't re-verify the same bundle per binary
+#[derive(Default)]
+pub struct DsymUuidCache {
+    entries: Mutex<HashMap<PathBuf, Arc<HashSet<String>>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl DsymUuidCache {
+    pub fn uuids(&self, bundle: &Path) -> Arc<HashSet<String>> {
+        if let Some(uuids) = self.entries.lock().unwrap().get(bundle) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Arc::clone(uuids);
+        }
+        
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let uuids = Arc::new(read_dsym_uuids(bundle));
+        self.entries.lock().unwrap().insert(bundle.to_path_buf(), Arc::clone(&uuids));
+        uuids
+    }
+    
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
     
-    pub fn home_dir() -> Option<PathBuf> {
-        env::var_os("HOME").map(PathBuf::from)
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
     }
 }
 
+/// Collect every UUID a dSYM bundle covers (uppercase, hyphenated)
+/// Reads the plists first and only parses the DWARF objects when they
+/// don't list any UUIDs
+fn read_dsym_uuids(bundle: &Path) -> HashSet<String> {
+    let mut uuids = plist_uuids(bundle);
+    
+    if uuids.is_empty() {
+        // Structure: <name>.dSYM/Contents/Resources/DWARF/<name>
+        if let Ok(entries) = fs::read_dir(bundle.join("Contents/Resources/DWARF")) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_file() {
+                    uuids.extend(macho_uuids(&path));
+                }
+            }
+        }
+    }
+    
+    uuids
+}
+
+/// UUIDs recorded in the bundle's plists: a `dSYM_UUID` key in
+/// Contents/Info.plist (string, array, or arch → UUID dictionary), and the
+/// DebugSymbols-style Contents/Resources/<UUID>.plist files
+fn plist_uuids(bundle: &Path) -> HashSet<String> {
+    let mut uuids = HashSet::new();
+    
+    if let Ok(plist::Value::Dictionary(info)) = plist::Value::from_file(bundle.join("Contents/Info.plist")) {
+        let mut values = Vec::new();
+        match info.get("dSYM_UUID") {
+            Some(plist::Value::String(s)) => values.push(s.clone()),
+            Some(plist::Value::Array(items)) => {
+                values.extend(items.iter().filter_map(|v| v.as_string()).map(String::from));
+            }
+            Some(plist::Value::Dictionary(by_arch)) => {
+                values.extend(by_arch.values().filter_map(|v| v.as_string()).map(String::from));
+            }
+            _ => {}
+        }
+        uuids.extend(values.iter().filter_map(|v| normalize_uuid(v)));
+    }
+    
+    if let Ok(entries) = fs::read_dir(bundle.join("Contents/Resources")) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) == Some("plist") {
+                if let Some(uuid) = path.file_stem().and_then(|s| s.to_str()).and_then(normalize_uuid) {
+                    uuids.insert(uuid);
+                }
+            }
+        }
+    }
+    
+    uuids
+}
+
+/// UUIDs of every slice of a (possibly universal) Mach-O file
+fn macho_uuids(path: &Path) -> Vec<String> {
+    use goblin::mach::{Mach, MachO};
+    use goblin::mach::load_command::CommandVariant;
+    
+    let uuid_of = |macho: &MachO| {
+        macho.load_commands.iter().find_map(|lc| match lc.command {
+            CommandVariant::Uuid(uuid_cmd) => Some(uuid::Uuid::from_bytes(uuid_cmd.uuid).to_string().to_uppercase()),
+            _ => None,
+        })
+    };
+    
+    let Ok(file) = fs::File::open(path) else {
+        return Vec::new();
+    };
+    let Ok(mmap) = (unsafe { memmap2::Mmap::map(&file) }) else {
+        return Vec::new();
+    };
+    
+    match Mach::parse(&mmap) {
+        Ok(Mach::Binary(macho)) => uuid_of(&macho).into_iter().collect(),
+        Ok(Mach::Fat(fat)) => fat.iter_arches()
+            .flatten()
+            .filter_map(|arch| {
+                let start = arch.offset as usize;
+                let end = start.checked_add(arch.size as usize)?;
+                let macho = MachO::parse(mmap.get(start..end)?, 0).ok()?;
+                uuid_of(&macho)
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn normalize_uuid(s: &str) -> Option<String> {
+    uuid::Uuid::parse_str(s.trim()).ok().map(|u| u.to_string().to_uppercase())
+}
+
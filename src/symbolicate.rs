@@ -0,0 +1,155 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use object::Object;
+use serde::{Serialize, Deserialize};
+
+use crate::binary::{BinaryInfo, thin_slice};
+use crate::demangle::demangle;
+use crate::dwarf;
+
+/// An address resolved to a function and, with line info, a source location
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Symbolication {
+    pub address: String,
+    pub function: Option<String>,
+    /// Offset from the start of `function`; null when only DWARF named it
+    pub offset: Option<u64>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    /// File the line info came from (the binary or its debug file)
+    pub debug_source: Option<PathBuf>,
+}
+
+impl fmt::Display for Symbolication {
+    /// `function+0xNN (file:line)`, as much of it as is known
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.function, self.offset) {
+            (Some(function), Some(offset)) => write!(f, "{}+0x{:x}", function, offset)?,
+            (Some(function), None) => write!(f, "{}", function)?,
+            (None, _) => write!(f, "??")?,
+        }
+
+        if let Some(ref file) = self.file {
+            match self.line {
+                Some(line) => write!(f, " ({}:{})", file, line)?,
+                None => write!(f, " ({})", file)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a hex address, with or without `0x`
+pub fn parse_address(value: &str) -> Result<u64, String> {
+    let digits = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")).unwrap_or(value);
+    u64::from_str_radix(digits, 16).map_err(|_| format!("invalid hex address '{}'", value))
+}
+
+/// Resolve `address` (a link-time virtual address, no ASLR slide) in a scanned
+/// binary, preferring its detached debug file and falling back to the binary
+pub fn symbolicate(info: &BinaryInfo, binary_data: &[u8], address: u64) -> Result<Symbolication> {
+    let mut result = Symbolication {
+        address: format!("0x{:x}", address),
+        ..Default::default()
+    };
+
+    let debug_file = dwarf::dwarf_source(info)
+        .and_then(|path| {
+            let file = fs::File::open(&path).ok()?;
+            let mmap = unsafe { memmap2::Mmap::map(&file) }.ok()?;
+            Some((path, mmap))
+        });
+
+    let mut sources: Vec<(&Path, &[u8])> = Vec::new();
+    if let Some((ref path, ref mmap)) = debug_file {
+        sources.push((path, mmap));
+    }
+    sources.push((&info.file_path, binary_data));
+
+    let mut dwarf_function = None;
+    for (path, data) in sources {
+        let Ok(data) = thin_slice(data) else {
+            continue;
+        };
+        let Ok(file) = object::File::parse(data) else {
+            continue;
+        };
+
+        if result.function.is_none() {
+            if let Some((name, offset)) = symbol_for(&file, address) {
+                result.function = Some(name);
+                result.offset = Some(offset);
+            }
+        }
+
+        if result.file.is_none() {
+            if let Ok(Some(lookup)) = dwarf_lookup(&file, address) {
+                result.file = lookup.file;
+                result.line = lookup.line;
+                result.debug_source = Some(path.to_path_buf());
+                dwarf_function = dwarf_function.or(lookup.function);
+            }
+        }
+    }
+
+    // Symbol tables can be stripped while DWARF still names the function
+    if result.function.is_none() {
+        result.function = dwarf_function.map(|name| demangle(&name));
+    }
+
+    Ok(result)
+}
+
+/// Closest symbol at or below `address`, demangled, with the offset into it
+fn symbol_for(file: &object::File, address: u64) -> Option<(String, u64)> {
+    let map = file.symbol_map();
+    let symbol = map.get(address)?;
+
+    // Mach-O prefixes C symbols with an underscore; mangled names keep it for the demangler
+    let name = match file.format() {
+        object::BinaryFormat::MachO if !symbol.name().starts_with("__") => {
+            symbol.name().strip_prefix('_').unwrap_or(symbol.name())
+        }
+        _ => symbol.name(),
+    };
+
+    Some((demangle(name), address - symbol.address()))
+}
+
+struct DwarfLookup {
+    file: Option<String>,
+    line: Option<u32>,
+    /// Outermost (non-inlined) function containing the address
+    function: Option<String>,
+}
+
+fn dwarf_lookup(file: &object::File, address: u64) -> Result<Option<DwarfLookup>> {
+    let Some((dwarf_sections, endian)) = dwarf::load_sections(file)? else {
+        return Ok(None);
+    };
+    let dwarf = dwarf_sections.borrow(|section| gimli::EndianSlice::new(section, endian));
+    let context = addr2line::Context::from_dwarf(dwarf)?;
+
+    let mut function = None;
+    let mut frames = context.find_frames(address).skip_all_loads()?;
+    while let Some(frame) = frames.next()? {
+        if let Some(name) = frame.function.as_ref().and_then(|name| name.raw_name().ok()) {
+            function = Some(name.into_owned());
+        }
+    }
+
+    let location = context.find_location(address)?;
+    if location.is_none() && function.is_none() {
+        return Ok(None);
+    }
+
+    Ok(Some(DwarfLookup {
+        file: location.as_ref().and_then(|location| location.file).map(str::to_string),
+        line: location.and_then(|location| location.line),
+        function,
+    }))
+}
+
@@ -0,0 +1,280 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use super::{Endian, Writer};
+
+const SQUASHFS_BLOCK_SIZE: u32 = 128 * 1024;
+const METADATA_BLOCK_SIZE: usize = 8192;
+const METADATA_UNCOMPRESSED: u16 = 0x8000;
+const COMPRESSION_GZIP: u16 = 1;
+
+/// Builds a squashfs 4.0 image holding only metadata: file inodes carry a
+/// size but no data blocks
+///
+/// Each table must fit in one metadata block, which is plenty for fixtures.
+pub struct SquashfsBuilder {
+    files: Vec<(String, u32)>,
+    compression: u16,
+    compress_metadata: bool,
+}
+
+impl Default for SquashfsBuilder {
+    fn default() -> Self {
+        Self { files: Vec::new(), compression: COMPRESSION_GZIP, compress_metadata: false }
+    }
+}
+
+/// Directory tree: subdirectories and files by name
+#[derive(Default)]
+struct Directory {
+    directories: BTreeMap<String, Directory>,
+    files: BTreeMap<String, u32>,
+}
+
+/// Where an inode landed in the inode table
+struct InodeRef {
+    offset: u16,
+    number: u32,
+    inode_type: u16,
+}
+
+impl SquashfsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Regular file at a `/`-separated path; parent directories are implied
+    pub fn file(mut self, path: &str, size: u32) -> Self {
+        self.files.push((path.to_string(), size));
+        self
+    }
+
+    /// Superblock compression id (1 gzip, 4 xz, 6 zstd, ...)
+    pub fn compression(mut self, compression: u16) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Zlib-compress the metadata blocks (needs gzip compression)
+    pub fn compress_metadata(mut self) -> Self {
+        self.compress_metadata = true;
+        self
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        let mut root = Directory::default();
+        for (path, size) in &self.files {
+            let mut components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+            let name = components.pop().expect("file path must not be empty");
+            let directory = components.into_iter().fold(&mut root, |directory, component| {
+                directory.directories.entry(component.to_string()).or_default()
+            });
+            directory.files.insert(name.to_string(), *size);
+        }
+
+        let mut inodes = Writer::new(Endian::Little, true);
+        let mut listings = Writer::new(Endian::Little, true);
+        let mut next_number = 1;
+        let root_ref = write_directory(&root, &mut inodes, &mut listings, &mut next_number);
+        let inode_count = next_number - 1;
+
+        let inode_table = self.metadata_block(&inodes.data);
+        let directory_table = self.metadata_block(&listings.data);
+        // One uid/gid (root), behind its lookup table
+        let id_block = self.metadata_block(&[0; 4]);
+
+        let inode_start = 96u64;
+        let directory_start = inode_start + inode_table.len() as u64;
+        let id_block_start = directory_start + directory_table.len() as u64;
+        let id_table_start = id_block_start + id_block.len() as u64;
+        let bytes_used = id_table_start + 8;
+
+        let mut w = Writer::new(Endian::Little, true);
+        w.bytes(b"hsqs");
+        w.u32(inode_count);
+        w.u32(0); // mtime
+        w.u32(SQUASHFS_BLOCK_SIZE);
+        w.u32(0); // fragments
+        w.u16(self.compression);
+        w.u16(SQUASHFS_BLOCK_SIZE.trailing_zeros() as u16);
+        w.u16(0); // flags
+        w.u16(1); // ids
+        w.u16(4);
+        w.u16(0);
+        w.u64(root_ref.offset as u64); // block 0
+        w.u64(bytes_used);
+        w.u64(id_table_start);
+        w.u64(u64::MAX); // xattrs
+        w.u64(inode_start);
+        w.u64(directory_start);
+        w.u64(u64::MAX); // fragments
+        w.u64(u64::MAX); // export
+        w.bytes(&inode_table);
+        w.bytes(&directory_table);
+        w.bytes(&id_block);
+        w.u64(id_block_start);
+        w.data
+    }
+
+    fn metadata_block(&self, data: &[u8]) -> Vec<u8> {
+        assert!(data.len() <= METADATA_BLOCK_SIZE, "fixture metadata must fit in one block");
+
+        let (header, payload) = if self.compress_metadata {
+            let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data).unwrap();
+            let compressed = encoder.finish().unwrap();
+            (compressed.len() as u16, compressed)
+        } else {
+            (data.len() as u16 | METADATA_UNCOMPRESSED, data.to_vec())
+        };
+
+        [&header.to_le_bytes()[..], &payload].concat()
+    }
+}
+
+/// Write the children's inodes, then the directory's listing and inode, like
+/// mksquashfs does (the root ends up last)
+fn write_directory(directory: &Directory, inodes: &mut Writer, listings: &mut Writer, next_number: &mut u32) -> InodeRef {
+    let mut entries = Vec::new();
+    for (name, child) in &directory.directories {
+        entries.push((name, write_directory(child, inodes, listings, next_number)));
+    }
+    for (name, &size) in &directory.files {
+        let inode = InodeRef { offset: inodes.len() as u16, number: *next_number, inode_type: 2 };
+        write_inode_header(inodes, 2, *next_number);
+        inodes.u32(0); // blocks start
+        inodes.u32(0); // fragment
+        inodes.u32(0); // fragment offset
+        inodes.u32(size);
+        for _ in 0..size / SQUASHFS_BLOCK_SIZE {
+            inodes.u32(0);
+        }
+        *next_number += 1;
+        entries.push((name, inode));
+    }
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+
+    let listing_offset = listings.len();
+    if let Some((_, first)) = entries.first() {
+        let base = first.number;
+        listings.u32(entries.len() as u32 - 1);
+        listings.u32(0); // inode block
+        listings.u32(base);
+        for (name, inode) in &entries {
+            listings.u16(inode.offset);
+            listings.u16((inode.number as i32 - base as i32) as i16 as u16);
+            listings.u16(inode.inode_type);
+            listings.u16(name.len() as u16 - 1);
+            listings.bytes(name.as_bytes());
+        }
+    }
+    let listing_size = listings.len() - listing_offset;
+
+    let inode = InodeRef { offset: inodes.len() as u16, number: *next_number, inode_type: 1 };
+    write_inode_header(inodes, 1, *next_number);
+    inodes.u32(0); // listing block
+    inodes.u32(2 + directory.directories.len() as u32);
+    inodes.u16(listing_size as u16 + 3); // "." and ".." aren't stored
+    inodes.u16(listing_offset as u16);
+    inodes.u32(0); // parent, unused by readers walking from the root
+    *next_number += 1;
+    inode
+}
+
+fn write_inode_header(w: &mut Writer, inode_type: u16, number: u32) {
+    w.u16(inode_type);
+    w.u16(0o755);
+    w.u16(0); // uid index
+    w.u16(0); // gid index
+    w.u32(0); // mtime
+    w.u32(number);
+}
+
+/// SHA-256 of ".net core bundle", which the apphost carries after the
+/// bundle header offset
+const BUNDLE_SIGNATURE: [u8; 32] = [
+    0x8b, 0x12, 0x02, 0xb9, 0x6a, 0x61, 0x20, 0x38, 0x72, 0x7b, 0x93, 0x02, 0x14, 0xd7, 0xa0, 0x32,
+    0x13, 0xf5, 0xb9, 0xe6, 0xef, 0xae, 0x33, 0x18, 0xee, 0x3b, 0x2d, 0xce, 0x24, 0xb3, 0x6a, 0xae,
+];
+
+/// Bundle file types from the host's manifest format
+pub const BUNDLE_ASSEMBLY: u8 = 1;
+pub const BUNDLE_NATIVE_BINARY: u8 = 2;
+
+/// An unbundled apphost's placeholder: a zero header offset and the
+/// signature; embed it in the host, e.g. as a `.data` section
+pub fn bundle_placeholder() -> Vec<u8> {
+    [&[0u8; 8][..], &BUNDLE_SIGNATURE].concat()
+}
+
+/// Appends a .NET single-file bundle to an apphost and patches its
+/// placeholder with the manifest offset
+pub struct DotnetBundleBuilder {
+    host: Vec<u8>,
+    major: u32,
+    files: Vec<(String, u8, Vec<u8>)>,
+}
+
+impl DotnetBundleBuilder {
+    /// `host` must contain `bundle_placeholder()`
+    pub fn new(host: Vec<u8>) -> Self {
+        Self { host, major: 6, files: Vec::new() }
+    }
+
+    /// Manifest major version (2 for .NET 5, 6 for .NET 6 and later)
+    pub fn version(mut self, major: u32) -> Self {
+        self.major = major;
+        self
+    }
+
+    pub fn file(mut self, path: &str, file_type: u8, data: &[u8]) -> Self {
+        self.files.push((path.to_string(), file_type, data.to_vec()));
+        self
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        let mut w = Writer::new(Endian::Little, true);
+        w.bytes(&self.host);
+
+        let mut offsets = Vec::new();
+        for (_, _, data) in &self.files {
+            offsets.push(w.len() as u64);
+            w.bytes(data);
+        }
+
+        let header_offset = w.len() as u64;
+        w.u32(self.major);
+        w.u32(0);
+        w.u32(self.files.len() as u32);
+        write_string(&mut w, "bundle-id");
+        if self.major >= 2 {
+            w.bytes(&[0; 8 * 5]); // deps.json, runtimeconfig.json, flags
+        }
+        for ((path, file_type, data), offset) in self.files.iter().zip(offsets) {
+            w.u64(offset);
+            w.u64(data.len() as u64);
+            if self.major >= 6 {
+                w.u64(0); // not compressed
+            }
+            w.u8(*file_type);
+            write_string(&mut w, path);
+        }
+
+        let signature = w.data.windows(BUNDLE_SIGNATURE.len())
+            .position(|window| window == BUNDLE_SIGNATURE)
+            .expect("host must contain bundle_placeholder()");
+        w.data[signature - 8..signature].copy_from_slice(&header_offset.to_le_bytes());
+        w.data
+    }
+}
+
+/// BinaryWriter string: 7-bit encoded length, then UTF-8
+fn write_string(w: &mut Writer, value: &str) {
+    let mut len = value.len();
+    while len >= 0x80 {
+        w.u8(len as u8 | 0x80);
+        len >>= 7;
+    }
+    w.u8(len as u8);
+    w.bytes(value.as_bytes());
+}
@@ -0,0 +1,944 @@
+use super::{crc32, Endian, Writer};
+
+const PT_LOAD: u32 = 1;
+const PT_DYNAMIC: u32 = 2;
+const PT_INTERP: u32 = 3;
+const PT_NOTE: u32 = 4;
+const PT_TLS: u32 = 7;
+const PT_GNU_STACK: u32 = 0x6474_e551;
+const PT_GNU_RELRO: u32 = 0x6474_e552;
+
+const SHT_PROGBITS: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+const SHT_STRTAB: u32 = 3;
+const SHT_DYNAMIC: u32 = 6;
+const SHT_NOTE: u32 = 7;
+const SHT_NOBITS: u32 = 8;
+const SHT_RELA: u32 = 4;
+const SHT_RELR: u32 = 19;
+const SHT_HASH: u32 = 5;
+const SHT_GNU_HASH: u32 = 0x6fff_fff6;
+const SHT_DYNSYM: u32 = 11;
+const SHT_GNU_VERDEF: u32 = 0x6fff_fffd;
+const SHT_GNU_VERNEED: u32 = 0x6fff_fffe;
+const SHT_GNU_VERSYM: u32 = 0x6fff_ffff;
+
+const DT_NULL: u64 = 0;
+const DT_NEEDED: u64 = 1;
+const DT_HASH: u64 = 4;
+const DT_STRTAB: u64 = 5;
+const DT_SYMTAB: u64 = 6;
+const DT_SYMENT: u64 = 11;
+const DT_STRSZ: u64 = 10;
+const DT_SONAME: u64 = 14;
+const DT_RPATH: u64 = 15;
+const DT_RUNPATH: u64 = 29;
+
+const SHF_ALLOC: u64 = 2;
+const SHF_EXECINSTR: u64 = 4;
+const SHF_WRITE: u64 = 1;
+const SHF_TLS: u64 = 0x400;
+
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+
+struct Section {
+    name: String,
+    sh_type: u32,
+    flags: u64,
+    data: Vec<u8>,
+    link: u32,
+    info: u32,
+    entsize: u64,
+    /// Also describe this section with a program header of this type
+    segment: Option<u32>,
+    /// `sh_size` of an `SHT_NOBITS` section, which has no file data
+    nobits_size: u64,
+}
+
+/// Builds a minimal ELF file in memory
+///
+/// Defaults to a little-endian 64-bit x86_64 `ET_EXEC` with a
+/// non-executable `PT_GNU_STACK`, a read-only `PT_LOAD` covering the file
+/// and no sections beyond `.shstrtab`.
+pub struct ElfBuilder {
+    endian: Endian,
+    is_64: bool,
+    e_type: u16,
+    machine: u16,
+    entry: u64,
+    /// Put the entry point at the start of this section instead
+    entry_section: Option<String>,
+    gnu_stack: Option<u32>,
+    relro: bool,
+    load: bool,
+    /// AppImage type written into the e_ident padding
+    appimage: Option<u8>,
+    /// EI_ABIVERSION and e_version
+    abi_version: u8,
+    elf_version: u32,
+    segments: Vec<(u32, u32)>,
+    interp: Option<String>,
+    sections: Vec<Section>,
+    symbols: Vec<(String, Option<String>, u64)>,
+    /// Local absolute function symbols, written before the global ones
+    local_symbols: Vec<String>,
+    /// DT_NEEDED/DT_RPATH/DT_RUNPATH entries with their string values
+    dynamic: Vec<(u64, String)>,
+    /// Undefined function symbols in `.dynsym`, with the library file and
+    /// version they need
+    imports: Vec<(String, Option<(String, String)>)>,
+    /// Versions `.gnu.version_d` defines, after the base entry
+    version_definitions: Vec<String>,
+}
+
+impl Default for ElfBuilder {
+    fn default() -> Self {
+        Self {
+            endian: Endian::Little,
+            is_64: true,
+            e_type: 2, // ET_EXEC
+            machine: 62, // EM_X86_64
+            entry: 0x1000,
+            entry_section: None,
+            gnu_stack: Some(PF_R | PF_W),
+            relro: false,
+            load: true,
+            appimage: None,
+            abi_version: 0,
+            elf_version: 1,
+            segments: Vec::new(),
+            interp: None,
+            sections: Vec::new(),
+            symbols: Vec::new(),
+            local_symbols: Vec::new(),
+            dynamic: Vec::new(),
+            imports: Vec::new(),
+            version_definitions: Vec::new(),
+        }
+    }
+}
+
+impl ElfBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn endian(mut self, endian: Endian) -> Self {
+        self.endian = endian;
+        self
+    }
+
+    /// ELFCLASS32 instead of ELFCLASS64
+    pub fn class32(mut self) -> Self {
+        self.is_64 = false;
+        self
+    }
+
+    pub fn e_type(mut self, e_type: u16) -> Self {
+        self.e_type = e_type;
+        self
+    }
+
+    pub fn machine(mut self, machine: u16) -> Self {
+        self.machine = machine;
+        self
+    }
+
+    pub fn entry(mut self, entry: u64) -> Self {
+        self.entry = entry;
+        self
+    }
+
+    /// Entry point at the start of `section`, wherever layout puts it
+    pub fn entry_in(mut self, section: &str) -> Self {
+        self.entry_section = Some(section.to_string());
+        self
+    }
+
+    /// `PT_GNU_STACK` with these `p_flags`, or no header at all for `None`
+    pub fn gnu_stack(mut self, flags: Option<u32>) -> Self {
+        self.gnu_stack = flags;
+        self
+    }
+
+    /// Shorthand for a `PT_GNU_STACK` with `PF_X` set
+    pub fn executable_stack(self) -> Self {
+        self.gnu_stack(Some(PF_R | PF_W | PF_X))
+    }
+
+    pub fn relro(mut self) -> Self {
+        self.relro = true;
+        self
+    }
+
+    /// Drop the `PT_LOAD` covering the whole file (kept anyway when dynamic
+    /// entries need it)
+    pub fn without_load(mut self) -> Self {
+        self.load = false;
+        self
+    }
+
+    /// AppImage magic ("AI" and the type) in `e_ident[8..11]`; the payload
+    /// goes right after the returned bytes
+    pub fn appimage(mut self, image_type: u8) -> Self {
+        self.appimage = Some(image_type);
+        self
+    }
+
+    /// `EI_ABIVERSION` and `e_version`, normally 0 and 1; the ABI version
+    /// byte is where the AppImage magic starts
+    pub fn versions(mut self, abi_version: u8, elf_version: u32) -> Self {
+        self.abi_version = abi_version;
+        self.elf_version = elf_version;
+        self
+    }
+
+    /// Empty program header, e.g. a `PT_LOAD` (1) with `PF_W | PF_X`
+    pub fn segment(mut self, p_type: u32, flags: u32) -> Self {
+        self.segments.push((p_type, flags));
+        self
+    }
+
+    /// `.interp` section plus `PT_INTERP`
+    pub fn interp(mut self, path: &str) -> Self {
+        self.interp = Some(path.to_string());
+        self
+    }
+
+    /// Arbitrary `SHT_PROGBITS` section
+    pub fn section(mut self, name: &str, data: &[u8]) -> Self {
+        self.sections.push(Section {
+            name: name.to_string(),
+            sh_type: SHT_PROGBITS,
+            flags: 0,
+            data: data.to_vec(),
+            link: 0,
+            info: 0,
+            entsize: 0,
+            segment: None,
+            nobits_size: 0,
+        });
+        self
+    }
+
+    /// Allocated, executable `SHT_PROGBITS` section, mapped at its file offset
+    pub fn code_section(mut self, name: &str, data: &[u8]) -> Self {
+        self.sections.push(Section {
+            name: name.to_string(),
+            sh_type: SHT_PROGBITS,
+            flags: SHF_ALLOC | SHF_EXECINSTR,
+            data: data.to_vec(),
+            link: 0,
+            info: 0,
+            entsize: 0,
+            segment: None,
+            nobits_size: 0,
+        });
+        self
+    }
+
+    /// `.tdata` holding the thread-local template `data`, plus its `PT_TLS`
+    pub fn tls(mut self, data: &[u8]) -> Self {
+        self.sections.push(Section {
+            name: ".tdata".to_string(),
+            sh_type: SHT_PROGBITS,
+            flags: SHF_ALLOC | SHF_WRITE | SHF_TLS,
+            data: data.to_vec(),
+            link: 0,
+            info: 0,
+            entsize: 0,
+            segment: Some(PT_TLS),
+            nobits_size: 0,
+        });
+        self
+    }
+
+    /// `SHT_NOBITS` section of `size` bytes, like `.bss` or the `.text` of a
+    /// debug file split off with `objcopy --only-keep-debug`
+    pub fn nobits_section(mut self, name: &str, size: u64) -> Self {
+        self.sections.push(Section {
+            name: name.to_string(),
+            sh_type: SHT_NOBITS,
+            flags: 0,
+            data: Vec::new(),
+            link: 0,
+            info: 0,
+            entsize: 0,
+            segment: None,
+            nobits_size: size,
+        });
+        self
+    }
+
+    /// `.gnu.hash` section; imports add the SysV `.hash` table, so both
+    /// together give a `--hash-style=both` link
+    pub fn gnu_hash(mut self) -> Self {
+        self.sections.push(Section {
+            name: ".gnu.hash".to_string(),
+            sh_type: SHT_GNU_HASH,
+            flags: SHF_ALLOC,
+            data: vec![0; 16],
+            link: 0,
+            info: 0,
+            entsize: 0,
+            segment: None,
+            nobits_size: 0,
+        });
+        self
+    }
+
+    /// Allocated `SHT_RELA` section with one entry of each relocation type
+    pub fn relocations(mut self, name: &str, r_types: &[u32]) -> Self {
+        let mut w = Writer::new(self.endian, self.is_64);
+        for (i, &r_type) in r_types.iter().enumerate() {
+            w.word(0x1000 + 8 * i as u64); // r_offset
+            if self.is_64 {
+                w.u64(r_type as u64);
+            } else {
+                w.u32(r_type & 0xff);
+            }
+            w.word(0); // r_addend
+        }
+        let entsize = if self.is_64 { 24 } else { 12 };
+        self.sections.push(Section {
+            name: name.to_string(),
+            sh_type: SHT_RELA,
+            flags: SHF_ALLOC,
+            data: w.data,
+            link: 0,
+            info: 0,
+            entsize,
+            segment: None,
+            nobits_size: 0,
+        });
+        self
+    }
+
+    /// `.relr.dyn` holding these raw RELR words
+    pub fn relr(mut self, words: &[u64]) -> Self {
+        let mut w = Writer::new(self.endian, self.is_64);
+        for &word in words {
+            w.word(word);
+        }
+        self.sections.push(Section {
+            name: ".relr.dyn".to_string(),
+            sh_type: SHT_RELR,
+            flags: SHF_ALLOC,
+            data: w.data,
+            link: 0,
+            info: 0,
+            entsize: if self.is_64 { 8 } else { 4 },
+            segment: None,
+            nobits_size: 0,
+        });
+        self
+    }
+
+    /// Note section, also exposed through a `PT_NOTE` segment
+    pub fn note(self, section: &str, owner: &str, note_type: u32, desc: &[u8]) -> Self {
+        let mut w = Writer::new(self.endian, self.is_64);
+        let owner = [owner.as_bytes(), b"\0"].concat();
+        w.u32(owner.len() as u32);
+        w.u32(desc.len() as u32);
+        w.u32(note_type);
+        w.bytes(&owner);
+        w.align(4);
+        w.bytes(desc);
+        w.align(4);
+        self.raw_note(section, &w.data)
+    }
+
+    /// `.note.ABI-tag` with an OS code and the minimum kernel version
+    pub fn abi_tag(self, os: u32, version: [u32; 3]) -> Self {
+        let mut w = Writer::new(self.endian, self.is_64);
+        for word in [os, version[0], version[1], version[2]] {
+            w.u32(word);
+        }
+        self.note(".note.ABI-tag", "GNU", 1, &w.data)
+    }
+
+    /// Note section and `PT_NOTE` segment holding `data` as is
+    pub fn raw_note(mut self, section: &str, data: &[u8]) -> Self {
+        self.sections.push(Section {
+            name: section.to_string(),
+            sh_type: SHT_NOTE,
+            flags: SHF_ALLOC,
+            data: data.to_vec(),
+            link: 0,
+            info: 0,
+            entsize: 0,
+            segment: Some(PT_NOTE),
+            nobits_size: 0,
+        });
+        self
+    }
+
+    /// `.note.gnu.build-id` (NT_GNU_BUILD_ID)
+    pub fn build_id(self, id: &[u8]) -> Self {
+        self.note(".note.gnu.build-id", "GNU", 3, id)
+    }
+
+    /// `.note.go.buildid` as written by the Go linker
+    pub fn go_build_id(self, id: &str) -> Self {
+        self.note(".note.go.buildid", "Go\0", 4, id.as_bytes())
+    }
+
+    /// `.note.package` FDO packaging metadata holding `json`
+    pub fn package_note(self, json: &str) -> Self {
+        self.note(".note.package", "FDO", 0xcafe_1a7e, &[json.as_bytes(), b"\0"].concat())
+    }
+
+    /// `.gnu_debuglink` naming `filename`, with the CRC of `debug_file`
+    pub fn debuglink(self, filename: &str, debug_file: &[u8]) -> Self {
+        let mut w = Writer::new(self.endian, self.is_64);
+        w.bytes(filename.as_bytes());
+        w.u8(0);
+        w.align(4);
+        w.u32(crc32(debug_file));
+        let data = w.data;
+        self.section(".gnu_debuglink", &data)
+    }
+
+    /// `DT_NEEDED` entry; any dynamic entry adds `.dynamic`, `.dynstr`,
+    /// `PT_DYNAMIC` and a `PT_LOAD` mapping the whole file at address 0
+    pub fn needed(mut self, library: &str) -> Self {
+        self.dynamic.push((DT_NEEDED, library.to_string()));
+        self
+    }
+
+    /// Undefined function symbol in `.dynsym` (with `.hash` sizing it), as
+    /// left by linking against a shared library; adds `.dynamic` like `needed`
+    pub fn import(mut self, name: &str) -> Self {
+        self.imports.push((name.to_string(), None));
+        self
+    }
+
+    /// Undefined function symbol needing `version` of `file`, as
+    /// `memcpy@GLIBC_2.14` from libc.so.6; adds `.gnu.version` and
+    /// `.gnu.version_r` to the `import` layout
+    pub fn import_versioned(mut self, name: &str, file: &str, version: &str) -> Self {
+        self.imports.push((name.to_string(), Some((file.to_string(), version.to_string()))));
+        self
+    }
+
+    /// Symbol version the library defines (`LIBFOO_1.0`) in `.gnu.version_d`,
+    /// after the base entry naming the soname; adds `.dynamic` like `needed`
+    pub fn version_definition(mut self, version: &str) -> Self {
+        self.version_definitions.push(version.to_string());
+        self
+    }
+
+    /// `DT_SONAME`
+    pub fn soname(mut self, value: &str) -> Self {
+        self.dynamic.push((DT_SONAME, value.to_string()));
+        self
+    }
+
+    /// `DT_RPATH` with a colon-separated list
+    pub fn rpath(mut self, value: &str) -> Self {
+        self.dynamic.push((DT_RPATH, value.to_string()));
+        self
+    }
+
+    /// `DT_RUNPATH` with a colon-separated list
+    pub fn runpath(mut self, value: &str) -> Self {
+        self.dynamic.push((DT_RUNPATH, value.to_string()));
+        self
+    }
+
+    /// Global absolute function symbol in `.symtab`; any symbol makes the
+    /// file "not stripped"
+    pub fn symbol(mut self, name: &str) -> Self {
+        self.symbols.push((name.to_string(), None, 0));
+        self
+    }
+
+    /// Local absolute function symbol in `.symtab`
+    pub fn local_symbol(mut self, name: &str) -> Self {
+        self.local_symbols.push(name.to_string());
+        self
+    }
+
+    /// Global function symbol at `address` in the named section, which must
+    /// be added too; unlike absolute symbols these show up in symbol lookups
+    pub fn symbol_at(mut self, name: &str, section: &str, address: u64) -> Self {
+        self.symbols.push((name.to_string(), Some(section.to_string()), address));
+        self
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        let mut sections = Vec::new();
+
+        if let Some(ref interp) = self.interp {
+            sections.push(Section {
+                name: ".interp".to_string(),
+                sh_type: SHT_PROGBITS,
+                flags: SHF_ALLOC,
+                data: [interp.as_bytes(), b"\0"].concat(),
+                link: 0,
+                info: 0,
+                entsize: 0,
+                segment: Some(PT_INTERP),
+                nobits_size: 0,
+            });
+        }
+
+        let ElfBuilder { endian, is_64, symbols, local_symbols, sections: user_sections, dynamic, imports, version_definitions, .. } = self;
+        sections.extend(user_sections);
+
+        // .dynamic needs the address of .dynstr, so it's filled in after layout
+        let mut dynstr = vec![0u8];
+        let mut dynamic_entries = Vec::new();
+        for (tag, value) in &dynamic {
+            dynamic_entries.push((*tag, dynstr.len() as u64));
+            dynstr.extend_from_slice(value.as_bytes());
+            dynstr.push(0);
+        }
+        let mut dynsym = Writer::new(endian, is_64);
+        write_symbol(&mut dynsym, 0, 0, 0, 0);
+        for (name, _) in &imports {
+            write_symbol(&mut dynsym, dynstr.len() as u32, 0x12, 0, 0); // GLOBAL FUNC, SHN_UNDEF
+            dynstr.extend_from_slice(name.as_bytes());
+            dynstr.push(0);
+        }
+        let versions = version_sections(endian, is_64, &imports, &mut dynstr);
+        let soname = dynamic.iter().find(|(tag, _)| *tag == DT_SONAME).map_or("", |(_, value)| value.as_str());
+        let verdef = version_definition_section(endian, is_64, soname, &version_definitions, &mut dynstr);
+        let mut symbol_sections = None;
+        let dynamic_index = if dynamic.is_empty() && imports.is_empty() && verdef.is_none() {
+            None
+        } else {
+            sections.push(Section {
+                name: ".dynstr".to_string(),
+                sh_type: SHT_STRTAB,
+                flags: SHF_ALLOC,
+                data: dynstr.clone(),
+                link: 0,
+                info: 0,
+                entsize: 0,
+                segment: None,
+                nobits_size: 0,
+            });
+            // Section indices are 1-based
+            let dynstr_index = sections.len() as u32;
+            if let Some(verdef) = verdef {
+                sections.push(Section {
+                    name: ".gnu.version_d".to_string(),
+                    sh_type: SHT_GNU_VERDEF,
+                    flags: SHF_ALLOC,
+                    data: verdef,
+                    link: dynstr_index,
+                    info: version_definitions.len() as u32 + 1,
+                    entsize: 0,
+                    segment: None,
+                    nobits_size: 0,
+                });
+            }
+            if !imports.is_empty() {
+                // One bucket; nchain is all goblin needs to size .dynsym
+                let mut hash = Writer::new(endian, is_64);
+                for word in [1, imports.len() as u32 + 1, 0] {
+                    hash.u32(word);
+                }
+                for _ in 0..=imports.len() {
+                    hash.u32(0);
+                }
+                sections.push(Section {
+                    name: ".dynsym".to_string(),
+                    sh_type: SHT_DYNSYM,
+                    flags: SHF_ALLOC,
+                    data: dynsym.data,
+                    link: dynstr_index,
+                    info: 1,
+                    entsize: if is_64 { 24 } else { 16 },
+                    segment: None,
+                    nobits_size: 0,
+                });
+                sections.push(Section {
+                    name: ".hash".to_string(),
+                    sh_type: SHT_HASH,
+                    flags: SHF_ALLOC,
+                    data: hash.data,
+                    link: dynstr_index + 1,
+                    info: 0,
+                    entsize: 4,
+                    segment: None,
+                    nobits_size: 0,
+                });
+                symbol_sections = Some((sections.len() - 2, sections.len() - 1));
+                if let Some((versym, verneed, files)) = versions {
+                    let dynsym_index = sections.len() as u32 - 1;
+                    sections.push(Section {
+                        name: ".gnu.version".to_string(),
+                        sh_type: SHT_GNU_VERSYM,
+                        flags: SHF_ALLOC,
+                        data: versym,
+                        link: dynsym_index,
+                        info: 0,
+                        entsize: 2,
+                        segment: None,
+                        nobits_size: 0,
+                    });
+                    sections.push(Section {
+                        name: ".gnu.version_r".to_string(),
+                        sh_type: SHT_GNU_VERNEED,
+                        flags: SHF_ALLOC,
+                        data: verneed,
+                        link: dynstr_index,
+                        info: files,
+                        entsize: 0,
+                        segment: None,
+                        nobits_size: 0,
+                    });
+                }
+            }
+            let entry_size = if is_64 { 16 } else { 8 };
+            let extra_entries = if imports.is_empty() { 3 } else { 6 };
+            sections.push(Section {
+                name: ".dynamic".to_string(),
+                sh_type: SHT_DYNAMIC,
+                flags: SHF_ALLOC,
+                data: vec![0; entry_size * (dynamic_entries.len() + extra_entries)],
+                link: dynstr_index,
+                info: 0,
+                entsize: entry_size as u64,
+                segment: Some(PT_DYNAMIC),
+                nobits_size: 0,
+            });
+            Some(sections.len() - 1)
+        };
+
+        if !symbols.is_empty() || !local_symbols.is_empty() {
+            let mut strtab = vec![0u8];
+            let mut symtab = Writer::new(endian, is_64);
+            write_symbol(&mut symtab, 0, 0, 0, 0);
+            for name in &local_symbols {
+                write_symbol(&mut symtab, strtab.len() as u32, 0x02, 0xfff1, 0); // LOCAL FUNC, SHN_ABS
+                strtab.extend_from_slice(name.as_bytes());
+                strtab.push(0);
+            }
+            for (name, section, address) in &symbols {
+                // Section indices are 1-based; unnamed sections mean SHN_ABS
+                let shndx = match section {
+                    Some(section) => sections.iter().position(|s| &s.name == section)
+                        .map(|index| index as u16 + 1)
+                        .expect("symbol_at section must be added to the builder"),
+                    None => 0xfff1,
+                };
+                write_symbol(&mut symtab, strtab.len() as u32, 0x12, shndx, *address); // GLOBAL FUNC
+                strtab.extend_from_slice(name.as_bytes());
+                strtab.push(0);
+            }
+
+            // Section indices are 1-based; .strtab directly follows .symtab
+            let strtab_index = sections.len() as u32 + 2;
+            sections.push(Section {
+                name: ".symtab".to_string(),
+                sh_type: SHT_SYMTAB,
+                flags: 0,
+                data: symtab.data,
+                link: strtab_index,
+                // One past the last local symbol
+                info: 1 + local_symbols.len() as u32,
+                entsize: if is_64 { 24 } else { 16 },
+                segment: None,
+                nobits_size: 0,
+            });
+            sections.push(Section {
+                name: ".strtab".to_string(),
+                sh_type: SHT_STRTAB,
+                flags: 0,
+                data: strtab,
+                link: 0,
+                info: 0,
+                entsize: 0,
+                segment: None,
+                nobits_size: 0,
+            });
+        }
+
+        let mut shstrtab = vec![0u8];
+        let mut name_offsets = Vec::new();
+        for section in &sections {
+            name_offsets.push(shstrtab.len() as u32);
+            shstrtab.extend_from_slice(section.name.as_bytes());
+            shstrtab.push(0);
+        }
+        let shstrtab_name = shstrtab.len() as u32;
+        shstrtab.extend_from_slice(b".shstrtab\0");
+
+        let (ehsize, phentsize, shentsize) = if is_64 { (64, 56, 64) } else { (52, 32, 40) };
+        let segment_count = sections.iter().filter(|s| s.segment.is_some()).count()
+            + self.gnu_stack.is_some() as usize
+            + self.relro as usize
+            + self.segments.len()
+            + (self.load || dynamic_index.is_some()) as usize;
+
+        // Layout: header, program headers, section data, .shstrtab, section headers
+        let phoff = ehsize;
+        let mut offset = phoff + phentsize * segment_count;
+        let mut data_offsets = Vec::new();
+        for section in &sections {
+            offset = offset.next_multiple_of(8);
+            data_offsets.push(offset);
+            offset += section.data.len();
+        }
+        let shstrtab_offset = offset;
+        let shoff = (shstrtab_offset + shstrtab.len()).next_multiple_of(8);
+        let file_size = shoff + shentsize * (sections.len() + 2);
+
+        if let Some(index) = dynamic_index {
+            let mut d = Writer::new(endian, is_64);
+            for &(tag, value) in &dynamic_entries {
+                d.word(tag);
+                d.word(value);
+            }
+            d.word(DT_STRTAB);
+            let dynstr_index = sections.iter().position(|section| section.name == ".dynstr").unwrap();
+            d.word(data_offsets[dynstr_index] as u64);
+            if let Some((dynsym_index, hash_index)) = symbol_sections {
+                d.word(DT_SYMTAB);
+                d.word(data_offsets[dynsym_index] as u64);
+                d.word(DT_SYMENT);
+                d.word(if is_64 { 24 } else { 16 });
+                d.word(DT_HASH);
+                d.word(data_offsets[hash_index] as u64);
+            }
+            d.word(DT_STRSZ);
+            d.word(dynstr.len() as u64);
+            d.word(DT_NULL);
+            d.word(0);
+            sections[index].data = d.data;
+        }
+
+        let mut w = Writer::new(endian, is_64);
+
+        // e_ident
+        w.bytes(b"\x7fELF");
+        w.u8(if is_64 { 2 } else { 1 });
+        w.u8(match endian { Endian::Little => 1, Endian::Big => 2 });
+        w.u8(1);
+        w.u8(0);
+        match self.appimage {
+            Some(image_type) => w.bytes(&[b'A', b'I', image_type]),
+            None => w.bytes(&[self.abi_version, 0, 0]),
+        }
+        w.bytes(&[0; 5]);
+
+        w.u16(self.e_type);
+        w.u16(self.machine);
+        w.u32(self.elf_version);
+        let entry = match self.entry_section {
+            Some(ref name) => sections.iter().position(|section| &section.name == name)
+                .map(|index| data_offsets[index] as u64)
+                .expect("entry_in names a section"),
+            None => self.entry,
+        };
+        w.word(entry);
+        w.word(phoff as u64);
+        w.word(shoff as u64);
+        w.u32(0);
+        w.u16(ehsize as u16);
+        w.u16(phentsize as u16);
+        w.u16(segment_count as u16);
+        w.u16(shentsize as u16);
+        w.u16(sections.len() as u16 + 2);
+        w.u16(sections.len() as u16 + 1);
+
+        for (section, &data_offset) in sections.iter().zip(&data_offsets) {
+            if let Some(p_type) = section.segment {
+                write_phdr(&mut w, p_type, PF_R, data_offset as u64, section.data.len() as u64);
+            }
+        }
+        if let Some(flags) = self.gnu_stack {
+            write_phdr(&mut w, PT_GNU_STACK, flags, 0, 0);
+        }
+        if self.relro {
+            write_phdr(&mut w, PT_GNU_RELRO, PF_R, 0, 0);
+        }
+        for &(p_type, flags) in &self.segments {
+            write_phdr(&mut w, p_type, flags, 0, 0);
+        }
+        if self.load || dynamic_index.is_some() {
+            write_phdr(&mut w, PT_LOAD, PF_R, 0, file_size as u64);
+        }
+
+        for (section, &data_offset) in sections.iter().zip(&data_offsets) {
+            w.align(8);
+            debug_assert_eq!(w.len(), data_offset);
+            w.bytes(&section.data);
+        }
+        w.bytes(&shstrtab);
+        w.align(8);
+
+        // Null section header
+        write_shdr(&mut w, 0, 0, 0, 0, 0, 0, 0, 0);
+        for ((section, &data_offset), &name) in sections.iter().zip(&data_offsets).zip(&name_offsets) {
+            write_shdr(
+                &mut w,
+                name,
+                section.sh_type,
+                section.flags,
+                data_offset as u64,
+                section.data.len() as u64 + section.nobits_size,
+                section.link,
+                section.info,
+                section.entsize,
+            );
+        }
+        write_shdr(&mut w, shstrtab_name, SHT_STRTAB, 0, shstrtab_offset as u64, shstrtab.len() as u64, 0, 0, 0);
+
+        w.data
+    }
+}
+
+/// `.gnu.version` and `.gnu.version_r` contents and the number of files
+/// needed, for imports with versions; names are appended to `dynstr`
+fn version_sections(
+    endian: Endian,
+    is_64: bool,
+    imports: &[(String, Option<(String, String)>)],
+    dynstr: &mut Vec<u8>,
+) -> Option<(Vec<u8>, Vec<u8>, u32)> {
+    if imports.iter().all(|(_, version)| version.is_none()) {
+        return None;
+    }
+    let mut add_string = |value: &str| {
+        let offset = dynstr.len() as u32;
+        dynstr.extend_from_slice(value.as_bytes());
+        dynstr.push(0);
+        offset
+    };
+
+    // Each distinct (file, version) gets an index from 2; 1 is unversioned
+    let mut needed: Vec<(&str, Vec<&str>)> = Vec::new();
+    let mut indices = Vec::new();
+    for (file, version) in imports.iter().filter_map(|(_, version)| version.as_ref()) {
+        let position = match needed.iter().position(|(needed_file, _)| needed_file == file) {
+            Some(position) => position,
+            None => {
+                needed.push((file, Vec::new()));
+                needed.len() - 1
+            }
+        };
+        if !needed[position].1.contains(&version.as_str()) {
+            needed[position].1.push(version);
+            indices.push((file.as_str(), version.as_str()));
+        }
+    }
+    let index_of = |file: &str, version: &str| indices.iter().position(|&entry| entry == (file, version)).unwrap() as u16 + 2;
+
+    let mut versym = Writer::new(endian, is_64);
+    versym.u16(0);
+    for (_, version) in imports {
+        versym.u16(version.as_ref().map_or(1, |(file, version)| index_of(file, version)));
+    }
+
+    let mut verneed = Writer::new(endian, is_64);
+    for (position, (file, versions)) in needed.iter().enumerate() {
+        let last_file = position + 1 == needed.len();
+        verneed.u16(1); // vn_version
+        verneed.u16(versions.len() as u16);
+        verneed.u32(add_string(file));
+        verneed.u32(16); // vn_aux: right after this entry
+        verneed.u32(if last_file { 0 } else { 16 * (1 + versions.len() as u32) });
+        for (aux, version) in versions.iter().enumerate() {
+            verneed.u32(elf_hash(version));
+            verneed.u16(0); // vna_flags
+            verneed.u16(index_of(file, version));
+            verneed.u32(add_string(version));
+            verneed.u32(if aux + 1 == versions.len() { 0 } else { 16 });
+        }
+    }
+    Some((versym.data, verneed.data, needed.len() as u32))
+}
+
+/// `.gnu.version_d` contents: the base entry for `soname`, then one entry
+/// per version; names are appended to `dynstr`
+fn version_definition_section(
+    endian: Endian,
+    is_64: bool,
+    soname: &str,
+    versions: &[String],
+    dynstr: &mut Vec<u8>,
+) -> Option<Vec<u8>> {
+    if versions.is_empty() {
+        return None;
+    }
+    let mut verdef = Writer::new(endian, is_64);
+    let names = std::iter::once(soname).chain(versions.iter().map(String::as_str));
+    for (index, name) in names.enumerate() {
+        let last = index == versions.len();
+        verdef.u16(1); // vd_version
+        verdef.u16(if index == 0 { 1 } else { 0 }); // VER_FLG_BASE
+        verdef.u16(index as u16 + 1);
+        verdef.u16(1); // vd_cnt
+        verdef.u32(elf_hash(name));
+        verdef.u32(20); // vd_aux: right after this entry
+        verdef.u32(if last { 0 } else { 28 });
+        verdef.u32(dynstr.len() as u32);
+        verdef.u32(0); // vda_next
+        dynstr.extend_from_slice(name.as_bytes());
+        dynstr.push(0);
+    }
+    Some(verdef.data)
+}
+
+/// The SysV ELF hash, as `vna_hash` holds
+fn elf_hash(name: &str) -> u32 {
+    name.bytes().fold(0u32, |hash, byte| {
+        let hash = (hash << 4).wrapping_add(byte as u32);
+        let high = hash & 0xf000_0000;
+        (hash ^ (high >> 24)) & !high
+    })
+}
+
+fn write_symbol(w: &mut Writer, name: u32, info: u8, shndx: u16, value: u64) {
+    if w.is_64 {
+        w.u32(name);
+        w.u8(info);
+        w.u8(0);
+        w.u16(shndx);
+        w.u64(value);
+        w.u64(0);
+    } else {
+        w.u32(name);
+        w.u32(value as u32);
+        w.u32(0);
+        w.u8(info);
+        w.u8(0);
+        w.u16(shndx);
+    }
+}
+
+fn write_phdr(w: &mut Writer, p_type: u32, flags: u32, offset: u64, size: u64) {
+    w.u32(p_type);
+    if w.is_64 {
+        w.u32(flags);
+    }
+    w.word(offset);
+    w.word(offset); // p_vaddr
+    w.word(offset); // p_paddr
+    w.word(size);
+    w.word(size);
+    if !w.is_64 {
+        w.u32(flags);
+    }
+    w.word(if p_type == PT_NOTE { 4 } else { 1 });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_shdr(w: &mut Writer, name: u32, sh_type: u32, flags: u64, offset: u64, size: u64, link: u32, info: u32, entsize: u64) {
+    w.u32(name);
+    w.u32(sh_type);
+    w.word(flags);
+    w.word(offset); // sh_addr
+    w.word(offset);
+    w.word(size);
+    w.u32(link);
+    w.u32(info);
+    w.word(if sh_type == SHT_NOTE { 4 } else { 1 });
+    w.word(entsize);
+}
@@ -0,0 +1,363 @@
+use super::{Endian, Writer};
+
+const LC_SEGMENT: u32 = 0x1;
+const LC_LOAD_DYLIB: u32 = 0xc;
+const LC_SYMTAB: u32 = 0x2;
+const LC_SEGMENT_64: u32 = 0x19;
+const LC_UUID: u32 = 0x1b;
+const LC_DYLD_ENVIRONMENT: u32 = 0x27;
+const LC_MAIN: u32 = 0x8000_0028;
+
+const N_EXT: u8 = 0x1;
+const N_ABS: u8 = 0x2;
+const N_UNDF: u8 = 0x0;
+
+pub const CPU_TYPE_X86: u32 = 7;
+pub const CPU_TYPE_X86_64: u32 = 0x0100_0007;
+pub const CPU_TYPE_ARM64: u32 = 0x0100_000c;
+
+struct Section {
+    segment: String,
+    name: String,
+    data: Vec<u8>,
+}
+
+/// Builds a minimal little-endian Mach-O file in memory
+///
+/// Defaults to a 64-bit x86_64 `MH_EXECUTE` with `MH_PIE` set, an empty
+/// `LC_SYMTAB` and no UUID.
+pub struct MachOBuilder {
+    is_64: bool,
+    cputype: u32,
+    cpusubtype: u32,
+    filetype: u32,
+    flags: u32,
+    uuid: Option<[u8; 16]>,
+    entry: Option<u64>,
+    dyld_environment: Vec<String>,
+    sections: Vec<Section>,
+    /// Names with their `n_type` type bits and `n_desc`
+    symbols: Vec<(String, u8, u16)>,
+    /// `LC_LOAD_DYLIB` paths; library ordinals count from 1
+    dylibs: Vec<String>,
+}
+
+impl Default for MachOBuilder {
+    fn default() -> Self {
+        Self {
+            is_64: true,
+            cputype: CPU_TYPE_X86_64,
+            cpusubtype: 3,
+            filetype: 2, // MH_EXECUTE
+            flags: 0x0020_0085, // MH_NOUNDEFS | MH_DYLDLINK | MH_TWOLEVEL | MH_PIE
+            uuid: None,
+            entry: None,
+            dyld_environment: Vec::new(),
+            sections: Vec::new(),
+            symbols: Vec::new(),
+            dylibs: Vec::new(),
+        }
+    }
+}
+
+impl MachOBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 32-bit `MH_MAGIC` i386 file
+    pub fn class32(mut self) -> Self {
+        self.is_64 = false;
+        self.cputype = CPU_TYPE_X86;
+        self
+    }
+
+    pub fn cpu(mut self, cputype: u32, cpusubtype: u32) -> Self {
+        self.cputype = cputype;
+        self.cpusubtype = cpusubtype;
+        self
+    }
+
+    pub fn filetype(mut self, filetype: u32) -> Self {
+        self.filetype = filetype;
+        self
+    }
+
+    pub fn flags(mut self, flags: u32) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    pub fn uuid(mut self, uuid: [u8; 16]) -> Self {
+        self.uuid = Some(uuid);
+        self
+    }
+
+    /// `LC_MAIN` with this entry offset; parsers expect a `__TEXT` segment too
+    pub fn entry(mut self, entryoff: u64) -> Self {
+        self.entry = Some(entryoff);
+        self
+    }
+
+    /// `LC_DYLD_ENVIRONMENT` carrying `value`, e.g. `DYLD_INSERT_LIBRARIES=/tmp/x.dylib`
+    pub fn dyld_environment(mut self, value: &str) -> Self {
+        self.dyld_environment.push(value.to_string());
+        self
+    }
+
+    /// Section in the named segment; sections of one segment share a load command
+    pub fn section(mut self, segment: &str, name: &str, data: &[u8]) -> Self {
+        self.sections.push(Section {
+            segment: segment.to_string(),
+            name: name.to_string(),
+            data: data.to_vec(),
+        });
+        self
+    }
+
+    /// Shorthand for a `__DWARF` section, as found in dSYM companions
+    pub fn dwarf_section(self, name: &str, data: &[u8]) -> Self {
+        self.section("__DWARF", name, data)
+    }
+
+    /// External absolute symbol in `LC_SYMTAB`
+    pub fn symbol(mut self, name: &str) -> Self {
+        self.symbols.push((name.to_string(), N_ABS, 0));
+        self
+    }
+
+    /// External undefined symbol in `LC_SYMTAB`, as imported from a dylib
+    pub fn import(mut self, name: &str) -> Self {
+        self.symbols.push((name.to_string(), N_UNDF, 0));
+        self
+    }
+
+    /// Undefined symbol bound to `dylib` through its two-level namespace
+    /// library ordinal, adding `LC_LOAD_DYLIB` for it
+    pub fn import_from(mut self, name: &str, dylib: &str) -> Self {
+        let ordinal = match self.dylibs.iter().position(|loaded| loaded == dylib) {
+            Some(index) => index + 1,
+            None => {
+                self.dylibs.push(dylib.to_string());
+                self.dylibs.len()
+            }
+        };
+        self.symbols.push((name.to_string(), N_UNDF, (ordinal as u16) << 8));
+        self
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        let (header_size, segment_size, section_size, nlist_size) = if self.is_64 {
+            (32, 72, 80, 16)
+        } else {
+            (28, 56, 68, 12)
+        };
+
+        // Group sections by segment, keeping first-seen order
+        let mut segments: Vec<(&str, Vec<&Section>)> = Vec::new();
+        for section in &self.sections {
+            match segments.iter_mut().find(|(name, _)| *name == section.segment) {
+                Some((_, members)) => members.push(section),
+                None => segments.push((&section.segment, vec![section])),
+            }
+        }
+
+        let mut commands_size = 24; // LC_SYMTAB
+        commands_size += segments.iter()
+            .map(|(_, members)| segment_size + section_size * members.len())
+            .sum::<usize>();
+        if self.uuid.is_some() {
+            commands_size += 24;
+        }
+        if self.entry.is_some() {
+            commands_size += 24;
+        }
+        // dylinker_command: cmd, cmdsize, name offset, then the padded string
+        let pointer_size = if self.is_64 { 8 } else { 4 };
+        let dyld_environment_sizes: Vec<usize> = self.dyld_environment.iter()
+            .map(|value| (12 + value.len() + 1).next_multiple_of(pointer_size))
+            .collect();
+        commands_size += dyld_environment_sizes.iter().sum::<usize>();
+        // dylib_command: cmd, cmdsize, name offset, timestamp, versions, then the padded path
+        let dylib_sizes: Vec<usize> = self.dylibs.iter()
+            .map(|path| (24 + path.len() + 1).next_multiple_of(pointer_size))
+            .collect();
+        commands_size += dylib_sizes.iter().sum::<usize>();
+        let command_count = 1 + segments.len() + self.uuid.is_some() as usize + self.entry.is_some() as usize
+            + self.dyld_environment.len() + self.dylibs.len();
+
+        // Layout: header, load commands, section data, nlist entries, string table
+        let mut offset = header_size + commands_size;
+        let mut section_offsets = Vec::new();
+        for (_, members) in &segments {
+            for section in members {
+                offset = offset.next_multiple_of(8);
+                section_offsets.push(offset);
+                offset += section.data.len();
+            }
+        }
+        let symoff = offset.next_multiple_of(8);
+        let stroff = symoff + nlist_size * self.symbols.len();
+
+        let mut strtab = vec![b' ', 0];
+        let mut string_offsets = Vec::new();
+        for (name, _, _) in &self.symbols {
+            string_offsets.push(strtab.len() as u32);
+            strtab.extend_from_slice(name.as_bytes());
+            strtab.push(0);
+        }
+
+        let mut w = Writer::new(Endian::Little, self.is_64);
+        w.u32(if self.is_64 { 0xfeed_facf } else { 0xfeed_face });
+        w.u32(self.cputype);
+        w.u32(self.cpusubtype);
+        w.u32(self.filetype);
+        w.u32(command_count as u32);
+        w.u32(commands_size as u32);
+        w.u32(self.flags);
+        if self.is_64 {
+            w.u32(0);
+        }
+
+        if let Some(uuid) = self.uuid {
+            w.u32(LC_UUID);
+            w.u32(24);
+            w.bytes(&uuid);
+        }
+
+        let mut remaining_offsets = section_offsets.as_slice();
+        for (segment, members) in &segments {
+            let (section_offsets, rest) = remaining_offsets.split_at(members.len());
+            remaining_offsets = rest;
+            let start = section_offsets[0] as u64;
+            let end = section_offsets.iter().zip(members)
+                .map(|(&offset, section)| (offset + section.data.len()) as u64)
+                .max()
+                .unwrap_or(start);
+
+            w.u32(if self.is_64 { LC_SEGMENT_64 } else { LC_SEGMENT });
+            w.u32((segment_size + section_size * members.len()) as u32);
+            w.name16(segment);
+            w.word(start); // vmaddr
+            w.word(end - start); // vmsize
+            w.word(start); // fileoff
+            w.word(end - start); // filesize
+            w.u32(7); // maxprot
+            w.u32(5); // initprot
+            w.u32(members.len() as u32);
+            w.u32(0);
+
+            for (section, &offset) in members.iter().zip(section_offsets) {
+                w.name16(&section.name);
+                w.name16(segment);
+                w.word(offset as u64); // addr
+                w.word(section.data.len() as u64);
+                w.u32(offset as u32);
+                w.u32(0); // align
+                w.u32(0); // reloff
+                w.u32(0); // nreloc
+                w.u32(0); // flags
+                w.u32(0);
+                w.u32(0);
+                if self.is_64 {
+                    w.u32(0);
+                }
+            }
+        }
+
+        w.u32(LC_SYMTAB);
+        w.u32(24);
+        w.u32(symoff as u32);
+        w.u32(self.symbols.len() as u32);
+        w.u32(stroff as u32);
+        w.u32(strtab.len() as u32);
+
+        if let Some(entryoff) = self.entry {
+            w.u32(LC_MAIN);
+            w.u32(24);
+            w.u64(entryoff);
+            w.u64(0);
+        }
+
+        for (value, &size) in self.dyld_environment.iter().zip(&dyld_environment_sizes) {
+            w.u32(LC_DYLD_ENVIRONMENT);
+            w.u32(size as u32);
+            w.u32(12);
+            w.bytes(value.as_bytes());
+            w.bytes(&vec![0; size - 12 - value.len()]);
+        }
+
+        for (path, &size) in self.dylibs.iter().zip(&dylib_sizes) {
+            w.u32(LC_LOAD_DYLIB);
+            w.u32(size as u32);
+            w.u32(24);
+            w.u32(2); // timestamp
+            w.u32(0x1_0000); // current_version 1.0.0
+            w.u32(0x1_0000); // compatibility_version
+            w.bytes(path.as_bytes());
+            w.bytes(&vec![0; size - 24 - path.len()]);
+        }
+
+        for (_, members) in &segments {
+            for section in members {
+                w.align(8);
+                w.bytes(&section.data);
+            }
+        }
+        w.align(8);
+
+        for (&strx, &(_, n_type, n_desc)) in string_offsets.iter().zip(&self.symbols) {
+            w.u32(strx);
+            w.u8(N_EXT | n_type);
+            w.u8(0); // NO_SECT
+            w.u16(n_desc);
+            w.word(0);
+        }
+        w.bytes(&strtab);
+
+        w.data
+    }
+}
+
+/// Wraps thin Mach-O files into a universal (fat) binary
+#[derive(Default)]
+pub struct FatBuilder {
+    slices: Vec<(u32, u32, Vec<u8>)>,
+}
+
+impl FatBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn slice(mut self, cputype: u32, cpusubtype: u32, data: Vec<u8>) -> Self {
+        self.slices.push((cputype, cpusubtype, data));
+        self
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        // fat_header and fat_arch are always big-endian; slices are page aligned
+        const ALIGN: usize = 1 << 12;
+
+        let mut w = Writer::new(Endian::Big, false);
+        w.u32(0xcafe_babe);
+        w.u32(self.slices.len() as u32);
+
+        let mut offset = ALIGN;
+        for (cputype, cpusubtype, data) in &self.slices {
+            w.u32(*cputype);
+            w.u32(*cpusubtype);
+            w.u32(offset as u32);
+            w.u32(data.len() as u32);
+            w.u32(12);
+            offset = (offset + data.len()).next_multiple_of(ALIGN);
+        }
+
+        for (_, _, data) in &self.slices {
+            w.align(ALIGN);
+            w.bytes(data);
+        }
+
+        w.data
+    }
+}
@@ -0,0 +1,145 @@
+//! Builders for synthetic ELF and Mach-O fixtures
+//!
+//! These write just enough structure for the analyzers to parse: headers,
+//! program headers/load commands, sections, notes and symbol tables, plus
+//! squashfs images and .NET bundles for container payloads. Nothing
+//! produced here is runnable. Enabled by the `testutil` feature.
+
+mod container;
+mod elf;
+mod macho;
+
+pub use container::{bundle_placeholder, DotnetBundleBuilder, SquashfsBuilder, BUNDLE_ASSEMBLY, BUNDLE_NATIVE_BINARY};
+pub use elf::ElfBuilder;
+pub use macho::{FatBuilder, MachOBuilder, CPU_TYPE_ARM64, CPU_TYPE_X86, CPU_TYPE_X86_64};
+
+use crate::go::PclntabVersion;
+
+/// Byte order of a synthesized file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+/// CRC-32 as used by `.gnu_debuglink` (IEEE polynomial, reflected)
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Growable buffer with endian- and class-aware integer writers
+struct Writer {
+    data: Vec<u8>,
+    endian: Endian,
+    is_64: bool,
+}
+
+impl Writer {
+    fn new(endian: Endian, is_64: bool) -> Self {
+        Self { data: Vec::new(), endian, is_64 }
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        match self.endian {
+            Endian::Little => self.bytes(&value.to_le_bytes()),
+            Endian::Big => self.bytes(&value.to_be_bytes()),
+        }
+    }
+
+    fn u32(&mut self, value: u32) {
+        match self.endian {
+            Endian::Little => self.bytes(&value.to_le_bytes()),
+            Endian::Big => self.bytes(&value.to_be_bytes()),
+        }
+    }
+
+    fn u64(&mut self, value: u64) {
+        match self.endian {
+            Endian::Little => self.bytes(&value.to_le_bytes()),
+            Endian::Big => self.bytes(&value.to_be_bytes()),
+        }
+    }
+
+    /// Address-sized field: 8 bytes for 64-bit files, 4 otherwise
+    fn word(&mut self, value: u64) {
+        if self.is_64 {
+            self.u64(value);
+        } else {
+            self.u32(value as u32);
+        }
+    }
+
+    /// Fixed-width, NUL-padded name field (Mach-O segment/section names)
+    fn name16(&mut self, name: &str) {
+        let mut field = [0u8; 16];
+        field[..name.len().min(16)].copy_from_slice(&name.as_bytes()[..name.len().min(16)]);
+        self.bytes(&field);
+    }
+
+    fn align(&mut self, alignment: usize) {
+        self.data.resize(self.data.len().next_multiple_of(alignment), 0);
+    }
+}
+
+/// Go pclntab with `functions` function table entries in the `version`
+/// layout; the name, file and line tables it would point to are left out
+pub fn go_pclntab(version: PclntabVersion, functions: u32, endian: Endian, is_64: bool) -> Vec<u8> {
+    let mut w = Writer::new(endian, is_64);
+    let ptr_size = if is_64 { 8 } else { 4 };
+    w.u32(version.magic());
+    w.bytes(&[0, 0, 1, ptr_size]);
+    w.word(functions as u64);
+
+    // Offsets of the tables: funcnametab, cutab, filetab, pctab and the
+    // function table, which comes right after the header
+    let words = match version {
+        PclntabVersion::Go12 => 0,
+        PclntabVersion::Go116 => 6,
+        PclntabVersion::Go118 | PclntabVersion::Go120 => 7,
+    };
+    let table = 8 + (words + 1) * ptr_size as u64;
+    if words > 0 {
+        w.word(1); // nfiles
+        if words == 7 {
+            w.word(0x40_1000); // textStart
+        }
+        for _ in 0..4 {
+            w.word(table);
+        }
+        w.word(table);
+    }
+
+    for index in 0..functions as u64 {
+        match version {
+            PclntabVersion::Go12 | PclntabVersion::Go116 => {
+                w.word(0x40_1000 + index * 0x20);
+                w.word(0);
+            }
+            PclntabVersion::Go118 | PclntabVersion::Go120 => {
+                w.u32(index as u32 * 0x20);
+                w.u32(0);
+            }
+        }
+    }
+    w.data
+}
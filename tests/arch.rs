@@ -0,0 +1,110 @@
+//! Architecture names must stay within the documented canonical set
+
+mod common;
+
+use symwalker::arch::{self, CANONICAL, UNKNOWN};
+use symwalker::output::{HumanFormatter, OutputFormatter};
+use symwalker::testutil::{ElfBuilder, Endian, FatBuilder, MachOBuilder, CPU_TYPE_ARM64, CPU_TYPE_X86_64};
+
+#[test]
+fn every_elf_machine_maps_into_the_canonical_set() {
+    for e_machine in 0..=u16::MAX {
+        for (is_64, is_little_endian) in [(false, false), (false, true), (true, false), (true, true)] {
+            let name = arch::from_elf(e_machine, is_64, is_little_endian);
+            assert!(CANONICAL.contains(&name), "e_machine {:#x} -> {}", e_machine, name);
+        }
+    }
+}
+
+#[test]
+fn every_macho_cputype_maps_into_the_canonical_set() {
+    // CPU_ARCH_ABI64 and CPU_ARCH_ABI64_32 variants of every base type
+    for base in 0..=0xffu32 {
+        for flags in [0, 0x0100_0000, 0x0200_0000] {
+            let name = arch::from_macho(base | flags);
+            assert!(CANONICAL.contains(&name), "cputype {:#x} -> {}", base | flags, name);
+        }
+    }
+}
+
+#[test]
+fn formats_agree_on_shared_architectures() {
+    use goblin::elf::header::{EM_386, EM_AARCH64, EM_ARM, EM_PPC, EM_PPC64, EM_X86_64};
+    use goblin::mach::constants::cputype::*;
+
+    let pairs = [
+        (EM_X86_64, true, CPU_TYPE_X86_64, "x86_64"),
+        (EM_386, false, CPU_TYPE_X86, "i386"),
+        (EM_AARCH64, true, CPU_TYPE_ARM64, "aarch64"),
+        (EM_ARM, false, CPU_TYPE_ARM, "arm"),
+        (EM_PPC, false, CPU_TYPE_POWERPC, "ppc"),
+        (EM_PPC64, true, CPU_TYPE_POWERPC64, "ppc64"),
+    ];
+    for (e_machine, is_64, cputype, expected) in pairs {
+        assert_eq!(arch::from_elf(e_machine, is_64, false), expected);
+        assert_eq!(arch::from_macho(cputype), expected);
+    }
+}
+
+#[test]
+fn aliases_normalize_to_canonical_names() {
+    for name in CANONICAL {
+        assert_eq!(arch::normalize(name), Some(*name));
+    }
+    for (alias, expected) in [("amd64", "x86_64"), ("ARM64", "aarch64"), ("AArch64", "aarch64"), ("i686", "i386"), ("ppc64el", "ppc64le")] {
+        assert_eq!(arch::normalize(alias), Some(expected), "{}", alias);
+    }
+    assert_eq!(arch::normalize("z80"), None);
+    assert!(arch::parse_arch("z80").is_err());
+}
+
+#[test]
+fn scans_report_canonical_and_raw_names() {
+    let elf = common::scan_bytes("app", &ElfBuilder::new().machine(0xb7).build(), &[]).unwrap();
+    assert_eq!((elf.architecture.as_str(), elf.architecture_raw.as_str()), ("aarch64", "AArch64"));
+
+    let thin = MachOBuilder::new().cpu(CPU_TYPE_ARM64, 0).build();
+    let macho = common::scan_bytes("app", &thin, &[]).unwrap();
+    assert_eq!((macho.architecture.as_str(), macho.architecture_raw.as_str()), ("aarch64", "ARM64"));
+
+    let fat = FatBuilder::new()
+        .slice(CPU_TYPE_X86_64, 3, MachOBuilder::new().build())
+        .slice(CPU_TYPE_ARM64, 0, thin)
+        .build();
+    let universal = common::scan_bytes("app", &fat, &[]).unwrap();
+    assert_eq!(universal.architecture, "x86_64");
+
+    let unknown = common::scan_bytes("app", &ElfBuilder::new().machine(0x1234).build(), &[]).unwrap();
+    assert_eq!(unknown.architecture, UNKNOWN);
+    assert_eq!(unknown.architecture_raw, "Unknown (0x1234)");
+    assert_eq!(unknown.schema_version, symwalker::binary::SCHEMA_VERSION);
+}
+
+#[test]
+fn byte_order_comes_from_ei_data_and_the_macho_magic() {
+    let little = common::scan_bytes("app", &ElfBuilder::new().build_id(&[0xab; 20]).build(), &[]).unwrap();
+    assert!(!little.is_big_endian);
+    // Note headers are read in the file's byte order too
+    let big = ElfBuilder::new().class32().endian(Endian::Big).machine(20).build_id(&[0xab; 20]).build();
+    let big = common::scan_bytes("app", &big, &[]).unwrap();
+    assert!(big.is_big_endian);
+    assert_eq!(big.build_id.as_deref(), Some("ab".repeat(20).as_str()));
+
+    let macho = common::scan_bytes("app", &MachOBuilder::new().build(), &[]).unwrap();
+    assert!(!macho.is_big_endian);
+    // MH_MAGIC stored big-endian: a PowerPC header as the header-only fallback sees it
+    let mut ppc = vec![0u8; 28];
+    ppc[..4].copy_from_slice(&0xfeed_face_u32.to_be_bytes());
+    ppc[4..8].copy_from_slice(&18u32.to_be_bytes());
+    ppc[12..16].copy_from_slice(&2u32.to_be_bytes());
+    let header = symwalker::macho::header_only(&ppc).unwrap();
+    assert!(header.is_big_endian && header.is_executable);
+
+    let show = |verbosity| {
+        let mut out = Vec::new();
+        HumanFormatter::new(verbosity).format(std::slice::from_ref(&big), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    assert!(show(1).contains("(32-bit, big-endian)"), "{}", show(1));
+    assert!(show(0).contains("(32-bit)"));
+}
@@ -0,0 +1,58 @@
+//! Check the fixture builders against independent parsers
+
+use object::{Object, ObjectSection};
+use symwalker::testutil::{crc32, ElfBuilder, Endian, FatBuilder, MachOBuilder, CPU_TYPE_ARM64, CPU_TYPE_X86_64};
+
+#[test]
+fn crc32_matches_reference_vector() {
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+}
+
+#[test]
+fn elf_builder_output_parses_in_both_byte_orders() {
+    for endian in [Endian::Little, Endian::Big] {
+        let data = ElfBuilder::new()
+            .endian(endian)
+            .section(".debug_info", b"\x01\x02\x03\x04")
+            .debuglink("app.debug", b"contents")
+            .symbol("main")
+            .build();
+
+        let file = object::File::parse(&*data).unwrap();
+        assert_eq!(file.is_little_endian(), endian == Endian::Little);
+        assert_eq!(file.section_by_name(".debug_info").unwrap().data().unwrap(), b"\x01\x02\x03\x04");
+        assert!(file.symbol_by_name("main").is_some());
+
+        let (name, crc) = file.gnu_debuglink().unwrap().unwrap();
+        assert_eq!(name, b"app.debug");
+        assert_eq!(crc, crc32(b"contents"));
+    }
+}
+
+#[test]
+fn elf_builder_notes_are_visible_to_goblin() {
+    let data = ElfBuilder::new().class32().build_id(&[0xab; 20]).build();
+    let elf = goblin::elf::Elf::parse(&data).unwrap();
+
+    let note = elf.iter_note_headers(&data).unwrap().next().unwrap().unwrap();
+    assert_eq!(note.name, "GNU");
+    assert_eq!(note.desc, &[0xab; 20]);
+}
+
+#[test]
+fn fat_builder_slices_parse() {
+    let fat = FatBuilder::new()
+        .slice(CPU_TYPE_X86_64, 3, MachOBuilder::new().uuid([1; 16]).build())
+        .slice(CPU_TYPE_ARM64, 0, MachOBuilder::new().cpu(CPU_TYPE_ARM64, 0).uuid([2; 16]).build())
+        .build();
+
+    let uuids: Vec<_> = object::read::macho::MachOFatFile32::parse(&*fat).unwrap()
+        .arches()
+        .iter()
+        .map(|arch| {
+            use object::read::macho::FatArch;
+            object::File::parse(arch.data(&*fat).unwrap()).unwrap().mach_uuid().unwrap()
+        })
+        .collect();
+    assert_eq!(uuids, vec![Some([1; 16]), Some([2; 16])]);
+}
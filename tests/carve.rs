@@ -0,0 +1,89 @@
+//! ELF images carved out of firmware blobs (`--carve`)
+
+mod common;
+
+use clap::Parser;
+use symwalker::binary::ScanContext;
+use symwalker::carve;
+use symwalker::cli::{self, Args};
+use symwalker::testutil::{ElfBuilder, Endian};
+
+/// Two ELF images between junk, including a bare magic and a header that
+/// claims more program headers than any real file; returns the blob and
+/// the images with their offsets
+fn firmware() -> (Vec<u8>, Vec<(u64, Vec<u8>)>) {
+    let kernel = ElfBuilder::new().build_id(&[1; 20]).symbol("start_kernel").build();
+    let module = ElfBuilder::new().class32().endian(Endian::Big).machine(20).build_id(&[2; 20]).build();
+
+    let mut bogus = kernel[..64].to_vec();
+    bogus[0x38..0x3a].copy_from_slice(&1000u16.to_le_bytes());
+
+    let mut blob = vec![0xff; 0x100];
+    blob.extend_from_slice(b"\x7fELF is the magic we look for");
+    blob.extend_from_slice(&bogus);
+    blob.resize(0x1000, 0);
+    let kernel_offset = blob.len() as u64;
+    blob.extend_from_slice(&kernel);
+    blob.extend_from_slice(&[0x5a; 0x123]);
+    let module_offset = blob.len() as u64;
+    blob.extend_from_slice(&module);
+    // A copy cut short by the end of the blob
+    blob.extend_from_slice(&kernel[..kernel.len() / 2]);
+
+    (blob, vec![(kernel_offset, kernel), (module_offset, module)])
+}
+
+#[test]
+fn only_complete_plausible_images_are_found() {
+    let (blob, images) = firmware();
+    let expected: Vec<(u64, u64)> = images.iter().map(|(offset, image)| (*offset, image.len() as u64)).collect();
+    assert_eq!(carve::find(&blob), expected);
+
+    // A file that is an ELF image itself is the normal scan's business
+    let (_, image) = &images[0];
+    assert!(carve::find(image).is_empty());
+}
+
+#[test]
+fn carved_images_are_reported_and_extracted() {
+    let (blob, images) = firmware();
+    let (scan, out) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    let path = scan.path().join("firmware.bin");
+    std::fs::write(&path, &blob).unwrap();
+
+    let args = common::args(&["--carve"]);
+    let ctx = ScanContext::new(&args).unwrap();
+    let found = carve::scan(&path, &args, &ctx).unwrap();
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].file_path, scan.path().join(format!("firmware.bin@0x{:x}", images[0].0)));
+    assert_eq!(found[0].build_id.as_deref(), Some("01".repeat(20).as_str()));
+    assert_eq!(found[0].file_size, images[0].1.len() as u64);
+    assert_eq!(found[1].architecture, "ppc");
+    assert_eq!(found[1].carved_from.as_ref().map(|carved| (carved.offset, carved.size)),
+        Some((images[1].0, images[1].1.len() as u64)));
+
+    assert!(carve::scan(&path, &common::args(&["--carve", "--max-file-size", "4096"]), &ctx).unwrap().is_empty());
+
+    let args = Args::parse_from(["symwalker", "--carve", "--show-stripped", "--copy-binaries",
+        "-o", out.path().to_str().unwrap(), scan.path().to_str().unwrap()]);
+    cli::run(args).unwrap();
+    for (offset, image) in &images {
+        let extracted = std::fs::read(out.path().join(format!("firmware.bin@0x{:x}", offset))).unwrap();
+        assert!(extracted == *image, "image at {:#x}", offset);
+    }
+}
+
+#[test]
+fn a_single_blob_given_as_path_is_carved() {
+    let (blob, images) = firmware();
+    let scan = tempfile::tempdir().unwrap();
+    let path = scan.path().join("firmware.bin");
+    std::fs::write(&path, &blob).unwrap();
+
+    let output = common::symwalker(&path, &["--carve", "--show-stripped", "--json"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let paths: Vec<&str> = report.as_array().unwrap().iter().map(|binary| binary["file_path"].as_str().unwrap()).collect();
+    let expected: Vec<String> = images.iter().map(|(offset, _)| format!("{}@0x{:x}", path.display(), offset)).collect();
+    assert_eq!(paths, expected);
+}
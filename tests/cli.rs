@@ -0,0 +1,516 @@
+//! Command-line parsing
+
+mod common;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use clap::Parser;
+use symwalker::binary::BinaryInfo;
+use symwalker::cli::{self, Args};
+use symwalker::output::{self, HumanFormatter, OutputFormatter};
+use symwalker::symbol_finder::Strategy;
+use symwalker::testutil::ElfBuilder;
+
+// One test so the environment isn't shared with parallel tests
+#[test]
+fn directory_falls_back_to_symwalker_dir() {
+    std::env::remove_var("SYMWALKER_DIR");
+    assert!(Args::try_parse_from(["symwalker"]).is_err());
+
+    std::env::set_var("SYMWALKER_DIR", "/mnt/scan");
+    let args = Args::try_parse_from(["symwalker"]).unwrap();
+    assert_eq!(args.paths, [PathBuf::from("/mnt/scan")]);
+
+    let args = Args::try_parse_from(["symwalker", "/usr/bin", "/usr/lib"]).unwrap();
+    assert_eq!(args.paths, [PathBuf::from("/usr/bin"), PathBuf::from("/usr/lib")]);
+
+    std::env::remove_var("SYMWALKER_DIR");
+}
+
+#[test]
+fn max_depth_counts_directory_levels_below_the_root() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("sub/deeper")).unwrap();
+    for file in ["top", "sub/middle", "sub/deeper/bottom"] {
+        std::fs::write(dir.path().join(file), b"").unwrap();
+    }
+
+    let root = [dir.path().to_str().unwrap()];
+    let files = |flags: &[&str]| {
+        let argv = ["symwalker"].iter().chain(flags).chain(&root);
+        let mut files: Vec<_> = cli::candidate_files(&Args::parse_from(argv))
+            .map(|path| path.strip_prefix(dir.path()).unwrap().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        files
+    };
+
+    assert_eq!(files(&["--max-depth", "0"]), ["top"]);
+    assert_eq!(files(&["--max-depth", "1"]), ["sub/middle", "top"]);
+    assert_eq!(files(&[]), ["sub/deeper/bottom", "sub/middle", "top"]);
+}
+
+#[test]
+fn count_by_rejects_unknown_fields_with_the_valid_ones() {
+    let err = Args::try_parse_from(["symwalker", "--count-by", "color", "."]).unwrap_err().to_string();
+    assert!(err.contains("arch") && err.contains("os_abi") && err.contains("is_stripped"), "{}", err);
+
+    let args = Args::try_parse_from(["symwalker", "--count-by", "is_stripped", "."]).unwrap();
+    assert_eq!(args.count_by, Some(cli::CountField::IsStripped));
+}
+
+#[test]
+fn count_by_groups_on_the_field() {
+    let binaries: Vec<_> = [
+        ElfBuilder::new().interp("/lib/ld-musl-x86_64.so.1").build(),
+        ElfBuilder::new().interp("/lib64/ld-linux-x86-64.so.2").needed("libc.so.6").build(),
+        ElfBuilder::new().e_type(3).needed("libc.so.6").build(),
+        ElfBuilder::new().build(),
+    ]
+    .iter()
+    .map(|data| common::scan_bytes("bin", data, &[]).unwrap())
+    .collect();
+
+    let mut counts = BTreeMap::new();
+    for binary in &binaries {
+        *counts.entry(cli::CountField::Libc.key(binary)).or_insert(0) += 1;
+    }
+    assert_eq!(counts, BTreeMap::from([("glibc".to_string(), 2), ("musl".to_string(), 1), ("static".to_string(), 1)]));
+    assert_eq!(cli::CountField::OsAbi.key(&binaries[0]), "SYSV");
+
+    let mut out = Vec::new();
+    output::write_counts("libc", &counts, None, true, &mut out).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(json, serde_json::json!({"field": "libc", "total": 4, "counts": {"glibc": 2, "musl": 1, "static": 1}}));
+}
+
+#[test]
+fn group_output_by_puts_a_header_before_each_group() {
+    let mut binaries: Vec<_> = [
+        ("usr/lib/libz.so", ElfBuilder::new().e_type(3).needed("libc.so.6").build()),
+        ("usr/bin/musl-app", ElfBuilder::new().interp("/lib/ld-musl-x86_64.so.1").build()),
+        ("usr/bin/app", ElfBuilder::new().interp("/lib64/ld-linux-x86-64.so.2").needed("libc.so.6").build()),
+    ]
+    .iter()
+    .map(|(path, data)| {
+        let mut info = common::scan_bytes("bin", data, &[]).unwrap();
+        info.file_path = PathBuf::from(path);
+        info
+    })
+    .collect();
+    binaries.push(common::scan_bytes("top", &ElfBuilder::new().build(), &[]).unwrap());
+    binaries[3].file_path = "top".into();
+
+    let report = |field: cli::CountField| {
+        let mut out = Vec::new();
+        HumanFormatter::new(0).group_by(Some(field)).format(&binaries, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    let text = report(cli::CountField::Libc);
+    let headers: Vec<&str> = text.lines().filter(|line| line.starts_with("libc: ")).collect();
+    assert_eq!(headers, ["libc: glibc (2)", "libc: musl (1)", "libc: static (1)"]);
+    // Numbered in report order, scan order kept within a group
+    let glibc = text.split_once("libc: glibc").unwrap().1.split_once("libc: musl").unwrap().0;
+    assert!(glibc.find("usr/lib/libz.so").unwrap() < glibc.find("usr/bin/app").unwrap(), "{}", glibc);
+    assert!(text.contains("Binary #4"), "{}", text);
+
+    let text = report(cli::CountField::Dir);
+    let headers: Vec<&str> = text.lines().filter(|line| line.starts_with("dir: ")).collect();
+    assert_eq!(headers, ["dir: . (1)", "dir: usr/bin (2)", "dir: usr/lib (1)"]);
+
+    // Compact lines are reordered, without headers
+    let mut out = Vec::new();
+    HumanFormatter::new(0).compact(true).group_by(Some(cli::CountField::Dir)).format(&binaries, &mut out).unwrap();
+    let paths: Vec<String> = String::from_utf8(out).unwrap().lines().map(|line| line.split('\t').next().unwrap().to_string()).collect();
+    assert_eq!(paths, ["top", "usr/bin/musl-app", "usr/bin/app", "usr/lib/libz.so"]);
+}
+
+#[test]
+fn first_rejecting_filter_explains_a_dropped_binary() {
+    let stripped = common::scan_bytes("stripped", &ElfBuilder::new().build(), &[]).unwrap();
+    let rejected_by = |flags: &[&str]| {
+        let args = common::args(flags);
+        cli::FILTERS.iter().find(|filter| filter.rejects(&stripped, &args)).map(|filter| filter.reason())
+    };
+
+    assert_eq!(rejected_by(&[]), Some("stripped-without-symbols (pass --show-stripped)"));
+    assert_eq!(rejected_by(&["--local-only"]), Some("local_only"));
+    assert_eq!(rejected_by(&["--arch", "arm64", "--show-stripped"]), Some("arch"));
+    assert_eq!(rejected_by(&["--show-stripped"]), None);
+}
+
+#[test]
+fn filtered_report_lists_up_to_the_limit() {
+    let mut report = output::FilteredReport::new(2);
+    for (path, filter) in [("a", "arch"), ("b", "local_only"), ("c", "arch")] {
+        report.record(std::path::Path::new(path), filter);
+    }
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json, serde_json::json!({
+        "total": 3,
+        "by_filter": {"arch": 2, "local_only": 1},
+        "binaries": [{"path": "a", "filter": "arch"}, {"path": "b", "filter": "local_only"}],
+        "truncated": true,
+    }));
+}
+
+#[test]
+fn deterministic_walks_in_sorted_order() {
+    let dir = tempfile::tempdir().unwrap();
+    for file in ["b/2", "a", "c", "b/1", "B"] {
+        let path = dir.path().join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, b"").unwrap();
+    }
+
+    let root = [dir.path().to_str().unwrap()];
+    let argv = ["symwalker", "--deterministic"].iter().chain(&root);
+    let files: Vec<_> = cli::candidate_files(&Args::parse_from(argv))
+        .map(|path| path.strip_prefix(dir.path()).unwrap().to_string_lossy().into_owned())
+        .collect();
+
+    assert_eq!(files, ["B", "a", "b/1", "b/2", "c"]);
+}
+
+#[test]
+fn globs_prune_excluded_directories_and_restrict_included_files() {
+    let dir = tempfile::tempdir().unwrap();
+    for file in ["bin/app", "lib/libz.so", "lib/test/libz.so", "test/fixture.so", "notes.txt"] {
+        let path = dir.path().join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, b"").unwrap();
+    }
+
+    let root = [dir.path().to_str().unwrap()];
+    let files = |flags: &[&str]| {
+        let argv = ["symwalker", "--deterministic"].iter().chain(flags).chain(&root);
+        cli::candidate_files(&Args::parse_from(argv))
+            .map(|path| path.strip_prefix(dir.path()).unwrap().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+    };
+
+    // Anchored, or `*` stopping at `/`, picks out one test/
+    assert_eq!(files(&["--exclude", "/test"]), ["bin/app", "lib/libz.so", "lib/test/libz.so", "notes.txt"]);
+    assert_eq!(files(&["--exclude", "*/test"]), ["bin/app", "lib/libz.so", "notes.txt", "test/fixture.so"]);
+    // Without a `/`, names match at any depth
+    assert_eq!(files(&["--exclude", "test", "--exclude", "*.txt"]), ["bin/app", "lib/libz.so"]);
+    assert_eq!(files(&["--include", "lib*.so*"]), ["lib/libz.so", "lib/test/libz.so"]);
+    assert_eq!(files(&["--include", "**/*.so"]), ["lib/libz.so", "lib/test/libz.so", "test/fixture.so"]);
+    // Exclude wins over include
+    assert_eq!(files(&["--include", "**/*.so", "--exclude", "lib/test"]), ["lib/libz.so", "test/fixture.so"]);
+
+    let err = Args::try_parse_from(["symwalker", "--exclude", "lib/[", "."]).unwrap_err().to_string();
+    assert!(err.contains("invalid glob 'lib/['"), "{}", err);
+}
+
+#[test]
+fn files_and_directories_scan_together_once_per_file() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("lib")).unwrap();
+    for (file, symbol) in [("app", "main"), ("lib/libz.so", "inflate"), ("lib/libm.so", "sin")] {
+        std::fs::write(dir.path().join(file), ElfBuilder::new().symbol(symbol).build()).unwrap();
+    }
+
+    let scan = |targets: &[&str]| {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_symwalker"))
+            .env_clear()
+            .arg("--json")
+            .args(targets.iter().map(|target| dir.path().join(target)))
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        let report: Vec<BinaryInfo> = serde_json::from_slice(&output.stdout).unwrap();
+        let mut files: Vec<_> = report.iter()
+            .map(|info| info.file_path.strip_prefix(dir.path()).unwrap().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        files
+    };
+
+    assert_eq!(scan(&["app"]), ["app"]);
+    assert_eq!(scan(&["app", "lib"]), ["app", "lib/libm.so", "lib/libz.so"]);
+    // lib/libz.so is reachable twice, app by another spelling
+    assert_eq!(scan(&["lib/libz.so", "lib", "lib/../app", "app"]), ["lib/../app", "lib/libm.so", "lib/libz.so"]);
+
+    let two = Args::parse_from(["symwalker", "--reverse-lookup", "0x1000", "a", "b"]);
+    assert!(cli::run(two).unwrap_err().to_string().contains("--reverse-lookup takes a single PATH, not 2"));
+}
+
+#[test]
+fn symbol_strategies_combine_allow_and_deny_lists() {
+    assert_eq!(common::args(&[]).symbol_strategies(), Strategy::ALL);
+    assert_eq!(
+        common::args(&["--no-symbol-strategy", "adjacent", "--no-symbol-strategy", "derived-data"]).symbol_strategies(),
+        [Strategy::BuildId, Strategy::Debuglink, Strategy::PathMirror, Strategy::DsymAdjacent],
+    );
+    assert_eq!(
+        common::args(&["--symbol-strategy", "debuglink", "--symbol-strategy", "build-id", "--no-symbol-strategy", "debuglink"]).symbol_strategies(),
+        [Strategy::BuildId],
+    );
+}
+
+#[test]
+fn disabled_strategies_find_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    let binary = dir.path().join("app");
+    std::fs::write(&binary, ElfBuilder::new().build_id(&[1; 20]).build()).unwrap();
+    std::fs::write(dir.path().join("app.debug"), ElfBuilder::new().build_id(&[1; 20]).build()).unwrap();
+
+    let info = common::scan_path(&binary, &[]).unwrap();
+    assert_eq!(info.debug_file_path, Some(dir.path().join("app.debug")));
+    assert_eq!(info.debug_file_source, Some(Strategy::Adjacent));
+
+    let info = common::scan_path(&binary, &["--no-symbol-strategy", "adjacent"]).unwrap();
+    assert_eq!(info.debug_file_path, None);
+    assert_eq!(info.debug_file_source, None);
+}
+
+#[test]
+fn resolution_method_names_where_symbols_came_from() {
+    let dir = tempfile::tempdir().unwrap();
+    let binary = dir.path().join("app");
+    std::fs::write(&binary, ElfBuilder::new().build_id(&[1; 20]).build()).unwrap();
+    assert_eq!(common::scan_path(&binary, &[]).unwrap().resolution_method, "none");
+
+    std::fs::write(dir.path().join("app.debug"), ElfBuilder::new().build_id(&[1; 20]).build()).unwrap();
+    assert_eq!(common::scan_path(&binary, &[]).unwrap().resolution_method, "adjacent");
+
+    // Embedded debug info wins over a debug file next to it
+    std::fs::write(&binary, ElfBuilder::new().build_id(&[1; 20]).section(".debug_info", &[0; 8]).build()).unwrap();
+    assert_eq!(common::scan_path(&binary, &[]).unwrap().resolution_method, "embedded");
+
+    let remote = BinaryInfo { debuginfod_available: Some(true), ..Default::default() };
+    assert_eq!(remote.resolution(), "remote");
+}
+
+#[test]
+fn path_mirror_finds_debug_files_without_a_debuglink() {
+    let sysroot = tempfile::tempdir().unwrap();
+    let binary = sysroot.path().join("usr/bin/app");
+    let debug_file = sysroot.path().join("usr/lib/debug/usr/bin/app.debug");
+    std::fs::create_dir_all(binary.parent().unwrap()).unwrap();
+    std::fs::create_dir_all(debug_file.parent().unwrap()).unwrap();
+    // Stripped after the debuglink was added: no .gnu_debuglink left
+    std::fs::write(&binary, ElfBuilder::new().build_id(&[1; 20]).build()).unwrap();
+    std::fs::write(&debug_file, ElfBuilder::new().build_id(&[1; 20]).build()).unwrap();
+
+    let sysroot_flag = ["--sysroot", sysroot.path().to_str().unwrap()];
+    let info = common::scan_path(&binary, &sysroot_flag).unwrap();
+    assert_eq!(info.gnu_debuglink, None);
+    assert_eq!(info.debug_file_path, Some(std::fs::canonicalize(&debug_file).unwrap()));
+    assert_eq!(info.debug_file_source, Some(Strategy::PathMirror));
+
+    // The mirrored path alone isn't enough when the build-ids disagree
+    std::fs::write(&debug_file, ElfBuilder::new().build_id(&[2; 20]).build()).unwrap();
+    let info = common::scan_path(&binary, &sysroot_flag).unwrap();
+    assert_eq!(info.debug_file_path, None);
+
+    let info = common::scan_path(&binary, &[]).unwrap();
+    assert_eq!(info.debug_file_path, None);
+}
+
+#[test]
+fn resolve_timeout_reports_no_debug_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let binary = dir.path().join("app");
+    std::fs::write(&binary, ElfBuilder::new().build_id(&[3; 20]).build()).unwrap();
+    std::fs::write(dir.path().join("app.debug"), ElfBuilder::new().build_id(&[3; 20]).build()).unwrap();
+
+    let info = common::scan_path(&binary, &["--resolve-timeout", "60"]).unwrap();
+    assert_eq!(info.debug_file_path, Some(dir.path().join("app.debug")));
+    assert!(info.warnings.is_empty(), "{:?}", info.warnings);
+
+    let info = common::scan_path(&binary, &["--resolve-timeout", "0"]).unwrap();
+    assert_eq!(info.debug_file_path, None);
+    let codes: Vec<&str> = info.warnings.iter().map(|w| w.code.as_str()).collect();
+    assert_eq!(codes, ["resolve-timeout"]);
+}
+
+#[test]
+fn sample_is_reproducible_and_keeps_stream_order() {
+    let draw = |seed| {
+        let mut sample = cli::Sample::new(5, seed);
+        (0..1000).for_each(|item| sample.offer(item));
+        assert_eq!(sample.seen(), 1000);
+        sample.into_vec()
+    };
+
+    let first = draw(42);
+    assert_eq!(first, draw(42));
+    assert_ne!(first, draw(43));
+    assert_eq!(first.len(), 5);
+    assert!(first.windows(2).all(|pair| pair[0] < pair[1]));
+    // Not just the head of the stream, as --limit-style truncation would give
+    assert!(first.iter().any(|&item| item >= 5));
+
+    let mut small = cli::Sample::new(5, 1);
+    (0..3).for_each(|item| small.offer(item));
+    assert_eq!(small.into_vec(), [0, 1, 2]);
+
+    assert!(Args::try_parse_from(["symwalker", "--seed", "1", "."]).is_err());
+}
+
+#[test]
+fn relative_paths_are_under_the_recorded_root() {
+    let sysroot = tempfile::tempdir().unwrap();
+    let bin_dir = sysroot.path().join("usr/bin");
+    let debug_file = sysroot.path().join("usr/lib/debug/usr/bin/app.debug");
+    std::fs::create_dir_all(&bin_dir).unwrap();
+    std::fs::create_dir_all(debug_file.parent().unwrap()).unwrap();
+    std::fs::write(bin_dir.join("app"), ElfBuilder::new().build_id(&[1; 20]).build()).unwrap();
+    std::fs::write(&debug_file, ElfBuilder::new().build_id(&[1; 20]).build()).unwrap();
+
+    // Scanning usr/bin leaves the mirrored debug file outside the root
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_symwalker"))
+        .args(["--json", "--relative-paths", "--sysroot"])
+        .args([sysroot.path(), &bin_dir])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    let root = std::fs::canonicalize(&bin_dir).unwrap();
+    assert_eq!(report["scan_root"], root.to_str().unwrap());
+    let mut info: symwalker::binary::BinaryInfo = serde_json::from_value(report["binaries"][0].clone()).unwrap();
+    assert_eq!(info.file_path, std::path::Path::new("app"));
+    assert_eq!(info.debug_file_path, Some(std::fs::canonicalize(&debug_file).unwrap()));
+    assert_eq!(info.external_paths, ["debug_file_path"]);
+
+    symwalker::relative::resolve(&mut info, &root);
+    assert_eq!(info.file_path, root.join("app"));
+    assert_eq!(info.debug_file_path, Some(std::fs::canonicalize(&debug_file).unwrap()));
+    assert!(info.external_paths.is_empty());
+}
+
+#[test]
+fn closed_stdout_ends_the_reports_but_not_the_run() {
+    use std::io::Read;
+    use std::process::{Command, Stdio};
+
+    // Far more JSON than a pipe buffers, so writing must hit the closed pipe
+    let scan = tempfile::tempdir().unwrap();
+    for i in 0..200u8 {
+        std::fs::write(scan.path().join(format!("app{}", i)), ElfBuilder::new().build_id(&[i; 20]).build()).unwrap();
+    }
+
+    for (flags, copied) in [(&[][..], true), (&["--skip-output-on-broken-pipe"][..], false)] {
+        let out = tempfile::tempdir().unwrap();
+        let mut child = Command::new(env!("CARGO_BIN_EXE_symwalker"))
+            .args(["--json", "--show-stripped", "--copy-binaries", "-o"])
+            .arg(out.path())
+            .args(flags)
+            .arg(scan.path())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut start = [0; 1000];
+        child.stdout.take().unwrap().read_exact(&mut start).unwrap();
+
+        let output = child.wait_with_output().unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(141), "{}", stderr);
+        assert!(stderr.is_empty(), "{}", stderr);
+        assert_eq!(out.path().join("manifest.json").exists(), copied);
+        assert_eq!(out.path().join("app7").exists(), copied);
+    }
+}
+
+#[test]
+fn a_saved_json_report_is_reformatted_instead_of_scanned() {
+    use std::process::Command;
+
+    let (scan, out) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    std::fs::write(scan.path().join("app"), ElfBuilder::new().build_id(&[1; 20]).build()).unwrap();
+    std::fs::write(scan.path().join("lib.so"), ElfBuilder::new().build_id(&[2; 20]).symbol("api").build()).unwrap();
+    let report = out.path().join("scan.json");
+    cli::run(Args::parse_from(["symwalker", "--show-stripped", "--json-array-file", report.to_str().unwrap(),
+        scan.path().to_str().unwrap()])).unwrap();
+    let saved = std::fs::read(&report).unwrap();
+
+    let reformat = |flags: &[&str]| Command::new(env!("CARGO_BIN_EXE_symwalker")).args(flags).arg(&report).output().unwrap();
+    let json = reformat(&["--json"]);
+    assert!(json.status.success(), "{}", String::from_utf8_lossy(&json.stderr));
+    assert_eq!(output::read_report(&json.stdout).unwrap().unwrap().len(), 2);
+    assert_eq!(serde_json::from_slice::<serde_json::Value>(&json.stdout).unwrap(),
+        serde_json::from_slice::<serde_json::Value>(&saved).unwrap());
+
+    let human = reformat(&["--arch", "x86_64"]);
+    let lines = String::from_utf8(human.stdout).unwrap();
+    assert_eq!(lines.lines().count(), 2, "{}", lines);
+    assert!(String::from_utf8(reformat(&["--arch", "aarch64"]).stdout).unwrap().is_empty());
+
+    let html = out.path().join("scan.html");
+    cli::run(Args::parse_from(["symwalker", "--count-by", "arch", "--html-report", html.to_str().unwrap(),
+        report.to_str().unwrap()])).unwrap();
+    assert!(std::fs::read_to_string(&html).unwrap().contains("lib.so"));
+
+    // Other files are scanned like any PATH
+    let notes = out.path().join("notes.txt");
+    std::fs::write(&notes, "not a report").unwrap();
+    assert_eq!(cli::run(Args::parse_from(["symwalker", "--json", notes.to_str().unwrap()])).unwrap(), std::process::ExitCode::SUCCESS);
+    std::fs::write(&notes, r#"{"summary": {}, "truncated": true}"#).unwrap();
+    let error = cli::run(Args::parse_from(["symwalker", notes.to_str().unwrap()])).unwrap_err();
+    assert!(format!("{:#}", error).contains("only has a summary"), "{:#}", error);
+}
+
+#[test]
+fn fail_on_warning_exits_after_the_full_report() {
+    use std::process::ExitCode;
+
+    let scan = tempfile::tempdir().unwrap();
+    let report = scan.path().join("report.json");
+    let app = scan.path().join("bin");
+    std::fs::create_dir(&app).unwrap();
+    let status = |flags: &[&str]| {
+        let argv = ["symwalker", "--json-array-file", report.to_str().unwrap()].into_iter()
+            .chain(flags.iter().copied())
+            .chain([app.to_str().unwrap()]);
+        cli::run(Args::parse_from(argv)).unwrap()
+    };
+
+    std::fs::write(app.join("app"), ElfBuilder::new().symbol("main").build()).unwrap();
+    assert_eq!(status(&["--fail-on-warning"]), ExitCode::SUCCESS);
+
+    std::fs::write(app.join("app"), ElfBuilder::new().symbol("main").interp("/nonexistent/ld.so").build()).unwrap();
+    assert_eq!(status(&[]), ExitCode::SUCCESS);
+    assert_eq!(status(&["--fail-on-warning"]), ExitCode::from(5));
+    let reported = output::read_report(&std::fs::read(&report).unwrap()).unwrap().unwrap();
+    assert_eq!(reported[0].warnings[0].code, "missing-interpreter");
+
+    // A --fail-if condition takes precedence
+    std::fs::write(app.join("app"), ElfBuilder::new().symbol("main").interp("/nonexistent/ld.so").rpath("lib").build()).unwrap();
+    assert_eq!(status(&["--fail-on-warning", "--fail-if", "insecure-rpath"]), ExitCode::from(3));
+
+    // Stripped, so filtered out of the report, and still counted
+    std::fs::write(app.join("app"), ElfBuilder::new().interp("/nonexistent/ld.so").build()).unwrap();
+    assert_eq!(status(&[]), ExitCode::SUCCESS);
+    assert_eq!(status(&["--fail-on-warning"]), ExitCode::from(5));
+    assert!(output::read_report(&std::fs::read(&report).unwrap()).unwrap().unwrap().is_empty());
+}
+
+#[test]
+fn fail_if_holds_for_binaries_the_filters_hide() {
+    use std::process::ExitCode;
+
+    let scan = tempfile::tempdir().unwrap();
+    let signatures = scan.path().join("signatures.toml");
+    std::fs::write(&signatures, "[[signature]]\nname = \"endbr64-prologue\"\nbytes = [\"f30f1efa4883ec08\"]\n").unwrap();
+    let app = scan.path().join("bin");
+    std::fs::create_dir(&app).unwrap();
+    // Stripped, so left out of the report without --show-stripped
+    std::fs::write(app.join("ls"), ElfBuilder::new().code_section(".text", b"\xf3\x0f\x1e\xfa\x48\x83\xec\x08").rpath("lib").build()).unwrap();
+    let status = |flags: &[&str]| {
+        let argv = ["symwalker", "--json", "--scan-signatures", signatures.to_str().unwrap()].into_iter()
+            .chain(flags.iter().copied())
+            .chain([app.to_str().unwrap()]);
+        cli::run(Args::parse_from(argv)).unwrap()
+    };
+
+    assert_eq!(status(&[]), ExitCode::SUCCESS);
+    assert_eq!(status(&["--fail-if", "signature-match"]), ExitCode::from(3));
+    assert_eq!(status(&["--fail-if", "insecure-rpath"]), ExitCode::from(3));
+    assert_eq!(status(&["--fail-if", "signature-match", "--show-stripped"]), ExitCode::from(3));
+}
@@ -0,0 +1,217 @@
+//! `--cluster` over a corpus whose source packages are known, and the
+//! source roots `--resolve-source-dirs` lists
+
+mod common;
+
+use std::path::PathBuf;
+use std::process::Command;
+
+use symwalker::binary::BinaryInfo;
+use symwalker::cluster::{self, Signal};
+use symwalker::source_dirs;
+use symwalker::elf::PackageInfo;
+use symwalker::output::{JsonFormatter, OutputFormatter};
+use symwalker::testutil::ElfBuilder;
+
+fn binary(path: &str) -> BinaryInfo {
+    BinaryInfo { file_path: PathBuf::from(path), ..Default::default() }
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+fn paths(values: &[&str]) -> Vec<PathBuf> {
+    values.iter().map(PathBuf::from).collect()
+}
+
+/// openssl: libssl and libcrypto share a build tree, the `openssl` tool only
+/// a producer and a source file, an older libssl only its soname family.
+/// zlib: two binaries from one package note. The rest stand alone, though
+/// they share generic signals (a shallow build root, the same compiler).
+fn corpus() -> Vec<BinaryInfo> {
+    let gcc = strings(&["GNU C17 12.2.0 -O2"]);
+    vec![
+        BinaryInfo {
+            soname: Some("libssl.so.3".to_string()),
+            comp_dirs: strings(&["/build/openssl-3.0.2/ssl", "/build/openssl-3.0.2/crypto"]),
+            ..binary("/usr/lib/libssl.so.3")
+        },
+        BinaryInfo {
+            soname: Some("libcrypto.so.3".to_string()),
+            comp_dirs: strings(&["/build/openssl-3.0.2/crypto"]),
+            producers: gcc.clone(),
+            source_files: strings(&["crypto/mem.c", "crypto/sha/sha256.c"]),
+            ..binary("/usr/lib/libcrypto.so.3")
+        },
+        BinaryInfo {
+            producers: gcc.clone(),
+            source_files: strings(&["apps/openssl.c", "crypto/mem.c"]),
+            ..binary("/usr/bin/openssl")
+        },
+        BinaryInfo {
+            soname: Some("libssl.so.1.1".to_string()),
+            ..binary("/opt/legacy/libssl.so.1.1")
+        },
+        BinaryInfo {
+            package_info: Some(PackageInfo { name: Some("zlib".to_string()), ..Default::default() }),
+            ..binary("/usr/lib/libz.so.1")
+        },
+        BinaryInfo {
+            package_info: Some(PackageInfo { name: Some("zlib".to_string()), ..Default::default() }),
+            ..binary("/usr/bin/minigzip")
+        },
+        BinaryInfo {
+            comp_dirs: strings(&["/build"]),
+            producers: gcc.clone(),
+            source_files: strings(&["main.c"]),
+            ..binary("/usr/bin/true")
+        },
+        BinaryInfo {
+            comp_dirs: strings(&["/build"]),
+            producers: gcc,
+            source_files: strings(&["ls.c"]),
+            ..binary("/usr/bin/ls")
+        },
+    ]
+}
+
+#[test]
+fn clusters_match_the_source_packages() {
+    let clusters = cluster::clusters(&corpus());
+
+    let groups: Vec<(&str, &[PathBuf])> = clusters.iter()
+        .map(|cluster| (cluster.name.as_str(), cluster.members.as_slice()))
+        .collect();
+    assert_eq!(groups, [
+        ("openssl-3.0.2", paths(&["/opt/legacy/libssl.so.1.1", "/usr/bin/openssl", "/usr/lib/libcrypto.so.3", "/usr/lib/libssl.so.3"]).as_slice()),
+        ("zlib", paths(&["/usr/bin/minigzip", "/usr/lib/libz.so.1"]).as_slice()),
+    ]);
+}
+
+#[test]
+fn every_cluster_lists_its_matched_signals() {
+    let clusters = cluster::clusters(&corpus());
+    let openssl = &clusters[0];
+
+    let evidence: Vec<(Signal, &str, Vec<PathBuf>)> = openssl.evidence.iter()
+        .map(|e| (e.signal, e.value.as_str(), e.members.clone()))
+        .collect();
+    assert_eq!(evidence, [
+        (Signal::CompDir, "/build/openssl-3.0.2", paths(&["/usr/lib/libcrypto.so.3", "/usr/lib/libssl.so.3"])),
+        (Signal::Producer, "GNU C17 12.2.0 -O2 (shared source crypto/mem.c)", paths(&["/usr/bin/openssl", "/usr/lib/libcrypto.so.3"])),
+        (Signal::SonameFamily, "libssl", paths(&["/opt/legacy/libssl.so.1.1", "/usr/lib/libssl.so.3"])),
+    ]);
+    // The cluster is named after its strongest non-producer signal
+    assert_eq!(openssl.name, "openssl-3.0.2");
+    assert_eq!(clusters[1].evidence[0].signal, Signal::Package);
+}
+
+#[test]
+fn clustering_ignores_input_order() {
+    let mut reversed = corpus();
+    reversed.reverse();
+    assert_eq!(cluster::clusters(&reversed), cluster::clusters(&corpus()));
+}
+
+#[test]
+fn signal_normalization() {
+    assert_eq!(cluster::soname_family("libssl.so.1.1").as_deref(), Some("libssl"));
+    assert_eq!(cluster::soname_family("/usr/lib/libssl.48.dylib").as_deref(), Some("libssl"));
+    assert_eq!(cluster::soname_family("libfoo-2.so.0").as_deref(), Some("libfoo"));
+    assert_eq!(cluster::soname_family("libstdc++.so.6").as_deref(), Some("libstdc++"));
+
+    assert_eq!(cluster::source_root(&strings(&["/build/pkg-1.0/src/lib"])).as_deref(), Some("/build/pkg-1.0"));
+    assert_eq!(cluster::source_root(&strings(&["/home/dev/pkg/src", "/home/dev/pkg/lib"])).as_deref(), Some("/home/dev/pkg"));
+    assert_eq!(cluster::source_root(&strings(&["/build/a", "/build/b"])), None);
+    assert_eq!(cluster::source_root(&strings(&["./obj-x86_64-linux-gnu"])), None);
+    assert_eq!(cluster::source_root(&[]), None);
+}
+
+#[test]
+fn soname_is_read_from_the_dynamic_section() {
+    let info = common::scan_bytes("libfoo.so.2", &ElfBuilder::new().e_type(3).soname("libfoo.so.2").build(), &[]).unwrap();
+    assert_eq!(info.soname.as_deref(), Some("libfoo.so.2"));
+}
+
+/// Binary with one DWARF 4 compile unit carrying name, producer and comp_dir
+fn compiled(name: &str, producer: &str, comp_dir: &str) -> Vec<u8> {
+    let (abbrev, info) = common::compile_unit_dwarf(name, producer, comp_dir);
+    ElfBuilder::new()
+        .section(".text", &[0xc3])
+        .section(".debug_abbrev", &abbrev)
+        .section(".debug_info", &info)
+        .build()
+}
+
+#[test]
+fn dwarf_signals_cluster_scanned_binaries() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("openssl"), compiled("apps/openssl.c", "GNU C17", "/build/openssl-3.0.2/apps")).unwrap();
+    std::fs::write(dir.path().join("c_rehash"), compiled("apps/rehash.c", "GNU C17", "/build/openssl-3.0.2/apps")).unwrap();
+    std::fs::write(dir.path().join("gzip"), compiled("gzip.c", "GNU C17", "/build/gzip-1.12")).unwrap();
+
+    let info = common::scan_path(&dir.path().join("gzip"), &["--dwarf"]).unwrap();
+    assert_eq!(info.source_files, ["gzip.c"]);
+    assert_eq!(info.producers, ["GNU C17"]);
+    assert_eq!(info.comp_dirs, ["/build/gzip-1.12"]);
+
+    let binaries: Vec<BinaryInfo> = ["c_rehash", "gzip", "openssl"].iter()
+        .map(|name| common::scan_path(&dir.path().join(name), &["--dwarf"]).unwrap())
+        .collect();
+    let clusters = cluster::clusters(&binaries);
+    assert_eq!(clusters.len(), 1);
+    assert_eq!(clusters[0].name, "openssl-3.0.2");
+    assert_eq!(clusters[0].members.len(), 2);
+
+    let mut json = Vec::new();
+    JsonFormatter::new().with_clusters(clusters).format(&binaries, &mut json).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(json["binaries"].as_array().unwrap().len(), 3);
+    assert_eq!(json["clusters"][0]["evidence"][0]["signal"], "comp-dir");
+}
+
+#[test]
+fn source_dirs_are_counted_per_source_root() {
+    let corpus = vec![
+        BinaryInfo { comp_dirs: strings(&["/build/openssl-3.0.2/ssl", "/build/openssl-3.0.2/crypto"]), ..binary("/usr/lib/libssl.so.3") },
+        BinaryInfo { comp_dirs: strings(&["/build/openssl-3.0.2/crypto", "/usr/src/zlib"]), ..binary("/usr/lib/libcrypto.so.3") },
+        // Prefix-mapped and shallow directories are kept as they are
+        BinaryInfo { comp_dirs: strings(&[".", "/build"]), ..binary("/usr/bin/tool") },
+        // No DWARF read: nothing to contribute
+        binary("/usr/bin/stripped"),
+    ];
+    let dirs: Vec<(String, usize)> = source_dirs::source_dirs(&corpus).into_iter().map(|dir| (dir.path, dir.binaries)).collect();
+    assert_eq!(dirs, [
+        ("/build/openssl-3.0.2".to_string(), 2),
+        (".".to_string(), 1),
+        ("/build".to_string(), 1),
+        ("/usr/src/zlib".to_string(), 1),
+    ]);
+}
+
+#[test]
+fn resolve_source_dirs_lists_the_scanned_roots() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("openssl"), compiled("apps/openssl.c", "GNU C17", "/build/openssl-3.0.2/apps")).unwrap();
+    std::fs::write(dir.path().join("c_rehash"), compiled("apps/rehash.c", "GNU C17", "/build/openssl-3.0.2/tools")).unwrap();
+    std::fs::write(dir.path().join("gzip"), compiled("gzip.c", "GNU C17", "/build/gzip-1.12")).unwrap();
+
+    let run = |flags: &[&str]| Command::new(env!("CARGO_BIN_EXE_symwalker")).env_clear().args(flags).arg(dir.path()).output().unwrap();
+    let output = run(&["--json", "--dwarf", "--resolve-source-dirs"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["source_dirs"], serde_json::json!([
+        {"path": "/build/openssl-3.0.2", "binaries": 2},
+        {"path": "/build/gzip-1.12", "binaries": 1},
+    ]));
+
+    let output = run(&["--dwarf", "--resolve-source-dirs"]);
+    let text = String::from_utf8_lossy(&output.stdout);
+    let section = text.split_once("Source Directories").map(|(_, section)| section).unwrap_or_else(|| panic!("{}", text));
+    assert!(section.contains("/build/openssl-3.0.2 (2 binaries)"), "{}", section);
+    assert!(section.contains("/build/gzip-1.12 (1 binary)"), "{}", section);
+
+    // DWARF has to be read for there to be anything to list
+    assert!(!run(&["--resolve-source-dirs"]).status.success());
+}
@@ -0,0 +1,103 @@
+//! Shared helpers for the integration tests
+
+#![allow(dead_code)]
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use clap::Parser;
+use serde_json::Value;
+use symwalker::binary::{scan_binary, BinaryInfo, ScanContext};
+use symwalker::cli::Args;
+
+/// Run the symwalker binary on `dir` with `flags` and a clean environment
+pub fn symwalker(dir: &Path, flags: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_symwalker"))
+        .env_clear()
+        .args(flags)
+        .arg(dir)
+        .output()
+        .unwrap()
+}
+
+/// Parse command-line flags as the binary would; the directory is a placeholder
+pub fn args(flags: &[&str]) -> Args {
+    let argv = ["symwalker"].iter().chain(flags).chain(&["."]);
+    Args::parse_from(argv)
+}
+
+/// Write `data` into a fresh temp dir as `name` and scan it
+pub fn scan_bytes(name: &str, data: &[u8], flags: &[&str]) -> anyhow::Result<BinaryInfo> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join(name);
+    std::fs::write(&path, data)?;
+    scan_path(&path, flags)
+}
+
+pub fn scan_path(path: &Path, flags: &[&str]) -> anyhow::Result<BinaryInfo> {
+    let args = args(flags);
+    let ctx = ScanContext::new(&args)?;
+    scan_binary(path, &args, &ctx)
+}
+
+/// JSON for a scan result with host-dependent fields replaced, for snapshots
+pub fn snapshot_json(info: &BinaryInfo) -> Value {
+    let mut value = serde_json::to_value(info).unwrap();
+    let object = value.as_object_mut().unwrap();
+    
+    let name = info.file_path.file_name().unwrap().to_string_lossy().into_owned();
+    object.insert("file_path".to_string(), Value::String(name));
+    object.insert("file_modified".to_string(), Value::String("[mtime]".to_string()));
+    object.insert("file_modified_epoch".to_string(), Value::String("[mtime]".to_string()));
+    
+    value
+}
+
+/// `.debug_abbrev` and `.debug_info` for one DWARF 4 compile unit carrying
+/// name, producer and comp_dir
+pub fn compile_unit_dwarf(name: &str, producer: &str, comp_dir: &str) -> (Vec<u8>, Vec<u8>) {
+    const DW_TAG_COMPILE_UNIT: u8 = 0x11;
+    const DW_AT_NAME: u8 = 0x03;
+    const DW_AT_COMP_DIR: u8 = 0x1b;
+    const DW_AT_PRODUCER: u8 = 0x25;
+    const DW_FORM_STRING: u8 = 0x08;
+
+    let abbrev = vec![
+        1, DW_TAG_COMPILE_UNIT, 0,
+        DW_AT_NAME, DW_FORM_STRING, DW_AT_PRODUCER, DW_FORM_STRING, DW_AT_COMP_DIR, DW_FORM_STRING, 0, 0,
+        0,
+    ];
+    let mut body = vec![4, 0, 0, 0, 0, 0, 8, 1]; // version, abbrev offset, address size
+    for value in [name, producer, comp_dir] {
+        body.extend(value.as_bytes());
+        body.push(0);
+    }
+    let info = [(body.len() as u32).to_le_bytes().to_vec(), body].concat();
+    (abbrev, info)
+}
+
+/// `.debug_abbrev` and `.debug_info` for one DWARF 4 unit with a single
+/// 0x40-byte function
+pub fn one_function_dwarf() -> (Vec<u8>, Vec<u8>) {
+    const DW_TAG_COMPILE_UNIT: u8 = 0x11;
+    const DW_TAG_SUBPROGRAM: u8 = 0x2e;
+    const DW_AT_NAME: u8 = 0x03;
+    const DW_AT_LOW_PC: u8 = 0x11;
+    const DW_AT_HIGH_PC: u8 = 0x12;
+    const DW_FORM_ADDR: u8 = 0x01;
+    const DW_FORM_DATA4: u8 = 0x06;
+    const DW_FORM_STRING: u8 = 0x08;
+
+    let abbrev = vec![
+        1, DW_TAG_COMPILE_UNIT, 1, 0, 0,
+        2, DW_TAG_SUBPROGRAM, 0, DW_AT_NAME, DW_FORM_STRING, DW_AT_LOW_PC, DW_FORM_ADDR, DW_AT_HIGH_PC, DW_FORM_DATA4, 0, 0,
+        0,
+    ];
+    let mut body = vec![4, 0, 0, 0, 0, 0, 8, 1, 2]; // version, abbrev offset, address size
+    body.extend(b"main\0");
+    body.extend(0x1000u64.to_le_bytes());
+    body.extend(0x40u32.to_le_bytes());
+    body.push(0);
+    let info = [(body.len() as u32).to_le_bytes().to_vec(), body].concat();
+    (abbrev, info)
+}
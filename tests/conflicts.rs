@@ -0,0 +1,74 @@
+//! Libraries in one tree sharing a SONAME (`soname-conflict`)
+
+mod common;
+
+use std::path::Path;
+
+use symwalker::testutil::ElfBuilder;
+
+/// A firmware-like tree: two builds of libfoo.so.1 in different directories,
+/// and two copies of the same libbar.so.2 build
+fn tree(root: &Path) {
+    let library = |soname: &str, id: u8, versions: &[&str]| {
+        let builder = ElfBuilder::new().e_type(3).soname(soname).build_id(&[id; 20]);
+        versions.iter().fold(builder, |builder, version| builder.version_definition(version)).build()
+    };
+    for (path, data) in [
+        ("lib/libfoo.so.1", library("libfoo.so.1", 0x11, &["LIBFOO_1.0"])),
+        ("opt/vendor/lib/libfoo.so.1", library("libfoo.so.1", 0x22, &["LIBFOO_1.0", "LIBFOO_1.1"])),
+        ("lib/libbar.so.2", library("libbar.so.2", 0x33, &[])),
+        ("usr/lib/libbar.so.2", library("libbar.so.2", 0x33, &[])),
+    ] {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, data).unwrap();
+    }
+}
+
+#[test]
+fn conflicting_sonames_are_listed_and_can_fail_the_run() {
+    let dir = tempfile::tempdir().unwrap();
+    tree(dir.path());
+
+    // The libraries are stripped, so the default filter drops them; they
+    // still count
+    let output = common::symwalker(dir.path(), &["--json", "--show-conflicts", "--relative-paths"]);
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["binaries"].as_array().unwrap().len(), 0);
+    assert_eq!(report["conflicts"], serde_json::json!([{
+        "soname": "libfoo.so.1",
+        "libraries": [
+            {"path": "lib/libfoo.so.1", "build_id": "11".repeat(20), "version_definitions": ["LIBFOO_1.0"]},
+            {"path": "opt/vendor/lib/libfoo.so.1", "build_id": "22".repeat(20), "version_definitions": ["LIBFOO_1.0", "LIBFOO_1.1"]},
+        ],
+    }]));
+    assert_eq!(common::symwalker(dir.path(), &["--json", "--fail-if", "soname-conflict"]).status.code(), Some(3));
+
+    // Reported libraries carry the warning; plain --json stays an array
+    let output = common::symwalker(dir.path(), &["--json", "--show-stripped"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    for binary in report.as_array().unwrap() {
+        let conflicted = binary["warnings"].as_array().unwrap().iter().any(|warning| warning["code"] == "soname-conflict");
+        assert_eq!(conflicted, binary["soname"] == "libfoo.so.1", "{}", binary);
+    }
+
+    std::fs::remove_file(dir.path().join("opt/vendor/lib/libfoo.so.1")).unwrap();
+    let output = common::symwalker(dir.path(), &["--json", "--fail-if", "soname-conflict"]);
+    assert!(output.status.success());
+    assert!(serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap().is_array());
+}
+
+#[test]
+fn human_output_has_a_conflicts_section() {
+    let dir = tempfile::tempdir().unwrap();
+    tree(dir.path());
+
+    let output = common::symwalker(dir.path(), &["--relative-paths"]);
+    let text = String::from_utf8_lossy(&output.stdout);
+    let section = text.split_once("Conflicts").map(|(_, section)| section).unwrap_or_else(|| panic!("{}", text));
+    assert!(section.contains("libfoo.so.1 (2 libraries)"), "{}", section);
+    assert!(section.contains(&format!("opt/vendor/lib/libfoo.so.1 {}", "22".repeat(20))), "{}", section);
+    assert!(section.contains("defines LIBFOO_1.0, LIBFOO_1.1"), "{}", section);
+    assert!(!section.contains("libbar"), "{}", section);
+}
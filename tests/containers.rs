@@ -0,0 +1,106 @@
+//! AppImage and .NET single-file bundle detection and payload listing
+
+mod common;
+
+use symwalker::container::{ContainerFormat, EmbeddedFile};
+use symwalker::testutil::{
+    bundle_placeholder, DotnetBundleBuilder, ElfBuilder, SquashfsBuilder, BUNDLE_ASSEMBLY, BUNDLE_NATIVE_BINARY,
+};
+
+/// Type 2 AppImage: runtime ELF with the squashfs image appended
+fn appimage(squashfs: SquashfsBuilder) -> Vec<u8> {
+    [ElfBuilder::new().appimage(2).build(), squashfs.build()].concat()
+}
+
+fn embedded(files: &[(&str, u64)]) -> Vec<EmbeddedFile> {
+    files.iter().map(|&(path, size)| EmbeddedFile { path: path.to_string(), size }).collect()
+}
+
+#[test]
+fn appimage_libraries_are_listed_with_scan_archives() {
+    let squashfs = SquashfsBuilder::new()
+        .compress_metadata()
+        .file("AppRun", 120)
+        .file("usr/bin/app", 300_000)
+        .file("usr/lib/libfoo.so.1", 4096)
+        .file("usr/lib/plugins/libbar.so", 512)
+        .file("usr/share/doc/app/README", 10);
+    let data = appimage(squashfs);
+
+    let info = common::scan_bytes("App-x86_64.AppImage", &data, &["--scan-archives"]).unwrap();
+
+    assert_eq!(info.container_format, Some(ContainerFormat::Appimage));
+    assert_eq!(info.payload_files, Some(5));
+    assert_eq!(info.embedded_libraries, embedded(&[
+        ("usr/lib/libfoo.so.1", 4096),
+        ("usr/lib/plugins/libbar.so", 512),
+    ]));
+    assert!(info.warnings.is_empty(), "{:?}", info.warnings);
+}
+
+#[test]
+fn payload_is_only_read_with_scan_archives() {
+    let data = appimage(SquashfsBuilder::new().file("usr/lib/libfoo.so", 1));
+
+    let info = common::scan_bytes("App.AppImage", &data, &[]).unwrap();
+
+    assert_eq!(info.container_format, Some(ContainerFormat::Appimage));
+    assert_eq!(info.payload_files, None);
+    assert!(info.embedded_libraries.is_empty());
+    assert_eq!(info.architecture, "x86_64");
+}
+
+#[test]
+fn unsupported_squashfs_compression_is_a_warning() {
+    let data = appimage(SquashfsBuilder::new().compression(4).file("AppRun", 1));
+
+    let info = common::scan_bytes("App.AppImage", &data, &["--scan-archives"]).unwrap();
+
+    assert_eq!(info.container_format, Some(ContainerFormat::Appimage));
+    assert_eq!(info.payload_files, None);
+    let warning = info.warnings.iter().find(|w| w.code == "unreadable-payload").unwrap();
+    assert_eq!(warning.message, "AppImage payload: xz-compressed squashfs is not supported");
+}
+
+#[test]
+fn dotnet_bundle_reports_native_libraries() {
+    let host = ElfBuilder::new().e_type(3).section(".data", &bundle_placeholder()).build();
+    let data = DotnetBundleBuilder::new(host)
+        .file("app.dll", BUNDLE_ASSEMBLY, &[0; 64])
+        .file("System.Private.CoreLib.dll", BUNDLE_ASSEMBLY, &[0; 32])
+        .file("libSystem.Native.so", BUNDLE_NATIVE_BINARY, &[0; 16])
+        .file("runtimes\\linux-x64\\native\\libe_sqlite3.so", BUNDLE_NATIVE_BINARY, &[0; 8])
+        .build();
+
+    let info = common::scan_bytes("app", &data, &["--scan-archives"]).unwrap();
+
+    assert_eq!(info.container_format, Some(ContainerFormat::DotnetBundle));
+    assert_eq!(info.payload_files, Some(4));
+    assert_eq!(info.embedded_libraries, embedded(&[
+        ("libSystem.Native.so", 16),
+        ("runtimes/linux-x64/native/libe_sqlite3.so", 8),
+    ]));
+}
+
+#[test]
+fn dotnet_bundle_manifest_before_version_6() {
+    let host = ElfBuilder::new().section(".data", &bundle_placeholder()).build();
+    let data = DotnetBundleBuilder::new(host)
+        .version(2)
+        .file("libclrjit.so", BUNDLE_NATIVE_BINARY, &[0; 4])
+        .build();
+
+    let info = common::scan_bytes("app", &data, &["--scan-archives"]).unwrap();
+
+    assert_eq!(info.embedded_libraries, embedded(&[("libclrjit.so", 4)]));
+}
+
+#[test]
+fn unbundled_apphost_is_not_a_container() {
+    let data = ElfBuilder::new().section(".data", &bundle_placeholder()).build();
+
+    let info = common::scan_bytes("apphost", &data, &["--scan-archives"]).unwrap();
+
+    assert_eq!(info.container_format, None);
+    assert_eq!(info.payload_files, None);
+}
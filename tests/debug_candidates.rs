@@ -0,0 +1,122 @@
+//! Binaries with more than one debug file (`alternate_debug_files`,
+//! `--prefer-symbols`)
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use symwalker::output::{HumanFormatter, OutputFormatter};
+use symwalker::symbol_finder::Strategy;
+use symwalker::testutil::ElfBuilder;
+
+const BUILD_ID: [u8; 20] = [0x5a; 20];
+
+/// A sysroot where each ELF strategy finds its own copy of `usr/bin/app`'s
+/// debug file; returns the binary and the copies in default strategy order
+fn sysroot(root: &Path) -> (PathBuf, Vec<PathBuf>) {
+    let debug = || ElfBuilder::new().build_id(&BUILD_ID).symbol("main").build();
+    let binary = root.join("usr/bin/app");
+    let copies = vec![
+        root.join("usr/lib/debug/.build-id/5a").join(format!("{}.debug", "5a".repeat(19))),
+        root.join("usr/bin/.debug/app.dbg"),
+        root.join("usr/lib/debug/usr/bin/app.debug"),
+        root.join("usr/bin/app.debug"),
+    ];
+    for copy in &copies {
+        std::fs::create_dir_all(copy.parent().unwrap()).unwrap();
+        std::fs::write(copy, debug()).unwrap();
+    }
+    std::fs::write(&binary, ElfBuilder::new().build_id(&BUILD_ID).debuglink("app.dbg", &debug()).build()).unwrap();
+    (binary, copies)
+}
+
+fn found(paths: &[PathBuf]) -> Vec<PathBuf> {
+    paths.iter().map(|path| std::fs::canonicalize(path).unwrap()).collect()
+}
+
+#[test]
+fn every_candidate_is_listed_in_strategy_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let (binary, copies) = sysroot(dir.path());
+    let sysroot_flag = ["--sysroot", dir.path().to_str().unwrap()];
+
+    let info = common::scan_path(&binary, &sysroot_flag).unwrap();
+    let expected = found(&copies);
+    assert_eq!(info.debug_file_source, Some(Strategy::BuildId));
+    assert_eq!(info.debug_file_path.as_ref().map(|path| std::fs::canonicalize(path).unwrap()), Some(expected[0].clone()));
+    let alternates: Vec<(PathBuf, Strategy)> = info.alternate_debug_files.iter()
+        .map(|candidate| (std::fs::canonicalize(&candidate.path).unwrap(), candidate.source))
+        .collect();
+    assert_eq!(alternates, [
+        (expected[1].clone(), Strategy::Debuglink),
+        (expected[2].clone(), Strategy::PathMirror),
+        (expected[3].clone(), Strategy::Adjacent),
+    ]);
+    assert!(info.warnings.iter().all(|warning| warning.code != "conflicting-debug-files"));
+
+    let mut out = Vec::new();
+    HumanFormatter::new(1).format(std::slice::from_ref(&info), &mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains("Also found"), "{}", text);
+    assert!(text.lines().any(|line| line.contains("app.dbg") && line.contains("(debuglink)")), "{}", text);
+}
+
+#[test]
+fn prefer_symbols_changes_the_chosen_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let (binary, _) = sysroot(dir.path());
+    let root = dir.path().to_str().unwrap();
+
+    let info = common::scan_path(&binary, &["--sysroot", root, "--prefer-symbols", "adjacent,debuglink"]).unwrap();
+    assert_eq!(info.debug_file_source, Some(Strategy::Adjacent));
+    let sources: Vec<Strategy> = info.alternate_debug_files.iter().map(|candidate| candidate.source).collect();
+    assert_eq!(sources, [Strategy::Debuglink, Strategy::BuildId, Strategy::PathMirror]);
+
+    // A disabled strategy stays disabled whatever the preference
+    let info = common::scan_path(&binary, &["--sysroot", root, "--prefer-symbols", "adjacent", "--no-symbol-strategy", "adjacent"]).unwrap();
+    assert_eq!(info.debug_file_source, Some(Strategy::BuildId));
+    assert!(info.alternate_debug_files.iter().all(|candidate| candidate.source != Strategy::Adjacent));
+}
+
+#[test]
+fn same_build_id_with_different_contents_is_a_warning() {
+    let dir = tempfile::tempdir().unwrap();
+    let (binary, copies) = sysroot(dir.path());
+    std::fs::write(&copies[3], ElfBuilder::new().build_id(&BUILD_ID).symbol("main").symbol("helper").build()).unwrap();
+
+    let info = common::scan_path(&binary, &["--sysroot", dir.path().to_str().unwrap()]).unwrap();
+    let warning = info.warnings.iter().find(|warning| warning.code == "conflicting-debug-files").unwrap();
+    assert!(warning.message.contains("4 debug files"), "{}", warning.message);
+    assert!(warning.message.contains("(adjacent)"), "{}", warning.message);
+
+    // A file for some other build is just a wrong candidate, not a conflict
+    std::fs::write(&copies[3], ElfBuilder::new().build_id(&[1; 20]).build()).unwrap();
+    let info = common::scan_path(&binary, &["--sysroot", dir.path().to_str().unwrap()]).unwrap();
+    assert!(info.warnings.iter().all(|warning| warning.code != "conflicting-debug-files"));
+}
+
+/// What a Windows host relies on: with no `/usr/lib/debug` of its own, every
+/// strategy has to find the files inside the sysroot
+#[test]
+fn sysroot_lookups_stay_inside_the_tree() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().to_str().unwrap();
+    let debug = ElfBuilder::new().build_id(&BUILD_ID).symbol("main").build();
+    let binary = dir.path().join("usr/bin/app");
+    std::fs::create_dir_all(binary.parent().unwrap()).unwrap();
+    std::fs::write(&binary, ElfBuilder::new().build_id(&BUILD_ID).debuglink("app.dbg", &debug).build()).unwrap();
+    let mirrored = dir.path().join("usr/lib/debug/usr/bin/app.dbg");
+    std::fs::create_dir_all(mirrored.parent().unwrap()).unwrap();
+    std::fs::write(&mirrored, &debug).unwrap();
+
+    let info = common::scan_path(&binary, &["--sysroot", root, "--symbol-strategy", "debuglink"]).unwrap();
+    assert_eq!(info.debug_file_source, Some(Strategy::Debuglink));
+    assert_eq!(found(&[info.debug_file_path.unwrap()]), found(&[mirrored]));
+
+    let (binary, copies) = sysroot(dir.path());
+    let sysroot = std::fs::canonicalize(dir.path()).unwrap();
+    let info = common::scan_path(&binary, &["--sysroot", root]).unwrap();
+    let paths = info.alternate_debug_files.iter().map(|candidate| candidate.path.clone()).chain(info.debug_file_path);
+    assert!(found(&paths.collect::<Vec<_>>()).iter().all(|path| path.starts_with(&sysroot)));
+    assert_eq!(info.alternate_debug_files.len(), copies.len() - 1);
+}
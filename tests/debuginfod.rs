@@ -0,0 +1,417 @@
+//! debuginfod server specs and lookups against a mock HTTP server
+
+mod common;
+
+use clap::Parser;
+use symwalker::cli::{self, Args};
+use symwalker::binary::{scan_binary, ScanContext};
+use symwalker::debuginfod::{Artifact, CircuitOpen, DebuginfodClient, Server};
+use symwalker::dwarf::DwarfOrigin;
+use symwalker::output::{JsonFormatter, OutputFormatter, StoreCoverage};
+use symwalker::testutil::{ElfBuilder, MachOBuilder};
+
+const BUILD_ID: &str = "000102030405060708090a0b0c0d0e0f10111213";
+
+#[test]
+fn standard_urls_get_the_debuginfod_suffix() {
+    let server = Server::parse("https://debuginfod.example.org/").unwrap();
+    assert!(!server.is_template());
+    assert!(!server.head_unsupported());
+    assert_eq!(
+        server.debuginfo_url(BUILD_ID),
+        format!("https://debuginfod.example.org/buildid/{}/debuginfo", BUILD_ID)
+    );
+}
+
+#[test]
+fn templates_expand_every_placeholder() {
+    let server = Server::parse("https://artifacts.example.org/symbols/{buildid}/debug.elf|head-unsupported").unwrap();
+    assert!(server.is_template());
+    assert!(server.head_unsupported());
+    assert_eq!(
+        server.debuginfo_url(BUILD_ID),
+        format!("https://artifacts.example.org/symbols/{}/debug.elf", BUILD_ID)
+    );
+
+    let nested = Server::parse("https://s/{buildid}/{buildid}.debug").unwrap();
+    assert_eq!(nested.debuginfo_url("ab"), "https://s/ab/ab.debug");
+}
+
+#[test]
+fn malformed_specs_are_rejected() {
+    for spec in [
+        "",
+        "https://s/{build_id}/debug",
+        "https://s/{buildid}/{arch}",
+        "https://s/{buildid",
+        "https://s/|no-such-annotation",
+    ] {
+        assert!(Server::parse(spec).is_err(), "{:?} should be rejected", spec);
+    }
+    assert!(DebuginfodClient::new(vec!["https://s/{bad}".to_string()]).is_err());
+}
+
+#[test]
+fn mixed_server_list_falls_through_to_template() {
+    let mut standard = mockito::Server::new();
+    let mut store = mockito::Server::new();
+
+    let miss = standard.mock("HEAD", format!("/buildid/{}/debuginfo", BUILD_ID).as_str())
+        .with_status(404)
+        .create();
+    let hit = store.mock("HEAD", format!("/symbols/{}/debug.elf", BUILD_ID).as_str())
+        .with_status(200)
+        .create();
+
+    let client = DebuginfodClient::new(vec![
+        standard.url(),
+        format!("{}/symbols/{{buildid}}/debug.elf", store.url()),
+    ]).unwrap();
+
+    let (available, url) = client.check_available(BUILD_ID).unwrap();
+    assert!(available);
+    assert_eq!(url, Some(format!("{}/symbols/{}/debug.elf", store.url(), BUILD_ID)));
+    miss.assert();
+    hit.assert();
+}
+
+#[test]
+fn head_unsupported_uses_a_ranged_get() {
+    let mut store = mockito::Server::new();
+    let path = format!("/symbols/{}/debug.elf", BUILD_ID);
+
+    let head = store.mock("HEAD", path.as_str()).with_status(405).expect(0).create();
+    let get = store.mock("GET", path.as_str())
+        .match_header("range", "bytes=0-0")
+        .with_status(206)
+        .with_body("\x7f")
+        .create();
+
+    let client = DebuginfodClient::new(vec![
+        format!("{}/symbols/{{buildid}}/debug.elf|head-unsupported", store.url()),
+    ]).unwrap();
+
+    assert!(client.check_available(BUILD_ID).unwrap().0);
+    head.assert();
+    get.assert();
+}
+
+#[test]
+fn download_expands_templates() {
+    let mut store = mockito::Server::new();
+    let body = b"\x7fELF debug file".to_vec();
+    let get = store.mock("GET", format!("/symbols/{}/debug.elf", BUILD_ID).as_str())
+        .with_body(&body)
+        .create();
+
+    let client = DebuginfodClient::new(vec![
+        format!("{}/symbols/{{buildid}}/debug.elf", store.url()),
+    ]).unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let dest = dir.path().join("app.debug");
+    client.download(BUILD_ID, &dest).unwrap();
+
+    assert_eq!(std::fs::read(&dest).unwrap(), body);
+    get.assert();
+}
+
+#[test]
+fn executables_need_an_artifact_placeholder_in_templates() {
+    let standard = Server::parse("https://debuginfod.example.org").unwrap();
+    assert_eq!(
+        standard.artifact_url(BUILD_ID, Artifact::Executable),
+        Some(format!("https://debuginfod.example.org/buildid/{}/executable", BUILD_ID))
+    );
+
+    let debug_only = Server::parse("https://s/{buildid}/debug.elf").unwrap();
+    assert_eq!(debug_only.artifact_url(BUILD_ID, Artifact::Executable), None);
+
+    let both = Server::parse("https://s/{buildid}/{artifact}").unwrap();
+    assert_eq!(both.debuginfo_url("ab"), "https://s/ab/debuginfo");
+    assert_eq!(both.artifact_url("ab", Artifact::Executable), Some("https://s/ab/executable".to_string()));
+}
+
+#[test]
+fn debug_only_files_fetch_their_executable() {
+    let build_id: Vec<u8> = (0..20).collect();
+    let debug_file = ElfBuilder::new()
+        .build_id(&build_id)
+        .nobits_section(".text", 0x1000)
+        .section(".debug_info", &[0; 16])
+        .symbol("main")
+        .build();
+    let executable = ElfBuilder::new()
+        .build_id(&build_id)
+        .section(".text", &[0xc3])
+        .build();
+
+    let mut server = mockito::Server::new();
+    let head = server.mock("HEAD", format!("/buildid/{}/executable", BUILD_ID).as_str())
+        .with_status(200)
+        .create();
+    let get = server.mock("GET", format!("/buildid/{}/executable", BUILD_ID).as_str())
+        .with_body(&executable)
+        .create();
+
+    let scan = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let debug_path = scan.path().join("app.debug");
+    std::fs::write(&debug_path, &debug_file).unwrap();
+
+    let url = server.url();
+    let info = common::scan_path(&debug_path, &["--check-remote", "--debuginfod-urls", &url]).unwrap();
+    assert!(info.is_debug_only);
+    assert_eq!(info.executable_available, Some(true));
+
+    let args = Args::parse_from([
+        "symwalker",
+        "--download-executable",
+        "--debuginfod-urls", &url,
+        "-o", out.path().to_str().unwrap(),
+        scan.path().to_str().unwrap(),
+    ]);
+    cli::run(args).unwrap();
+
+    let dest = out.path().join("app");
+    assert_eq!(std::fs::read(&dest).unwrap(), executable);
+    let manifest: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(out.path().join("manifest.json")).unwrap()).unwrap();
+    assert_eq!(manifest["files"][0]["executable_downloaded"], dest.display().to_string());
+    head.assert();
+    get.assert();
+}
+
+/// Build-id for the nth lookup, so no two requests look alike
+fn nth_build_id(n: usize) -> String {
+    format!("{:040x}", n)
+}
+
+#[test]
+fn breaker_opens_after_consecutive_failures() {
+    let mut server = mockito::Server::new();
+    let failing = server.mock("HEAD", mockito::Matcher::Any).with_status(503).expect(3).create();
+
+    let client = DebuginfodClient::new(vec![server.url()]).unwrap().with_circuit_breaker(Some(3));
+
+    for n in 0..3 {
+        assert_eq!(client.check_available(&nth_build_id(n)).unwrap(), (false, None));
+    }
+    assert!(client.is_circuit_open());
+    let err = client.check_available(&nth_build_id(3)).unwrap_err();
+    assert!(err.is::<CircuitOpen>());
+    assert!(client.download(&nth_build_id(4), &std::env::temp_dir().join("unused")).unwrap_err().is::<CircuitOpen>());
+    failing.assert();
+}
+
+#[test]
+fn not_found_answers_keep_the_breaker_closed() {
+    let mut server = mockito::Server::new();
+    let missing = server.mock("HEAD", mockito::Matcher::Any).with_status(404).expect(5).create();
+
+    let client = DebuginfodClient::new(vec![server.url()]).unwrap().with_circuit_breaker(Some(2));
+
+    for n in 0..5 {
+        assert_eq!(client.check_available(&nth_build_id(n)).unwrap(), (false, None));
+    }
+    assert!(!client.is_circuit_open());
+    missing.assert();
+}
+
+#[test]
+fn breaker_is_shared_by_concurrent_lookups() {
+    let mut server = mockito::Server::new();
+    let _failing = server.mock("HEAD", mockito::Matcher::Any).with_status(500).create();
+
+    let client = DebuginfodClient::new(vec![server.url()]).unwrap().with_circuit_breaker(Some(8));
+
+    let results: Vec<bool> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..4)
+            .map(|worker| {
+                let client = &client;
+                scope.spawn(move || {
+                    (0..10)
+                        .map(|n| client.check_available(&nth_build_id(worker * 10 + n)).is_err())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+    });
+
+    assert!(client.is_circuit_open());
+    // Lookups already in flight when it opened may finish, the rest are skipped
+    let issued = results.iter().filter(|skipped| !**skipped).count();
+    assert!((8..8 + 4).contains(&issued), "{} lookups issued", issued);
+}
+
+#[test]
+fn scans_after_the_breaker_opens_are_marked_skipped() {
+    let mut server = mockito::Server::new();
+    let failing = server.mock("HEAD", mockito::Matcher::Any).with_status(503).expect(2).create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let url = server.url();
+    let args = Args::parse_from([
+        "symwalker", "--check-remote", "--debuginfod-urls", &url, "--max-remote-failures", "2", ".",
+    ]);
+    let ctx = ScanContext::new(&args).unwrap();
+
+    let infos: Vec<_> = (0..4u8)
+        .map(|n| {
+            let path = dir.path().join(format!("app{}", n));
+            std::fs::write(&path, ElfBuilder::new().build_id(&[n; 20]).build()).unwrap();
+            scan_binary(&path, &args, &ctx).unwrap()
+        })
+        .collect();
+
+    let checked: Vec<_> = infos.iter()
+        .map(|info| (info.debuginfod_available, info.skipped_reason.as_deref()))
+        .collect();
+    assert_eq!(checked, [
+        (Some(false), None),
+        (Some(false), None),
+        (None, Some("circuit-open")),
+        (None, Some("circuit-open")),
+    ]);
+    failing.assert();
+}
+
+#[test]
+fn no_circuit_breaker_keeps_checking() {
+    let mut server = mockito::Server::new();
+    let failing = server.mock("HEAD", mockito::Matcher::Any).with_status(503).expect(5).create();
+
+    let client = DebuginfodClient::new(vec![server.url()]).unwrap().with_circuit_breaker(None);
+
+    for n in 0..5 {
+        assert!(client.check_available(&nth_build_id(n)).is_ok());
+    }
+    assert!(!client.is_circuit_open());
+    failing.assert();
+}
+
+#[test]
+fn remote_dwarf_analyzes_downloaded_debug_info_once() {
+    let build_id: Vec<u8> = (0..20).collect();
+    let (abbrev, info) = common::one_function_dwarf();
+    let debug_file = ElfBuilder::new()
+        .build_id(&build_id)
+        .section(".debug_abbrev", &abbrev)
+        .section(".debug_info", &info)
+        .build();
+
+    let mut server = mockito::Server::new();
+    server.mock("HEAD", format!("/buildid/{}/debuginfo", BUILD_ID).as_str())
+        .with_status(200)
+        .create();
+    let get = server.mock("GET", format!("/buildid/{}/debuginfo", BUILD_ID).as_str())
+        .with_body(&debug_file)
+        .expect(1)
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let url = server.url();
+    let binary = ElfBuilder::new().build_id(&build_id).build();
+    for name in ["app", "app-copy"] {
+        std::fs::write(dir.path().join(name), &binary).unwrap();
+    }
+
+    let info = common::scan_path(&dir.path().join("app"), &["--dwarf", "--check-remote", "--debuginfod-urls", &url]).unwrap();
+    assert_eq!(info.function_count, None);
+
+    let args = Args::parse_from(["symwalker", "--dwarf", "--check-remote", "--remote-dwarf", "--debuginfod-urls", &url, "."]);
+    let ctx = ScanContext::new(&args).unwrap();
+    for name in ["app", "app-copy"] {
+        let info = scan_binary(&dir.path().join(name), &args, &ctx).unwrap();
+        assert_eq!(info.function_count, Some(1));
+        assert_eq!(info.largest_functions.unwrap()[0].size, 0x40);
+        assert_eq!(info.dwarf_origin, Some(DwarfOrigin::Remote));
+    }
+    get.assert();
+}
+
+#[test]
+fn store_coverage_counts_binaries_the_servers_have() {
+    let mut server = mockito::Server::new();
+    server.mock("HEAD", format!("/buildid/{}/debuginfo", BUILD_ID).as_str())
+        .with_status(200)
+        .create();
+    server.mock("HEAD", mockito::Matcher::Any).with_status(404).create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let files = [
+        ("covered", ElfBuilder::new().build_id(&(0..20).collect::<Vec<u8>>()).build()),
+        ("absent", ElfBuilder::new().build_id(&[9; 20]).build()),
+        ("no-id", ElfBuilder::new().build()),
+        ("macho", MachOBuilder::new().uuid([0xab; 16]).build()),
+    ];
+    let url = server.url();
+    let args = Args::parse_from(["symwalker", "--check-remote", "--compare-to-store", "--debuginfod-urls", &url, "."]);
+    let ctx = ScanContext::new(&args).unwrap();
+    let binaries: Vec<_> = files.iter()
+        .map(|(name, data)| {
+            std::fs::write(dir.path().join(name), data).unwrap();
+            scan_binary(&dir.path().join(name), &args, &ctx).unwrap()
+        })
+        .collect();
+
+    let coverage = StoreCoverage::new(&binaries);
+    assert_eq!((coverage.present, coverage.total), (1, 4));
+    assert_eq!(coverage.percent, 25.0);
+    let missing: Vec<(&str, Option<&str>)> = coverage.missing.iter()
+        .map(|m| (m.reason.as_str(), m.id.as_deref()))
+        .collect();
+    assert_eq!(missing, [
+        ("absent", Some("0909090909090909090909090909090909090909")),
+        ("no-id", None),
+        ("not-checked", Some("ABABABAB-ABAB-ABAB-ABAB-ABABABABABAB")),
+    ]);
+
+    let mut json = Vec::new();
+    JsonFormatter::new().with_store_coverage(coverage).format(&binaries, &mut json).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(json["store_coverage"]["percent"], 25.0);
+    assert_eq!(json["binaries"].as_array().unwrap().len(), 4);
+}
+
+#[test]
+fn batched_checks_ask_once_per_build_id() {
+    let mut server = mockito::Server::new();
+    let found = server.mock("HEAD", format!("/buildid/{}/debuginfo", BUILD_ID).as_str()).with_status(200).expect(1).create();
+    let missing = server.mock("HEAD", mockito::Matcher::Any).with_status(404).expect(1).create();
+
+    let client = DebuginfodClient::new(vec![server.url()]).unwrap();
+    let ids = [BUILD_ID.to_string(), nth_build_id(7)];
+    let answers = client.check_artifacts(&ids, Artifact::Debuginfo, 2);
+    assert_eq!(answers[BUILD_ID], (true, Some(format!("{}/buildid/{}/debuginfo", server.url(), BUILD_ID))));
+    assert_eq!(answers[&nth_build_id(7)], (false, None));
+    found.assert();
+    missing.assert();
+
+    // A scan asks once for copies of a build, and --remote-only waits for the answers
+    let found = server.mock("HEAD", format!("/buildid/{}/debuginfo", BUILD_ID).as_str()).with_status(200).expect(1).create();
+    let missing = server.mock("HEAD", mockito::Matcher::Any).with_status(404).expect(1).create();
+    let dir = tempfile::tempdir().unwrap();
+    let covered = ElfBuilder::new().build_id(&(0..20).collect::<Vec<u8>>()).build();
+    for (name, data) in [("app", covered.clone()), ("app-copy", covered), ("absent", ElfBuilder::new().build_id(&[7; 20]).build()), ("no-id", ElfBuilder::new().build())] {
+        std::fs::write(dir.path().join(name), data).unwrap();
+    }
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_symwalker"))
+        .env_clear()
+        .args(["--json", "--show-stripped", "--check-remote", "--remote-only", "--remote-concurrency", "3", "--explain-filtered", "--debuginfod-urls", &server.url()])
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let mut reported: Vec<&str> = report["binaries"].as_array().unwrap().iter()
+        .map(|binary| binary["file_path"].as_str().unwrap().rsplit('/').next().unwrap())
+        .collect();
+    reported.sort();
+    assert_eq!(reported, ["app", "app-copy"]);
+    assert!(report["binaries"].as_array().unwrap().iter().all(|binary| binary["resolution_method"] == "remote"));
+    assert_eq!(report["filtered"]["by_filter"]["remote_only"], 2, "{:#}", report["filtered"]);
+    found.assert();
+    missing.assert();
+}
@@ -0,0 +1,38 @@
+//! One report per build across the tree (`--dedup-by-id`)
+
+mod common;
+
+use symwalker::testutil::ElfBuilder;
+
+#[test]
+fn later_copies_of_a_build_are_skipped_and_not_looked_up_again() {
+    let (shared, other) = ([0x51u8; 20], [0x52u8; 20]);
+    let dir = tempfile::tempdir().unwrap();
+    for (path, id) in [("a/app", shared), ("b/app", shared), ("c/app.old", shared), ("d/tool", other)] {
+        let path = dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, ElfBuilder::new().build_id(&id).symbol("main").build()).unwrap();
+    }
+
+    let mut server = mockito::Server::new();
+    let lookups = server.mock("HEAD", format!("/buildid/{}/debuginfo", hex::encode(shared)).as_str())
+        .with_status(404)
+        .expect(1)
+        .create();
+    let url = server.url();
+    let output = common::symwalker(dir.path(), &["--json", "--dedup-by-id", "--check-remote", "--debuginfod-urls", &url, "--explain-filtered"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["binaries"].as_array().unwrap().len(), 2, "{}", report);
+    assert_eq!(report["duplicates_skipped"], 2);
+    assert_eq!(report["filtered"]["by_filter"]["duplicate-id (--dedup-by-id)"], 2);
+    lookups.assert();
+
+    // Every copy without the flag
+    let output = common::symwalker(dir.path(), &["--json"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report.as_array().unwrap().len(), 4);
+
+    let output = common::symwalker(dir.path(), &["--dedup-by-id", "-v"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Duplicate builds skipped: 2"));
+}
@@ -0,0 +1,108 @@
+//! The deep-analysis worker pool
+
+mod common;
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use symwalker::binary::{scan_headers, ScanContext};
+use symwalker::deep::{self, Limits};
+use symwalker::dwarf::DwarfOrigin;
+use symwalker::testutil::ElfBuilder;
+
+/// What the tasks themselves saw running, independent of the pool's stats
+#[derive(Default)]
+struct Observed {
+    running: AtomicUsize,
+    running_bytes: AtomicU64,
+    peak_tasks: AtomicUsize,
+    peak_bytes: AtomicU64,
+    /// (task size, tasks running when it started, and when it finished)
+    spans: Mutex<Vec<(u64, usize, usize)>>,
+}
+
+impl Observed {
+    fn work(&self, (id, bytes): (usize, u64)) -> usize {
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        let running_bytes = self.running_bytes.fetch_add(bytes, Ordering::SeqCst) + bytes;
+        self.peak_tasks.fetch_max(running, Ordering::SeqCst);
+        self.peak_bytes.fetch_max(running_bytes, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(20));
+        let still_running = self.running.load(Ordering::SeqCst);
+        self.spans.lock().unwrap().push((bytes, running, still_running));
+        self.running_bytes.fetch_sub(bytes, Ordering::SeqCst);
+        self.running.fetch_sub(1, Ordering::SeqCst);
+        id
+    }
+}
+
+fn tasks(sizes: &[u64]) -> Vec<(u64, (usize, u64))> {
+    sizes.iter().enumerate().map(|(id, &bytes)| (bytes, (id, bytes))).collect()
+}
+
+#[test]
+fn running_tasks_stay_within_jobs_and_memory_budget() {
+    let sizes = [40, 40, 40, 10, 10, 30, 60, 20];
+    let observed = Observed::default();
+    let limits = Limits { jobs: 4, memory_bytes: 100 };
+    let (results, stats) = deep::run(tasks(&sizes), limits, |task| observed.work(task));
+
+    assert_eq!(results, (0..sizes.len()).collect::<Vec<_>>());
+    assert_eq!(stats.tasks, sizes.len());
+    assert!(observed.peak_tasks.load(Ordering::SeqCst) <= 4);
+    assert!(observed.peak_bytes.load(Ordering::SeqCst) <= 100);
+    assert!(stats.peak_bytes <= 100 && stats.peak_tasks <= 4, "{:?}", stats);
+    // Two 40-byte tasks fit together, so work did overlap
+    assert!(stats.peak_tasks >= 2, "{:?}", stats);
+    assert!(stats.busy >= Duration::from_millis(20 * sizes.len() as u64), "{:?}", stats);
+}
+
+#[test]
+fn task_over_the_budget_runs_alone() {
+    let observed = Observed::default();
+    let limits = Limits { jobs: 3, memory_bytes: 100 };
+    let (results, stats) = deep::run(tasks(&[10, 500, 10, 10]), limits, |task| observed.work(task));
+
+    assert_eq!(results, [0, 1, 2, 3]);
+    let spans = observed.spans.lock().unwrap();
+    let big = spans.iter().find(|(bytes, _, _)| *bytes == 500).unwrap();
+    assert_eq!((big.1, big.2), (1, 1), "{:?}", spans);
+    assert_eq!(stats.peak_bytes, 500);
+}
+
+#[test]
+fn single_job_runs_tasks_one_at_a_time() {
+    let observed = Observed::default();
+    let limits = Limits { jobs: 1, memory_bytes: u64::MAX };
+    let (_, stats) = deep::run(tasks(&[1, 2, 3]), limits, |task| observed.work(task));
+    assert_eq!(observed.peak_tasks.load(Ordering::SeqCst), 1);
+    assert_eq!(stats.peak_tasks, 1);
+
+    let (results, stats) = deep::run(Vec::<(u64, u8)>::new(), limits, |_| ());
+    assert!(results.is_empty());
+    assert_eq!(stats.tasks, 0);
+}
+
+#[test]
+fn deep_analysis_is_merged_into_header_results() {
+    let (abbrev, info) = common::one_function_dwarf();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app");
+    std::fs::write(&path, ElfBuilder::new()
+        .section(".debug_abbrev", &abbrev)
+        .section(".debug_info", &info)
+        .build()).unwrap();
+
+    let args = common::args(&["--dwarf", "--deep-jobs", "2"]);
+    let ctx = ScanContext::new(&args).unwrap();
+    let mut binaries = vec![scan_headers(&path, &args, &ctx).unwrap()];
+    assert_eq!(binaries[0].function_count, None);
+    assert_eq!(deep::estimate(&binaries[0]), (abbrev.len() + info.len()) as u64);
+
+    let stats = deep::annotate_all(&mut binaries, &args, &ctx);
+    assert_eq!(stats.tasks, 1);
+    assert_eq!(binaries[0].function_count, Some(1));
+    assert_eq!(binaries[0].dwarf_origin, Some(DwarfOrigin::Binary));
+    assert_eq!(binaries[0].function_count, common::scan_path(&path, &["--dwarf"]).unwrap().function_count);
+}
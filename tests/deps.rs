@@ -0,0 +1,81 @@
+//! DT_NEEDED resolution against RPATH/RUNPATH and the system library paths
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use symwalker::deps;
+use symwalker::testutil::ElfBuilder;
+
+fn write(path: &Path, data: &[u8]) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, data).unwrap();
+}
+
+#[test]
+fn system_dirs_follow_ld_so_conf_includes() {
+    let sysroot = tempfile::tempdir().unwrap();
+    write(&sysroot.path().join("etc/ld.so.conf"), b"# comment\ninclude ld.so.conf.d/*.conf\n/opt/first\n");
+    write(&sysroot.path().join("etc/ld.so.conf.d/b.conf"), b"/opt/b\n");
+    write(&sysroot.path().join("etc/ld.so.conf.d/a.conf"), b"/opt/a:/opt/a2\n");
+    write(&sysroot.path().join("etc/ld.so.conf.d/skip.txt"), b"/opt/skip\n");
+
+    let dirs = deps::system_dirs(Some(sysroot.path()));
+    let guest: Vec<_> = dirs.iter()
+        .map(|dir| Path::new("/").join(dir.strip_prefix(sysroot.path()).unwrap()))
+        .collect();
+    assert_eq!(guest, [
+        "/opt/a", "/opt/a2", "/opt/b", "/opt/first", "/lib64", "/usr/lib64", "/lib", "/usr/lib",
+    ].map(Path::new));
+}
+
+#[test]
+fn scan_resolves_needed_libraries_in_loader_order() {
+    let sysroot = tempfile::tempdir().unwrap();
+    let root = sysroot.path();
+    let library = ElfBuilder::new().e_type(3).build();
+
+    // Wrong class in /lib64 is skipped in favour of /usr/lib
+    write(&root.join("lib64/libz.so.1"), &ElfBuilder::new().class32().e_type(3).build());
+    write(&root.join("usr/lib/libz.so.1"), &library);
+    // RUNPATH hides RPATH, and $ORIGIN is the binary's directory
+    write(&root.join("rpath/libapp.so"), &library);
+    write(&root.join("app/lib/libapp.so"), &library);
+    // Linker scripts aren't loadable
+    write(&root.join("usr/lib/libscript.so"), b"GROUP ( /lib/libc.so.6 )\n");
+
+    let data = ElfBuilder::new()
+        .e_type(3)
+        .needed("libz.so.1")
+        .needed("libapp.so")
+        .needed("libscript.so")
+        .needed("libgone.so.2")
+        .rpath("/rpath")
+        .runpath("$ORIGIN/lib")
+        .build();
+    let binary = root.join("app/app");
+    write(&binary, &data);
+
+    let info = common::scan_path(&binary, &["--check-deps", "--sysroot", root.to_str().unwrap()]).unwrap();
+
+    let resolved: Vec<_> = info.resolved_dependencies.iter()
+        .map(|dep| (dep.name.as_str(), dep.path.clone()))
+        .collect();
+    assert_eq!(resolved, [
+        ("libz.so.1", root.join("usr/lib/libz.so.1")),
+        ("libapp.so", std::path::absolute(root.join("app/lib/libapp.so")).unwrap()),
+    ]);
+    assert_eq!(info.missing_dependencies, ["libscript.so", "libgone.so.2"]);
+    assert!(info.warnings.iter().any(|w| w.code == "missing-dependency"));
+}
+
+#[test]
+fn dependencies_are_only_resolved_on_request() {
+    let data = ElfBuilder::new().needed("libgone.so.2").build();
+    let info = common::scan_bytes("app", &data, &[]).unwrap();
+
+    assert!(info.resolved_dependencies.is_empty());
+    assert!(info.missing_dependencies.is_empty());
+    assert!(info.warnings.iter().all(|w| w.code != "missing-dependency"));
+}
@@ -0,0 +1,51 @@
+//! dSYM bundle discovery in DerivedData-style trees
+
+use std::path::Path;
+use std::time::Duration;
+
+use symwalker::symbol_finder::SymbolFinder;
+
+const UUID: &str = "0C9D3D4E-7A5B-4C1F-9E2D-8B6A5F4E3D2C";
+
+/// Bundle whose UUID is listed as a DebugSymbols-style Resources plist
+fn bundle(path: &Path) {
+    let resources = path.join("Contents/Resources");
+    std::fs::create_dir_all(&resources).unwrap();
+    std::fs::write(resources.join(format!("{}.plist", UUID)), b"").unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_build_products_are_searched_on_request() {
+    let root = tempfile::tempdir().unwrap();
+    let cache = root.path().join("cache");
+    let derived_data = root.path().join("DerivedData");
+    bundle(&cache.join("Debug/App.dSYM"));
+    std::fs::create_dir_all(derived_data.join("App-abc/Build")).unwrap();
+    std::os::unix::fs::symlink(&cache, derived_data.join("App-abc/Build/Products")).unwrap();
+    // A loop must not stall or fail the search
+    std::os::unix::fs::symlink(&derived_data, derived_data.join("App-abc/Build/loop")).unwrap();
+
+    let binary = root.path().join("App");
+    let finder = SymbolFinder::new(&binary);
+    assert_eq!(finder.search_derived_data(&derived_data, UUID), None);
+
+    let found = SymbolFinder::new(&binary).follow_symlinks(true).search_derived_data(&derived_data, UUID);
+    assert_eq!(found, Some(derived_data.join("App-abc/Build/Products/Debug/App.dSYM")));
+}
+
+#[test]
+fn resolve_timeout_stops_the_search() {
+    let root = tempfile::tempdir().unwrap();
+    let derived_data = root.path().join("DerivedData");
+    bundle(&derived_data.join("App-abc/Build/Products/Debug/App.dSYM"));
+    let binary = root.path().join("App");
+
+    let finder = SymbolFinder::new(&binary).with_timeout(Some(Duration::from_secs(60)));
+    assert!(finder.search_derived_data(&derived_data, UUID).is_some());
+    assert!(finder.timeout_warning().is_none());
+
+    let finder = SymbolFinder::new(&binary).with_timeout(Some(Duration::ZERO));
+    assert_eq!(finder.search_derived_data(&derived_data, UUID), None);
+    assert_eq!(finder.timeout_warning().unwrap().code, "resolve-timeout");
+}
@@ -0,0 +1,94 @@
+//! Environment variables that change a scan, and `--hermetic`
+
+mod common;
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use symwalker::binary::ScanContext;
+use symwalker::environment::Environment;
+use symwalker::testutil::MachOBuilder;
+
+const UUID: [u8; 16] = [0x0c, 0x9d, 0x3d, 0x4e, 0x7a, 0x5b, 0x4c, 0x1f, 0x9e, 0x2d, 0x8b, 0x6a, 0x5f, 0x4e, 0x3d, 0x2c];
+
+#[test]
+fn hermetic_environments_answer_nothing() {
+    let vars = || [
+        ("DEBUGINFOD_URLS", "https://a.example/ https://b.example/".to_string()),
+        ("HOME", "/home/dev".to_string()),
+        ("LANG", "C".to_string()),
+    ];
+    let open = Environment::from_vars(false, vars());
+    assert_eq!(open.debuginfod_urls(), ["https://a.example/", "https://b.example/"]);
+    assert_eq!(open.home_dir(), Some("/home/dev".into()));
+    assert_eq!(open.var("LANG"), None);
+    // Windows keeps the home directory in USERPROFILE
+    let windows = Environment::from_vars(false, [("USERPROFILE", r"C:\Users\dev".to_string())]);
+    assert_eq!(windows.home_dir(), Some(r"C:\Users\dev".into()));
+
+    let hermetic = Environment::from_vars(true, vars());
+    assert!(hermetic.debuginfod_urls().is_empty());
+    assert_eq!((hermetic.home_dir(), hermetic.var("HOME")), (None, None));
+
+    let recorded = hermetic.record(false);
+    let names: Vec<&str> = recorded.iter().map(|variable| variable.name).collect();
+    assert_eq!(names, ["SYMWALKER_DIR", "DEBUGINFOD_URLS", "HOME", "DEBUGINFOD_CACHE_PATH", "XDG_CACHE_HOME", "USERPROFILE"]);
+    assert!(!recorded[0].set && !recorded[0].ignored);
+    assert!(recorded[2].set && recorded[2].ignored && recorded[2].value.is_none());
+    assert_eq!(open.record(true)[2].value.as_deref(), Some("/home/dev"));
+    assert!(!open.record(true)[2].ignored);
+}
+
+// The only test here that touches the process environment
+#[test]
+fn hermetic_scans_ignore_debuginfod_urls() {
+    std::env::set_var("DEBUGINFOD_URLS", "https://symbols.example/");
+    let servers = |flags: &[&str]| {
+        let ctx = ScanContext::new(&common::args(flags)).unwrap();
+        ctx.debuginfod.unwrap().servers().len()
+    };
+    assert_eq!(servers(&["--check-remote"]), 1);
+    // The public defaults, as on a machine without the variable
+    assert_eq!(servers(&["--check-remote", "--hermetic"]), 4);
+    assert_eq!(servers(&["--check-remote", "--hermetic", "--debuginfod-urls", "https://a/,https://b/"]), 2);
+    std::env::remove_var("DEBUGINFOD_URLS");
+}
+
+fn symwalker(dir: Option<&Path>, flags: &[&str], vars: &[(&str, &Path)]) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_symwalker"));
+    command.env_clear().args(flags).envs(vars.iter().copied());
+    command.args(dir);
+    command.output().unwrap()
+}
+
+#[test]
+fn setting_the_variables_changes_nothing_under_hermetic() {
+    let (home, scan) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    let resources = home.path().join("Library/Developer/Xcode/DerivedData/App-abc/Build/Products/Debug/App.dSYM/Contents/Resources");
+    std::fs::create_dir_all(&resources).unwrap();
+    std::fs::write(resources.join("0C9D3D4E-7A5B-4C1F-9E2D-8B6A5F4E3D2C.plist"), b"").unwrap();
+    std::fs::write(scan.path().join("App"), MachOBuilder::new().uuid(UUID).symbol("_main").build()).unwrap();
+
+    let vars = [("HOME", home.path()), ("DEBUGINFOD_URLS", Path::new("http://127.0.0.1:9/")), ("SYMWALKER_DIR", scan.path())];
+    let flags = ["--json", "--check-dsym"];
+    let found = symwalker(Some(scan.path()), &flags, &vars);
+    let report: serde_json::Value = serde_json::from_slice(&found.stdout).unwrap();
+    assert_eq!(report[0]["debug_file_source"], "derived-data");
+
+    let hermetic = ["--json", "--check-dsym", "--hermetic"];
+    let with_vars = symwalker(Some(scan.path()), &hermetic, &vars);
+    let without = symwalker(Some(scan.path()), &hermetic, &[]);
+    assert!(with_vars.status.success(), "{}", String::from_utf8_lossy(&with_vars.stderr));
+    assert_eq!(with_vars.stdout, without.stdout);
+    let report: serde_json::Value = serde_json::from_slice(&with_vars.stdout).unwrap();
+    assert!(report[0]["dsym_bundle"].is_null());
+
+    let recorded = symwalker(Some(scan.path()), &["--json", "--hermetic", "--record-env"], &vars);
+    let report: serde_json::Value = serde_json::from_slice(&recorded.stdout).unwrap();
+    assert_eq!(report["environment"][2], serde_json::json!({"name": "HOME", "set": true, "ignored": true, "value": null}));
+
+    // The directory itself must come from the command line
+    let from_env = symwalker(None, &hermetic, &vars);
+    assert!(!from_env.status.success());
+    assert!(String::from_utf8_lossy(&from_env.stderr).contains("SYMWALKER_DIR"));
+}
@@ -0,0 +1,85 @@
+//! Universal binary slice layout, per-slice analysis and obsolete
+//! architectures
+
+mod common;
+
+use symwalker::macho::SliceInfo;
+use symwalker::output::{HumanFormatter, OutputFormatter, Summary};
+use symwalker::testutil::{FatBuilder, MachOBuilder, CPU_TYPE_ARM64, CPU_TYPE_X86, CPU_TYPE_X86_64};
+
+#[test]
+fn slices_report_their_layout_and_padding() {
+    let arm64 = MachOBuilder::new().cpu(CPU_TYPE_ARM64, 0).build();
+    let i386 = MachOBuilder::new().class32().build();
+    let (arm64_size, i386_size) = (arm64.len() as u64, i386.len() as u64);
+    let fat = FatBuilder::new().slice(CPU_TYPE_ARM64, 0, arm64).slice(CPU_TYPE_X86, 3, i386).build();
+
+    let info = common::scan_bytes("app", &fat, &[]).unwrap();
+    let i386_offset = (0x1000 + arm64_size).next_multiple_of(0x1000);
+    assert_eq!(info.fat_slices.as_deref(), Some(&[
+        SliceInfo {
+            architecture: "aarch64".to_string(), offset: 0x1000, size: arm64_size, align: 4096, obsolete: false,
+            uuid: None, is_stripped: Some(true), has_debug_info: Some(false),
+        },
+        SliceInfo {
+            architecture: "i386".to_string(), offset: i386_offset, size: i386_size, align: 4096, obsolete: true,
+            uuid: None, is_stripped: Some(true), has_debug_info: Some(false),
+        },
+    ][..]));
+    // After the 48-byte header, and between the end of arm64 and i386
+    assert_eq!(info.fat_padding_bytes, Some(0x1000 - 48 + i386_offset - 0x1000 - arm64_size));
+    assert_eq!(info.reclaimable_bytes(), i386_size);
+
+    let summary = Summary::new(&[info.clone(), info.clone()]);
+    assert_eq!((summary.obsolete_slices, summary.reclaimable_bytes), (2, 2 * i386_size));
+
+    let show = |verbosity| {
+        let mut out = Vec::new();
+        HumanFormatter::new(verbosity).format(std::slice::from_ref(&info), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    let verbose = show(1);
+    assert!(verbose.contains("Fat Slices: 2"), "{}", verbose);
+    assert!(verbose.lines().any(|line| line.contains("i386") && line.contains("obsolete")), "{}", verbose);
+    assert!(!show(0).contains("Fat Slices"));
+
+    let thin = common::scan_bytes("app", &MachOBuilder::new().build(), &[]).unwrap();
+    assert_eq!((thin.fat_slices, thin.fat_padding_bytes), (None, None));
+}
+
+#[test]
+fn every_slice_is_analyzed_and_matched_against_dsyms() {
+    let arm64_uuid = [0xa1; 16];
+    let x86_64_uuid = [0x86; 16];
+    let arm64 = MachOBuilder::new().cpu(CPU_TYPE_ARM64, 0).uuid(arm64_uuid).build();
+    let x86_64 = MachOBuilder::new().cpu(CPU_TYPE_X86_64, 3).uuid(x86_64_uuid).symbol("_main").dwarf_section("__debug_info", &[0; 16]).build();
+    let fat = FatBuilder::new().slice(CPU_TYPE_ARM64, 0, arm64).slice(CPU_TYPE_X86_64, 3, x86_64).build();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("App");
+    std::fs::write(&path, &fat).unwrap();
+    let info = common::scan_path(&path, &["--check-dsym"]).unwrap();
+    let slices = info.fat_slices.as_deref().unwrap();
+    let x86_64_uuid = slices[1].uuid.clone().unwrap();
+    assert_ne!(slices[0].uuid, slices[1].uuid);
+    assert_eq!((slices[0].is_stripped, slices[0].has_debug_info), (Some(true), Some(false)));
+    assert_eq!((slices[1].is_stripped, slices[1].has_debug_info), (Some(false), Some(true)));
+    // The top-level fields still describe the first slice
+    assert_eq!(info.uuid, slices[0].uuid);
+    assert!(info.is_stripped);
+    assert_eq!(info.dsym_bundle, None);
+
+    let mut out = Vec::new();
+    HumanFormatter::new(0).format(std::slice::from_ref(&info), &mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains("Architecture: Universal (aarch64, x86_64) (reporting aarch64)"), "{}", text);
+    assert!(text.lines().any(|line| line.contains("aarch64") && line.contains("stripped")), "{}", text);
+    assert!(text.lines().any(|line| line.contains(&x86_64_uuid) && line.contains("symbols, debug info")), "{}", text);
+
+    // A dSYM built for the second slice only still matches
+    let resources = dir.path().join("App.dSYM/Contents/Resources");
+    std::fs::create_dir_all(&resources).unwrap();
+    std::fs::write(resources.join(format!("{}.plist", x86_64_uuid)), b"").unwrap();
+    let info = common::scan_path(&path, &["--check-dsym"]).unwrap();
+    assert_eq!(info.dsym_bundle, Some(dir.path().join("App.dSYM")));
+}
@@ -0,0 +1,76 @@
+//! The test fixture heuristic (`likely_test_fixture`, `--include-fixtures`)
+
+mod common;
+
+use std::path::Path;
+
+use symwalker::fixtures::{self, FixtureConfig};
+use symwalker::testutil::ElfBuilder;
+
+fn write(root: &Path, path: &str, data: &[u8]) {
+    let path = root.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, data).unwrap();
+}
+
+/// An application, next to a test tree and a test driver that runs a helper
+fn tree(root: &Path) {
+    let app = ElfBuilder::new().needed("libc.so.6").code_section(".text", &[0x90; 8192]).symbol("main").build();
+    write(root, "bin/app", &app);
+    write(root, "bin/NOTES.txt", b"app is the server; see src/app.c and \"myapp\"");
+    write(root, "testdata/elf/tiny", &ElfBuilder::new().build());
+    write(root, "tools/helper", &app);
+    write(root, "tools/run_test.sh", b"#!/bin/sh\nexec ./helper --selftest\n");
+}
+
+#[test]
+fn signals_are_recorded_per_binary() {
+    let dir = tempfile::tempdir().unwrap();
+    tree(dir.path());
+    let scan = |path: &str| common::scan_path(&dir.path().join(path), &[]).unwrap();
+    let mut binaries = vec![scan("bin/app"), scan("testdata/elf/tiny"), scan("tools/helper")];
+    fixtures::classify_all(&mut binaries, &[dir.path().to_path_buf()], &FixtureConfig::default());
+
+    let signals = |index: usize| -> Vec<(String, String)> {
+        binaries[index].fixture_signals.iter().map(|signal| (signal.signal.clone(), signal.detail.clone())).collect()
+    };
+    // NOTES.txt mentions app, but not as a file
+    assert!(!binaries[0].likely_test_fixture, "{:?}", signals(0));
+    assert!(binaries[1].likely_test_fixture);
+    assert_eq!(signals(1)[0], ("path".to_string(), "in a testdata directory".to_string()));
+    assert_eq!(signals(1)[1].0, "tiny");
+    assert_eq!(signals(2), [("source-reference".to_string(), "named in run_test.sh".to_string())]);
+
+    // The scan root's own directories don't count
+    let mut inside = vec![scan("testdata/elf/tiny")];
+    let config = FixtureConfig::parse("signals = [\"path\"]").unwrap();
+    fixtures::classify_all(&mut inside, &[dir.path().join("testdata")], &config);
+    assert!(!inside[0].likely_test_fixture);
+
+    assert!(FixtureConfig::parse("signals = [\"size\"]").unwrap_err().to_string().contains("unknown signal 'size'"));
+    assert!(FixtureConfig::parse("max_bytes = 1").is_err());
+    assert_eq!(FixtureConfig::parse("tiny_bytes = 100").unwrap().path_components, FixtureConfig::default().path_components);
+}
+
+#[test]
+fn fixtures_are_shown_and_left_out_of_fail_if() {
+    let dir = tempfile::tempdir().unwrap();
+    write(dir.path(), "tests/bad-rpath", &ElfBuilder::new().rpath("lib").symbol("main").build());
+
+    let output = common::symwalker(dir.path(), &["--json", "--fail-if", "insecure-rpath"]);
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report[0]["likely_test_fixture"], true);
+    assert_eq!(report[0]["fixture_signals"][0]["signal"], "path");
+    assert_eq!(common::symwalker(dir.path(), &["--json", "--fail-if", "insecure-rpath", "--include-fixtures"]).status.code(), Some(3));
+
+    let output = common::symwalker(dir.path(), &["-v"]);
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert!(text.contains("Test Fixture: likely (path: in a tests directory"), "{}", text);
+    assert!(text.contains("Likely test fixtures: 1"), "{}", text);
+
+    let config = dir.path().join("fixtures.toml");
+    std::fs::write(&config, "signals = []").unwrap();
+    let output = common::symwalker(dir.path(), &["--json", "--fail-if", "insecure-rpath", "--fixture-config", config.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(3));
+}
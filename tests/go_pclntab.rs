@@ -0,0 +1,62 @@
+//! Go runtime symbol tables in stripped binaries
+
+mod common;
+
+use symwalker::cli;
+use symwalker::go::{self, PclntabVersion};
+use symwalker::testutil::{go_pclntab, ElfBuilder, Endian, MachOBuilder};
+
+#[test]
+fn every_header_layout_is_parsed() {
+    for version in PclntabVersion::ALL {
+        for (endian, is_64) in [(Endian::Little, true), (Endian::Big, true), (Endian::Little, false)] {
+            let table = go_pclntab(version, 3, endian, is_64);
+            assert_eq!(go::parse(&table, endian == Endian::Little), Some((version, 3)), "{:?} {:?}", version, endian);
+        }
+    }
+
+    let table = go_pclntab(PclntabVersion::Go118, 3, Endian::Little, true);
+    assert_eq!(go::parse(&table, false), None);
+    // A function table running past the end of the section
+    assert_eq!(go::parse(&table[..table.len() - 4], true), None);
+    // Quantum 3 is no architecture's instruction size
+    let mut bad_quantum = table.clone();
+    bad_quantum[6] = 3;
+    assert_eq!(go::parse(&bad_quantum, true), None);
+}
+
+#[test]
+fn stripped_go_binaries_are_symbolizable_and_shown() {
+    // Go 1.16 layout, before entry PCs became offsets
+    let data = ElfBuilder::new()
+        .section(".gopclntab", &go_pclntab(PclntabVersion::Go116, 12, Endian::Little, true))
+        .build();
+    let info = common::scan_bytes("go116", &data, &[]).unwrap();
+    assert!(info.is_stripped);
+    assert!(info.has_go_pclntab);
+    assert_eq!(info.go_function_count, Some(12));
+    assert_eq!(info.debug_status(), "go-runtime-symbols");
+
+    let args = common::args(&[]);
+    assert!(cli::FILTERS.iter().all(|filter| !filter.rejects(&info, &args)));
+
+    let plain = common::scan_bytes("c", &ElfBuilder::new().build(), &[]).unwrap();
+    assert!(!plain.has_go_pclntab && plain.go_function_count.is_none());
+    assert_eq!(plain.debug_status(), "stripped");
+}
+
+#[test]
+fn pclntab_merged_into_noptrdata_is_found() {
+    // Current layout, after other data and without its own section
+    let noptrdata = [vec![0xaa; 36], go_pclntab(PclntabVersion::Go120, 40, Endian::Little, true)].concat();
+    let data = ElfBuilder::new().section(".noptrdata", &noptrdata).build();
+    let info = common::scan_bytes("go120", &data, &[]).unwrap();
+    assert_eq!(info.go_function_count, Some(40));
+
+    let data = MachOBuilder::new()
+        .section("__TEXT", "__gopclntab", &go_pclntab(PclntabVersion::Go118, 7, Endian::Little, true))
+        .build();
+    let info = common::scan_bytes("go-macho", &data, &[]).unwrap();
+    assert!(info.has_go_pclntab);
+    assert_eq!(info.go_function_count, Some(7));
+}
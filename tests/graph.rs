@@ -0,0 +1,49 @@
+//! Library dependency graph of the scanned tree (`--graph`)
+
+use std::path::Path;
+use std::process::Command;
+
+use symwalker::testutil::{ElfBuilder, MachOBuilder};
+
+fn write(root: &Path, path: &str, data: Vec<u8>) {
+    let path = root.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, data).unwrap();
+}
+
+#[test]
+fn dependencies_link_scanned_binaries_and_mark_the_rest_external() {
+    let dir = tempfile::tempdir().unwrap();
+    // Matched by SONAME, by file name, and not at all (libc)
+    write(dir.path(), "bin/app", ElfBuilder::new().needed("libfoo.so.1").needed("libc.so.6").symbol("main").build());
+    write(dir.path(), "lib/libfoo.so.1", ElfBuilder::new().e_type(3).soname("libfoo.so.1").needed("libbar.so.2").needed("libc.so.6").build());
+    write(dir.path(), "lib/libbar.so.2", ElfBuilder::new().e_type(3).build());
+    write(dir.path(), "App.app/App", MachOBuilder::new().import_from("_malloc", "/usr/lib/libSystem.B.dylib").build());
+    let graph = dir.path().join("deps.dot");
+
+    // Stripped libraries are filtered from the report, not the graph
+    let output = Command::new(env!("CARGO_BIN_EXE_symwalker"))
+        .env_clear()
+        .args(["--relative-paths", "--graph"])
+        .arg(&graph)
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(std::fs::read_to_string(&graph).unwrap(), r#"digraph dependencies {
+    rankdir=LR;
+    node [shape=box];
+    n0 [label="App.app/App"];
+    n1 [label="bin/app"];
+    n2 [label="lib/libbar.so.2", shape=ellipse];
+    n3 [label="lib/libfoo.so.1", shape=ellipse];
+    x0 [label="/usr/lib/libSystem.B.dylib", shape=ellipse, style=dashed, color=gray50, fontcolor=gray50];
+    x1 [label="libc.so.6", shape=ellipse, style=dashed, color=gray50, fontcolor=gray50];
+    n0 -> x0 [style=dashed, color=gray50];
+    n1 -> n3;
+    n1 -> x1 [style=dashed, color=gray50];
+    n3 -> n2;
+    n3 -> x1 [style=dashed, color=gray50];
+}
+"#);
+}
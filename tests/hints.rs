@@ -0,0 +1,73 @@
+//! Next-step hints after the human summary
+
+use symwalker::binary::{BinaryInfo, ScanWarning};
+use symwalker::hints::{self, RunOptions, MAX_HINTS};
+use symwalker::output::Summary;
+
+fn elf(build_id: Option<&str>, is_stripped: bool) -> BinaryInfo {
+    BinaryInfo {
+        binary_type: "ELF".to_string(),
+        build_id: build_id.map(str::to_string),
+        is_stripped,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn check_remote_for_stripped_binaries_with_build_ids() {
+    let found = Summary::new(&[elf(Some("ab"), true), elf(Some("cd"), true), elf(None, true)]);
+    let hint = hints::check_remote(&found, &RunOptions::default()).unwrap();
+    assert!(hint.starts_with("2 stripped binaries") && hint.contains("--check-remote"), "{}", hint);
+
+    let checked = RunOptions { check_remote: true, ..Default::default() };
+    assert!(hints::check_remote(&found, &checked).is_none());
+    assert!(hints::check_remote(&Summary::new(&[elf(Some("ab"), false)]), &RunOptions::default()).is_none());
+}
+
+#[test]
+fn download_remote_when_remote_symbols_were_found() {
+    let mut binary = elf(Some("ab"), true);
+    binary.debuginfod_available = Some(true);
+    let found = Summary::new(&[binary]);
+    let checked = RunOptions { check_remote: true, ..Default::default() };
+    let hint = hints::download_remote(&found, &checked).unwrap();
+    assert!(hint.contains("1 binary") && hint.contains("--download-remote --output"), "{}", hint);
+
+    let downloading = RunOptions { check_remote: true, download_remote: true };
+    assert!(hints::download_remote(&found, &downloading).is_none());
+}
+
+#[test]
+fn stale_debug_files_point_at_the_warning_code() {
+    let mut binary = elf(Some("ab"), true);
+    binary.warnings.push(ScanWarning::new("debug-file-mismatch", "app.debug has build-id cd, expected ab"));
+    let hint = hints::stale_debug_files(&Summary::new(&[binary]), &RunOptions::default()).unwrap();
+    assert!(hint.contains("debug-file-mismatch"), "{}", hint);
+
+    assert!(hints::stale_debug_files(&Summary::new(&[elf(Some("ab"), true)]), &RunOptions::default()).is_none());
+}
+
+#[test]
+fn missing_build_ids_suggest_the_linker_flag_when_most_lack_one() {
+    let most = Summary::new(&[elf(None, false), elf(None, false), elf(Some("ab"), false)]);
+    let hint = hints::missing_build_ids(&most, &RunOptions::default()).unwrap();
+    assert!(hint.starts_with("2 of 3") && hint.contains("-Wl,--build-id"), "{}", hint);
+
+    let half = Summary::new(&[elf(None, false), elf(Some("ab"), false)]);
+    assert!(hints::missing_build_ids(&half, &RunOptions::default()).is_none());
+}
+
+#[test]
+fn hints_are_capped() {
+    let mut remote = elf(Some("ab"), true);
+    remote.debuginfod_available = Some(true);
+    remote.warnings.push(ScanWarning::new("debug-file-mismatch", ""));
+    let binaries = [remote, elf(Some("cd"), true), elf(None, false), elf(None, false), elf(None, false), elf(None, false)];
+
+    let found = Summary::new(&binaries);
+    assert!(hints::RULES.iter().all(|rule| rule(&found, &RunOptions::default()).is_some()));
+    let shown = hints::hints(&found, &RunOptions::default());
+    assert_eq!(shown.len(), MAX_HINTS);
+    assert!(shown[0].contains("--check-remote"));
+    assert!(hints::hints(&Summary::new(&[]), &RunOptions::default()).is_empty());
+}
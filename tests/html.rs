@@ -0,0 +1,45 @@
+//! Golden file for the `--html-report` layout
+//!
+//! Update with `INSTA_UPDATE=always cargo test --test html` after an
+//! intentional template change.
+
+mod common;
+
+use symwalker::binary::BinaryInfo;
+use symwalker::html::{self, HtmlFormatter};
+use symwalker::output::OutputFormatter;
+use symwalker::testutil::{ElfBuilder, MachOBuilder};
+
+/// Scan `data`, pinning the host-dependent fields
+fn binary(path: &str, data: &[u8]) -> BinaryInfo {
+    let mut info = common::scan_bytes("bin", data, &[]).unwrap();
+    info.file_path = path.into();
+    info.file_modified = Default::default();
+    info
+}
+
+fn render(binaries: &[BinaryInfo]) -> String {
+    let mut out = Vec::new();
+    HtmlFormatter.format(binaries, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn report_layout() {
+    let binaries = [
+        binary("/opt/app/bin/server", &ElfBuilder::new().e_type(3).interp("/lib64/ld-linux-x86-64.so.2").needed("libc.so.6").relro().symbol("main").build()),
+        binary("/opt/app/lib/libfoo.so", &ElfBuilder::new().e_type(3).section(".debug_info", &[0; 8]).build()),
+        binary("/opt/app/Frameworks/Helper", &MachOBuilder::new().build()),
+    ];
+
+    insta::assert_snapshot!(render(&binaries));
+}
+
+#[test]
+fn paths_are_escaped() {
+    let report = render(&[binary("/tmp/<script>&\"x\".so", &ElfBuilder::new().build())]);
+
+    assert!(!report.contains("<script>&"));
+    assert!(report.contains("/tmp/&lt;script&gt;&amp;&quot;x&quot;.so"));
+    assert_eq!(html::escape("it's"), "it&#39;s");
+}
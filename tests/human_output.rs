@@ -0,0 +1,117 @@
+//! The human-readable report, byte for byte, with and without colors and
+//! in each `--time-format`
+
+mod common;
+
+use std::process::Command;
+
+use chrono::{Duration, TimeZone, Utc};
+use symwalker::binary::BinaryInfo;
+use symwalker::output::{HumanFormatter, OutputFormatter, TimeFormat};
+use symwalker::testutil::{ElfBuilder, MachOBuilder};
+
+/// Scans with most blocks filled in, under fixed paths and times
+fn binaries() -> Vec<BinaryInfo> {
+    let elf = ElfBuilder::new()
+        .build_id(&[0xab; 20])
+        .interp("/lib64/ld-linux-x86-64.so.2")
+        .needed("libc.so.6")
+        .rpath("$ORIGIN/../lib")
+        .import_versioned("strcpy", "libc.so.6", "GLIBC_2.2.5")
+        .gnu_hash()
+        .tls(&[0; 16])
+        .symbol("main")
+        .local_symbol("helper")
+        .build();
+    let macho = MachOBuilder::new().import_from("_malloc", "/usr/lib/libSystem.B.dylib").symbol("_main").build();
+
+    let mut binaries = vec![
+        common::scan_bytes("server", &elf, &["--security", "--imports"]).unwrap(),
+        common::scan_bytes("App", &macho, &["--security"]).unwrap(),
+    ];
+    for (index, info) in binaries.iter_mut().enumerate() {
+        info.file_path = format!("/srv/tree/bin{}", index).into();
+        info.file_modified = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+    }
+    binaries
+}
+
+fn render(binaries: &[BinaryInfo], verbosity: u8) -> String {
+    let mut out = Vec::new();
+    HumanFormatter::new(verbosity).format(binaries, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+// Both in one test: the color override is process-wide
+#[test]
+fn blocks_render_the_same_with_and_without_colors() {
+    let binaries = binaries();
+
+    colored::control::set_override(true);
+    let colored = render(&binaries, 2);
+    colored::control::set_override(false);
+    let plain = render(&binaries, 2);
+    colored::control::unset_override();
+
+    assert!(colored.contains("\u{1b}["));
+    assert!(!plain.contains("\u{1b}["));
+    insta::assert_snapshot!("colored", colored.escape_debug().to_string().replace("\\n", "\n"));
+    insta::assert_snapshot!("plain", plain);
+}
+
+#[test]
+fn modification_times_follow_the_time_format() {
+    let mut info = common::scan_bytes("app", &ElfBuilder::new().symbol("main").build(), &[]).unwrap();
+    info.file_path = "/srv/tree/app".into();
+    info.file_modified = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+    let now = Utc.with_ymd_and_hms(2024, 5, 4, 13, 30, 0).unwrap();
+
+    // Colors may be forced on by the other test meanwhile
+    let plain = |text: String| text.split('\u{1b}').enumerate()
+        .map(|(index, part)| if index == 0 { part } else { part.split_once('m').map_or(part, |(_, rest)| rest) })
+        .collect::<String>();
+    for (name, time_format) in [("utc", TimeFormat::Utc), ("relative", TimeFormat::Relative), ("epoch", TimeFormat::Epoch)] {
+        let mut out = Vec::new();
+        HumanFormatter::new(0).time_format(time_format).relative_to(now).format(std::slice::from_ref(&info), &mut out).unwrap();
+        insta::assert_snapshot!(format!("time_format_{}", name), plain(String::from_utf8(out).unwrap()));
+    }
+
+    let relative = |seconds| TimeFormat::Relative.render(&(now - Duration::seconds(seconds)), &now);
+    assert_eq!(relative(5), "just now");
+    assert_eq!(relative(60), "1 minute ago");
+    assert_eq!(relative(2 * 3600 + 59), "2 hours ago");
+    assert_eq!(relative(400 * 86400), "1 year ago");
+    assert_eq!(relative(-3 * 86400), "in 3 days");
+}
+
+#[test]
+fn local_times_use_tz_and_survive_a_bogus_one() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app");
+    std::fs::write(&path, ElfBuilder::new().symbol("main").build()).unwrap();
+    let modified = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_714_564_800); // 2024-05-01 12:00:00 UTC
+    std::fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+
+    let modified_line = |tz: &str| {
+        let output = Command::new(env!("CARGO_BIN_EXE_symwalker"))
+            .env_clear()
+            .env("TZ", tz)
+            .args(["-v", "--time-format", "local"])
+            .arg(dir.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        stdout.lines().find(|line| line.contains("Modified:")).unwrap().trim().to_string()
+    };
+    assert_eq!(modified_line("IST-5:30"), "Modified: 2024-05-01 17:30:00 +05:30");
+    assert_eq!(modified_line("UTC0"), "Modified: 2024-05-01 12:00:00 +00:00");
+    // No time zone data to find: still a time, not a panic
+    assert!(modified_line("Nowhere/Bogus").starts_with("Modified: 2024-05-01"));
+
+    // JSON carries both forms
+    let output = Command::new(env!("CARGO_BIN_EXE_symwalker")).env_clear().args(["--json"]).arg(dir.path()).output().unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report[0]["file_modified"], "2024-05-01T12:00:00Z");
+    assert_eq!(report[0]["file_modified_epoch"], 1_714_564_800);
+}
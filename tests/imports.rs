@@ -0,0 +1,83 @@
+//! Imported functions by providing library (`--imports`)
+
+mod common;
+
+use symwalker::imports::{self, ImportedSymbol};
+use symwalker::output::{HumanFormatter, OutputFormatter, Summary};
+use symwalker::testutil::{ElfBuilder, MachOBuilder};
+
+fn import(name: &str, demangled: Option<&str>, version: Option<&str>, library: Option<&str>) -> ImportedSymbol {
+    ImportedSymbol {
+        name: name.to_string(),
+        demangled: demangled.map(str::to_string),
+        version: version.map(str::to_string),
+        library: library.map(str::to_string),
+    }
+}
+
+fn show(info: &symwalker::binary::BinaryInfo, verbosity: u8) -> String {
+    let mut out = Vec::new();
+    HumanFormatter::new(verbosity).format(std::slice::from_ref(info), &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn elf_imports_are_grouped_by_symbol_version() {
+    let data = ElfBuilder::new()
+        .import_versioned("memcpy", "libc.so.6", "GLIBC_2.14")
+        .import_versioned("puts", "libc.so.6", "GLIBC_2.2.5")
+        .import_versioned("_ZNSt8ios_base4InitC1Ev", "libstdc++.so.6", "GLIBCXX_3.4")
+        .import_versioned("inflate", "libz.so.1", "ZLIB_1.2.0")
+        .import("plugin_init")
+        .build();
+
+    let info = common::scan_bytes("app", &data, &["--imports"]).unwrap();
+    assert_eq!(info.imports.unwrap(), [
+        import("plugin_init", None, None, None),
+        import("memcpy", None, Some("GLIBC_2.14"), Some("glibc")),
+        import("puts", None, Some("GLIBC_2.2.5"), Some("glibc")),
+        import("_ZNSt8ios_base4InitC1Ev", Some("std::ios_base::Init::Init()"), Some("GLIBCXX_3.4"), Some("libstdc++")),
+        import("inflate", None, Some("ZLIB_1.2.0"), Some("libz.so.1")),
+    ]);
+
+    // Left out of the default JSON
+    assert_eq!(common::scan_bytes("app", &data, &[]).unwrap().imports, None);
+}
+
+#[test]
+fn macho_imports_name_their_dylib() {
+    let data = MachOBuilder::new()
+        .import_from("_malloc", "/usr/lib/libSystem.B.dylib")
+        .import_from("_objc_msgSend", "/usr/lib/libobjc.A.dylib")
+        .import("_dlsym_lookup")
+        .symbol("_main")
+        .build();
+
+    let info = common::scan_bytes("App", &data, &["--imports"]).unwrap();
+    assert_eq!(info.imports.unwrap(), [
+        import("dlsym_lookup", None, None, None),
+        import("malloc", None, None, Some("libSystem.B.dylib")),
+        import("objc_msgSend", None, None, Some("libobjc.A.dylib")),
+    ]);
+}
+
+#[test]
+fn counts_by_default_and_the_list_with_vv() {
+    let data = ElfBuilder::new()
+        .import_versioned("memcpy", "libc.so.6", "GLIBC_2.14")
+        .import_versioned("inflate", "libz.so.1", "ZLIB_1.2.0")
+        .symbol("main")
+        .build();
+    let info = common::scan_bytes("app", &data, &["--imports"]).unwrap();
+
+    let default = show(&info, 0);
+    assert!(default.contains("Imports: 2 (glibc 1, libz.so.1 1)"), "{}", default);
+    assert!(!default.contains("memcpy"));
+    let list = show(&info, 2);
+    assert!(list.lines().any(|line| line.contains("memcpy@GLIBC_2.14") && line.contains("glibc")), "{}", list);
+
+    let other = common::scan_bytes("tool", &ElfBuilder::new().import_versioned("memcpy", "libc.so.6", "GLIBC_2.14").build(), &["--imports"]).unwrap();
+    assert_eq!(imports::most_common(&[info.clone(), other.clone()], 10), [("memcpy".to_string(), 2), ("inflate".to_string(), 1)]);
+    assert_eq!(Summary::new(&[info, other]).common_imports.len(), 2);
+    assert!(Summary::new(&[]).common_imports.is_empty());
+}
@@ -0,0 +1,57 @@
+//! Diagnostics on stderr (`--log-level`, `RUST_LOG`)
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use log::Level;
+use symwalker::logging::Filter;
+use symwalker::testutil::ElfBuilder;
+
+fn symwalker(dir: &Path, flags: &[&str], rust_log: Option<&str>) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_symwalker"));
+    command.env_clear().args(flags).arg(dir);
+    if let Some(spec) = rust_log {
+        command.env("RUST_LOG", spec);
+    }
+    command.output().unwrap()
+}
+
+#[test]
+fn rust_log_directives_pick_levels_per_module() {
+    let filter = Filter::parse("warn, symwalker::debuginfod=debug,symwalker::debuginfod::server=trace,nonsense=loud");
+    assert!(filter.enabled("symwalker::cli", Level::Warn));
+    assert!(!filter.enabled("symwalker::cli", Level::Info));
+    assert!(filter.enabled("symwalker::debuginfod", Level::Debug));
+    assert!(!filter.enabled("symwalker::debuginfod", Level::Trace));
+    assert!(filter.enabled("symwalker::debuginfod::server", Level::Trace));
+    // Module names match whole path segments
+    assert!(!filter.enabled("symwalker::debuginfodx", Level::Debug));
+
+    let module_only = Filter::parse("symwalker::symbol_finder");
+    assert!(module_only.enabled("symwalker::symbol_finder", Level::Trace));
+    assert!(!module_only.enabled("symwalker::cli", Level::Error));
+}
+
+#[test]
+fn probes_and_skips_go_to_stderr() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("app"), ElfBuilder::new().build_id(&[0x7c; 20]).build()).unwrap();
+    std::fs::write(dir.path().join("notes.txt"), b"not a binary, but long enough to be read").unwrap();
+
+    let output = symwalker(dir.path(), &["--json", "--log-level", "trace"], None);
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.lines().any(|line| line.contains("build-id: probed") && line.contains(&"7c".repeat(19))), "{}", stderr);
+    assert!(stderr.lines().any(|line| line.contains("skipped") && line.contains("notes.txt")), "{}", stderr);
+    assert!(stderr.lines().any(|line| line.contains("filtered out") && line.contains("app")), "{}", stderr);
+    // The report is untouched
+    serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap();
+
+    let from_env = symwalker(dir.path(), &["--json"], Some("symwalker::cli=debug"));
+    let stderr = String::from_utf8_lossy(&from_env.stderr);
+    assert!(stderr.contains("notes.txt") && !stderr.contains("probed"), "{}", stderr);
+
+    // --log-level wins, and nothing is logged by default
+    assert!(symwalker(dir.path(), &["--json", "--log-level", "off"], Some("trace")).stderr.is_empty());
+    assert!(symwalker(dir.path(), &["--json"], None).stderr.is_empty());
+}
@@ -0,0 +1,189 @@
+//! Files with a valid magic that the full parse rejects, or accepts with
+//! parts missing
+
+mod common;
+
+use symwalker::output::{HumanFormatter, OutputFormatter};
+use symwalker::testutil::{ElfBuilder, FatBuilder, MachOBuilder, CPU_TYPE_ARM64, CPU_TYPE_X86_64};
+
+/// `e_shoff` of a 64-bit ELF pointed far past the end of the file
+fn elf_with_bad_section_headers() -> Vec<u8> {
+    let mut data = ElfBuilder::new().e_type(3).machine(183).entry(0x4010).symbol("main").build();
+    data[0x28..0x30].copy_from_slice(&0x7fff_0000u64.to_le_bytes());
+    data
+}
+
+/// 64-bit ELF with a build-id, `.symtab` and `.debug_info` whose
+/// `e_shstrndx` is replaced; its PT_NOTE is blanked so the build-id can
+/// only come from the SHT_NOTE section
+fn elf_with_shstrndx(shstrndx: u16) -> Vec<u8> {
+    let mut data = ElfBuilder::new()
+        .build_id(&[0xab; 20])
+        .section(".debug_info", &[0; 16])
+        .symbol("main")
+        .build();
+    data[0x3e..0x40].copy_from_slice(&shstrndx.to_le_bytes());
+    let phoff = u64::from_le_bytes(data[0x20..0x28].try_into().unwrap()) as usize;
+    assert_eq!(data[phoff..phoff + 4], 4u32.to_le_bytes(), "first program header is the PT_NOTE");
+    data[phoff..phoff + 4].copy_from_slice(&0u32.to_le_bytes());
+    data
+}
+
+/// 64-bit little-endian ELF with every `sh_type` section and `p_type`
+/// segment moved to `offset`
+fn elf_with_offsets(mut data: Vec<u8>, sh_type: u32, p_type: u32, offset: u64) -> Vec<u8> {
+    let word = |data: &[u8], at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap()) as usize;
+    let half = |data: &[u8], at: usize| u16::from_le_bytes(data[at..at + 2].try_into().unwrap()) as usize;
+    let (phoff, shoff) = (word(&data, 0x20), word(&data, 0x28));
+    for index in 0..half(&data, 0x38) {
+        let ph = phoff + index * 56;
+        if data[ph..ph + 4] == p_type.to_le_bytes() {
+            data[ph + 8..ph + 16].copy_from_slice(&offset.to_le_bytes());
+        }
+    }
+    for index in 0..half(&data, 0x3c) {
+        let sh = shoff + index * 64;
+        if data[sh + 4..sh + 8] == sh_type.to_le_bytes() {
+            data[sh + 24..sh + 32].copy_from_slice(&offset.to_le_bytes());
+        }
+    }
+    data
+}
+
+/// `ncmds` claiming far more load commands than the file holds
+fn macho_with_truncated_load_commands() -> Vec<u8> {
+    let mut data = MachOBuilder::new().filetype(2).build();
+    data[16..20].copy_from_slice(&1000u32.to_le_bytes());
+    data
+}
+
+#[test]
+fn unparseable_elf_keeps_its_header_fields() {
+    let info = common::scan_bytes("broken", &elf_with_bad_section_headers(), &[]).unwrap();
+
+    assert!(info.partial_analysis);
+    assert!(info.parse_error.is_some());
+    assert_eq!(info.binary_type, "ELF");
+    assert_eq!(info.architecture, "aarch64");
+    assert_eq!(info.architecture_raw, "AArch64");
+    assert_eq!(info.machine_code, Some(183));
+    assert_eq!(info.entry_point.as_deref(), Some("0x4010"));
+    assert!(info.is_64bit && info.is_library);
+    assert_eq!(info.warnings.len(), 1);
+    assert_eq!(info.warnings[0].code, "partial-analysis");
+}
+
+#[test]
+fn unparseable_macho_keeps_its_header_fields() {
+    let info = common::scan_bytes("broken", &macho_with_truncated_load_commands(), &[]).unwrap();
+
+    assert!(info.partial_analysis);
+    assert_eq!(info.binary_type, "Mach-O");
+    assert_eq!(info.architecture, "x86_64");
+    assert!(info.is_64bit && info.is_executable);
+}
+
+#[test]
+fn reserved_macho_header_flags_are_reported() {
+    // MH_PIE and MH_TWOLEVEL, plus 0x10000000 and 0x40000000, which no
+    // header flag is defined as
+    let data = MachOBuilder::new().flags(0x0020_0080 | 0x5000_0000).build();
+    let info = common::scan_bytes("app", &data, &[]).unwrap();
+    assert_eq!(info.unknown_header_flags, 0x5000_0000);
+    assert!(info.is_pie);
+
+    let show = |info: &symwalker::binary::BinaryInfo, verbosity| {
+        let mut out = Vec::new();
+        HumanFormatter::new(verbosity).format(std::slice::from_ref(info), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    assert!(show(&info, 1).contains("Unknown Header Flags: 0x50000000"));
+    assert!(!show(&info, 0).contains("Unknown Header Flags"));
+
+    let clean = common::scan_bytes("app", &MachOBuilder::new().flags(0x8020_0085).build(), &[]).unwrap();
+    assert_eq!(clean.unknown_header_flags, 0);
+    assert!(!show(&clean, 1).contains("Unknown Header Flags"));
+
+    // Also read from headers the full parse rejects
+    let mut broken = MachOBuilder::new().flags(0x2000_0000).build();
+    broken[16..20].copy_from_slice(&1000u32.to_le_bytes());
+    let info = common::scan_bytes("broken", &broken, &[]).unwrap();
+    assert!(info.partial_analysis);
+    assert_eq!(info.unknown_header_flags, 0x2000_0000);
+}
+
+#[test]
+fn unparseable_fat_slice_uses_the_fat_header() {
+    let mut slice = MachOBuilder::new().cpu(CPU_TYPE_ARM64, 0).build();
+    slice[16..20].copy_from_slice(&1000u32.to_le_bytes());
+    let data = FatBuilder::new().slice(CPU_TYPE_ARM64, 0, slice).build();
+
+    let info = common::scan_bytes("broken", &data, &[]).unwrap();
+
+    assert!(info.partial_analysis);
+    assert_eq!(info.architecture, "aarch64");
+}
+
+#[test]
+fn strict_parse_drops_unparseable_files() {
+    for data in [elf_with_bad_section_headers(), macho_with_truncated_load_commands()] {
+        assert!(common::scan_bytes("broken", &data, &["--strict-parse"]).is_err());
+    }
+}
+
+#[test]
+fn well_formed_files_are_fully_analyzed() {
+    let data = ElfBuilder::new().symbol("main").build();
+    let info = common::scan_bytes("ok", &data, &[]).unwrap();
+    assert!(!info.partial_analysis);
+    assert_eq!(info.parse_error, None);
+
+    let data = MachOBuilder::new().cpu(CPU_TYPE_X86_64, 3).build();
+    assert!(!common::scan_bytes("ok", &data, &["--strict-parse"]).unwrap().partial_analysis);
+}
+
+#[test]
+fn missing_section_names_fall_back_to_section_types() {
+    // SHN_UNDEF, past the last section, and `.debug_info` (SHT_PROGBITS)
+    for shstrndx in [0, 0x7fff, 2] {
+        let info = common::scan_bytes("obfuscated", &elf_with_shstrndx(shstrndx), &[]).unwrap();
+
+        assert!(!info.partial_analysis, "e_shstrndx {}: {:?}", shstrndx, info.parse_error);
+        assert!(info.section_names_unavailable, "e_shstrndx {}", shstrndx);
+        assert_eq!(info.build_id, Some("ab".repeat(20)));
+        assert!(!info.is_stripped);
+        assert_eq!(info.has_debug_info, None);
+        assert!(info.debug_sections.is_empty());
+        let codes: Vec<&str> = info.warnings.iter().map(|w| w.code.as_str()).collect();
+        assert_eq!(codes, ["section-names-unavailable"], "e_shstrndx {}", shstrndx);
+    }
+
+    // `.shstrtab` is the last section
+    let data = elf_with_shstrndx(0);
+    let shnum = u16::from_le_bytes([data[0x3c], data[0x3d]]);
+    let info = common::scan_bytes("ok", &elf_with_shstrndx(shnum - 1), &[]).unwrap();
+    assert!(!info.section_names_unavailable);
+    assert_eq!(info.has_debug_info, Some(true));
+    assert_eq!(info.build_id, Some("ab".repeat(20)));
+}
+
+#[test]
+fn note_offsets_that_wrap_are_skipped() {
+    const SHT_NOTE: u32 = 7;
+    const PT_NOTE: u32 = 4;
+    let data = elf_with_offsets(ElfBuilder::new().build_id(&[0xab; 20]).symbol("main").build(), SHT_NOTE, PT_NOTE, u64::MAX - 15);
+    let info = common::scan_bytes("app", &data, &[]).unwrap();
+    assert_eq!(info.build_id, None);
+    let info = common::scan_bytes("app", &data, &["-vv"]).unwrap();
+    assert_eq!(info.build_id, None);
+}
+
+#[test]
+fn debuglink_and_interpreter_offsets_that_wrap_are_skipped() {
+    const SHT_PROGBITS: u32 = 1;
+    const PT_INTERP: u32 = 3;
+    let data = ElfBuilder::new().interp("/lib64/ld-linux-x86-64.so.2").debuglink("app.debug", b"").symbol("main").build();
+    let data = elf_with_offsets(data, SHT_PROGBITS, PT_INTERP, u64::MAX - 15);
+    let info = common::scan_bytes("app", &data, &[]).unwrap();
+    assert_eq!((info.gnu_debuglink, info.interpreter), (None, None));
+}
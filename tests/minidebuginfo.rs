@@ -0,0 +1,48 @@
+//! MiniDebugInfo (`.gnu_debugdata`) in stripped Fedora/RHEL binaries
+
+mod common;
+
+use symwalker::cli;
+use symwalker::elf;
+use symwalker::output::{HumanFormatter, OutputFormatter};
+use symwalker::testutil::ElfBuilder;
+
+fn xz(data: &[u8]) -> Vec<u8> {
+    let mut compressed = Vec::new();
+    lzma_rs::xz_compress(&mut &data[..], &mut compressed).unwrap();
+    compressed
+}
+
+#[test]
+fn stripped_binaries_with_minidebuginfo_are_kept_and_shown() {
+    let symbols = ElfBuilder::new().symbol("main").local_symbol("helper").symbol("parse").build();
+    let data = ElfBuilder::new().section(".gnu_debugdata", &xz(&symbols)).build();
+    let info = common::scan_bytes("app", &data, &[]).unwrap();
+    assert!(info.is_stripped);
+    assert!(info.has_minidebuginfo);
+    assert_eq!(info.minidebuginfo_symbols, Some(3));
+    assert_eq!(info.debug_status(), "minidebuginfo");
+
+    let args = common::args(&[]);
+    assert!(cli::FILTERS.iter().all(|filter| !filter.rejects(&info, &args)));
+
+    let mut out = Vec::new();
+    HumanFormatter::new(0).format(std::slice::from_ref(&info), &mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains("MiniDebugInfo: present ("), "{}", text);
+
+    let plain = common::scan_bytes("c", &ElfBuilder::new().build(), &[]).unwrap();
+    assert!(!plain.has_minidebuginfo && plain.minidebuginfo_symbols.is_none());
+    assert_eq!(plain.debug_status(), "stripped");
+}
+
+#[test]
+fn undecodable_minidebuginfo_is_still_present() {
+    let data = ElfBuilder::new().section(".gnu_debugdata", b"\xfd7zXZ\0 truncated").build();
+    let info = common::scan_bytes("app", &data, &[]).unwrap();
+    assert!(info.has_minidebuginfo);
+    assert_eq!(info.minidebuginfo_symbols, None);
+
+    // An xz stream that isn't an ELF inside
+    assert_eq!(elf::minidebuginfo_symbols(&xz(b"not an ELF file")), None);
+}
@@ -0,0 +1,205 @@
+//! ELF notes, read in the file's byte order
+
+mod common;
+
+use symwalker::debuginfod::Server;
+use symwalker::elf::{build_id_type, decode_abi_tag};
+use symwalker::binary::BinaryInfo;
+use symwalker::cli::CountField;
+use symwalker::output::{HumanFormatter, OutputFormatter, Summary};
+use symwalker::symbol_finder::Strategy;
+use symwalker::testutil::{ElfBuilder, Endian};
+
+#[test]
+fn abi_tag_words_follow_the_file_byte_order() {
+    let words = [0u32, 3, 2, 0];
+    let little: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+    let big: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+    let expected = Some(("Linux".to_string(), "3.2.0".to_string()));
+    assert_eq!(decode_abi_tag(&little, true), expected);
+    assert_eq!(decode_abi_tag(&big, false), expected);
+    // Read in the wrong order, the version is nonsense
+    assert_eq!(decode_abi_tag(&little, false).unwrap().1, format!("{}.{}.0", 3u32.swap_bytes(), 2u32.swap_bytes()));
+
+    let freebsd: Vec<u8> = [3u32, 13, 2, 1].iter().flat_map(|word| word.to_le_bytes()).collect();
+    assert_eq!(decode_abi_tag(&freebsd, true), Some(("FreeBSD".to_string(), "13.2.1".to_string())));
+    assert_eq!(decode_abi_tag(&little[..12], true), None);
+}
+
+#[test]
+fn big_endian_notes_are_found() {
+    for endian in [Endian::Little, Endian::Big] {
+        let data = ElfBuilder::new().endian(endian).build_id(&[0xab; 20]).abi_tag(0, [4, 19, 7]).build();
+        let info = common::scan_bytes("app", &data, &[]).unwrap();
+        assert_eq!(info.build_id.as_deref(), Some("ab".repeat(20).as_str()), "{:?}", endian);
+        assert_eq!((info.abi_os.as_deref(), info.min_kernel_version.as_deref()), (Some("Linux"), Some("4.19.7")), "{:?}", endian);
+    }
+
+    let untagged = common::scan_bytes("app", &ElfBuilder::new().build(), &[]).unwrap();
+    assert_eq!((untagged.abi_os, untagged.min_kernel_version), (None, None));
+}
+
+#[test]
+fn verbose_shows_the_minimum_kernel() {
+    let info = common::scan_bytes("app", &ElfBuilder::new().abi_tag(0, [3, 2, 0]).build(), &[]).unwrap();
+    let show = |verbosity| {
+        let mut out = Vec::new();
+        HumanFormatter::new(verbosity).format(std::slice::from_ref(&info), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    assert!(show(1).contains("ABI Tag: Linux 3.2.0 or later"));
+    assert!(!show(0).contains("ABI Tag"));
+}
+
+#[test]
+fn build_ids_of_every_length_are_looked_up() {
+    // --build-id=md5 and --build-id=sha256 style ids
+    for (id, kind) in [(vec![0x3c; 16], "md5"), (vec![0x7e; 32], "sha256")] {
+        let hex = hex::encode(&id);
+        let sysroot = tempfile::tempdir().unwrap();
+        let binary = sysroot.path().join("usr/bin/app");
+        let debug_file = sysroot.path().join("usr/lib/debug/.build-id").join(&hex[..2]).join(format!("{}.debug", &hex[2..]));
+        std::fs::create_dir_all(binary.parent().unwrap()).unwrap();
+        std::fs::create_dir_all(debug_file.parent().unwrap()).unwrap();
+        std::fs::write(&binary, ElfBuilder::new().build_id(&id).build()).unwrap();
+        std::fs::write(&debug_file, ElfBuilder::new().build_id(&id).symbol("main").build()).unwrap();
+
+        let info = common::scan_path(&binary, &["--sysroot", sysroot.path().to_str().unwrap()]).unwrap();
+        assert_eq!(info.build_id.as_deref(), Some(hex.as_str()));
+        assert_eq!(info.build_id_type.as_deref(), Some(kind));
+        assert_eq!(info.debug_file_path, Some(debug_file));
+        assert_eq!(info.debug_file_source, Some(Strategy::BuildId));
+        assert!(info.warnings.is_empty(), "{:?}", info.warnings);
+
+        let server = Server::parse("https://debuginfod.example.org/").unwrap();
+        assert_eq!(server.debuginfo_url(&hex), format!("https://debuginfod.example.org/buildid/{}/debuginfo", hex));
+    }
+}
+
+#[test]
+fn build_id_types_follow_the_length() {
+    assert_eq!(build_id_type(&"ab".repeat(20)), "sha1");
+    assert_eq!(build_id_type(&"3c".repeat(16)), "md5");
+    // Version 4, variant 10xx: what --build-id=uuid writes
+    assert_eq!(build_id_type("6f1c2a9e0b3d4e7fa1c2d3e4f5a6b7c8"), "uuid");
+    assert_eq!(build_id_type(&"01".repeat(8)), "xxhash");
+    assert_eq!(build_id_type(&"7e".repeat(32)), "sha256");
+    assert_eq!(build_id_type("deadbeef"), "hex");
+
+    let info = common::scan_bytes("app", &ElfBuilder::new().build_id(&[0x3c; 16]).build(), &[]).unwrap();
+    let mut out = Vec::new();
+    HumanFormatter::new(1).format(std::slice::from_ref(&info), &mut out).unwrap();
+    assert!(String::from_utf8(out).unwrap().contains("(md5, 16 bytes)"));
+}
+
+/// A little-endian note entry
+fn note_entry(owner: &str, note_type: u32, desc: &[u8]) -> Vec<u8> {
+    let owner = [owner.as_bytes(), b"\0"].concat();
+    let padded = |bytes: &[u8]| [bytes, &vec![0; (4 - bytes.len() % 4) % 4]].concat();
+    [
+        (owner.len() as u32).to_le_bytes().as_slice(),
+        &(desc.len() as u32).to_le_bytes(),
+        &note_type.to_le_bytes(),
+        &padded(&owner),
+        &padded(desc),
+    ].concat()
+}
+
+#[test]
+fn very_verbose_scans_list_every_note() {
+    let probes = [note_entry("stapsdt", 3, &[0; 40]), note_entry("stapsdt", 3, &[0; 44])].concat();
+    // A good entry, then one claiming more bytes than the section has
+    let broken = [note_entry("Vendor", 7, &[1; 4]), 64u32.to_le_bytes().to_vec(), 4u32.to_le_bytes().to_vec(), 1u32.to_le_bytes().to_vec()].concat();
+    let data = ElfBuilder::new()
+        .build_id(&[0xab; 20])
+        .note(".note.gnu.gold-version", "GNU", 4, b"gold 1.16\0")
+        .raw_note(".note.stapsdt", &probes)
+        .raw_note(".note.broken", &broken)
+        .abi_tag(0, [3, 2, 0])
+        .build();
+
+    let info = common::scan_bytes("app", &data, &["-vv"]).unwrap();
+    let notes = info.notes.as_ref().unwrap();
+    let summary: Vec<_> = notes.iter()
+        .map(|note| (note.location.as_str(), note.owner.as_str(), note.type_name.as_deref(), note.desc_size, note.malformed.is_some()))
+        .collect();
+    assert_eq!(summary, [
+        (".note.gnu.build-id", "GNU", Some("NT_GNU_BUILD_ID"), 20, false),
+        (".note.gnu.gold-version", "GNU", Some("NT_GNU_GOLD_VERSION"), 10, false),
+        (".note.stapsdt", "stapsdt", Some("NT_STAPSDT"), 40, false),
+        (".note.stapsdt", "stapsdt", Some("NT_STAPSDT"), 44, false),
+        (".note.broken", "Vendor", None, 4, false),
+        (".note.broken", "", None, 4, true),
+        // The broken section doesn't end the listing
+        (".note.ABI-tag", "GNU", Some("NT_GNU_ABI_TAG"), 16, false),
+    ]);
+    assert_eq!(info.stapsdt_probes, Some(2));
+
+    let mut out = Vec::new();
+    HumanFormatter::new(2).format(std::slice::from_ref(&info), &mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains("Notes: 7 (2 stapsdt probes)"), "{}", text);
+    assert!(text.contains(".note.gnu.gold-version  GNU  NT_GNU_GOLD_VERSION (4)  10 bytes"), "{}", text);
+    assert!(text.contains(".note.broken  Vendor  type 0x7  4 bytes"), "{}", text);
+    assert!(text.contains(".note.broken  malformed: name size 64 and descriptor size 4 run past the end of the region"), "{}", text);
+
+    // Only listed at -vv
+    let info = common::scan_bytes("app", &data, &["-v"]).unwrap();
+    assert_eq!((info.notes, info.stapsdt_probes), (None, None));
+    assert_eq!(serde_json::to_value(common::scan_bytes("app", &data, &[]).unwrap()).unwrap()["notes"], serde_json::Value::Null);
+}
+
+#[test]
+fn segments_without_note_sections_are_listed() {
+    // Section headers gone, as sstrip leaves a binary
+    let mut data = ElfBuilder::new().build_id(&[0xcd; 20]).build();
+    data[0x28..0x30].copy_from_slice(&0u64.to_le_bytes()); // e_shoff
+    data[0x3c..0x40].copy_from_slice(&[0; 4]); // e_shnum, e_shstrndx
+
+    let info = common::scan_bytes("app", &data, &["-vv"]).unwrap();
+    let notes = info.notes.unwrap();
+    assert_eq!(notes.len(), 1, "{:?}", notes);
+    assert!(notes[0].location.starts_with("PT_NOTE@0x"), "{:?}", notes[0]);
+    assert_eq!(notes[0].type_name.as_deref(), Some("NT_GNU_BUILD_ID"));
+    assert_eq!(info.stapsdt_probes, Some(0));
+}
+
+/// NT_GNU_PROPERTY_TYPE_0 descriptor: (type, value) properties, 8-byte aligned
+fn properties(entries: &[(u32, u32)]) -> Vec<u8> {
+    entries.iter()
+        .flat_map(|&(pr_type, value)| [pr_type.to_le_bytes(), 4u32.to_le_bytes(), value.to_le_bytes(), [0; 4]].concat())
+        .collect()
+}
+
+#[test]
+fn isa_levels_come_from_gnu_property_notes() {
+    const ISA_1_NEEDED: u32 = 0xc000_8002;
+    const ISA_1_USED: u32 = 0xc001_0002;
+    const FEATURE_1_AND: u32 = 0xc000_0002; // IBT/SHSTK, not an ISA level
+    let level = |desc: &[u8], machine: u16, flags: &[&str]| {
+        let data = ElfBuilder::new().machine(machine).note(".note.gnu.property", "GNU", 5, desc).build();
+        common::scan_bytes("app", &data, flags).unwrap().isa_level
+    };
+    let check = |desc: &[u8]| level(desc, 62, &["--check-isa"]);
+
+    assert_eq!(check(&properties(&[(FEATURE_1_AND, 3), (ISA_1_USED, 0b0111)])).as_deref(), Some("x86-64-v3"));
+    assert_eq!(check(&properties(&[(ISA_1_USED, 0b0001), (ISA_1_NEEDED, 0b0010)])).as_deref(), Some("x86-64-v2"));
+    assert_eq!(check(&properties(&[(ISA_1_USED, 0b1000)])).as_deref(), Some("x86-64-v4"));
+    assert_eq!(check(&properties(&[(ISA_1_USED, 0b0001)])).as_deref(), Some("x86-64"));
+    assert_eq!(check(&properties(&[(FEATURE_1_AND, 3)])), None);
+    // A property claiming more bytes than the note has ends the walk
+    assert_eq!(check(&[0x02, 0x00, 0x01, 0xc0, 0xff, 0, 0, 0, 7, 0, 0, 0]), None);
+
+    let v3 = properties(&[(ISA_1_USED, 0b0111)]);
+    // Opt-in, and x86-64 only (the bits mean something else elsewhere)
+    assert_eq!(level(&v3, 62, &[]), None);
+    assert_eq!(level(&v3, 183, &["--check-isa"]), None);
+
+    let data = ElfBuilder::new().note(".note.gnu.property", "GNU", 5, &v3).build();
+    let info = common::scan_bytes("app", &data, &["--check-isa"]).unwrap();
+    let mut out = Vec::new();
+    HumanFormatter::new(1).format(std::slice::from_ref(&info), &mut out).unwrap();
+    assert!(String::from_utf8(out).unwrap().contains("ISA Level: x86-64-v3"));
+    assert_eq!(Summary::new(&[info.clone(), info]).isa_levels.get("x86-64-v3"), Some(&2));
+    assert_eq!(CountField::IsaLevel.key(&BinaryInfo::default()), "none");
+}
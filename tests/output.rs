@@ -0,0 +1,126 @@
+//! Human output layouts
+
+mod common;
+
+use symwalker::output::{self, HumanFormatter, OutputFormatter};
+use symwalker::testutil::{ElfBuilder, MachOBuilder};
+
+#[test]
+fn compact_output_is_a_line_per_binary() {
+    let stripped = common::scan_bytes("app", &ElfBuilder::new().build(), &[]).unwrap();
+    let debug = common::scan_bytes("foo.o", &ElfBuilder::new().e_type(1).section(".debug_info", &[0; 8]).build(), &[]).unwrap();
+
+    let mut out = Vec::new();
+    HumanFormatter::new(0).compact(true).format(&[stripped.clone(), debug.clone()], &mut out).unwrap();
+
+    let expected = format!(
+        "{}\tELF\tEXE\tx86_64\tstripped\n{}\tELF\tBIN\tx86_64\tdebug\n",
+        stripped.file_path.display(),
+        debug.file_path.display(),
+    );
+    assert_eq!(String::from_utf8(out).unwrap(), expected);
+}
+
+#[test]
+fn verbose_keeps_the_detailed_block() {
+    let formatter = HumanFormatter::new(1).compact(true);
+    assert!(!formatter.is_compact());
+
+    let info = common::scan_bytes("app", &ElfBuilder::new().build(), &[]).unwrap();
+    let mut out = Vec::new();
+    formatter.format(&[info], &mut out).unwrap();
+    assert!(String::from_utf8(out).unwrap().contains("Binary #1"));
+}
+
+#[test]
+fn verbose_shows_the_elf_header_versions() {
+    let info = common::scan_bytes("app", &ElfBuilder::new().versions(3, 2).build(), &[]).unwrap();
+    assert_eq!((info.abi_version, info.elf_version), (Some(3), Some(2)));
+
+    let show = |verbosity| {
+        let mut out = Vec::new();
+        HumanFormatter::new(verbosity).format(std::slice::from_ref(&info), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    assert!(show(1).contains("OS/ABI SYSV, ABI version 3, ELF version 2"));
+    assert!(!show(0).contains("ABI version"));
+}
+
+#[test]
+fn verbose_shows_global_and_local_symbol_counts() {
+    let data = ElfBuilder::new().symbol("main").symbol("api_call").local_symbol("helper").build();
+    let info = common::scan_bytes("app", &data, &[]).unwrap();
+    assert_eq!((info.global_symbol_count, info.local_symbol_count), (Some(2), Some(1)));
+
+    let mut out = Vec::new();
+    HumanFormatter::new(1).format(std::slice::from_ref(&info), &mut out).unwrap();
+    assert!(String::from_utf8(out).unwrap().contains("Symbol Table: 2 global, 1 local"));
+
+    let stripped = common::scan_bytes("app", &ElfBuilder::new().build(), &[]).unwrap();
+    assert_eq!((stripped.global_symbol_count, stripped.local_symbol_count), (None, None));
+}
+
+#[test]
+fn saved_reports_are_read_back() {
+    let info = common::scan_bytes("app", &ElfBuilder::new().build_id(&[1; 20]).build(), &[]).unwrap();
+    let list = serde_json::to_vec(&[&info]).unwrap();
+    let read = output::read_report(&list).unwrap().unwrap();
+    assert_eq!(serde_json::to_value(&read).unwrap(), serde_json::to_value([&info]).unwrap());
+
+    let wrapped = serde_json::json!({ "binaries": [&info], "scan_root": "/mnt" });
+    assert_eq!(output::read_report(wrapped.to_string().as_bytes()).unwrap().unwrap().len(), 1);
+
+    for other in [&b"not json"[..], b"{\"binaries\": 3}", b"[{\"file_path\": \"app\"}]", b"\"text\""] {
+        assert!(output::read_report(other).unwrap().is_none());
+    }
+
+    let mut old = serde_json::to_value(&info).unwrap();
+    old["schema_version"] = 1.into();
+    assert!(output::read_report(serde_json::json!([old]).to_string().as_bytes()).is_err());
+    assert!(output::read_report(br#"{"truncated": true, "summary": {"total": 9}}"#).is_err());
+}
+
+#[test]
+fn verbose_shows_the_objc_and_swift_runtimes() {
+    let data = MachOBuilder::new()
+        .section("__DATA", "__objc_classlist", &[0; 8])
+        .section("__TEXT", "__swift5_types", &[0; 4])
+        .build();
+    let info = common::scan_bytes("App", &data, &[]).unwrap();
+    assert!(info.has_objc && info.has_swift);
+
+    let mut out = Vec::new();
+    HumanFormatter::new(1).format(std::slice::from_ref(&info), &mut out).unwrap();
+    assert!(String::from_utf8(out).unwrap().contains("Runtime: Objective-C, Swift"));
+
+    let objc = common::scan_bytes("App", &MachOBuilder::new().section("__DATA", "__objc_imageinfo", &[0; 8]).build(), &[]).unwrap();
+    assert!(objc.has_objc && !objc.has_swift);
+    let plain = common::scan_bytes("App", &MachOBuilder::new().section("__DATA", "__data", &[0; 8]).build(), &[]).unwrap();
+    assert!(!plain.has_objc && !plain.has_swift);
+}
+
+#[test]
+fn separate_debug_files_are_labelled_without_hardening() {
+    let split = ElfBuilder::new()
+        .build_id(&[0x5d; 20])
+        .nobits_section(".text", 0x1000)
+        .section(".debug_info", &[0; 16])
+        .symbol("main")
+        .build();
+    let info = common::scan_bytes("app.debug", &split, &["--security"]).unwrap();
+    assert!(info.is_debug_only);
+    assert_eq!(info.kind_label(), "DEBUG");
+    assert!(!info.has_problems(true));
+
+    let mut out = Vec::new();
+    HumanFormatter::new(1).format(std::slice::from_ref(&info), &mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains("Binary #1 (DEBUG)") && text.contains("Type: ELF (separate debug file)"), "{}", text);
+    assert!(!text.contains("Security Features"), "{}", text);
+
+    // Allocated contents left in place: DWARF in a binary with code
+    let with_data = ElfBuilder::new().nobits_section(".bss", 0x100).tls(&[1; 16]).section(".debug_info", &[0; 16]).build();
+    let info = common::scan_bytes("app", &with_data, &[]).unwrap();
+    assert!(!info.is_debug_only);
+    assert_eq!(info.kind_label(), "EXE");
+}
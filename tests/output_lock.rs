@@ -0,0 +1,79 @@
+//! The `--output` directory lock
+
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use chrono::{Duration, Utc};
+use symwalker::lock::{LockInfo, OutputLock, LOCK_NAME};
+use symwalker::manifest;
+use symwalker::testutil::ElfBuilder;
+
+fn write_lock(dir: &Path, owner: &LockInfo) {
+    std::fs::write(dir.join(LOCK_NAME), serde_json::to_string(owner).unwrap()).unwrap();
+}
+
+fn read_lock(dir: &Path) -> LockInfo {
+    serde_json::from_str(&std::fs::read_to_string(dir.join(LOCK_NAME)).unwrap()).unwrap()
+}
+
+#[test]
+fn stale_lock_is_taken_over_only_with_force_lock() {
+    let out = tempfile::tempdir().unwrap();
+    let mut exited = Command::new(std::env::current_exe().unwrap()).arg("--list").stdout(Stdio::null()).spawn().unwrap();
+    exited.wait().unwrap();
+    write_lock(out.path(), &LockInfo { pid: exited.id(), ..LockInfo::current() });
+
+    let err = OutputLock::acquire(out.path(), false).unwrap_err();
+    assert!(err.to_string().contains("--force-lock"), "{}", err);
+
+    let lock = OutputLock::acquire(out.path(), true).unwrap();
+    assert_eq!(read_lock(out.path()).pid, std::process::id());
+    drop(lock);
+    assert!(!out.path().join(LOCK_NAME).exists());
+
+    // Another host's lock can only go stale with age
+    let remote = LockInfo { pid: 1, hostname: "build-07.invalid".to_string(), started: Utc::now() };
+    write_lock(out.path(), &remote);
+    assert!(OutputLock::acquire(out.path(), true).is_err());
+    write_lock(out.path(), &LockInfo { started: Utc::now() - Duration::days(2), ..remote });
+    drop(OutputLock::acquire(out.path(), true).unwrap());
+
+    // A live lock is never taken over
+    write_lock(out.path(), &LockInfo::current());
+    let err = OutputLock::acquire(out.path(), true).unwrap_err();
+    assert!(err.to_string().contains("in use by another run"), "{}", err);
+}
+
+#[test]
+fn concurrent_runs_on_one_output_directory_are_refused() {
+    let (scan_dir, out) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    std::fs::write(scan_dir.path().join("app"), ElfBuilder::new().build_id(&[7; 20]).build()).unwrap();
+
+    // A server that accepts but never answers holds the first run mid-scan;
+    // only reported binaries are looked up, so the stripped app is shown
+    let server = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", server.local_addr().unwrap());
+    let run = || {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_symwalker"));
+        command.args(["--json", "--show-stripped", "--check-remote", "--debuginfod-urls", &url, "-o"])
+            .args([out.path(), scan_dir.path()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        command
+    };
+
+    let mut first = run().spawn().unwrap();
+    let (connection, _) = server.accept().unwrap();
+    assert_eq!(read_lock(out.path()).pid, first.id());
+
+    let second = run().output().unwrap();
+    assert!(!second.status.success());
+    let stderr = String::from_utf8_lossy(&second.stderr);
+    assert!(stderr.contains("in use by another run"), "{}", stderr);
+
+    drop((connection, server));
+    assert!(first.wait().unwrap().success());
+    assert!(!out.path().join(LOCK_NAME).exists());
+    assert!(manifest::verify(out.path(), false).unwrap().is_ok());
+}
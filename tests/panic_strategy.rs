@@ -0,0 +1,36 @@
+//! Rust panic strategy guessed from the linked panic runtime
+
+mod common;
+
+use symwalker::testutil::{ElfBuilder, MachOBuilder};
+
+fn strategy(builder: ElfBuilder) -> Option<String> {
+    common::scan_bytes("app", &builder.build(), &[]).unwrap().panic_strategy
+}
+
+fn rust() -> ElfBuilder {
+    ElfBuilder::new().symbol("_ZN4core3fmt5write17h0123456789abcdefE")
+}
+
+#[test]
+fn the_panic_runtime_crate_decides() {
+    assert_eq!(strategy(rust().symbol("_ZN11panic_abort18__rust_start_panic5abort17h0123456789abcdefE")).as_deref(), Some("abort"));
+    assert_eq!(strategy(rust().symbol("_RNvCs1234_11panic_abort5abort")).as_deref(), Some("abort"));
+    assert_eq!(strategy(rust().symbol("_ZN12panic_unwind3imp5panic17h0123456789abcdefE")).as_deref(), Some("unwind"));
+    // Raising through libunwind, unless C++ might be the one doing it
+    assert_eq!(strategy(rust().import("_Unwind_RaiseException")).as_deref(), Some("unwind"));
+    assert_eq!(strategy(rust().import("_Unwind_RaiseException").symbol("_ZNSt6vectorIiSaIiEE9push_backERKi")), None);
+
+    // Inconclusive: nothing to go on, both runtimes, or not Rust at all
+    assert_eq!(strategy(rust().symbol("rust_eh_personality")), None);
+    assert_eq!(strategy(rust()
+        .symbol("_ZN11panic_abort18__rust_start_panic5abort17h0123456789abcdefE")
+        .symbol("_ZN12panic_unwind3imp5panic17h0123456789abcdefE")), None);
+    assert_eq!(strategy(ElfBuilder::new().symbol("my_11panic_abort").import("_Unwind_RaiseException")), None);
+
+    let macho = MachOBuilder::new()
+        .symbol("__ZN4core3fmt5write17h0123456789abcdefE")
+        .import("__Unwind_RaiseException")
+        .build();
+    assert_eq!(common::scan_bytes("App", &macho, &[]).unwrap().panic_strategy.as_deref(), Some("unwind"));
+}
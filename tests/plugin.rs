@@ -0,0 +1,59 @@
+//! External analyzers (`--plugin`) for formats symwalker doesn't read
+
+mod common;
+
+use symwalker::plugin;
+
+#[test]
+fn answers_are_merged_over_defaults() {
+    assert!(plugin::parse_answer(b"").unwrap().is_none());
+    assert!(plugin::parse_answer(b" null\n").unwrap().is_none());
+
+    let info = plugin::parse_answer(br#"{"binary_type": "uImage", "architecture": "arm64", "build_id": "abcd", "unknown_key": 1}"#)
+        .unwrap()
+        .unwrap();
+    assert_eq!(info.binary_type, "uImage");
+    assert_eq!((info.architecture.as_str(), info.architecture_raw.as_str()), ("aarch64", "arm64"));
+    assert_eq!(info.build_id.as_deref(), Some("abcd"));
+    assert!(!info.is_stripped && info.warnings.is_empty());
+
+    assert!(plugin::parse_answer(br#"{"architecture": "arm64"}"#).is_err());
+    assert!(plugin::parse_answer(br#"{"binary_type": "uImage", "is_stripped": "yes"}"#).is_err());
+    assert!(plugin::parse_answer(br#"["uImage"]"#).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn plugin_classifies_what_the_scanner_cannot() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("uimage.sh");
+    std::fs::write(&script, concat!(
+        "#!/bin/sh\n",
+        "grep -q '\"protocol\": *1' || exit 2\n",
+        "case \"$(head -c 4 \"$1\")\" in\n",
+        "  UIMG) echo '{\"binary_type\": \"uImage\", \"architecture\": \"mips\", \"is_stripped\": false}' ;;\n",
+        "  FAIL) exit 1 ;;\n",
+        "  *) echo null ;;\n",
+        "esac\n",
+    )).unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let flags = ["--plugin", script.to_str().unwrap()];
+
+    let image = dir.path().join("kernel.img");
+    std::fs::write(&image, b"UIMG and a payload").unwrap();
+    let info = common::scan_path(&image, &flags).unwrap();
+    assert_eq!(info.binary_type, "uImage");
+    assert_eq!(info.architecture, "mips");
+    assert_eq!(info.file_path, image);
+    assert_eq!(info.file_size, 18);
+
+    for contents in [&b"plain text"[..], b"FAIL"] {
+        let other = dir.path().join("other");
+        std::fs::write(&other, contents).unwrap();
+        assert!(common::scan_path(&other, &flags).is_err());
+    }
+
+    assert!(common::scan_path(&image, &["--plugin", "/nonexistent/plugin"]).is_err());
+}
@@ -0,0 +1,90 @@
+//! Filling the debuginfod client cache for a tree (`--prefetch`)
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use symwalker::debuginfod::{Artifact, DebuginfodClient};
+use symwalker::environment::Environment;
+use symwalker::prefetch::{self, cache_path};
+use symwalker::testutil::ElfBuilder;
+
+fn symwalker(dir: &Path, flags: &[&str], vars: &[(&str, &Path)]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_symwalker"))
+        .env_clear()
+        .envs(vars.iter().copied())
+        .args(flags)
+        .arg(dir)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn the_cache_follows_the_debuginfod_client_variables() {
+    let vars = |pairs: &[(&'static str, &str)]| pairs.iter().map(|&(name, value)| (name, value.to_string())).collect::<Vec<_>>();
+    let cache = |hermetic, pairs: &[(&'static str, &str)]| prefetch::default_cache_dir(&Environment::from_vars(hermetic, vars(pairs)));
+
+    let home = [("HOME", "/home/dev")];
+    assert_eq!(cache(false, &home), Some("/home/dev/.cache/debuginfod_client".into()));
+    assert_eq!(cache(false, &[home[0], ("XDG_CACHE_HOME", "/var/cache/dev")]), Some("/var/cache/dev/debuginfod_client".into()));
+    assert_eq!(cache(false, &[home[0], ("XDG_CACHE_HOME", "/x"), ("DEBUGINFOD_CACHE_PATH", "/srv/debuginfod")]), Some("/srv/debuginfod".into()));
+    assert_eq!(cache(true, &home), None);
+    assert_eq!(cache_path(Path::new("/c"), "ab12", Artifact::Executable), Path::new("/c/ab12/executable"));
+}
+
+#[test]
+fn prefetch_counts_fetched_cached_and_unavailable_files() {
+    let (fetched, cached, missing, wrong) = ([1u8; 20], [2u8; 20], [3u8; 20], [4u8; 20]);
+    let (scan, cache) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    // Stripped, so the default report leaves them out; they're still prefetched
+    for (name, id) in [("fetched", fetched), ("cached", cached), ("missing", missing), ("wrong", wrong)] {
+        std::fs::write(scan.path().join(name), ElfBuilder::new().build_id(&id).build()).unwrap();
+    }
+    // Same build-id as `fetched`, which is downloaded once
+    std::fs::write(scan.path().join("copy"), ElfBuilder::new().build_id(&fetched).symbol("main").build()).unwrap();
+
+    let mut server = mockito::Server::new();
+    let debug_file = ElfBuilder::new().build_id(&fetched).symbol("main").build();
+    let get = server.mock("GET", format!("/buildid/{}/debuginfo", hex::encode(fetched)).as_str())
+        .with_body(&debug_file)
+        .expect(1)
+        .create();
+    let other = server.mock("GET", format!("/buildid/{}/debuginfo", hex::encode(wrong)).as_str())
+        .with_body(ElfBuilder::new().build_id(&[9; 20]).build())
+        .create();
+    let not_found = server.mock("GET", format!("/buildid/{}/debuginfo", hex::encode(missing)).as_str())
+        .with_status(404)
+        .create();
+    let already = cache_path(cache.path(), &hex::encode(cached), Artifact::Debuginfo);
+    std::fs::create_dir_all(already.parent().unwrap()).unwrap();
+    std::fs::write(&already, b"from an earlier run").unwrap();
+
+    let url = server.url();
+    let flags = ["--json", "--prefetch", "--debuginfod-urls", &url, "--cache-dir", cache.path().to_str().unwrap()];
+    let output = symwalker(scan.path(), &flags, &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let prefetch = &report["prefetch"];
+    assert_eq!(prefetch["build_ids"], 4, "{}", prefetch);
+    assert_eq!((&prefetch["fetched"], &prefetch["already_cached"], &prefetch["unavailable"]), (&1.into(), &1.into(), &2.into()));
+    assert_eq!(prefetch["fetched_bytes"], debug_file.len());
+    get.assert();
+    other.assert();
+    not_found.assert();
+
+    assert_eq!(std::fs::read(cache_path(cache.path(), &hex::encode(fetched), Artifact::Debuginfo)).unwrap(), debug_file);
+    assert!(!cache_path(cache.path(), &hex::encode(wrong), Artifact::Debuginfo).exists());
+    assert_eq!(std::fs::read(&already).unwrap(), b"from an earlier run");
+
+    // Run again: everything that could be fetched is cached now
+    let client = DebuginfodClient::new(vec![url]).unwrap();
+    let again = prefetch::run(&client, cache.path(), &[hex::encode(fetched)], false, 2);
+    assert_eq!((again.fetched, again.already_cached), (0, 1));
+}
+
+#[test]
+fn hermetic_prefetch_needs_a_cache_dir() {
+    let (scan, home) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    let output = symwalker(scan.path(), &["--prefetch", "--hermetic"], &[("HOME", home.path())]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--cache-dir"));
+}
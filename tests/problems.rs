@@ -0,0 +1,183 @@
+//! Warnings behind `--problems`
+
+mod common;
+
+use symwalker::binary::BinaryInfo;
+use symwalker::testutil::{ElfBuilder, MachOBuilder};
+
+const PT_LOAD: u32 = 1;
+const PT_DYNAMIC: u32 = 2;
+const PT_INTERP: u32 = 3;
+const PF_RWX: u32 = 7;
+
+fn codes(info: &BinaryInfo) -> Vec<&str> {
+    info.warnings.iter().map(|w| w.code.as_str()).collect()
+}
+
+#[test]
+fn clean_binary_has_no_problems() {
+    let data = ElfBuilder::new().segment(PT_LOAD, 5).symbol("main").build();
+    let info = common::scan_bytes("clean", &data, &[]).unwrap();
+    assert!(codes(&info).is_empty());
+    assert!(!info.has_problems(false));
+}
+
+#[test]
+fn writable_executable_segments_are_flagged() {
+    let elf = ElfBuilder::new().segment(PT_LOAD, PF_RWX).build();
+    let info = common::scan_bytes("wx", &elf, &[]).unwrap();
+    assert_eq!(codes(&info), ["wx-segment"]);
+
+    let macho = MachOBuilder::new().section("__DATA", "__data", &[0; 8]).build();
+    let info = common::scan_bytes("rx", &macho, &[]).unwrap();
+    assert!(codes(&info).is_empty());
+}
+
+#[test]
+fn missing_interpreter_is_flagged() {
+    let data = ElfBuilder::new().e_type(3).interp("/nonexistent/ld.so.1").needed("libc.so.6").build();
+    let info = common::scan_bytes("app", &data, &[]).unwrap();
+    assert_eq!(codes(&info), ["missing-interpreter"]);
+}
+
+#[test]
+fn inconsistent_program_headers_are_flagged() {
+    let messages = |data: Vec<u8>| -> Vec<String> {
+        let info = common::scan_bytes("crafted", &data, &[]).unwrap();
+        info.warnings.iter()
+            .filter(|w| w.code == "inconsistent-structure")
+            .map(|w| w.message.clone())
+            .collect()
+    };
+
+    assert_eq!(messages(ElfBuilder::new().without_load().build()), ["ET_EXEC without PT_LOAD segments"]);
+    assert_eq!(
+        messages(ElfBuilder::new().segment(PT_DYNAMIC, 4).section(".text", &[0xc3]).build()),
+        ["PT_DYNAMIC without a .dynamic section"]
+    );
+    assert_eq!(
+        messages(ElfBuilder::new().segment(PT_INTERP, 4).segment(PT_INTERP, 4).build()),
+        ["PT_INTERP without PT_DYNAMIC", "2 PT_INTERP segments"]
+    );
+
+    // Relocatable objects have no program headers to check
+    assert!(messages(ElfBuilder::new().e_type(1).without_load().build()).is_empty());
+}
+
+#[test]
+fn truncated_segments_are_flagged() {
+    // Program headers: PT_GNU_STACK, then the PT_LOAD covering the file;
+    // grow that one's p_filesz past the end
+    let mut data = ElfBuilder::new().build();
+    let p_filesz = 64 + 56 + 32;
+    data[p_filesz..p_filesz + 8].copy_from_slice(&0x10_0000u64.to_le_bytes());
+
+    let info = common::scan_bytes("truncated", &data, &[]).unwrap();
+    assert_eq!(codes(&info), ["inconsistent-structure"]);
+    assert_eq!(info.warnings[0].message, "segment extends past the end of the file");
+}
+
+#[test]
+fn adjacent_debug_file_with_other_build_id_is_flagged() {
+    let dir = tempfile::tempdir().unwrap();
+    let binary = dir.path().join("app");
+    std::fs::write(&binary, ElfBuilder::new().build_id(&[1; 20]).build()).unwrap();
+    std::fs::write(dir.path().join("app.debug"), ElfBuilder::new().build_id(&[2; 20]).build()).unwrap();
+
+    let info = common::scan_path(&binary, &[]).unwrap();
+    assert_eq!(codes(&info), ["debug-file-mismatch"]);
+
+    std::fs::write(dir.path().join("app.debug"), ElfBuilder::new().build_id(&[1; 20]).build()).unwrap();
+    let info = common::scan_path(&binary, &[]).unwrap();
+    assert!(codes(&info).is_empty());
+}
+
+#[test]
+fn hardening_failures_count_only_with_security() {
+    let data = ElfBuilder::new().executable_stack().build();
+    let info = common::scan_bytes("execstack", &data, &[]).unwrap();
+    assert!(!info.has_problems(false));
+    assert!(info.has_problems(true));
+}
+
+#[test]
+fn entry_point_outside_text_is_flagged() {
+    let entry_in = |section: &str| {
+        let data = ElfBuilder::new()
+            .code_section(".init", &[0xc3])
+            .code_section(".text", &[0xc3])
+            .entry_in(section)
+            .build();
+        common::scan_bytes("app", &data, &[]).unwrap()
+    };
+
+    let info = entry_in(".text");
+    assert_eq!(info.entry_section.as_deref(), Some(".text"));
+    assert!(codes(&info).is_empty());
+
+    let info = entry_in(".init");
+    assert_eq!(info.entry_section.as_deref(), Some(".init"));
+    assert_eq!(codes(&info), ["unusual-entry-point"]);
+    assert!(info.warnings[0].message.ends_with("is in .init, not .text"));
+
+    let data = ElfBuilder::new().code_section(".text", &[0xc3]).entry(0x10_0000).build();
+    let info = common::scan_bytes("app", &data, &[]).unwrap();
+    assert_eq!(info.entry_section, None);
+    assert_eq!(codes(&info), ["unusual-entry-point"]);
+}
+
+/// `count` notes owned by "XYZ" with no descriptor, 16 bytes each
+fn filler_notes(count: usize) -> Vec<u8> {
+    let note = [4u32.to_le_bytes(), 0u32.to_le_bytes(), 1u32.to_le_bytes()].concat();
+    [note, b"XYZ\0".to_vec()].concat().repeat(count)
+}
+
+#[test]
+fn huge_note_segments_are_searched_within_limits() {
+    // Megabytes of zero padding stop at the run of empty entries
+    let data = ElfBuilder::new().raw_note(".note.junk", &vec![0; 8 << 20]).build();
+    let info = common::scan_bytes("padded", &data, &[]).unwrap();
+    assert_eq!(codes(&info), ["truncated-notes"]);
+    assert_eq!(info.build_id, None);
+
+    let data = ElfBuilder::new().raw_note(".note.junk", &filler_notes(1024)).build();
+    let info = common::scan_bytes("filler", &data, &["--max-note-bytes", "4096"]).unwrap();
+    assert_eq!(codes(&info), ["truncated-notes"]);
+    assert!(info.warnings[0].message.contains("after 4096 bytes"));
+    assert!(codes(&common::scan_bytes("filler", &data, &[]).unwrap()).is_empty());
+
+    // Notes ahead of the junk are still found
+    let data = ElfBuilder::new().build_id(&[7; 20]).raw_note(".note.junk", &vec![0; 1 << 20]).build();
+    let info = common::scan_bytes("app", &data, &["--max-note-bytes", "4096"]).unwrap();
+    assert_eq!(info.build_id, Some("07".repeat(20)));
+}
+
+#[test]
+fn dangerous_imports_are_listed() {
+    let data = ElfBuilder::new().import("strcpy").import("puts").import("system").import("strcpy").symbol("gets").build();
+    let info = common::scan_bytes("app", &data, &[]).unwrap();
+    // Only undefined dynamic symbols are imports; the local `gets` isn't one
+    assert_eq!(info.dangerous_imports, ["strcpy", "system"]);
+    assert!(info.warnings.is_empty());
+
+    let data = MachOBuilder::new().import("_popen").import("_printf").symbol("_execv").build();
+    let info = common::scan_bytes("app", &data, &[]).unwrap();
+    assert_eq!(info.dangerous_imports, ["popen"]);
+}
+
+#[test]
+fn dyld_environment_is_reported() {
+    let data = MachOBuilder::new()
+        .dyld_environment("DYLD_LIBRARY_PATH=@executable_path/../lib")
+        .dyld_environment("DYLD_INSERT_LIBRARIES=/tmp/hook.dylib")
+        .build();
+    let info = common::scan_bytes("app", &data, &[]).unwrap();
+    assert_eq!(info.dyld_environment, [
+        "DYLD_LIBRARY_PATH=@executable_path/../lib",
+        "DYLD_INSERT_LIBRARIES=/tmp/hook.dylib",
+    ]);
+    assert_eq!(codes(&info), ["dyld-insert-libraries"]);
+
+    let info = common::scan_bytes("plain", &MachOBuilder::new().class32().build(), &[]).unwrap();
+    assert!(info.dyld_environment.is_empty());
+}
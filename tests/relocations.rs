@@ -0,0 +1,82 @@
+//! Relocation counts, GOT/PLT sizes and TLS: what the loader sets up at startup
+
+mod common;
+
+use symwalker::output::OutputFormatter;
+use symwalker::testutil::ElfBuilder;
+
+const R_X86_64_GLOB_DAT: u32 = 6;
+const R_X86_64_JUMP_SLOT: u32 = 7;
+const R_X86_64_RELATIVE: u32 = 8;
+const R_X86_64_IRELATIVE: u32 = 37;
+
+#[test]
+fn relocations_are_counted_per_section() {
+    let data = ElfBuilder::new()
+        .e_type(3)
+        .relocations(".rela.dyn", &[R_X86_64_RELATIVE, R_X86_64_GLOB_DAT, R_X86_64_IRELATIVE])
+        .relocations(".rela.plt", &[R_X86_64_JUMP_SLOT, R_X86_64_JUMP_SLOT, R_X86_64_IRELATIVE])
+        .section(".got", &[0; 16])
+        .section(".got.plt", &[0; 40])
+        .build();
+
+    let info = common::scan_bytes("libfoo.so", &data, &[]).unwrap();
+
+    assert_eq!(info.relocation_count, Some(6));
+    assert_eq!(info.irelative_count, Some(2));
+    assert_eq!(info.plt_entries, Some(3));
+    assert_eq!(info.got_bytes, Some(56));
+}
+
+#[test]
+fn relr_words_are_decoded() {
+    // One address, then a bitmap relocating the 3 words after bits 1, 2 and 5
+    let data = ElfBuilder::new().e_type(3).relr(&[0x2000, 0b100111]).build();
+
+    let info = common::scan_bytes("libfoo.so", &data, &[]).unwrap();
+
+    assert_eq!(info.relocation_count, Some(4));
+    assert_eq!(info.irelative_count, Some(0));
+}
+
+#[test]
+fn relocations_in_32_bit_files() {
+    const R_386_IRELATIVE: u32 = 42;
+    let data = ElfBuilder::new()
+        .class32()
+        .machine(3)
+        .relocations(".rela.dyn", &[R_386_IRELATIVE, 8])
+        .build();
+
+    let info = common::scan_bytes("app", &data, &[]).unwrap();
+
+    assert_eq!(info.relocation_count, Some(2));
+    assert_eq!(info.irelative_count, Some(1));
+    assert_eq!(info.plt_entries, Some(0));
+}
+
+#[test]
+fn tls_segment_is_reported() {
+    let data = ElfBuilder::new().e_type(3).tls(&[0; 24]).build();
+    let info = common::scan_bytes("libtls.so", &data, &[]).unwrap();
+    assert!(info.has_tls);
+    assert_eq!(info.tls_size, 24);
+
+    let info = common::scan_bytes("plain", &ElfBuilder::new().build(), &[]).unwrap();
+    assert!(!info.has_tls);
+    assert_eq!(info.tls_size, 0);
+}
+
+#[test]
+fn hash_style_follows_the_hash_sections() {
+    let style = |builder: ElfBuilder| common::scan_bytes("lib.so", &builder.e_type(3).build(), &[]).unwrap().hash_style;
+    assert_eq!(style(ElfBuilder::new().import("puts")).as_deref(), Some("sysv"));
+    assert_eq!(style(ElfBuilder::new().gnu_hash()).as_deref(), Some("gnu"));
+    assert_eq!(style(ElfBuilder::new().import("puts").gnu_hash()).as_deref(), Some("both"));
+    assert_eq!(style(ElfBuilder::new()), None);
+
+    let info = common::scan_bytes("lib.so", &ElfBuilder::new().gnu_hash().build(), &[]).unwrap();
+    let mut out = Vec::new();
+    symwalker::output::HumanFormatter::new(1).format(std::slice::from_ref(&info), &mut out).unwrap();
+    assert!(String::from_utf8(out).unwrap().contains("Hash Style: gnu"));
+}
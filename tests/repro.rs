@@ -0,0 +1,55 @@
+//! `--repro-check`: markers of a build that won't reproduce byte for byte
+
+mod common;
+
+use symwalker::repro;
+use symwalker::testutil::ElfBuilder;
+
+fn codes(info: &symwalker::binary::BinaryInfo) -> Vec<&str> {
+    info.warnings.iter().map(|warning| warning.code.as_str()).collect()
+}
+
+#[test]
+fn build_paths_and_timestamps_are_warnings_under_repro_check() {
+    let (abbrev, info) = common::compile_unit_dwarf("main.c", "GNU C17", "/home/alice/src");
+    let data = ElfBuilder::new()
+        .section(".rodata", b"version 1.0, built Mar  4 2024 12:34:56\0")
+        .section(".debug_str", b"main.c\0/home/alice/src/main.c\0int\0")
+        .section(".debug_abbrev", &abbrev)
+        .section(".debug_info", &info)
+        .build();
+
+    let info = common::scan_bytes("app", &data, &["--repro-check"]).unwrap();
+    assert_eq!(codes(&info), ["repro-comp-dir", "repro-timestamp", "repro-build-path"]);
+    assert_eq!(info.warnings[1].message, "__DATE__-style \"Mar  4 2024\" and __TIME__-style \"12:34:56\" in read-only data");
+
+    let info = common::scan_bytes("app", &data, &[]).unwrap();
+    assert!(codes(&info).iter().all(|code| !code.starts_with("repro-")), "{:?}", codes(&info));
+
+    // -fdebug-prefix-map=/home/alice/src=. and no timestamps
+    let (abbrev, info) = common::compile_unit_dwarf("main.c", "GNU C17", ".");
+    let clean = ElfBuilder::new()
+        .section(".rodata", b"version 1.0\0")
+        .section(".debug_abbrev", &abbrev)
+        .section(".debug_info", &info)
+        .build();
+    let info = common::scan_bytes("app", &clean, &["--repro-check"]).unwrap();
+    assert!(codes(&info).iter().all(|code| !code.starts_with("repro-")), "{:?}", codes(&info));
+}
+
+#[test]
+fn date_and_time_patterns_need_word_boundaries() {
+    assert_eq!(repro::build_dates(b"Jan 12 2023\0Feb  1 1999"), ["Jan 12 2023", "Feb  1 1999"]);
+    assert!(repro::build_dates(b"XJan 12 2023").is_empty());
+    assert!(repro::build_dates(b"Foo 12 2023").is_empty());
+    assert_eq!(repro::build_dates("Jan 12 2023 \u{e9}".as_bytes()), ["Jan 12 2023"]);
+
+    assert_eq!(repro::build_times(b"at 23:59:07\0"), ["23:59:07"]);
+    // Out of range, or part of a longer timestamp
+    assert!(repro::build_times(b"24:00:00").is_empty());
+    assert!(repro::build_times(b"12:34:56.789").is_empty());
+
+    assert!(repro::is_build_host_path("/Users/bob/project/lib.c"));
+    assert!(repro::is_build_host_path("C:\\Users\\bob\\lib.c"));
+    assert!(!repro::is_build_host_path("/usr/include/stdio.h"));
+}
@@ -0,0 +1,75 @@
+//! Interrupted `--output` runs and `--resume`
+
+use std::path::Path;
+
+use clap::Parser;
+use symwalker::binary::{scan_binary, BinaryInfo, ScanContext};
+use symwalker::cli::{self, Args};
+use symwalker::manifest;
+use symwalker::testutil::ElfBuilder;
+
+fn output_args(out: &Path, extra: &[&str]) -> Args {
+    let argv = ["symwalker", "--copy-binaries", "-o", out.to_str().unwrap()].into_iter()
+        .chain(extra.iter().copied())
+        .chain(["."]);
+    Args::parse_from(argv)
+}
+
+fn scan(dir: &Path, names: &[&str], args: &Args) -> Vec<BinaryInfo> {
+    let ctx = ScanContext::new(args).unwrap();
+    names.iter()
+        .map(|name| {
+            std::fs::write(dir.join(name), ElfBuilder::new().symbol(name).build()).unwrap();
+            scan_binary(&dir.join(name), args, &ctx).unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn resume_completes_an_interrupted_run_without_recopying() {
+    let (scan_dir, out) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    let args = output_args(out.path(), &[]);
+    let ctx = ScanContext::new(&args).unwrap();
+    let binaries = scan(scan_dir.path(), &["a", "b", "c"], &args);
+
+    // "b" vanishing after the scan stops the copy loop at the second entry
+    std::fs::remove_file(scan_dir.path().join("b")).unwrap();
+    assert!(cli::handle_output(&args, &ctx, &binaries).is_err());
+    assert!(!out.path().join(manifest::MANIFEST_NAME).exists());
+    assert_eq!(manifest::read_journal(out.path()).unwrap().len(), 1);
+
+    // Leftovers of a copy that was in flight when the process died
+    std::fs::write(out.path().join(".b.tmp.4242"), b"partial").unwrap();
+    // A finished copy is kept as long as it still matches its checksum
+    std::fs::write(scan_dir.path().join("a"), b"rebuilt").unwrap();
+    std::fs::write(scan_dir.path().join("b"), ElfBuilder::new().symbol("b").build()).unwrap();
+
+    let args = output_args(out.path(), &["--resume"]);
+    cli::handle_output(&args, &ctx, &binaries).unwrap();
+
+    assert_ne!(std::fs::read(out.path().join("a")).unwrap(), b"rebuilt");
+    assert!(!out.path().join(".b.tmp.4242").exists());
+    assert!(!out.path().join(manifest::JOURNAL_NAME).exists());
+    let report = manifest::verify(out.path(), false).unwrap();
+    assert_eq!(report.checked, 3);
+    assert!(report.is_ok(), "{:?}", report);
+}
+
+#[test]
+fn resume_redoes_entries_whose_files_changed() {
+    let (scan_dir, out) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    let args = output_args(out.path(), &[]);
+    let ctx = ScanContext::new(&args).unwrap();
+    let binaries = scan(scan_dir.path(), &["a", "b"], &args);
+
+    std::fs::remove_file(scan_dir.path().join("b")).unwrap();
+    assert!(cli::handle_output(&args, &ctx, &binaries).is_err());
+    std::fs::write(out.path().join("a"), b"corrupted").unwrap();
+    std::fs::write(scan_dir.path().join("b"), ElfBuilder::new().symbol("b").build()).unwrap();
+
+    let args = output_args(out.path(), &["--resume"]);
+    cli::handle_output(&args, &ctx, &binaries).unwrap();
+
+    assert_eq!(std::fs::read(out.path().join("a")).unwrap(), std::fs::read(scan_dir.path().join("a")).unwrap());
+    assert!(manifest::verify(out.path(), false).unwrap().is_ok());
+}
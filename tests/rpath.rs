@@ -0,0 +1,112 @@
+//! RPATH/RUNPATH extraction and search path audit
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use symwalker::rpath::{self, AuditRoot, RpathIssue};
+use symwalker::testutil::ElfBuilder;
+
+#[test]
+fn origin_expands_in_both_spellings() {
+    let origin = Path::new("/opt/app/bin");
+    assert_eq!(rpath::expand_origin("$ORIGIN/../lib", origin).unwrap(), "/opt/app/bin/../lib");
+    assert_eq!(rpath::expand_origin("${ORIGIN}/../lib", origin).unwrap(), "/opt/app/bin/../lib");
+    assert_eq!(rpath::expand_origin("$ORIGIN", origin).unwrap(), "/opt/app/bin");
+    assert_eq!(rpath::expand_origin("/usr/lib", origin).unwrap(), "/usr/lib");
+}
+
+#[test]
+fn origin_lookalikes_and_other_tokens_are_not_expanded() {
+    let origin = Path::new("/opt/app/bin");
+    assert_eq!(rpath::expand_origin("$ORIGINAL/lib", origin), None);
+    assert_eq!(rpath::expand_origin("$ORIGIN/$LIB", origin), None);
+    assert_eq!(rpath::expand_origin("${PLATFORM}/lib", origin), None);
+    assert_eq!(rpath::expand_origin("${ORIGIN/lib", origin), None);
+}
+
+#[test]
+fn normalize_resolves_parents_lexically() {
+    assert_eq!(rpath::normalize(Path::new("/opt/app/bin/../../../tmp")), PathBuf::from("/tmp"));
+    assert_eq!(rpath::normalize(Path::new("/opt/app/bin/../../../../../tmp")), PathBuf::from("/tmp"));
+    assert_eq!(rpath::normalize(Path::new("/opt/./app//lib/")), PathBuf::from("/opt/app/lib"));
+    assert_eq!(rpath::normalize(Path::new("../x/../..")), PathBuf::from("../.."));
+}
+
+#[test]
+fn split_keeps_empty_entries() {
+    assert_eq!(rpath::split_entries(["/a::b", "c:"]), ["/a", "", "b", "c", ""]);
+}
+
+fn audit(entries: &[&str], dir: &Path) -> Vec<(String, RpathIssue)> {
+    let binary = dir.join("bin/app");
+    let root = AuditRoot { binary: &binary, prefix: dir, sysroot: None };
+    let entries: Vec<String> = entries.iter().map(|e| e.to_string()).collect();
+    rpath::audit(&entries, &root)
+        .into_iter()
+        .map(|f| (f.entry, f.issue))
+        .collect()
+}
+
+#[test]
+fn audit_flags_each_kind_of_problem() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("bin")).unwrap();
+    std::fs::create_dir_all(dir.path().join("lib")).unwrap();
+
+    let findings = audit(
+        &["", "lib", "$ORIGIN/../lib", "${ORIGIN}/../../../../tmp", "$ORIGIN/../missing", "$ORIGIN/$LIB"],
+        dir.path(),
+    );
+    assert_eq!(findings, [
+        ("".to_string(), RpathIssue::Empty),
+        ("lib".to_string(), RpathIssue::Relative),
+        ("${ORIGIN}/../../../../tmp".to_string(), RpathIssue::OriginEscapes),
+        ("$ORIGIN/../missing".to_string(), RpathIssue::Missing),
+    ]);
+}
+
+#[cfg(unix)]
+#[test]
+fn audit_flags_world_writable_directories() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let shared = dir.path().join("shared");
+    std::fs::create_dir(&shared).unwrap();
+    std::fs::set_permissions(&shared, std::fs::Permissions::from_mode(0o777)).unwrap();
+
+    let findings = audit(&[shared.to_str().unwrap()], dir.path());
+    assert_eq!(findings, [(shared.display().to_string(), RpathIssue::WorldWritable)]);
+}
+
+#[test]
+fn sysroot_resolves_absolute_entries() {
+    let sysroot = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(sysroot.path().join("usr/lib/app")).unwrap();
+    let binary = sysroot.path().join("usr/bin/app");
+    let entries = vec!["/usr/lib/app".to_string(), "/usr/lib/nope".to_string()];
+
+    let root = AuditRoot { binary: &binary, prefix: sysroot.path(), sysroot: Some(sysroot.path()) };
+    let findings = rpath::audit(&entries, &root);
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].entry, "/usr/lib/nope");
+    assert_eq!(findings[0].issue, RpathIssue::Missing);
+}
+
+#[test]
+fn scan_reports_rpath_runpath_and_findings() {
+    let data = ElfBuilder::new()
+        .e_type(3)
+        .needed("libc.so.6")
+        .rpath("$ORIGIN/../../../tmp")
+        .runpath("/nonexistent/lib:")
+        .build();
+    let info = common::scan_bytes("app", &data, &[]).unwrap();
+
+    assert_eq!(info.rpath, ["$ORIGIN/../../../tmp"]);
+    assert_eq!(info.runpath, ["/nonexistent/lib", ""]);
+    let issues: Vec<_> = info.rpath_findings.iter().map(|f| f.issue).collect();
+    assert_eq!(issues, [RpathIssue::OriginEscapes, RpathIssue::Missing, RpathIssue::Empty]);
+    assert!(info.warnings.iter().any(|w| w.code == "insecure-rpath"));
+}
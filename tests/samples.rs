@@ -0,0 +1,53 @@
+//! Scan real-world binaries collected by `scripts/fetch-samples.sh`
+//!
+//! Results depend on the host, so this only checks that every sample scans
+//! without error; it's a no-op when `tests/samples/` hasn't been populated.
+
+mod common;
+
+use std::path::Path;
+
+#[test]
+fn samples_scan_cleanly() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/samples");
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return;
+    };
+
+    for entry in entries {
+        let path = entry.unwrap().path();
+        if !path.is_file() {
+            continue;
+        }
+
+        let info = common::scan_path(&path, &["--security", "--dwarf"])
+            .unwrap_or_else(|e| panic!("{}: {:#}", path.display(), e));
+        serde_json::to_string(&info).unwrap();
+    }
+}
+
+/// Relocation totals match the entries `readelf -rW` lists, when it's installed
+#[test]
+fn sample_relocations_match_readelf() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/samples");
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return;
+    };
+
+    for entry in entries {
+        let path = entry.unwrap().path();
+        let Ok(output) = std::process::Command::new("readelf").arg("-rW").arg(&path).output() else {
+            return;
+        };
+        if !output.status.success() {
+            continue;
+        }
+
+        let listed = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| line.len() >= 8 && line.as_bytes()[..8].iter().all(u8::is_ascii_hexdigit))
+            .count() as u64;
+        let info = common::scan_path(&path, &[]).unwrap();
+        assert_eq!(info.relocation_count, Some(listed), "{}", path.display());
+    }
+}
@@ -0,0 +1,92 @@
+//! Files the scan couldn't read or parse (`--show-errors`, `--strict`)
+
+mod common;
+
+use std::os::unix::fs::{symlink, PermissionsExt};
+use std::path::Path;
+use std::process::Output;
+
+use symwalker::testutil::ElfBuilder;
+
+/// One good binary, a text file, an empty file, an ELF that doesn't parse,
+/// a broken link and a link back up the tree
+fn tree(root: &Path) {
+    std::fs::write(root.join("app"), ElfBuilder::new().symbol("main").build()).unwrap();
+    std::fs::write(root.join("README"), "not a binary").unwrap();
+    std::fs::write(root.join("empty"), b"").unwrap();
+    let mut broken = ElfBuilder::new().symbol("main").build();
+    broken[0x28..0x30].copy_from_slice(&0x7fff_0000u64.to_le_bytes()); // e_shoff past the end
+    std::fs::write(root.join("broken.so"), broken).unwrap();
+    symlink(root.join("gone"), root.join("dangling")).unwrap();
+    std::fs::create_dir(root.join("sub")).unwrap();
+    symlink(root, root.join("sub/loop")).unwrap();
+}
+
+fn errors(output: &Output) -> Vec<(String, String)> {
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let mut errors: Vec<_> = report["errors"].as_array().unwrap().iter()
+        .map(|error| {
+            let path = Path::new(error["path"].as_str().unwrap()).file_name().unwrap().to_string_lossy().into_owned();
+            (path, error["kind"].as_str().unwrap().to_string())
+        })
+        .collect();
+    errors.sort();
+    errors
+}
+
+#[test]
+fn errors_are_listed_by_kind_and_can_fail_the_run() {
+    let dir = tempfile::tempdir().unwrap();
+    tree(dir.path());
+
+    // Without --strict-parse the ELF keeps its header fields; not an error
+    let output = common::symwalker(dir.path(), &["--json", "--show-errors", "--follow-symlinks"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(errors(&output), [("dangling".to_string(), "broken-symlink".to_string()), ("loop".to_string(), "symlink-loop".to_string())]);
+
+    let output = common::symwalker(dir.path(), &["--json", "--show-errors", "--strict-parse"]);
+    assert_eq!(errors(&output), [("broken.so".to_string(), "malformed".to_string())]);
+    // Links aren't followed, so they aren't errors either
+    let output = common::symwalker(dir.path(), &["--json", "--show-errors"]);
+    assert_eq!(errors(&output), []);
+
+    // Only listed when asked for
+    let output = common::symwalker(dir.path(), &["--json", "--strict-parse"]);
+    assert!(serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap().is_array());
+
+    assert_eq!(common::symwalker(dir.path(), &["--json", "--strict", "--strict-parse"]).status.code(), Some(7));
+    assert_eq!(common::symwalker(dir.path(), &["--json", "--strict"]).status.code(), Some(0));
+}
+
+#[test]
+fn human_output_counts_errors_and_lists_them_on_request() {
+    let dir = tempfile::tempdir().unwrap();
+    tree(dir.path());
+
+    let output = common::symwalker(dir.path(), &["-v", "--follow-symlinks"]);
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert!(text.contains("Scan errors: 2 (--show-errors lists them)"), "{}", text);
+    assert!(!text.contains("Scan Errors"), "{}", text);
+
+    let output = common::symwalker(dir.path(), &["--show-errors", "--follow-symlinks"]);
+    let text = String::from_utf8_lossy(&output.stdout);
+    let section = text.split_once("Scan Errors").map(|(_, section)| section).unwrap_or_else(|| panic!("{}", text));
+    assert!(section.contains("dangling [broken-symlink]"), "{}", section);
+    assert!(section.contains("[symlink-loop]"), "{}", section);
+    assert!(!section.contains("README") && !section.contains("empty"), "{}", section);
+}
+
+#[test]
+fn unreadable_files_are_permission_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app");
+    std::fs::write(&path, ElfBuilder::new().symbol("main").build()).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o000)).unwrap();
+    if std::fs::File::open(&path).is_ok() {
+        // Running as root; nothing to deny
+        return;
+    }
+
+    let output = common::symwalker(dir.path(), &["--json", "--show-errors"]);
+    assert_eq!(errors(&output), [("app".to_string(), "permission-denied".to_string())]);
+}
@@ -0,0 +1,157 @@
+//! Snapshot the JSON of `scan_binary` for every synthesized fixture
+//!
+//! Update with `INSTA_UPDATE=always cargo test --test scan_snapshots`
+//! (or `cargo insta review`) after an intentional output change.
+
+mod common;
+
+use symwalker::testutil::{
+    ElfBuilder, Endian, FatBuilder, MachOBuilder, CPU_TYPE_ARM64, CPU_TYPE_X86_64,
+};
+
+// Synthetic ids that won't collide with anything under /usr/lib/debug
+const BUILD_ID: [u8; 20] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09,
+    0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13,
+];
+
+const UUID_X86_64: [u8; 16] = [
+    0x11, 0x11, 0x11, 0x11, 0x22, 0x22, 0x33, 0x33,
+    0x44, 0x44, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+];
+
+const UUID_ARM64: [u8; 16] = [
+    0xaa, 0xaa, 0xaa, 0xaa, 0xbb, 0xbb, 0xcc, 0xcc,
+    0xdd, 0xdd, 0xee, 0xee, 0xee, 0xee, 0xee, 0xee,
+];
+
+fn corpus() -> Vec<(&'static str, Vec<u8>)> {
+    vec![
+        (
+            "elf64_pie_hardened",
+            ElfBuilder::new()
+                .e_type(3) // ET_DYN
+                .interp("/nonexistent/ld-linux-x86-64.so.2")
+                .needed("libc.so.6")
+                .segment(1, 5) // PT_LOAD, R+X
+                .relro()
+                .build_id(&BUILD_ID)
+                .debuglink("elf64_pie_hardened.debug", b"debug file contents")
+                .symbol("main")
+                .symbol("__stack_chk_fail")
+                .symbol("__memcpy_chk")
+                .symbol("_ZN4core3fmt5write17h0123456789abcdefE")
+                .symbol("_ZNSt6vectorIiSaIiEE9push_backERKi")
+                .build(),
+        ),
+        (
+            "elf64_exec_stripped_execstack",
+            ElfBuilder::new()
+                .executable_stack()
+                .build_id(&BUILD_ID)
+                .build(),
+        ),
+        (
+            "elf64_no_gnu_stack_with_debug_info",
+            ElfBuilder::new()
+                .gnu_stack(None)
+                .section(".text", &[0xc3])
+                .section(".debug_info", &[0; 16])
+                .section(".debug_line", &[0; 8])
+                .symbol("main")
+                .build(),
+        ),
+        (
+            "elf64_debug_only",
+            ElfBuilder::new()
+                .build_id(&BUILD_ID)
+                .nobits_section(".text", 0x1000)
+                .section(".debug_info", &[0; 16])
+                .symbol("main")
+                .build(),
+        ),
+        (
+            "elf64_go",
+            ElfBuilder::new()
+                .go_build_id("abcDEF123/xyz789")
+                .symbol("main.main")
+                .symbol("runtime.main")
+                .build(),
+        ),
+        (
+            "elf64_package_note",
+            ElfBuilder::new()
+                .e_type(3)
+                .package_note(r#"{"type":"rpm","name":"systemd","version":"256.7-1.fc41","architecture":"x86_64","osCpe":"cpe:/o:fedoraproject:fedora:41"}"#)
+                .symbol("main")
+                .build(),
+        ),
+        (
+            "elf32_big_endian_ppc",
+            ElfBuilder::new()
+                .class32()
+                .endian(Endian::Big)
+                .machine(20) // EM_PPC
+                .symbol("main")
+                .build(),
+        ),
+        (
+            "elf32_unknown_machine",
+            ElfBuilder::new()
+                .class32()
+                .machine(94) // EM_XTENSA
+                .build(),
+        ),
+        (
+            "macho64_thin",
+            MachOBuilder::new()
+                .uuid(UUID_X86_64)
+                .section("__TEXT", "__text", &[0xc3; 16])
+                .entry(0x3f50)
+                .symbol("_main")
+                .symbol("___stack_chk_fail")
+                .symbol("__ZNSt6vectorIiSaIiEE9push_backERKi")
+                .build(),
+        ),
+        (
+            "macho64_stripped_no_pie",
+            MachOBuilder::new()
+                .flags(0x85)
+                .uuid(UUID_X86_64)
+                .build(),
+        ),
+        (
+            "macho64_dwarf_companion",
+            MachOBuilder::new()
+                .filetype(0xa) // MH_DSYM
+                .uuid(UUID_X86_64)
+                .dwarf_section("__debug_info", &[0; 16])
+                .dwarf_section("__debug_line", &[0; 8])
+                .symbol("_main")
+                .build(),
+        ),
+        (
+            "macho32_i386",
+            MachOBuilder::new()
+                .class32()
+                .symbol("_main")
+                .build(),
+        ),
+        (
+            "macho_fat",
+            FatBuilder::new()
+                .slice(CPU_TYPE_X86_64, 3, MachOBuilder::new().uuid(UUID_X86_64).symbol("_main").build())
+                .slice(CPU_TYPE_ARM64, 0, MachOBuilder::new().cpu(CPU_TYPE_ARM64, 0).uuid(UUID_ARM64).symbol("_main").build())
+                .build(),
+        ),
+    ]
+}
+
+#[test]
+fn corpus_snapshots() {
+    for (name, data) in corpus() {
+        let info = common::scan_bytes(name, &data, &["--security"])
+            .unwrap_or_else(|e| panic!("{}: {:#}", name, e));
+        insta::assert_json_snapshot!(name, common::snapshot_json(&info));
+    }
+}
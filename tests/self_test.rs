@@ -0,0 +1,75 @@
+//! Environment checks (`--self-test`)
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn symwalker(dir: &Path, flags: &[&str], vars: &[(&str, &str)]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_symwalker"))
+        .env_clear()
+        .envs(vars.iter().copied())
+        .arg("--self-test")
+        .args(flags)
+        .arg(dir)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn checks_report_pass_warn_and_fail_with_hints() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = dir.path().join("cache/debuginfod_client");
+    let mut server = mockito::Server::new();
+    let probe = server.mock("HEAD", mockito::Matcher::Any).with_status(404).create();
+    let servers = format!("{},http://127.0.0.1:9/", server.url());
+
+    let vars = [("DEBUGINFOD_CACHE_PATH", cache.to_str().unwrap()), ("LANG", "C")];
+    let output = symwalker(dir.path(), &["--json", "--debuginfod-urls", &servers, "--skip-check", "derived-data,debug-dirs"], &vars);
+    assert_eq!(output.status.code(), Some(6), "{}", String::from_utf8_lossy(&output.stderr));
+    probe.assert();
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let checks = report["checks"].as_array().unwrap();
+    let status = |name: &str| checks.iter()
+        .filter(|check| check["check"] == name)
+        .map(|check| check["status"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(status("scan-root"), ["pass"]);
+    assert_eq!(status("derived-data"), ["skip"]);
+    assert_eq!(status("debug-dirs"), ["skip"]);
+    // A 404 still proves the server answers
+    assert_eq!(status("debuginfod"), ["pass", "fail"]);
+    assert_eq!(status("cache-dir"), ["pass"]);
+    assert_eq!(status("output-dir"), ["skip"]);
+    assert_eq!(status("locale"), ["warn"]);
+    assert_eq!(status("parse"), ["pass"]);
+    assert!(checks.iter().all(|check| check["status"] == "pass" || check["status"] == "skip" || check["hint"].is_string()), "{:#}", report);
+    // Nothing is left behind by the write probes
+    assert!(!dir.path().join("cache").exists());
+    assert!(std::fs::read_dir(dir.path()).unwrap().next().is_none());
+
+    let vars = [("DEBUGINFOD_CACHE_PATH", cache.to_str().unwrap()), ("LANG", "C.UTF-8")];
+    let output = symwalker(dir.path(), &["--debuginfod-urls", &server.url(), "--skip-check", "debug-dirs"], &vars);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert!(text.lines().any(|line| line.contains("locale") && line.contains("LANG=C.UTF-8")), "{}", text);
+
+    let missing = symwalker(&dir.path().join("missing"), &["--skip-check", "debuginfod"], &[]);
+    assert_eq!(missing.status.code(), Some(6));
+    let text = String::from_utf8_lossy(&missing.stdout);
+    assert!(text.contains("doesn't exist") && text.contains("→ Check the path"), "{}", text);
+}
+
+#[test]
+fn a_silent_server_fails_within_the_timeout() {
+    let dir = tempfile::tempdir().unwrap();
+    // Connections queue up and never get an answer
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+
+    let start = std::time::Instant::now();
+    let output = symwalker(dir.path(), &["--json", "--debuginfod-urls", &url, "--self-test-timeout", "1"], &[]);
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    assert_eq!(output.status.code(), Some(6));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let check = report["checks"].as_array().unwrap().iter().find(|check| check["check"] == "debuginfod").unwrap();
+    assert_eq!(check["status"], "fail", "{:#}", check);
+}
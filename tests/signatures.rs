@@ -0,0 +1,18 @@
+//! `--scan-signatures` patterns against the sections they're searched in
+
+use symwalker::signatures::SignatureSet;
+use symwalker::testutil::ElfBuilder;
+
+#[test]
+fn strings_are_found_in_string_tables() {
+    let set = SignatureSet::parse("[[signature]]\nname = \"liblzma-5.6\"\nstrings = [\"liblzma.so.5.6\", \"lzma_code\"]\n").unwrap();
+    let found = |data: &[u8]| -> Vec<String> {
+        set.scan(data).into_iter().map(|found| found.pattern).collect()
+    };
+
+    // Stripped: only .dynstr is left
+    assert_eq!(found(&ElfBuilder::new().needed("liblzma.so.5.6.1").build()), ["liblzma.so.5.6"]);
+    assert_eq!(found(&ElfBuilder::new().local_symbol("lzma_code").build()), ["lzma_code"]);
+    // Code isn't searched for strings
+    assert!(found(&ElfBuilder::new().code_section(".text", b"lzma_code").build()).is_empty());
+}
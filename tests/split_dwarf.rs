@@ -0,0 +1,97 @@
+//! Skeleton units pointing at external `.dwo` files
+
+mod common;
+
+use symwalker::testutil::ElfBuilder;
+
+const DW_TAG_COMPILE_UNIT: u8 = 0x11;
+const DW_TAG_SKELETON_UNIT: u8 = 0x4a;
+const DW_AT_NAME: u8 = 0x03;
+const DW_AT_DWO_NAME: u8 = 0x76;
+const DW_AT_GNU_DWO_NAME: [u8; 2] = [0xb0, 0x42]; // 0x2130 as ULEB128
+const DW_AT_GNU_DWO_ID: [u8; 2] = [0xb1, 0x42]; // 0x2131
+const DW_FORM_STRING: u8 = 0x08;
+const DW_FORM_DATA8: u8 = 0x07;
+
+/// Abbreviation 1 of each kind: a DWARF 5 skeleton unit, a DWARF 4 GNU
+/// split unit and a plain compile unit, all without children
+fn debug_abbrev() -> Vec<u8> {
+    let mut abbrev = vec![1, DW_TAG_SKELETON_UNIT, 0, DW_AT_DWO_NAME, DW_FORM_STRING, 0, 0];
+    abbrev.extend([2, DW_TAG_COMPILE_UNIT, 0]);
+    abbrev.extend(DW_AT_GNU_DWO_NAME);
+    abbrev.push(DW_FORM_STRING);
+    abbrev.extend(DW_AT_GNU_DWO_ID);
+    abbrev.extend([DW_FORM_DATA8, 0, 0]);
+    abbrev.extend([3, DW_TAG_COMPILE_UNIT, 0, DW_AT_NAME, DW_FORM_STRING, 0, 0]);
+    abbrev.push(0);
+    abbrev
+}
+
+/// Unit with a 32-bit length prefix
+fn unit(body: Vec<u8>) -> Vec<u8> {
+    [(body.len() as u32).to_le_bytes().to_vec(), body].concat()
+}
+
+fn string(value: &str) -> Vec<u8> {
+    [value.as_bytes(), b"\0"].concat()
+}
+
+fn dwarf5_skeleton(dwo_name: &str, dwo_id: u64) -> Vec<u8> {
+    let mut body = vec![5, 0, 4, 8]; // version, DW_UT_skeleton, address size
+    body.extend(0u32.to_le_bytes()); // abbrev offset
+    body.extend(dwo_id.to_le_bytes());
+    body.push(1);
+    body.extend(string(dwo_name));
+    unit(body)
+}
+
+fn dwarf4_gnu_split(dwo_name: &str, dwo_id: u64) -> Vec<u8> {
+    let mut body = vec![4, 0];
+    body.extend(0u32.to_le_bytes());
+    body.push(8);
+    body.push(2);
+    body.extend(string(dwo_name));
+    body.extend(dwo_id.to_le_bytes());
+    unit(body)
+}
+
+fn dwarf4_full(name: &str) -> Vec<u8> {
+    let mut body = vec![4, 0];
+    body.extend(0u32.to_le_bytes());
+    body.push(8);
+    body.push(3);
+    body.extend(string(name));
+    unit(body)
+}
+
+fn binary(debug_info: &[u8]) -> Vec<u8> {
+    ElfBuilder::new()
+        .section(".text", &[0xc3])
+        .section(".debug_abbrev", &debug_abbrev())
+        .section(".debug_info", debug_info)
+        .build()
+}
+
+#[test]
+fn skeleton_units_report_their_dwo_files() {
+    let debug_info = [
+        dwarf5_skeleton("main.dwo", 1),
+        dwarf4_gnu_split("obj/util.dwo", 2),
+        dwarf5_skeleton("main.dwo", 3),
+    ].concat();
+
+    let info = common::scan_bytes("app", &binary(&debug_info), &[]).unwrap();
+
+    assert_eq!(info.has_debug_info, Some(true));
+    assert!(info.is_skeleton);
+    assert_eq!(info.dwo_names, ["main.dwo", "obj/util.dwo"]);
+}
+
+#[test]
+fn complete_dwarf_is_not_a_skeleton() {
+    let info = common::scan_bytes("app", &binary(&dwarf4_full("main.c")), &[]).unwrap();
+
+    assert_eq!(info.has_debug_info, Some(true));
+    assert!(!info.is_skeleton);
+    assert!(info.dwo_names.is_empty());
+}
@@ -0,0 +1,74 @@
+//! Files named like binaries that aren't, and binaries named like data
+
+mod common;
+
+use std::path::Path;
+
+use symwalker::suspicious;
+use symwalker::testutil::ElfBuilder;
+
+const GLIBC_LIBC_SO: &str = "/* GNU ld script
+   Use the shared library, but some functions are only in
+   the static library, so try that secondarily.  */
+OUTPUT_FORMAT(elf64-x86-64)
+GROUP ( /lib/x86_64-linux-gnu/libc.so.6 /usr/lib/x86_64-linux-gnu/libc_nonshared.a  AS_NEEDED ( /lib64/ld-linux-x86-64.so.2 ) )
+";
+
+#[test]
+fn linker_scripts_list_their_group_and_input_files() {
+    assert_eq!(suspicious::linker_script_libraries(GLIBC_LIBC_SO).unwrap(), [
+        "/lib/x86_64-linux-gnu/libc.so.6",
+        "/usr/lib/x86_64-linux-gnu/libc_nonshared.a",
+        "/lib64/ld-linux-x86-64.so.2",
+    ]);
+    assert_eq!(suspicious::linker_script_libraries("INPUT(-lm, libextra.so)").unwrap(), ["-lm", "libextra.so"]);
+    // A GROUP inside a comment isn't one
+    assert_eq!(suspicious::linker_script_libraries("/* GROUP ( libc.so.6 ) */ hello"), None);
+}
+
+#[test]
+fn non_binaries_with_binary_names_are_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::write(root.join("libc.so"), GLIBC_LIBC_SO).unwrap();
+    std::fs::write(root.join("libfoo.so.1"), "see the README").unwrap();
+    std::fs::write(root.join("main.o"), b"").unwrap();
+    std::fs::write(root.join("libreal.a"), b"!<arch>\n").unwrap();
+    std::fs::write(root.join("libfake.so"), b"!<arch>\n").unwrap();
+    std::fs::write(root.join("driver.ko"), b"\x00\x01\x02\x03").unwrap();
+    std::fs::write(root.join("README"), "not watched").unwrap();
+    std::fs::write(root.join("payload.dat"), ElfBuilder::new().symbol("main").build()).unwrap();
+
+    // Plain --json stays an array of binaries
+    let output = common::symwalker(root, &["--json"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report.as_array().unwrap()[0]["warnings"][0]["code"], "misleading-extension");
+
+    let output = common::symwalker(root, &["--json", "--show-suspicious"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let mut files: Vec<(String, String)> = report["suspicious_files"].as_array().unwrap().iter()
+        .map(|file| {
+            let name = Path::new(file["path"].as_str().unwrap()).file_name().unwrap().to_string_lossy().into_owned();
+            (name, file["kind"].as_str().unwrap().to_string())
+        })
+        .collect();
+    files.sort();
+    assert_eq!(files, [
+        ("driver.ko", "data"),
+        ("libc.so", "linker-script"),
+        ("libfake.so", "archive"),
+        ("libfoo.so.1", "text"),
+        ("main.o", "empty"),
+    ].map(|(name, kind)| (name.to_string(), kind.to_string())));
+
+    let binaries = report["binaries"].as_array().unwrap();
+    assert_eq!(binaries.len(), 1);
+    assert_eq!(binaries[0]["warnings"][0]["code"], "misleading-extension");
+
+    let output = common::symwalker(root, &[]);
+    let text = String::from_utf8_lossy(&output.stdout);
+    let section = text.split_once("Suspicious Files").map(|(_, section)| section).unwrap_or_else(|| panic!("{}", text));
+    assert!(section.contains("libc.so [linker-script] linker script named like a .so binary"), "{}", section);
+    assert!(section.contains("-> /lib/x86_64-linux-gnu/libc.so.6 "), "{}", section);
+}
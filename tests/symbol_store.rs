@@ -0,0 +1,64 @@
+//! Debug files in a symbol store that no scanned binary needs (`--symbol-store`)
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::Command;
+
+use symwalker::orphans::StoreOrphans;
+use symwalker::testutil::{ElfBuilder, MachOBuilder};
+
+/// A store with debug files for build-ids 01.. and 02.., a dSYM, a
+/// symlinked alias and a file that is neither ELF nor Mach-O
+fn store(dir: &Path) {
+    let build_id_dir = |id: u8| dir.join(".build-id").join(format!("{:02x}", id));
+    for id in [1u8, 2] {
+        std::fs::create_dir_all(build_id_dir(id)).unwrap();
+        let debug = ElfBuilder::new().build_id(&[id; 20]).symbol("main").build();
+        std::fs::write(build_id_dir(id).join(format!("{}.debug", format!("{:02x}", id).repeat(19))), debug).unwrap();
+    }
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(build_id_dir(2).join(format!("{}.debug", "02".repeat(19))), dir.join("alias.debug")).unwrap();
+
+    let dwarf = dir.join("App.dSYM/Contents/Resources/DWARF");
+    std::fs::create_dir_all(&dwarf).unwrap();
+    std::fs::write(dwarf.join("App"), MachOBuilder::new().uuid([0xaa; 16]).build()).unwrap();
+    std::fs::write(dir.join("README"), "symbols for the 2.x releases").unwrap();
+}
+
+#[test]
+fn files_no_binary_refers_to_are_orphans() {
+    let dir = tempfile::tempdir().unwrap();
+    store(dir.path());
+
+    let referenced: HashSet<String> = ["01".repeat(20)].into();
+    let found = StoreOrphans::find(dir.path(), &referenced).unwrap();
+    assert_eq!((found.identified, found.unidentified), (3, 1));
+    let ids: Vec<&str> = found.orphans.iter().map(|orphan| orphan.id.as_str()).collect();
+    assert_eq!(ids, ["02".repeat(20), "AAAAAAAA-AAAA-AAAA-AAAA-AAAAAAAAAAAA".to_string()]);
+    assert_eq!(found.orphan_bytes, found.orphans.iter().map(|orphan| std::fs::metadata(&orphan.path).unwrap().len()).sum::<u64>());
+
+    let everything: HashSet<String> = ["01".repeat(20), "02".repeat(20), "AAAAAAAA-AAAA-AAAA-AAAA-AAAAAAAAAAAA".to_string()].into();
+    assert!(StoreOrphans::find(dir.path(), &everything).unwrap().orphans.is_empty());
+    assert!(StoreOrphans::find(&dir.path().join("README"), &everything).is_err());
+}
+
+#[test]
+fn stripped_binaries_keep_their_store_files() {
+    let (scan, symbols) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    store(symbols.path());
+    // Stripped, so the default report leaves it out, but its symbols are still needed
+    std::fs::write(scan.path().join("app"), ElfBuilder::new().build_id(&[1; 20]).build()).unwrap();
+    std::fs::write(scan.path().join("App"), MachOBuilder::new().uuid([0xaa; 16]).symbol("_main").build()).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_symwalker"))
+        .args(["--json", "--symbol-store"])
+        .arg(symbols.path())
+        .arg(scan.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let orphans = &report["store_orphans"]["orphans"];
+    assert_eq!(orphans.as_array().unwrap().len(), 1, "{}", report);
+    assert_eq!(orphans[0]["id"], "02".repeat(20));
+}
@@ -0,0 +1,56 @@
+//! Address symbolication (--reverse-lookup)
+
+mod common;
+
+use symwalker::symbolicate::{self, Symbolication};
+use symwalker::testutil::ElfBuilder;
+
+#[test]
+fn addresses_parse_with_or_without_prefix() {
+    assert_eq!(symbolicate::parse_address("0x401a2f"), Ok(0x401a2f));
+    assert_eq!(symbolicate::parse_address("0X10"), Ok(0x10));
+    assert_eq!(symbolicate::parse_address("ff"), Ok(0xff));
+    assert!(symbolicate::parse_address("0xzz").is_err());
+    assert!(symbolicate::parse_address("").is_err());
+}
+
+#[test]
+fn display_includes_what_is_known() {
+    let mut result = Symbolication {
+        address: "0x1010".to_string(),
+        function: Some("main".to_string()),
+        offset: Some(0x10),
+        ..Default::default()
+    };
+    assert_eq!(result.to_string(), "main+0x10");
+
+    result.file = Some("src/main.c".to_string());
+    result.line = Some(12);
+    assert_eq!(result.to_string(), "main+0x10 (src/main.c:12)");
+
+    result.function = None;
+    assert_eq!(result.to_string(), "?? (src/main.c:12)");
+}
+
+#[test]
+fn symbol_table_gives_function_and_offset() {
+    let data = ElfBuilder::new()
+        .section(".text", &[0xc3; 0x80])
+        .symbol_at("first", ".text", 0x1000)
+        .symbol_at("_ZN4core3fmt5write17h0123456789abcdefE", ".text", 0x1040)
+        .build();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app");
+    std::fs::write(&path, &data).unwrap();
+    let info = common::scan_path(&path, &[]).unwrap();
+
+    let result = symbolicate::symbolicate(&info, &data, 0x1052).unwrap();
+    assert_eq!(result.address, "0x1052");
+    assert_eq!(result.function.as_deref(), Some("core::fmt::write"));
+    assert_eq!(result.offset, Some(0x12));
+    assert_eq!(result.file, None);
+    assert_eq!(result.debug_source, None);
+
+    let below = symbolicate::symbolicate(&info, &data, 0x10).unwrap();
+    assert_eq!(below.to_string(), "??");
+}
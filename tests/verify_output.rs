@@ -0,0 +1,143 @@
+//! `symwalker verify-output` against the manifest an `--output` run wrote
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use symwalker::cli::{self, Args};
+use symwalker::manifest;
+use symwalker::testutil::ElfBuilder;
+
+/// Scan a binary with an adjacent debug file into a fresh output directory
+fn populate(scan: &Path, out: &Path) {
+    populate_with(scan, out, &[]);
+}
+
+fn populate_with(scan: &Path, out: &Path, flags: &[&str]) {
+    std::fs::write(scan.join("app"), ElfBuilder::new().build_id(&[1; 20]).build()).unwrap();
+    std::fs::write(scan.join("app.debug"), ElfBuilder::new().build_id(&[1; 20]).section(".debug_info", &[0; 8]).build()).unwrap();
+
+    let args = Args::parse_from(["symwalker", "--copy-binaries"].iter()
+        .chain(flags)
+        .chain(&["-o", out.to_str().unwrap(), scan.to_str().unwrap()]));
+    cli::run(args).unwrap();
+}
+
+fn paths(names: &[&str]) -> Vec<PathBuf> {
+    names.iter().map(PathBuf::from).collect()
+}
+
+#[test]
+fn fresh_output_verifies() {
+    let (scan, out) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    populate(scan.path(), out.path());
+
+    let report = manifest::verify(out.path(), false).unwrap();
+
+    assert_eq!(report.checked, 2);
+    assert!(report.is_ok(), "{:?}", report);
+}
+
+#[test]
+fn verify_output_subcommand_exits_4_on_damage() {
+    let (scan, out) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    populate(scan.path(), out.path());
+
+    let fresh = common::symwalker(out.path(), &["verify-output", "--json"]);
+    assert_eq!(fresh.status.code(), Some(0), "{}", String::from_utf8_lossy(&fresh.stderr));
+    let report: serde_json::Value = serde_json::from_slice(&fresh.stdout).unwrap();
+    assert_eq!(report["checked"], 2);
+
+    std::fs::remove_file(out.path().join("app")).unwrap();
+    let damaged = common::symwalker(out.path(), &["verify-output"]);
+    assert_eq!(damaged.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&damaged.stdout).contains("missing: app"));
+
+    let repaired = common::symwalker(out.path(), &["verify-output", "--repair"]);
+    assert_eq!(repaired.status.code(), Some(0), "{}", String::from_utf8_lossy(&repaired.stdout));
+}
+
+#[test]
+fn missing_modified_and_extra_files_are_reported() {
+    let (scan, out) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    populate(scan.path(), out.path());
+
+    std::fs::remove_file(out.path().join("app")).unwrap();
+    std::fs::write(out.path().join("app.debug"), b"truncated").unwrap();
+    std::fs::write(out.path().join("stray"), b"").unwrap();
+
+    let report = manifest::verify(out.path(), false).unwrap();
+
+    assert!(!report.is_ok());
+    assert_eq!(report.missing, paths(&["app"]));
+    assert_eq!(report.modified, paths(&["app.debug"]));
+    assert_eq!(report.extra, paths(&["stray"]));
+}
+
+#[test]
+fn repair_copies_from_unchanged_sources() {
+    let (scan, out) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    populate(scan.path(), out.path());
+
+    std::fs::remove_file(out.path().join("app")).unwrap();
+    std::fs::write(out.path().join("app.debug"), b"truncated").unwrap();
+    // A rebuilt source no longer matches what was recorded
+    std::fs::write(scan.path().join("app.debug"), b"rebuilt").unwrap();
+
+    let report = manifest::verify(out.path(), true).unwrap();
+
+    assert_eq!(report.repaired, paths(&["app"]));
+    assert_eq!(report.modified, paths(&["app.debug"]));
+    assert_eq!(std::fs::read(out.path().join("app")).unwrap(), std::fs::read(scan.path().join("app")).unwrap());
+}
+
+#[test]
+fn relative_sources_are_repaired_from_the_recorded_root() {
+    let (scan, out) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    populate_with(scan.path(), out.path(), &["--relative-paths"]);
+
+    let manifest: serde_json::Value = serde_json::from_slice(&std::fs::read(out.path().join(manifest::MANIFEST_NAME)).unwrap()).unwrap();
+    assert_eq!(manifest["scan_root"], std::fs::canonicalize(scan.path()).unwrap().to_str().unwrap());
+    assert_eq!(manifest["files"][0]["binary"], "app");
+    let sources: Vec<&str> = manifest["artifacts"].as_array().unwrap().iter()
+        .map(|artifact| artifact["source"].as_str().unwrap())
+        .collect();
+    assert_eq!(sources, ["app", "app.debug"]);
+
+    std::fs::remove_file(out.path().join("app")).unwrap();
+    let report = manifest::verify(out.path(), true).unwrap();
+    assert_eq!(report.repaired, paths(&["app"]));
+    assert!(report.is_ok(), "{:?}", report);
+}
+
+#[test]
+fn changed_build_id_and_incomplete_dsym_are_reported() {
+    let out = tempfile::tempdir().unwrap();
+    let debug = out.path().join("app.debug");
+    std::fs::write(&debug, ElfBuilder::new().build_id(&[2; 20]).build()).unwrap();
+    let plist = out.path().join("App.dSYM/Contents/Info.plist");
+    std::fs::create_dir_all(plist.parent().unwrap()).unwrap();
+    std::fs::write(&plist, b"<plist/>").unwrap();
+
+    let mut artifacts = manifest::artifacts(out.path(), &debug, None).unwrap();
+    artifacts.extend(manifest::artifacts(out.path(), &out.path().join("App.dSYM"), None).unwrap());
+    artifacts[0].build_id = Some("01".repeat(20));
+    let json = serde_json::json!({ "files": [], "count": 0, "artifacts": artifacts });
+    std::fs::write(out.path().join("manifest.json"), json.to_string()).unwrap();
+
+    let report = manifest::verify(out.path(), false).unwrap();
+
+    assert_eq!(report.build_id_mismatch, paths(&["app.debug"]));
+    assert_eq!(report.incomplete_dsyms, paths(&["App.dSYM"]));
+}
+
+#[test]
+fn manifest_without_checksums_is_an_error() {
+    let out = tempfile::tempdir().unwrap();
+    std::fs::write(out.path().join("manifest.json"), r#"{"files": [], "count": 0}"#).unwrap();
+
+    let err = manifest::verify(out.path(), false).unwrap_err();
+
+    assert!(err.to_string().contains("has no checksums"), "{}", err);
+}